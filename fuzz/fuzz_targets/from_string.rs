@@ -0,0 +1,15 @@
+// Feeds arbitrary bytes to `from_string`. Besides never panicking, whatever it accepts must print
+// back to source that parses to an alpha-equivalent term, and must either encode as a net or be
+// rejected. Run with `cargo fuzz run from_string -- -timeout=5`; a timeout means something looped.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sic::term::*;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(term) = from_string(data) {
+        let again = from_string(&to_string(&term)).expect("printed term doesn't parse");
+        assert!(alpha_eq(&term, &again), "printed term parses differently");
+        let _ = to_net(&term);
+    }
+});
@@ -0,0 +1,9 @@
+// Feeds arbitrary bytes to the parser, which must return a term or an error without panicking.
+// Run with `cargo fuzz run parse_checked -- -timeout=5`; a timeout means the parser looped.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = sic::term::parse_checked(data);
+});
@@ -0,0 +1,39 @@
+/* C interface to the Symmetric Interaction Calculus evaluator. Build the library with
+ * `cargo build --features ffi` and link against libsic. */
+
+#ifndef SIC_H
+#define SIC_H
+
+#include <stdint.h>
+
+#define SIC_OK 0
+#define SIC_ERR_ARGUMENT 1
+#define SIC_ERR_PARSE 2
+#define SIC_ERR_AFFINITY 3
+#define SIC_ERR_NET 4
+#define SIC_ERR_READBACK 5
+#define SIC_ERR_LIMIT 6
+#define SIC_ERR_PANIC 7
+
+typedef struct {
+    /* Maximum number of rewrites; 0 means no limit. */
+    uint32_t max_rewrites;
+} SicOptions;
+
+typedef struct {
+    /* The normal form, or NULL on error. Owned by the library. */
+    char *output;
+    /* A description of the error, or NULL on success. Owned by the library. */
+    char *error;
+    uint32_t rules;
+    uint32_t max_nodes;
+} SicResult;
+
+/* Evaluates `src`, with `input` (which may be NULL) as the main term. `opts` may be NULL for the
+ * defaults. Fills `out`, which must be released with `sic_free_result`, and returns an error code. */
+int32_t sic_eval(const char *src, const char *input, const SicOptions *opts, SicResult *out);
+
+/* Releases the strings of a result filled by `sic_eval`. Safe to call twice. */
+void sic_free_result(SicResult *res);
+
+#endif
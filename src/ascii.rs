@@ -0,0 +1,207 @@
+// Drawing nets as text, with box-drawing characters, for terminals and golden files. Nodes are
+// laid out in rows by their distance from the root, as `net::depths` gives it, with the nodes the
+// root can't reach in a last row. Each node is a box labeled with its kind and address, its main
+// port on top and its auxiliary ports below, the first on the left; the root's port is below it.
+// Nodes in active pairs are drawn with double lines.
+//
+// Each wire runs in the gap above or below the rows of its ports, on a line of its own. A wire
+// between ports that face different gaps goes around the right of the drawing, on a column of its
+// own, so wires only ever meet where they cross. Auxiliary ports that nodes other than CONs and
+// FANs loop to each other are left undrawn.
+
+use prelude::*;
+use net::*;
+use dot::label;
+
+// The most nodes `to_ascii` draws. Larger nets don't fit a terminal; draw them with `dot::to_dot`.
+pub const MAX_ASCII_NODES : u32 = 40;
+
+const UP : u8 = 1;
+const DOWN : u8 = 2;
+const LEFT : u8 = 4;
+const RIGHT : u8 = 8;
+
+// A grid of characters, with the wires drawn on it as the directions they leave each cell in.
+struct Canvas {
+    chars: Vec<Vec<Option<char>>>,
+    wires: Vec<Vec<u8>>
+}
+
+impl Canvas {
+    fn new(width : usize, height : usize) -> Canvas {
+        Canvas { chars: vec![vec![None; width]; height], wires: vec![vec![0; width]; height] }
+    }
+
+    fn vertical(&mut self, col : usize, from : usize, to : usize) {
+        let (top, bottom) = (from.min(to), from.max(to));
+        for row in top..bottom + 1 {
+            self.wires[row][col] |= if row > top { UP } else { 0 } | if row < bottom { DOWN } else { 0 };
+        }
+    }
+
+    fn horizontal(&mut self, row : usize, from : usize, to : usize) {
+        let (left, right) = (from.min(to), from.max(to));
+        for col in left..right + 1 {
+            self.wires[row][col] |= if col > left { LEFT } else { 0 } | if col < right { RIGHT } else { 0 };
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for (chars, wires) in self.chars.iter().zip(&self.wires) {
+            let line : String = chars.iter().zip(wires).map(|(chr, wire)| chr.unwrap_or(match *wire {
+                0 => ' ',
+                UP | DOWN => '│',
+                LEFT | RIGHT => '─',
+                wire if wire == DOWN | RIGHT => '┌',
+                wire if wire == DOWN | LEFT => '┐',
+                wire if wire == UP | RIGHT => '└',
+                wire if wire == UP | LEFT => '┘',
+                wire if wire == UP | DOWN => '│',
+                wire if wire == LEFT | RIGHT => '─',
+                _ => '┼'
+            })).collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// Draws a net as text, or returns `None` if it has more than `MAX_ASCII_NODES` nodes in use.
+pub fn to_ascii(net : &Net) -> Option<String> {
+    if node_count(net) > MAX_ASCII_NODES {
+        return None;
+    }
+    let count = net.nodes.len() / NODE_SIZE;
+    let mut freed = vec![false; count];
+    for node in &net.reuse {
+        freed[*node as usize] = true;
+    }
+    let depths = depths(net);
+    let deepest = depths.iter().flatten().max().map_or(0, |depth| *depth as usize);
+    let mut rows : Vec<Vec<u32>> = vec![Vec::new(); deepest + 1];
+    let mut lost = Vec::new();
+    for node in (0..count as u32).filter(|node| !freed[*node as usize]) {
+        match depths[node as usize] {
+            Some(depth) => rows[depth as usize].push(node),
+            None => lost.push(node)
+        }
+    }
+    if !lost.is_empty() {
+        rows.push(lost);
+    }
+
+    // Boxes are as wide as their labels, rounded up so that main ports, at their middle, fall on
+    // odd columns, and auxiliary ports on even ones: the ports facing a gap never share a column.
+    let labels : Vec<String> = (0..count as u32).map(|node| format!("{} {}", label(net, node), node)).collect();
+    let mut places = vec![(0, 0, 0); count];
+    let mut width = 0;
+    for (idx, row) in rows.iter().enumerate() {
+        let mut col = 0;
+        for node in row {
+            let size = (labels[*node as usize].chars().count() + 4) | 3;
+            places[*node as usize] = (idx, col, size);
+            width = width.max(col + size);
+            col += size + 3;
+        }
+    }
+    // The column of a port, and the gap it faces: gap `n` is the one above row `n`.
+    let port_at = |ptr : Link| {
+        let (row, col, size) = places[addr(ptr) as usize];
+        match port(ptr) {
+            _ if kind(net, addr(ptr)) == ROOT => (col + (size - 3) / 2, row + 1),
+            PORT_MAIN => (col + (size - 1) / 2, row),
+            PORT_AUX1 => (col + 2, row + 1),
+            _ => (col + size - 3, row + 1)
+        }
+    };
+
+    // Gives each wire its lines in the gaps, and a column on the right if it needs one.
+    let mut lines = vec![0; rows.len() + 1];
+    let mut wires = Vec::new();
+    let mut around = 0;
+    for node in (0..count as u32).filter(|node| !freed[*node as usize]) {
+        for slot in 0..3 {
+            let (from, to) = (link(node, slot), enter(net, link(node, slot)));
+            let looped = addr(to) == node && slot != PORT_MAIN;
+            if from > to || (looped && kind(net, node) != CON && kind(net, node) != FAN) {
+                continue;
+            }
+            let (gap_a, gap_b) = (port_at(from).1, port_at(to).1);
+            let line_a = lines[gap_a];
+            lines[gap_a] += 1;
+            let line_b = if gap_a == gap_b { line_a } else { lines[gap_b] += 1; lines[gap_b] - 1 };
+            let column = if gap_a == gap_b { None } else { around += 1; Some(around - 1) };
+            wires.push((from, to, line_a, line_b, column));
+        }
+    }
+
+    // Lays the gaps and rows out top to bottom. A gap with wires has a blank line on either side;
+    // one without is a blank line, unless it's above or below all the rows.
+    let gaps : Vec<usize> = lines.iter().enumerate().map(|(idx, count)| match *count {
+        0 if idx == 0 || idx == rows.len() => 0,
+        0 => 1,
+        count => count + 2
+    }).collect();
+    let mut tops = Vec::new();
+    let mut height = 0;
+    for gap in &gaps {
+        tops.push(height);
+        height += gap + 3;
+    }
+    height -= 3;
+    let total = if around == 0 { width } else { width + 2 * around };
+    let mut canvas = Canvas::new(total, height);
+    let edge = |ptr : Link| {
+        let (row, _, _) = places[addr(ptr) as usize];
+        let (_, gap) = port_at(ptr);
+        if gap == row { tops[row] + gaps[row] } else { tops[row] + gaps[row] + 2 }
+    };
+    for (from, to, line_a, line_b, column) in &wires {
+        let ((col_a, gap_a), (col_b, gap_b)) = (port_at(*from), port_at(*to));
+        let (row_a, row_b) = (tops[gap_a] + 1 + line_a, tops[gap_b] + 1 + line_b);
+        canvas.vertical(col_a, edge(*from), row_a);
+        match column {
+            None => canvas.horizontal(row_a, col_a, col_b),
+            Some(column) => {
+                let col = width + 1 + 2 * column;
+                canvas.horizontal(row_a, col_a, col);
+                canvas.vertical(col, row_a, row_b);
+                canvas.horizontal(row_b, col, col_b);
+            }
+        }
+        canvas.vertical(col_b, row_b, edge(*to));
+    }
+
+    // Draws the boxes over the wires, then where the wires meet them.
+    let active : BTreeSet<u32> = active_pairs(net).into_iter().flat_map(|(a, b)| [a, b]).collect();
+    for row in &rows {
+        for node in row {
+            let (row, col, size) = places[*node as usize];
+            let top = tops[row] + gaps[row];
+            let [tl, tr, bl, br, across, down] = if active.contains(node) { ['╔', '╗', '╚', '╝', '═', '║'] } else { ['┌', '┐', '└', '┘', '─', '│'] };
+            let name : Vec<char> = format!("{:^width$}", labels[*node as usize], width = size - 2).chars().collect();
+            for idx in 0..size {
+                let (first, last) = (idx == 0, idx + 1 == size);
+                canvas.chars[top][col + idx] = Some(if first { tl } else if last { tr } else { across });
+                canvas.chars[top + 1][col + idx] = Some(if first || last { down } else { name[idx - 1] });
+                canvas.chars[top + 2][col + idx] = Some(if first { bl } else if last { br } else { across });
+            }
+        }
+    }
+    for (from, to, _, _, _) in &wires {
+        for ptr in [*from, *to] {
+            let (col, gap) = port_at(ptr);
+            let (row, _, _) = places[addr(ptr) as usize];
+            let double = active.contains(&addr(ptr));
+            canvas.chars[edge(ptr)][col] = Some(match (gap == row, double) {
+                (true, false) => '┴',
+                (true, true) => '╧',
+                (false, false) => '┬',
+                (false, true) => '╤'
+            });
+        }
+    }
+    Some(canvas.render())
+}
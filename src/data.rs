@@ -0,0 +1,408 @@
+// Builders for terms, and encodings of common data as terms.
+//
+// Numerals and lists use the Church encoding. Since variables are affine, every function the
+// encoding applies more than once is copied with a chain of `=` bindings:
+//
+// - The numeral `n` is `\s \z /s0 /s1 ... /s(n-1) z`, where `s0` ... `s(n-1)` are copies of `s`.
+// - The list `[x0, x1, ... x(n-1)]` is `\c \n //c0 x0 //c1 x1 ... //c(n-1) x(n-1) n`, where
+//   `c0` ... `c(n-1)` are copies of `c`. Folding a list is applying it: `//list f init`.
+// - Strings and binary data are lists of numerals, one per byte.
+// - A pair of numerals is the Church pair `\p //p a b`, not `| a b`: a program takes it apart by
+//   applying it to a function of two arguments, like `/pair \a \b ...`.
+// - Binary numbers are `\o \i \e` applied bit by bit, least significant bit outermost: `o` is a
+//   zero bit, `i` a one bit and `e` the end. So 6 (110) is `\o \i \e /o /i /i e`, and 0 is
+//   `\o \i \e e`. Encoded numbers have no leading zeros; decoding ignores them.
+
+use prelude::*;
+use term::*;
+use net::*;
+use error::{EncodeError, ReadbackError};
+use term::Term::{*};
+
+pub fn var(nam : &str) -> Term {
+    Var{nam: nam.as_bytes().to_vec()}
+}
+
+pub fn lam(nam : &str, bod : Term) -> Term {
+    Lam{nam: nam.as_bytes().to_vec(), bod: Box::new(bod)}
+}
+
+pub fn app(fun : Term, arg : Term) -> Term {
+    App{fun: Box::new(fun), arg: Box::new(arg)}
+}
+
+pub fn par(fst : Term, snd : Term) -> Term {
+    Par{fst: Box::new(fst), snd: Box::new(snd)}
+}
+
+pub fn dup(fst : &str, snd : &str, val : Term, nxt : Term) -> Term {
+    Dup{fst: fst.as_bytes().to_vec(), snd: snd.as_bytes().to_vec(), val: Box::new(val), nxt: Box::new(nxt)}
+}
+
+// The name of the `idx`-th of `len` copies of the variable `fun`.
+fn copy_name(fun : &str, idx : usize, len : usize) -> String {
+    if len == 1 { fun.to_string() } else { format!("{}{}", fun, idx) }
+}
+
+// Wraps `body`, which uses `len` copies of the variable `fun`, in the chain of `=` bindings that
+// makes them. Each binding splits the remaining copies: `= fun0 fun_0 fun`, `= fun1 fun_1 fun_0`...
+fn share(fun : &str, len : usize, body : Term) -> Term {
+    let rest = |idx : usize| format!("{}_{}", fun, idx);
+    let mut body = body;
+    for idx in (0..len.saturating_sub(1)).rev() {
+        let src = if idx == 0 { fun.to_string() } else { rest(idx - 1) };
+        let snd = if idx == len - 2 { copy_name(fun, len - 1, len) } else { rest(idx) };
+        body = dup(&copy_name(fun, idx, len), &snd, var(&src), body);
+    }
+    body
+}
+
+// Builds `n` applications of copies of the variable `fun` around `end`: `/fun0 /fun1 ... end`.
+fn applications(fun : &str, args : Vec<Option<Term>>, end : Term) -> Term {
+    let len = args.len();
+    let mut body = end;
+    for (idx, arg) in args.into_iter().enumerate().rev() {
+        let fun = var(&copy_name(fun, idx, len));
+        body = match arg {
+            Some(arg) => app(app(fun, arg), body),
+            None => app(fun, body)
+        };
+    }
+    share(fun, len, body)
+}
+
+// Encodes a natural number as a Church numeral.
+pub fn nat_of(n : u64) -> Term {
+    let args = (0..n).map(|_| None).collect();
+    lam("s", lam("z", applications("s", args, var("z"))))
+}
+
+// Encodes a list of terms as a Church list. Since binders are global, the items are expected to
+// be closed terms, and their names are namespaced apart.
+pub fn list_of(items : Vec<Term>) -> Term {
+    let mut idx = 0;
+    let args = items.iter().map(|item| Some(copy(b"x", &mut idx, item))).collect();
+    name_copies(&lam("c", lam("n", applications("c", args, var("n")))))
+}
+
+// Encodes a natural number in binary.
+pub fn bits_of(n : u64) -> Term {
+    let mut bits = Vec::new();
+    let mut n = n;
+    while n > 0 {
+        bits.push(n & 1 == 1);
+        n >>= 1;
+    }
+    let ones = bits.iter().filter(|bit| **bit).count();
+    let zeros = bits.len() - ones;
+    let mut body = var("e");
+    let (mut one, mut zero) = (ones, zeros);
+    for bit in bits.iter().rev() {
+        body = if *bit {
+            one -= 1;
+            app(var(&copy_name("i", one, ones)), body)
+        } else {
+            zero -= 1;
+            app(var(&copy_name("o", zero, zeros)), body)
+        };
+    }
+    lam("o", lam("i", lam("e", share("o", zeros, share("i", ones, body)))))
+}
+
+// Encodes bytes as a Church list of Church numerals, one per byte.
+pub fn chars_of(bytes : &[u8]) -> Term {
+    list_of(bytes.iter().map(|byte| nat_of(*byte as u64)).collect())
+}
+
+// Applies the term of a net to bytes, encoded as `chars_of` encodes them, but built in the net
+// node by node rather than as a term, so large inputs take no more room than their nodes. Each
+// numeral is encoded once, as a fragment, and grafted for every byte it stands for.
+pub fn apply_bytes(net : &mut Net, bytes : &[u8]) {
+    let mut numerals : Vec<Option<NetFragment>> = vec![None; 256];
+    // The list's two lambdas, `\c \n`.
+    let (cons, nil) = (new_node(net, CON), new_node(net, CON));
+    connect(net, link(cons, PORT_AUX2), link(nil, PORT_MAIN));
+    // The copies of `c` left to use, and the port the next item's application goes to.
+    let mut copies = link(cons, PORT_AUX1);
+    let mut up = link(nil, PORT_AUX2);
+    for (idx, byte) in bytes.iter().enumerate() {
+        let copy = if idx + 1 == bytes.len() {
+            copies
+        } else {
+            let fan = new_node(net, FAN);
+            connect(net, copies, link(fan, PORT_MAIN));
+            copies = link(fan, PORT_AUX2);
+            link(fan, PORT_AUX1)
+        };
+        // `//c_idx x_idx rest`.
+        let (outer, inner) = (new_node(net, CON), new_node(net, CON));
+        connect(net, link(inner, PORT_MAIN), copy);
+        let numeral = numerals[*byte as usize].get_or_insert_with(|| NetFragment::from_net(&to_net(&nat_of(*byte as u64)).unwrap()));
+        let item = net.graft(numeral);
+        connect(net, link(inner, PORT_AUX1), item);
+        connect(net, link(inner, PORT_AUX2), link(outer, PORT_MAIN));
+        connect(net, up, link(outer, PORT_AUX2));
+        up = link(outer, PORT_AUX1);
+    }
+    if bytes.is_empty() {
+        let era = new_node(net, ERA);
+        connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+        connect(net, copies, link(era, PORT_MAIN));
+    }
+    connect(net, up, link(nil, PORT_AUX1));
+    // The application of the term to the list, in place of the term.
+    let app = new_node(net, CON);
+    let term = enter(net, 0);
+    connect(net, link(app, PORT_MAIN), term);
+    connect(net, link(app, PORT_AUX1), link(cons, PORT_MAIN));
+    connect(net, link(app, PORT_AUX2), 0);
+}
+
+// Encodes a string as the list of its UTF-8 bytes.
+pub fn string_of(text : &str) -> Term {
+    chars_of(text.as_bytes())
+}
+
+// Encodes an ASCII string as a list of numerals, failing on the first non-ASCII character.
+pub fn ascii_list(text : &str) -> Result<Term, EncodeError> {
+    match text.char_indices().find(|(_, chr)| !chr.is_ascii()) {
+        Some((index, chr)) => Err(EncodeError::NonAscii{index, chr}),
+        None => Ok(chars_of(text.as_bytes()))
+    }
+}
+
+// Encodes two natural numbers as a Church pair of numerals.
+pub fn pair_of_nats(a : u64, b : u64) -> Term {
+    let mut idx = 0;
+    let a = copy(b"a", &mut idx, &nat_of(a));
+    name_copies(&lam("p", app(app(var("p"), a), copy(b"b", &mut idx, &nat_of(b)))))
+}
+
+// Maps a closed function over a Church list, as a fold that conses `/f x` onto the rest:
+// `\c \n //list \x \acc //c /f x acc n`. The list's lets copy the step, and with it `f` and the
+// `c` of the new list. Since all fans are alike, those copies mustn't meet lets of their own: `f`
+// must have none, and the new list must be taken apart by a function without any, like one that
+// builds constructors. The items may have lets. Until then, the normal form shares the step's body
+// between the items, so it doesn't read back as a list.
+pub fn church_map(f : Term, list : Term) -> Term {
+    let mut idx = 0;
+    let list = copy(b"l", &mut idx, &list);
+    let f = copy(b"f", &mut idx, &f);
+    let step = lam("x", lam("acc", app(app(var("c"), app(f, var("x"))), var("acc"))));
+    name_copies(&lam("c", lam("n", app(app(list, step), var("n")))))
+}
+
+// Folds a Church list from the right with a closed function: `//list f init`.
+pub fn church_fold(f : Term, init : Term, list : Term) -> Term {
+    let mut idx = 0;
+    let list = copy(b"l", &mut idx, &list);
+    let f = copy(b"f", &mut idx, &f);
+    name_copies(&app(app(list, f), copy(b"i", &mut idx, &init)))
+}
+
+// Replaces each variable bound by a `=` with the value it copies, giving a tree without sharing.
+// This is meant for decoding data, not for evaluation: the copies aren't renamed apart.
+fn unshare(term : &Term) -> Term {
+    fn collect(term : &Term, vals : &mut BTreeMap<Vec<u8>, Term>) {
+        match term {
+            Lam{bod, ..} => collect(bod, vals),
+            App{fun, arg} => {
+                collect(fun, vals);
+                collect(arg, vals);
+            },
+            Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                collect(a, vals);
+                collect(b, vals);
+            },
+            Dup{fst, snd, val, nxt} => {
+                vals.insert(fst.clone(), (**val).clone());
+                vals.insert(snd.clone(), (**val).clone());
+                collect(val, vals);
+                collect(nxt, vals);
+            },
+            Var{..} | Ctr{..} | Set => {}
+        }
+    }
+    // Each binding is substituted once, then its value is reused, so long chains of copies, as
+    // large lists have, take time in their length.
+    fn subst(term : &Term, vals : &BTreeMap<Vec<u8>, Term>, seen : &mut BTreeSet<Vec<u8>>, done : &mut BTreeMap<Vec<u8>, Term>) -> Term {
+        match term {
+            Lam{nam, bod} => Lam{nam: nam.clone(), bod: Box::new(subst(bod, vals, seen, done))},
+            App{fun, arg} => App{fun: Box::new(subst(fun, vals, seen, done)), arg: Box::new(subst(arg, vals, seen, done))},
+            Par{fst, snd} => Par{fst: Box::new(subst(fst, vals, seen, done)), snd: Box::new(subst(snd, vals, seen, done))},
+            Dup{nxt, ..} | Era{nxt, ..} => subst(nxt, vals, seen, done),
+            // A binding that (indirectly) copies itself is left as is.
+            Var{nam} => match (vals.get(nam), done.get(nam)) {
+                (_, Some(val)) => val.clone(),
+                (Some(val), None) if !seen.contains(nam) => {
+                    seen.insert(nam.clone());
+                    let val = subst(val, vals, seen, done);
+                    seen.remove(nam);
+                    done.insert(nam.clone(), val.clone());
+                    val
+                },
+                _ => term.clone()
+            },
+            Ctr{..} | Set => term.clone()
+        }
+    }
+    let mut vals = BTreeMap::new();
+    collect(term, &mut vals);
+    subst(term, &vals, &mut BTreeSet::new(), &mut BTreeMap::new())
+}
+
+// Decodes a Church numeral, as built by `nat_of` or read back from a net.
+pub fn nat_to(term : &Term) -> Option<u64> {
+    match unshare(term) {
+        Lam{nam: s, bod} => match *bod {
+            Lam{nam: z, bod} => {
+                let mut count = 0;
+                let mut body = *bod;
+                loop {
+                    match body {
+                        App{fun, arg} => match *fun {
+                            Var{ref nam} if *nam == s => {
+                                count += 1;
+                                body = *arg;
+                            },
+                            _ => return None
+                        },
+                        Var{ref nam} if *nam == z => return Some(count),
+                        _ => return None
+                    }
+                }
+            },
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// Decodes a Church list, as built by `list_of` or read back from a net, returning its elements.
+pub fn list_to(term : &Term) -> Option<Vec<Term>> {
+    match unshare(term) {
+        Lam{nam: c, bod} => match *bod {
+            Lam{nam: n, bod} => {
+                let mut items = Vec::new();
+                let mut body = *bod;
+                loop {
+                    match body {
+                        App{fun, arg: rest} => match *fun {
+                            App{fun, arg: item} => match *fun {
+                                Var{ref nam} if *nam == c => {
+                                    items.push(*item);
+                                    body = *rest;
+                                },
+                                _ => return None
+                            },
+                            _ => return None
+                        },
+                        Var{ref nam} if *nam == n => return Some(items),
+                        _ => return None
+                    }
+                }
+            },
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// Decodes a binary number, as built by `bits_of` or read back from a net. Fails if it doesn't fit
+// in 64 bits.
+pub fn bits_to(term : &Term) -> Option<u64> {
+    match unshare(term) {
+        Lam{nam: o, bod} => match *bod {
+            Lam{nam: i, bod} => match *bod {
+                Lam{nam: e, bod} => {
+                    let mut n : u64 = 0;
+                    let mut idx = 0;
+                    let mut body = *bod;
+                    loop {
+                        match body {
+                            App{fun, arg} => {
+                                match *fun {
+                                    Var{ref nam} if *nam == o => {},
+                                    Var{ref nam} if *nam == i && idx < 64 => n |= 1 << idx,
+                                    _ => return None
+                                }
+                                idx += 1;
+                                body = *arg;
+                            },
+                            Var{ref nam} if *nam == e => return Some(n),
+                            _ => return None
+                        }
+                    }
+                },
+                _ => None
+            },
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// Decodes a list of numerals as bytes, failing if an element isn't a numeral below 256.
+pub fn bytes_of(term : &Term) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_bytes(term, &mut |byte| bytes.push(byte)).ok()?;
+    Some(bytes)
+}
+
+// Decodes a list of numerals as bytes like `bytes_of`, passing each to `out` as soon as it's
+// decoded, and returns how many there were. If the term isn't such a list, the error says where it
+// stops being one; the bytes before that have already been passed.
+pub fn write_bytes(term : &Term, out : &mut dyn FnMut(u8)) -> Result<usize, ReadbackError> {
+    let shape = |at : String, expected, found : &Term| {
+        let (code, _) = pretty_limited(found, OutputLimits { depth: Some(4), size: Some(24) });
+        ReadbackError::Shape { at, expected, found: String::from_utf8_lossy(&code).into_owned() }
+    };
+    let (c, n, mut body) = match unshare(term) {
+        Lam{nam: c, bod} => match *bod {
+            Lam{nam: n, bod} => (c, n, *bod),
+            bod => return Err(shape("the term".to_string(), "a list, `\\c \\n ...`", &Lam{nam: c, bod: Box::new(bod)}))
+        },
+        term => return Err(shape("the term".to_string(), "a list, `\\c \\n ...`", &term))
+    };
+    let tail = |count : usize| if count == 0 { "the body of the list".to_string() } else { format!("the rest of the list after byte {}", count - 1) };
+    let mut count = 0;
+    loop {
+        body = match body {
+            App{fun, arg: rest} => match *fun {
+                App{fun, arg: item} if matches!(*fun, Var{ref nam} if *nam == c) => {
+                    match nat_to(&item) {
+                        Some(byte) if byte < 256 => out(byte as u8),
+                        _ => return Err(shape(format!("byte {}", count), "a numeral below 256", &item))
+                    }
+                    count += 1;
+                    *rest
+                },
+                fun => return Err(shape(tail(count), "`//c byte rest` or `n`", &App{fun: Box::new(fun), arg: rest}))
+            },
+            Var{ref nam} if *nam == n => return Ok(count),
+            body => return Err(shape(tail(count), "`//c byte rest` or `n`", &body))
+        };
+    }
+}
+
+// Decodes a list of numerals as a UTF-8 string.
+pub fn string_to(term : &Term) -> Option<String> {
+    String::from_utf8(bytes_of(term)?).ok()
+}
+
+// Decodes a Church pair of numerals, as built by `pair_of_nats` or read back from a net.
+pub fn pair_to_nats(term : &Term) -> Option<(u64, u64)> {
+    match unshare(term) {
+        Lam{nam: p, bod} => match *bod {
+            App{fun, arg: snd} => match *fun {
+                App{fun, arg: fst} => match *fun {
+                    Var{ref nam} if *nam == p => Some((nat_to(&fst)?, nat_to(&snd)?)),
+                    _ => None
+                },
+                _ => None
+            },
+            _ => None
+        },
+        _ => None
+    }
+}
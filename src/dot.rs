@@ -0,0 +1,101 @@
+// Drawing nets with Graphviz. Each node in use is a vertex, labeled with its kind, and each wire an
+// edge between the two nodes whose ports it links, labeled with the ports, as `0-2`. Wires have no
+// direction, so the graph doesn't either, and each is drawn once, from its lower port.
+
+use prelude::*;
+use net::*;
+
+// How `to_dot` draws a net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DotOptions {
+    // Ranks nodes by their distance from the root, in wires, so Graphviz lays the net out top to
+    // bottom from it, the way lazy reduction walks it. Nodes the root can't reach aren't ranked.
+    pub rank: bool,
+    // The color nodes of each kind are filled with, as Graphviz names it. A kind listed twice
+    // takes the later color, so pushing one overrides the defaults; kinds not listed are unfilled.
+    pub colors: Vec<(Kind, String)>
+}
+
+// ERAs are gray, CONs blue and FANs orange.
+impl Default for DotOptions {
+    fn default() -> DotOptions {
+        let colors = [(Kind::Era, "gray"), (Kind::Con, "lightblue"), (Kind::Fan, "orange")];
+        DotOptions { rank: false, colors: colors.iter().map(|(kind, color)| (*kind, color.to_string())).collect() }
+    }
+}
+
+// The label of a node: its kind, or its name for a constructor.
+pub(crate) fn label(net : &Net, node : u32) -> String {
+    match net.kind(node) {
+        Kind::Ctr{idx} => String::from_utf8_lossy(&net.ctrs[idx as usize].0).into_owned(),
+        kind => format!("{:?}", kind).to_uppercase()
+    }
+}
+
+// Draws a net in Graphviz's format. Nodes are named by address, as `n3`; freed nodes are left out.
+pub fn to_dot(net : &Net, opts : &DotOptions) -> String {
+    let mut freed = vec![false; net.nodes.len() / NODE_SIZE];
+    for node in &net.reuse {
+        freed[*node as usize] = true;
+    }
+    let nodes : Vec<u32> = (0..freed.len() as u32).filter(|node| !freed[*node as usize]).collect();
+    let mut dot = String::from("graph net {\n");
+    for node in &nodes {
+        match opts.colors.iter().rev().find(|(kind, _)| *kind == net.kind(*node)) {
+            Some((_, color)) => dot.push_str(&format!("  n{} [label=\"{}\", style=filled, fillcolor=\"{}\"];\n", node, label(net, *node), color)),
+            None => dot.push_str(&format!("  n{} [label=\"{}\"];\n", node, label(net, *node)))
+        }
+    }
+    for node in &nodes {
+        for slot in 0..3 {
+            let (from, to) = (link(*node, slot), enter(net, link(*node, slot)));
+            if from <= to {
+                dot.push_str(&format!("  n{} -- n{} [label=\"{}-{}\"];\n", node, addr(to), slot, port(to)));
+            }
+        }
+    }
+    if opts.rank {
+        let depths = depths(net);
+        let deepest = depths.iter().flatten().max().cloned().unwrap_or(0);
+        for dist in 0..deepest + 1 {
+            let level : Vec<String> = nodes.iter()
+                .filter(|node| depths[**node as usize] == Some(dist))
+                .map(|node| format!("n{};", node))
+                .collect();
+            dot.push_str(&format!("  {{ rank=same; {} }}\n", level.join(" ")));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+// Reduces a net, drawing it in `dir` before the first rewrite and after every `stride` rewrites, as
+// `step_000.dot`, `step_001.dot`..., and once more in normal form if the last rewrite fell between
+// strides. Assembled in order, the drawings animate the reduction. Returns how many were written.
+#[cfg(feature = "std")]
+pub fn write_dot_animation(net : &mut Net, dir : &std::path::Path, stride : u32, opts : &DotOptions) -> std::io::Result<usize> {
+    let stride = stride.max(1);
+    let write = |net : &Net, frame : usize| std::fs::write(dir.join(format!("step_{:03}.dot", frame)), to_dot(net, opts));
+    std::fs::create_dir_all(dir)?;
+    write(net, 0)?;
+    let mut frames = 1;
+    let mut rules : u32 = 0;
+    let failed = core::cell::RefCell::new(None);
+    let (stats, _) = reduce_steps(net, u32::MAX, 1, &mut |_| failed.borrow().is_none(), &mut |net, _, _, _| {
+        rules += 1;
+        if rules.is_multiple_of(stride) {
+            match write(net, frames) {
+                Ok(()) => frames += 1,
+                Err(err) => *failed.borrow_mut() = Some(err)
+            }
+        }
+    });
+    if let Some(err) = failed.into_inner() {
+        return Err(err);
+    }
+    if !stats.rules.is_multiple_of(stride) {
+        write(net, frames)?;
+        frames += 1;
+    }
+    Ok(frames)
+}
@@ -0,0 +1,305 @@
+// Errors reported by the library. Each stage of the pipeline has its own error type, and `SicError`
+// wraps all of them (plus I/O), so a whole evaluation can be written with `?`.
+
+use alloc::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::io;
+use prelude::*;
+use term::REC_DEPTH;
+
+// The source code isn't a well-formed term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    // Byte offset where parsing failed.
+    pub index: usize,
+    pub message: String
+}
+
+impl ParseError {
+    // Builds an error at the start of `rest`, the code left unparsed. Its `index` holds the length
+    // of `rest` until `located` converts it to an offset.
+    pub fn new(rest : &[u8], message : &str) -> ParseError {
+        ParseError { index: rest.len(), message: message.to_string() }
+    }
+
+    // Converts the index from "bytes left" to an offset into `code`, the whole source.
+    pub fn located(self, code : &[u8]) -> ParseError {
+        ParseError { index: code.len() - self.index, message: self.message }
+    }
+}
+
+// A term that can't be encoded as a net: variables must be bound, and used at most once, and each
+// constructor must be given as many fields as where it first occurs, and at most two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AffinityError {
+    Unbound(Vec<u8>),
+    Duplicated(Vec<u8>),
+    Arity{nam: Vec<u8>, arity: u32, args: u32}
+}
+
+// A net whose nodes aren't consistently wired.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetError {
+    // The nodes vector isn't made of whole nodes, or lacks the root.
+    Truncated(usize),
+    // A port points outside of the nodes vector.
+    OutOfBounds{from: u32, to: u32},
+    // A port points to another port that doesn't point back.
+    Asymmetric{from: u32, to: u32},
+    // A node has a kind that isn't ERA, CON, FAN, FREE, or one of the net's constructors.
+    UnknownKind{node: u32, kind: u32},
+    // The reuse list holds an address that isn't a node.
+    BadReuse(u32),
+    // A serialized net has a layout version this library can't read.
+    Version(u32),
+    // A serialized net whose checksum doesn't match its contents.
+    Checksum{stored: u32, computed: u32},
+    // Bytes that aren't laid out like a serialized net.
+    BadEncoding(&'static str)
+}
+
+// A net that can't be read back as a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReadbackError {
+    UnknownKind{node: u32, kind: u32},
+    // A FREE node with no name, read by `from_net` or missing from the names given to
+    // `from_net_open`.
+    Unnamed(u32),
+    // A constructor reached through one of its fields, which only a net reduced unsoundly has.
+    Field(u32),
+    // A normal form that doesn't encode the data it was expected to (say, "a numeral").
+    Decode(&'static str),
+    // A normal form that isn't a list of bytes, as `data::write_bytes` found: the part of it `at`
+    // isn't `expected`, but `found`, printed with its depth limited.
+    Shape{at: String, expected: &'static str, found: String},
+    // A term that contains itself, through variables used outside of their lambdas, which
+    // `term::eval_machine` can't read back.
+    Cyclic
+}
+
+// A resource limit was reached before the net got to normal form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    Rewrites(u32),
+    // A definition was attributed more rewrites than its `%fuel` (see `fuel`).
+    Fuel{nam: Vec<u8>, fuel: u32},
+    // Expanding the lets of a term (see `term::expand_dups`) would make it larger than this.
+    Expansion(u64),
+    // The net is larger than this, too large to read back after each rewrite (see
+    // `term::trace_terms`).
+    Traced(u32),
+    // The normal form holds what a recursive definition is unfolded to past its last unfolding (see
+    // `term::too_deep`): the recursion went deeper than `term::REC_DEPTH`.
+    Recursion(Vec<u8>)
+}
+
+// Data that can't be encoded as a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    // A character outside of ASCII, at the given byte offset.
+    NonAscii{index: usize, chr: char}
+}
+
+// A program that can't be translated to another language.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportError {
+    // Sets have no counterpart.
+    Set,
+    // Neither do constructors, which SIC applies like functions.
+    Ctr(Vec<u8>),
+    // A name the target can't spell.
+    Name(Vec<u8>),
+    // A variable that isn't bound by an enclosing binder, nor a definition.
+    Unbound(Vec<u8>),
+    // A definition that is given twice.
+    Redefined(Vec<u8>)
+}
+
+// Any error of the library. Without `std`, there are no I/O errors, and errors don't implement
+// `std::error::Error`.
+#[derive(Debug)]
+pub enum SicError {
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    Parse(ParseError),
+    Affinity(AffinityError),
+    Net(NetError),
+    Readback(ReadbackError),
+    Limit(LimitError),
+    Encode(EncodeError),
+    Export(ExportError)
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse error at byte {}: {}", self.index, self.message)
+    }
+}
+
+impl fmt::Display for AffinityError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AffinityError::Unbound(nam) => write!(f, "unbound variable: {}", String::from_utf8_lossy(nam)),
+            AffinityError::Duplicated(nam) => write!(f, "variable used more than once: {}", String::from_utf8_lossy(nam)),
+            AffinityError::Arity{nam, arity, args} => write!(f, "constructor {} has {} fields, but is applied to {} arguments", String::from_utf8_lossy(nam), arity, args)
+        }
+    }
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::Truncated(len) => write!(f, "malformed net: {} words don't make whole nodes", len),
+            NetError::OutOfBounds{from, to} => write!(f, "malformed net: port {} points to missing port {}", from, to),
+            NetError::Asymmetric{from, to} => write!(f, "malformed net: port {} points to port {}, which doesn't point back", from, to),
+            NetError::UnknownKind{node, kind} => write!(f, "malformed net: node {} has unknown kind {}", node, kind),
+            NetError::BadReuse(node) => write!(f, "malformed net: reuse list holds missing node {}", node),
+            NetError::Version(version) => write!(f, "unsupported net version {}", version),
+            NetError::Checksum{stored, computed} => write!(f, "corrupted net: checksum {:08x} doesn't match contents ({:08x})", stored, computed),
+            NetError::BadEncoding(reason) => write!(f, "malformed net file: {}", reason)
+        }
+    }
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadbackError::UnknownKind{node, kind} => write!(f, "can't read back node {} of unknown kind {}", node, kind),
+            ReadbackError::Unnamed(node) => write!(f, "can't read back free variable {}: it has no name", node),
+            ReadbackError::Field(node) => write!(f, "can't read back constructor {}: it was reached through a field", node),
+            ReadbackError::Decode(expected) => write!(f, "the normal form isn't {}", expected),
+            ReadbackError::Shape{at, expected, found} => write!(f, "the normal form isn't a list of bytes: {} should be {}, but is `{}`", at, expected, found),
+            ReadbackError::Cyclic => write!(f, "can't read back a term that contains itself")
+        }
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitError::Rewrites(limit) => write!(f, "reached the limit of {} rewrites", limit),
+            LimitError::Fuel{nam, fuel} => write!(f, "definition `{}` exceeded its fuel of {} interactions", String::from_utf8_lossy(nam), fuel),
+            LimitError::Expansion(limit) => write!(f, "expanding the lets would take more than {} nodes", limit),
+            LimitError::Traced(limit) => write!(f, "the net has more than {} nodes, too many to read back after each rewrite", limit),
+            LimitError::Recursion(nam) => write!(f, "recursive definition `{}` went deeper than its {} unfoldings", String::from_utf8_lossy(nam), REC_DEPTH)
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::NonAscii{index, chr} => write!(f, "non-ASCII character {:?} at byte {}", chr, index)
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Set => write!(f, "can't export a set (`*`)"),
+            ExportError::Ctr(nam) => write!(f, "can't export constructor {}", String::from_utf8_lossy(nam)),
+            ExportError::Name(nam) => write!(f, "can't export the name {}", String::from_utf8_lossy(nam)),
+            ExportError::Unbound(nam) => write!(f, "can't export variable {}, bound outside of its scope", String::from_utf8_lossy(nam)),
+            ExportError::Redefined(nam) => write!(f, "can't export {}, defined more than once", String::from_utf8_lossy(nam))
+        }
+    }
+}
+
+impl fmt::Display for SicError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            SicError::Io(err) => write!(f, "{}", err),
+            SicError::Parse(err) => write!(f, "{}", err),
+            SicError::Affinity(err) => write!(f, "{}", err),
+            SicError::Net(err) => write!(f, "{}", err),
+            SicError::Readback(err) => write!(f, "{}", err),
+            SicError::Limit(err) => write!(f, "{}", err),
+            SicError::Encode(err) => write!(f, "{}", err),
+            SicError::Export(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ParseError {}
+#[cfg(feature = "std")]
+impl Error for AffinityError {}
+#[cfg(feature = "std")]
+impl Error for NetError {}
+#[cfg(feature = "std")]
+impl Error for ReadbackError {}
+#[cfg(feature = "std")]
+impl Error for LimitError {}
+#[cfg(feature = "std")]
+impl Error for EncodeError {}
+#[cfg(feature = "std")]
+impl Error for ExportError {}
+
+#[cfg(feature = "std")]
+impl Error for SicError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SicError::Io(err) => Some(err),
+            SicError::Parse(err) => Some(err),
+            SicError::Affinity(err) => Some(err),
+            SicError::Net(err) => Some(err),
+            SicError::Readback(err) => Some(err),
+            SicError::Limit(err) => Some(err),
+            SicError::Encode(err) => Some(err),
+            SicError::Export(err) => Some(err)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for SicError {
+    fn from(err : io::Error) -> SicError {
+        SicError::Io(err)
+    }
+}
+
+impl From<ParseError> for SicError {
+    fn from(err : ParseError) -> SicError {
+        SicError::Parse(err)
+    }
+}
+
+impl From<AffinityError> for SicError {
+    fn from(err : AffinityError) -> SicError {
+        SicError::Affinity(err)
+    }
+}
+
+impl From<NetError> for SicError {
+    fn from(err : NetError) -> SicError {
+        SicError::Net(err)
+    }
+}
+
+impl From<ReadbackError> for SicError {
+    fn from(err : ReadbackError) -> SicError {
+        SicError::Readback(err)
+    }
+}
+
+impl From<LimitError> for SicError {
+    fn from(err : LimitError) -> SicError {
+        SicError::Limit(err)
+    }
+}
+
+impl From<EncodeError> for SicError {
+    fn from(err : EncodeError) -> SicError {
+        SicError::Encode(err)
+    }
+}
+
+impl From<ExportError> for SicError {
+    fn from(err : ExportError) -> SicError {
+        SicError::Export(err)
+    }
+}
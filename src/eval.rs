@@ -0,0 +1,496 @@
+// Evaluation of a program in one call, with the options the command line offers. The `sic`
+// binary is written on top of this, so the two behave the same.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use term::*;
+use net::*;
+use module::*;
+use data::*;
+use error::*;
+use fans::*;
+use fuel::*;
+
+// How to present a normal form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decode {
+    // As a term, like `to_string`.
+    Term,
+    // As a Church numeral, printed in decimal.
+    Nat,
+    // As a binary number (see `data::bits_of`), printed in decimal.
+    Bits,
+    // As a list of numerals holding UTF-8 bytes.
+    String,
+    // As a list of numerals holding any bytes, which aren't printed: `data::write_bytes` writes
+    // them out, from the term.
+    Bytes
+}
+
+// What reduces a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    // Its net, with `net::reduce_steps`.
+    Net,
+    // The term itself, with `term::eval_machine`. Only `max_rewrites` limits it, and reaching the
+    // limit is an error, since the machine has no partial result to give.
+    Machine
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalOptions {
+    // Terms the entry point is applied to, in order.
+    pub inputs: Vec<String>,
+    // Definition to evaluate instead of the main term.
+    pub entry: Option<String>,
+    // Limits on the reduction. Reaching one stops it early, with `completed` unset.
+    pub max_rewrites: Option<u32>,
+    pub max_nodes: Option<u32>,
+    pub max_time: Option<Duration>,
+    // Budgets of rewrites for definitions, added to the `%fuel` annotations of the source (see
+    // `fuel`). Running out of one stops the reduction, with `exhausted` set.
+    pub fuel: Vec<(String, u32)>,
+    pub decode: Decode,
+    // Whether to return the stats of the reduction.
+    pub stats: bool,
+    // Whether to trace FAN nodes, to report annihilations the calculus wouldn't do (see `fans`).
+    pub check_fans: bool,
+    // Whether to allow free variables, which reduction leaves in place (see `term::to_net_open`).
+    pub open: bool,
+    // Whether to tidy the result with `term::simplify`.
+    pub simplify: bool,
+    // Whether to expand the lets of the result with `term::expand_dups`, and the largest it may
+    // grow to, in nodes. By default, they're kept.
+    pub expand_dups: Option<u64>,
+    // How much of a term to print. With any limit, terms are laid out by `term::pretty_limited`.
+    pub output_limits: OutputLimits,
+    // Directory where normal forms are kept from one run to the next (see `cache_path`). A program
+    // found there isn't reduced again.
+    pub cache_dir: Option<PathBuf>,
+    pub engine: Engine,
+    // A file to write the size of the net to as it's reduced, and every how many rewrites (see
+    // `SizeTrace`). The machine doesn't reduce nets, so it writes nothing.
+    pub trace_csv: Option<(PathBuf, u32)>,
+    // Bytes the entry point is applied to after the inputs, as a list of numerals (see
+    // `data::chars_of`). Rather than parsed, they're built in the net, so they may be many.
+    pub bytes_input: Option<Vec<u8>>,
+    // Whether only whitespace and comments may follow the main term (see `parse_module_all`). By
+    // default, whatever follows it is ignored.
+    pub strict: bool,
+    // Whether to drop the definitions the entry point doesn't reach before anything is expanded
+    // (see `parse_module_pruned`), so that those that don't parse don't stop it.
+    pub prune: bool
+}
+
+impl Default for EvalOptions {
+    fn default() -> EvalOptions {
+        EvalOptions {
+            inputs: Vec::new(),
+            entry: None,
+            max_rewrites: None,
+            max_nodes: None,
+            max_time: None,
+            fuel: Vec::new(),
+            decode: Decode::Term,
+            stats: false,
+            check_fans: false,
+            open: false,
+            simplify: false,
+            expand_dups: None,
+            output_limits: OutputLimits::default(),
+            cache_dir: None,
+            engine: Engine::Net,
+            trace_csv: None,
+            bytes_input: None,
+            strict: false,
+            prune: false
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalOutcome {
+    // The normal form, or the partially reduced term if a limit was reached.
+    pub term: Term,
+    // The term as `decode` asks. Partial results are always printed as terms.
+    pub printed: String,
+    // How many nodes of the term `output_limits` left out of `printed`.
+    pub elided: u64,
+    // The stats of the reduction, if asked for.
+    pub stats: Option<Stats>,
+    // Whether the term reached normal form.
+    pub completed: bool,
+    // The budget of a definition that ran out, which stopped the reduction.
+    pub exhausted: Option<LimitError>,
+    // Whether the normal form was taken from `cache_dir` rather than reduced. The stats then count
+    // no rewrites, and the nodes of the normal form.
+    pub cached: bool,
+    // With `check_fans`, the annihilations between FANs of different lets, in order. Each may have
+    // made the result wrong.
+    pub unsound: Vec<(FanOrigin, FanOrigin)>
+}
+
+// How often the time limit is checked, in rewrites.
+const TIME_STRIDE : u32 = 1024;
+
+// Version of the encoder and reducer, as far as the normal forms and stats kept in `cache_dir` go.
+// Bump it whenever a program may reach a different normal form, or with different stats.
+pub const CACHE_VERSION : u32 = 1;
+
+// Builds the term a program evaluates: the entry point (a definition, or the main term) applied to
+// the inputs, with every definition inlined, and recursive ones unfolded (see
+// `Module::unfold_recursion`). The main term and the inputs become definitions too,
+// named `#main`, `#0`, `#1`..., so that, like any inlined definition, their binders are renamed
+// apart and can't clash with each other.
+pub fn program(src : &str, opts : &EvalOptions) -> Result<Term, SicError> {
+    Ok(from_string(&program_source(src, opts)?)?)
+}
+
+// The source of the program `program` builds, before definitions are inlined.
+fn program_source(src : &str, opts : &EvalOptions) -> Result<Vec<u8>, SicError> {
+    if opts.strict && !opts.prune {
+        parse_module_all(src.as_bytes())?;
+    }
+    let recursive = parse_module(src.as_bytes()).is_ok_and(|module| !module.cycles().is_empty());
+    if opts.entry.is_none() && opts.inputs.is_empty() && !recursive && !opts.prune {
+        return Ok(src.as_bytes().to_vec());
+    }
+    for input in &opts.inputs {
+        parse_checked(input.as_bytes())?;
+    }
+    let module = if opts.prune {
+        parse_module_pruned(src.as_bytes(), opts.entry.as_ref().map(|entry| entry.as_bytes()), opts.strict)?.0
+    } else {
+        parse_module(src.as_bytes())?
+    };
+    let module = module.unfold_recursion()?;
+    let mut code = format_module(&Module { defs: module.defs, main: None, comments: Vec::new(), notes: Vec::new(), fuel: Vec::new(), params: Vec::new() });
+    code.push(b'\n');
+    let head = match (&opts.entry, &module.main) {
+        (Some(entry), _) => entry.as_bytes().to_vec(),
+        (None, Some(main)) => {
+            code.extend_from_slice(b":#main ");
+            code.append(&mut to_string(main));
+            code.push(b'\n');
+            b"#main".to_vec()
+        },
+        (None, None) => return Err(ParseError::new(b"", "expected a main term or an entry").located(src.as_bytes()).into())
+    };
+    for (idx, input) in opts.inputs.iter().enumerate() {
+        code.extend_from_slice(format!(":#{} {}\n", idx, input).as_bytes());
+    }
+    code.extend(opts.inputs.iter().map(|_| b'/'));
+    code.extend_from_slice(&head);
+    for idx in 0..opts.inputs.len() {
+        code.extend_from_slice(format!(" #{}", idx).as_bytes());
+    }
+    Ok(code)
+}
+
+// Evaluates a program: builds it with `program`, reduces it within the limits, and reads back and
+// decodes the result.
+pub fn eval_str(src : &str, opts : &EvalOptions) -> Result<EvalOutcome, SicError> {
+    let term = program(src, opts)?;
+    if opts.engine == Engine::Machine {
+        return match opts.bytes_input {
+            Some(ref bytes) => run_machine(opts, &app(term, chars_of(bytes))),
+            None => run_machine(opts, &term)
+        };
+    }
+    on_disk(src, opts, || {
+        let mut encoded = to_net_with_fans(&term, opts.open)?;
+        if let Some(ref bytes) = opts.bytes_input {
+            apply_bytes(&mut encoded.0, bytes);
+        }
+        run(src, opts, encoded)
+    })
+}
+
+// Evaluates a program like `eval_str`, but encodes it with `Module::to_net_cached`, so the
+// definitions that didn't change since an earlier call with the same cache aren't encoded again.
+pub fn eval_cached(src : &str, opts : &EvalOptions, cache : &mut NetCache) -> Result<EvalOutcome, SicError> {
+    if !plain(src, opts) {
+        return eval_str(src, opts);
+    }
+    on_disk(src, opts, || {
+        let code = program_source(src, opts)?;
+        let net = parse_module(&code)?.to_net_cached(None, cache)?;
+        run(src, opts, (net, Vec::new(), FreeNames::new(), BinderNames::new()))
+    })
+}
+
+// Whether a program can be evaluated without the names of its binders. Checking fans, open terms
+// and fuel need them, which cached nets and normal forms don't keep, so those are never cached.
+// Nor is what the machine evaluates, whose stats aren't a net's, or a reduction traced to a file,
+// which has to be run to write it.
+fn plain(src : &str, opts : &EvalOptions) -> bool {
+    let annotated = !annotated_fuel(src, opts).is_empty();
+    !(opts.check_fans || opts.open || !opts.fuel.is_empty() || annotated || opts.engine == Engine::Machine || opts.trace_csv.is_some() || opts.bytes_input.is_some())
+}
+
+// The `%fuel` annotations of the definitions of a program, or of those kept if it's pruned. None
+// if it doesn't parse.
+fn annotated_fuel(src : &str, opts : &EvalOptions) -> Vec<(Vec<u8>, u32)> {
+    let module = if opts.prune {
+        parse_module_pruned(src.as_bytes(), opts.entry.as_ref().map(|entry| entry.as_bytes()), false).map(|(module, _)| module)
+    } else {
+        parse_module(src.as_bytes())
+    };
+    module.map_or(Vec::new(), |module| module.fuel)
+}
+
+// The file, in `cache_dir`, that keeps the normal form of a program: named after the canonical
+// hash of the term `program` builds, folded (as FNV-1a) with `CACHE_VERSION`, `NET_VERSION` and
+// whether the result is simplified. None without a directory, or for programs that aren't cached.
+pub fn cache_path(src : &str, opts : &EvalOptions) -> Result<Option<PathBuf>, SicError> {
+    let dir = match opts.cache_dir {
+        Some(ref dir) if plain(src, opts) => dir,
+        _ => return Ok(None)
+    };
+    let mut key = canonical_hash(&program(src, opts)?);
+    for word in [CACHE_VERSION, NET_VERSION, opts.simplify as u32] {
+        for byte in word.to_le_bytes() {
+            key ^= byte as u64;
+            key = key.wrapping_mul(0x100000001b3);
+        }
+    }
+    Ok(Some(dir.join(format!("{:016x}.norm", key))))
+}
+
+// Removes the normal forms kept in a cache directory, returning how many there were.
+pub fn clear_cache(dir : &Path) -> io::Result<u32> {
+    let mut count = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "norm" || ext == "tmp") {
+            fs::remove_file(&path)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+// Evaluates a program with `eval`, unless its normal form is in `cache_dir`, and keeps the normal
+// forms it reaches there. A file holds the stats of the reduction, which tell whether this run's
+// limits would have let it finish, as seven little-endian words in the order of their fields, then
+// the normal form's net, as `net_to_bytes` lays it out. Files are written whole and renamed into
+// place, so a run never reads one half written; one that can't be read is reduced again. The
+// stats are only returned if asked for.
+fn on_disk<F : FnOnce() -> Result<EvalOutcome, SicError>>(src : &str, opts : &EvalOptions, eval : F) -> Result<EvalOutcome, SicError> {
+    let path = cache_path(src, opts)?;
+    let kept = path.as_ref().and_then(|path| fs::read(path).ok()).and_then(|bytes| {
+        if bytes.len() < 28 {
+            return None;
+        }
+        let word = |idx : usize| u32::from_le_bytes([bytes[idx * 4], bytes[idx * 4 + 1], bytes[idx * 4 + 2], bytes[idx * 4 + 3]]);
+        let stats = Stats { loops: word(0), rules: word(1), betas: word(2), dupls: word(3), annis: word(4), erasures: word(5), max_nodes: word(6) };
+        net_from_bytes(&bytes[28..]).ok().map(|net| (stats, net))
+    });
+    // A normal form reached past a limit is one the limits wouldn't have let this run reach.
+    let within = |stats : &Stats| opts.max_rewrites.is_none_or(|max| stats.rules <= max) &&
+        opts.max_nodes.is_none_or(|max| stats.max_nodes <= max);
+    let mut outcome = match kept {
+        Some((stats, net)) if within(&stats) => {
+            let term = tidy(opts, from_net(&net)?)?;
+            let (printed, elided) = print(opts, &term, true)?;
+            let stats = Stats { max_nodes: node_count(&net), ..Stats::default() };
+            EvalOutcome { term, printed, elided, stats: Some(stats), completed: true, exhausted: None, cached: true, unsound: Vec::new() }
+        },
+        _ => {
+            let outcome = eval()?;
+            // Expanded terms can't be encoded again, so they aren't kept.
+            if let (Some(path), Some(stats), true, None) = (&path, &outcome.stats, outcome.completed, opts.expand_dups) {
+                let mut bytes = Vec::new();
+                for word in [stats.loops, stats.rules, stats.betas, stats.dupls, stats.annis, stats.erasures, stats.max_nodes] {
+                    bytes.extend_from_slice(&word.to_le_bytes());
+                }
+                bytes.append(&mut net_to_bytes(&to_net(&outcome.term)?));
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+                fs::write(&temp, &bytes)?;
+                fs::rename(&temp, path)?;
+            }
+            outcome
+        }
+    };
+    if !opts.stats {
+        outcome.stats = None;
+    }
+    Ok(outcome)
+}
+
+// How many rows `SizeTrace` writes between flushes.
+const FLUSH_ROWS : u32 = 64;
+
+// Samples the size of a net as it's reduced, writing it to a CSV file: before the first rewrite and
+// after every `every` rewrites, a row with the rewrites so far, the nodes in use, the length of the
+// free list, and how many of those in use are ERAs, CONs, FANs, FREEs and constructors. The census
+// is taken once, then kept by each rewrite: annihilating two nodes frees them, and commuting them
+// makes a copy of each. Rows are flushed every `FLUSH_ROWS`, so a run that's killed leaves most.
+struct SizeTrace {
+    out: io::BufWriter<fs::File>,
+    every: u32,
+    rules: u32,
+    rows: u32,
+    census: [u32; 5],
+    error: Option<io::Error>
+}
+
+impl SizeTrace {
+    fn new(path : &Path, every : u32, net : &Net) -> io::Result<SizeTrace> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        io::Write::write_all(&mut out, b"rewrites,nodes,free_list,era,con,fan,free,ctr\n")?;
+        let mut census = [0; 5];
+        for node in 0..(net.nodes.len() / NODE_SIZE) as u32 {
+            if !net.reuse.contains(&node) && kind(net, node) != ROOT {
+                census[SizeTrace::column(kind(net, node))] += 1;
+            }
+        }
+        let mut trace = SizeTrace { out, every: every.max(1), rules: 0, rows: 0, census, error: None };
+        trace.sample(net);
+        Ok(trace)
+    }
+
+    // The column of the census counting nodes of a kind.
+    fn column(kind : u32) -> usize {
+        if kind >= CTR { 4 } else { kind as usize }
+    }
+
+    fn rewritten(&mut self, net : &Net, x : u32, copies : Option<(u32, u32)>) {
+        match copies {
+            None => self.census[SizeTrace::column(kind(net, x))] -= 2,
+            Some((a, b)) => {
+                self.census[SizeTrace::column(kind(net, a))] += 1;
+                self.census[SizeTrace::column(kind(net, b))] += 1;
+            }
+        }
+        self.rules += 1;
+        if self.rules.is_multiple_of(self.every) {
+            self.sample(net);
+        }
+    }
+
+    fn sample(&mut self, net : &Net) {
+        let [era, con, fan, free, ctr] = self.census;
+        let row = format!("{},{},{},{},{},{},{},{}\n", self.rules, node_count(net), net.reuse.len(), era, con, fan, free, ctr);
+        self.rows += 1;
+        let mut written = io::Write::write_all(&mut self.out, row.as_bytes());
+        if written.is_ok() && self.rows.is_multiple_of(FLUSH_ROWS) {
+            written = io::Write::flush(&mut self.out);
+        }
+        if let Err(err) = written {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    // Flushes the rows left, returning the first error writing any.
+    fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => io::Write::flush(&mut self.out)
+        }
+    }
+}
+
+// Reduces an encoded program within the limits, and reads back and decodes the result.
+fn run(src : &str, opts : &EvalOptions, encoded : Encoded) -> Result<EvalOutcome, SicError> {
+    let (mut net, fans, free, binders) = encoded;
+    let mut trace = if opts.check_fans { Some(FanTrace::new(&net, fans)) } else { None };
+    let mut budgets = annotated_fuel(src, opts);
+    budgets.extend(opts.fuel.iter().map(|(nam, fuel)| (nam.as_bytes().to_vec(), *fuel)));
+    let mut fuel = if budgets.is_empty() { None } else { Some(Fuel::new(&net, &binders, &budgets)) };
+    let mut sizes = match opts.trace_csv {
+        Some((ref path, every)) => Some(SizeTrace::new(path, every, &net)?),
+        None => None
+    };
+    // Told by each rewrite, and read by `progress`, which stops the reduction.
+    let exhausted = RefCell::new(None);
+    let start = Instant::now();
+    let max_rules = opts.max_rewrites.unwrap_or(u32::MAX);
+    let (stats, completed) = reduce_steps(&mut net, max_rules, 1, &mut |stats| {
+        opts.max_nodes.is_none_or(|max| stats.max_nodes <= max) &&
+        opts.max_time.is_none_or(|max| !stats.rules.is_multiple_of(TIME_STRIDE) || start.elapsed() < max) &&
+        exhausted.borrow().is_none()
+    }, &mut |net, x, y, copies| {
+        if let Some(ref mut trace) = trace {
+            trace.rewritten(net, x, y, copies);
+        }
+        if let Some(ref mut fuel) = fuel {
+            if let Some(err) = fuel.rewritten(x, y, copies) {
+                exhausted.borrow_mut().get_or_insert(err);
+            }
+        }
+        if let Some(ref mut sizes) = sizes {
+            sizes.rewritten(net, x, copies);
+        }
+    });
+    if let Some(sizes) = sizes {
+        sizes.finish()?;
+    }
+    let term = from_net_open(&net, &free)?;
+    if let Some(nam) = too_deep(&term).filter(|_| completed) {
+        return Err(LimitError::Recursion(nam).into());
+    }
+    let term = tidy(opts, term)?;
+    let (printed, elided) = print(opts, &term, completed)?;
+    let unsound = trace.map_or(Vec::new(), |trace| trace.unsound.iter()
+        .map(|(fst, snd)| (trace.origins[*fst as usize].clone(), trace.origins[*snd as usize].clone()))
+        .collect());
+    Ok(EvalOutcome { term, printed, elided, stats: Some(stats), completed, exhausted: exhausted.into_inner(), cached: false, unsound })
+}
+
+// Evaluates a program with the abstract machine, and decodes the result. Free variables are
+// rejected unless `open` is set, as nets reject them.
+fn run_machine(opts : &EvalOptions, term : &Term) -> Result<EvalOutcome, SicError> {
+    if !opts.open {
+        let mut bound = BTreeSet::new();
+        binders(term, &mut bound);
+        let mut counts = BTreeMap::new();
+        occurrences(term, &mut counts);
+        if let Some(nam) = counts.keys().find(|nam| !bound.contains(*nam)) {
+            return Err(AffinityError::Unbound(nam.clone()).into());
+        }
+    }
+    let (norm, stats) = eval_machine(term, opts.max_rewrites.unwrap_or(u32::MAX))?;
+    if let Some(nam) = too_deep(&norm) {
+        return Err(LimitError::Recursion(nam).into());
+    }
+    let term = tidy(opts, norm)?;
+    let (printed, elided) = print(opts, &term, true)?;
+    let stats = if opts.stats { Some(stats) } else { None };
+    Ok(EvalOutcome { term, printed, elided, stats, completed: true, exhausted: None, cached: false, unsound: Vec::new() })
+}
+
+// Simplifies a result and expands its lets, as asked.
+fn tidy(opts : &EvalOptions, term : Term) -> Result<Term, SicError> {
+    let term = if opts.simplify { simplify(&term) } else { term };
+    Ok(match opts.expand_dups {
+        Some(max) => expand_dups(&term, max)?,
+        None => term
+    })
+}
+
+// Prints a result as `decode` asks, with how many nodes were left out. Partial results are always
+// printed as terms.
+fn print(opts : &EvalOptions, term : &Term, completed : bool) -> Result<(String, u64), SicError> {
+    let as_term = || if opts.output_limits == OutputLimits::default() {
+        (String::from_utf8_lossy(&to_string(term)).into_owned(), 0)
+    } else {
+        let (code, elided) = pretty_limited(term, opts.output_limits);
+        (String::from_utf8_lossy(&code).into_owned(), elided)
+    };
+    Ok(match opts.decode {
+        _ if !completed => as_term(),
+        Decode::Term => as_term(),
+        Decode::Nat => (nat_to(term).ok_or(ReadbackError::Decode("a numeral"))?.to_string(), 0),
+        Decode::Bits => (bits_to(term).ok_or(ReadbackError::Decode("a binary number"))?.to_string(), 0),
+        Decode::String => (string_to(term).ok_or(ReadbackError::Decode("a string"))?, 0),
+        Decode::Bytes => (String::new(), 0)
+    })
+}
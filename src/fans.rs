@@ -0,0 +1,136 @@
+// Tracking of where FAN nodes come from. Pairs and lets both become FAN nodes, and any two FANs
+// annihilate when they meet. That's right when a let meets a pair (it projects it) or a copy of
+// itself, but when two different lets meet, the calculus would have them commute, and the result
+// may be wrong. A traced reduction records those meetings; if there are none, it was faithful to
+// the calculus.
+
+use prelude::*;
+use net::*;
+use term::*;
+use error::AffinityError;
+
+// Where a FAN node comes from: the `idx`th pair or let `to_net` encoded. Lets keep their binder
+// names, which tell where they were written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FanOrigin {
+    Par{idx: u32},
+    Dup{idx: u32, fst: Vec<u8>, snd: Vec<u8>}
+}
+
+impl FanOrigin {
+    pub fn idx(&self) -> u32 {
+        match self {
+            FanOrigin::Par{idx} | FanOrigin::Dup{idx, ..} => *idx
+        }
+    }
+}
+
+// A table kept beside a net, giving each FAN node its origin. Copies made when a FAN commutes have
+// the origin of the node they copy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FanTrace {
+    // The pairs and lets of the term, by number.
+    pub origins: Vec<FanOrigin>,
+    // Annihilations between FANs of different lets, as pairs of numbers.
+    pub unsound: Vec<(u32, u32)>,
+    // The origin of each node, by address. Meaningless for nodes that aren't FANs.
+    node_origin: Vec<u32>
+}
+
+impl FanTrace {
+    // Starts a table for a net just encoded, from the FAN nodes the encoding made.
+    pub(crate) fn new(net : &Net, fans : Vec<(u32, FanOrigin)>) -> FanTrace {
+        let mut node_origin = vec![u32::MAX; net.nodes.len() / NODE_SIZE];
+        for (node, origin) in &fans {
+            node_origin[*node as usize] = origin.idx();
+        }
+        let origins = fans.into_iter().map(|(_, origin)| origin).collect();
+        FanTrace { origins, unsound: Vec::new(), node_origin }
+    }
+
+    // Updates the table after `rewrite` was given `x` and `y` and made `copies`, recording the
+    // annihilation if it's unsound.
+    pub fn rewritten(&mut self, net : &Net, x : u32, y : u32, copies : Option<(u32, u32)>) {
+        match copies {
+            Some((a, b)) => {
+                let len = self.node_origin.len().max(a as usize + 1).max(b as usize + 1);
+                self.node_origin.resize(len, u32::MAX);
+                self.node_origin[a as usize] = self.node_origin[x as usize];
+                self.node_origin[b as usize] = self.node_origin[y as usize];
+            },
+            None if kind(net, x) == FAN => {
+                let fst = self.node_origin[x as usize];
+                let snd = self.node_origin[y as usize];
+                // FANs added to the net after it was encoded, like those of `data::apply_bytes`,
+                // have no origin, and aren't told apart.
+                let lets = matches!((self.origins.get(fst as usize), self.origins.get(snd as usize)),
+                    (Some(FanOrigin::Dup{..}), Some(FanOrigin::Dup{..})));
+                if lets && fst != snd {
+                    self.unsound.push((fst, snd));
+                }
+            },
+            None => {}
+        }
+    }
+}
+
+// Converts a term to a net like `to_net`, with a table of where its FAN nodes come from.
+pub fn to_net_traced(term : &Term) -> Result<(Net, FanTrace), AffinityError> {
+    let (net, fans, _, _) = to_net_with_fans(term, false)?;
+    let trace = FanTrace::new(&net, fans);
+    Ok((net, trace))
+}
+
+// Reduces a net like `net::reduce`, keeping its table up to date.
+pub fn reduce_traced(net : &mut Net, trace : &mut FanTrace) -> Stats {
+    reduce_steps(net, u32::MAX, 0, &mut |_| true, &mut |net, x, y, copies| trace.rewritten(net, x, y, copies)).0
+}
+
+// Finds where a let was written in `src`, as a byte offset. Names of lets from definitions were
+// prefixed on inlining, like `two#0#s0`, which gives the definition to look in. Pairs have no
+// names, so they can't be found.
+pub fn locate(src : &[u8], origin : &FanOrigin) -> Option<usize> {
+    fn strip(nam : &[u8]) -> (Option<&[u8]>, &[u8]) {
+        let parts : Vec<&[u8]> = nam.split(|chr| *chr == b'#').collect();
+        match parts.len() {
+            len if len >= 3 => (Some(parts[len - 3]), parts[len - 1]),
+            _ => (None, nam)
+        }
+    }
+    fn word(src : &[u8], at : usize) -> (&[u8], usize) {
+        let start = at + src[at..].iter().take_while(|chr| chr.is_ascii_whitespace()).count();
+        let len = src[start..].iter().take_while(|chr| !chr.is_ascii_whitespace()).count();
+        (&src[start..start + len], start + len)
+    }
+    let (def, fst, snd) = match origin {
+        FanOrigin::Dup{fst, snd, ..} => (strip(fst).0, strip(fst).1, strip(snd).1),
+        FanOrigin::Par{..} => return None
+    };
+    let head = |at : usize| src[at] == b':' && def.is_some_and(|def| word(src, at + 1).0 == def);
+    let start = (0..src.len()).find(|at| head(*at)).unwrap_or(0);
+    (start..src.len()).find(|at| {
+        if src[*at] != b'=' {
+            return false;
+        }
+        let (first, end) = word(src, at + 1);
+        first == fst && word(src, end).0 == snd
+    })
+}
+
+// Describes an origin for a person: a let, with its binders and where it was written, or a pair,
+// with its number.
+pub fn describe(src : &[u8], origin : &FanOrigin) -> String {
+    match origin {
+        FanOrigin::Par{idx} => format!("pair #{}", idx),
+        FanOrigin::Dup{fst, snd, ..} => {
+            let show = |nam : &[u8]| String::from_utf8_lossy(nam.rsplit(|chr| *chr == b'#').next().unwrap()).into_owned();
+            let mut text = format!("let `= {} {}`", show(fst), show(snd));
+            if let Some(at) = locate(src, origin) {
+                let line = src[..at].iter().filter(|chr| **chr == b'\n').count() + 1;
+                let column = at - src[..at].iter().rposition(|chr| *chr == b'\n').map_or(0, |nl| nl + 1) + 1;
+                text.push_str(&format!(" at {}:{}", line, column));
+            }
+            text
+        }
+    }
+}
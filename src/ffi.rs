@@ -0,0 +1,106 @@
+// C bindings, enabled by the `ffi` feature. The declarations are in `include/sic.h`.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic;
+use std::ptr;
+use error::SicError;
+use term;
+
+pub const SIC_OK : i32 = 0;
+pub const SIC_ERR_ARGUMENT : i32 = 1;
+pub const SIC_ERR_PARSE : i32 = 2;
+pub const SIC_ERR_AFFINITY : i32 = 3;
+pub const SIC_ERR_NET : i32 = 4;
+pub const SIC_ERR_READBACK : i32 = 5;
+pub const SIC_ERR_LIMIT : i32 = 6;
+pub const SIC_ERR_PANIC : i32 = 7;
+
+#[repr(C)]
+pub struct SicOptions {
+    // Maximum number of rewrites; 0 means no limit.
+    pub max_rewrites: u32
+}
+
+#[repr(C)]
+pub struct SicResult {
+    // The normal form, or null on error. Owned by the library.
+    pub output: *mut c_char,
+    // A description of the error, or null on success. Owned by the library.
+    pub error: *mut c_char,
+    pub rules: u32,
+    pub max_nodes: u32
+}
+
+fn error_code(err : &SicError) -> i32 {
+    match err {
+        SicError::Io(_) | SicError::Encode(_) | SicError::Export(_) => SIC_ERR_ARGUMENT,
+        SicError::Parse(_) => SIC_ERR_PARSE,
+        SicError::Affinity(_) => SIC_ERR_AFFINITY,
+        SicError::Net(_) => SIC_ERR_NET,
+        SicError::Readback(_) => SIC_ERR_READBACK,
+        SicError::Limit(_) => SIC_ERR_LIMIT
+    }
+}
+
+// Strings handed to C can't hold NUL bytes; neither printed terms nor error messages have them.
+fn c_string(text : Vec<u8>) -> *mut c_char {
+    CString::new(text).map(CString::into_raw).unwrap_or(ptr::null_mut())
+}
+
+/// Evaluates `src`, with `input` (which may be null) as the main term. `opts` may be null for the
+/// defaults. Fills `out`, which must be released with `sic_free_result`, and returns an error code.
+///
+/// # Safety
+/// `src` and `input` must be null or NUL-terminated strings, `opts` null or valid, and `out` valid.
+#[no_mangle]
+pub unsafe extern "C" fn sic_eval(src : *const c_char, input : *const c_char, opts : *const SicOptions, out : *mut SicResult) -> i32 {
+    if out.is_null() {
+        return SIC_ERR_ARGUMENT;
+    }
+    *out = SicResult { output: ptr::null_mut(), error: ptr::null_mut(), rules: 0, max_nodes: 0 };
+    if src.is_null() {
+        (*out).error = c_string(b"source is null".to_vec());
+        return SIC_ERR_ARGUMENT;
+    }
+    let src = CStr::from_ptr(src).to_bytes();
+    let input = if input.is_null() { None } else { Some(CStr::from_ptr(input).to_bytes()) };
+    let max_rewrites = match opts.as_ref() {
+        Some(opts) if opts.max_rewrites > 0 => opts.max_rewrites,
+        _ => u32::MAX
+    };
+    match panic::catch_unwind(|| term::evaluate(src, input, max_rewrites)) {
+        Ok(Ok((norm, stats))) => {
+            (*out).output = c_string(term::to_string(&norm));
+            (*out).rules = stats.rules;
+            (*out).max_nodes = stats.max_nodes;
+            SIC_OK
+        },
+        Ok(Err(err)) => {
+            (*out).error = c_string(err.to_string().into_bytes());
+            error_code(&err)
+        },
+        Err(_) => {
+            (*out).error = c_string(b"internal error: the evaluator panicked".to_vec());
+            SIC_ERR_PANIC
+        }
+    }
+}
+
+/// Releases the strings of a result filled by `sic_eval`. Safe to call twice.
+///
+/// # Safety
+/// `res` must be null or point to a result filled by `sic_eval`.
+#[no_mangle]
+pub unsafe extern "C" fn sic_free_result(res : *mut SicResult) {
+    if let Some(res) = res.as_mut() {
+        if !res.output.is_null() {
+            drop(CString::from_raw(res.output));
+            res.output = ptr::null_mut();
+        }
+        if !res.error.is_null() {
+            drop(CString::from_raw(res.error));
+            res.error = ptr::null_mut();
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Budgets of rewrites for single definitions, set with `%fuel n` after a definition's name (or
+// `--fuel name=n`). Rewrites are attributed to definitions through their binders: each lambda and
+// let node is tagged with the definition it was inlined from, as its name tells (`f#0#x`), and the
+// copies made when a node commutes keep its tag. A rewrite counts against the definition of the
+// first node of the pair that has one, so a definition that keeps running is stopped on the
+// rewrite that exceeds its budget, while the rest of the program only answers to the global limits.
+
+use prelude::*;
+use net::*;
+use term::*;
+use error::{AffinityError, LimitError};
+
+// A table kept beside a net, giving the nodes of each budgeted definition, and counting the
+// rewrites attributed to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fuel {
+    // The budgeted definitions: the name, the budget, and the rewrites attributed so far.
+    pub budgets: Vec<(Vec<u8>, u32, u32)>,
+    // The index in `budgets` of each node's definition, by address, or `u32::MAX` for nodes of
+    // other definitions, and nodes that aren't binders.
+    node_tag: Vec<u32>
+}
+
+impl Fuel {
+    // Starts a table for a net just encoded, from the binders the encoding made.
+    pub(crate) fn new(net : &Net, binders : &BinderNames, budgets : &[(Vec<u8>, u32)]) -> Fuel {
+        let mut node_tag = vec![u32::MAX; net.nodes.len() / NODE_SIZE];
+        for (port, nam) in binders {
+            let def = split_namespace(nam).map(|(def, _, _)| def);
+            if let Some(tag) = budgets.iter().position(|(nam, _)| Some(&nam[..]) == def) {
+                node_tag[addr(*port) as usize] = tag as u32;
+            }
+        }
+        let budgets = budgets.iter().map(|(nam, fuel)| (nam.clone(), *fuel, 0)).collect();
+        Fuel { budgets, node_tag }
+    }
+
+    // Updates the table after `rewrite` was given `x` and `y` and made `copies`, attributing the
+    // rewrite. Returns the error if its definition just ran out of fuel.
+    pub fn rewritten(&mut self, x : u32, y : u32, copies : Option<(u32, u32)>) -> Option<LimitError> {
+        let tag = if self.node_tag[x as usize] != u32::MAX { self.node_tag[x as usize] } else { self.node_tag[y as usize] };
+        if let Some((a, b)) = copies {
+            let len = self.node_tag.len().max(a as usize + 1).max(b as usize + 1);
+            self.node_tag.resize(len, u32::MAX);
+            self.node_tag[a as usize] = self.node_tag[x as usize];
+            self.node_tag[b as usize] = self.node_tag[y as usize];
+        }
+        let (nam, fuel, used) = self.budgets.get_mut(tag as usize)?;
+        *used += 1;
+        if *used > *fuel {
+            Some(LimitError::Fuel{nam: nam.clone(), fuel: *fuel})
+        } else {
+            None
+        }
+    }
+}
+
+// Converts a term to a net like `to_net`, with a table for the given budgets.
+pub fn to_net_fueled(term : &Term, budgets : &[(Vec<u8>, u32)]) -> Result<(Net, Fuel), AffinityError> {
+    let (net, _, _, binders) = to_net_with_fans(term, false)?;
+    let fuel = Fuel::new(&net, &binders, budgets);
+    Ok((net, fuel))
+}
+
+// Reduces a net like `net::reduce`, stopping on the rewrite that runs a definition out of fuel.
+pub fn reduce_fueled(net : &mut Net, fuel : &mut Fuel) -> Result<Stats, LimitError> {
+    let exhausted = core::cell::RefCell::new(None);
+    let (stats, _) = reduce_steps(net, u32::MAX, 1, &mut |_| exhausted.borrow().is_none(), &mut |_, x, y, copies| {
+        *exhausted.borrow_mut() = fuel.rewritten(x, y, copies);
+    });
+    exhausted.into_inner().map_or(Ok(stats), Err)
+}
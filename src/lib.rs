@@ -1,2 +1,199 @@
+//! The Symmetric Interaction Calculus: a minimal language that matches the abstract part of
+//! Lamping's optimal reduction algorithm, evaluated through symmetric interaction combinators.
+//!
+//! - `term`: the `Term` syntax tree, its parser and printers, and the translation to and from nets.
+//! - `net`: interaction nets (`Net`) and their reduction, which reports `Stats`. With the `serde`
+//!   feature, nets can be serialized; deserializing checks them with `check_net`.
+//! - `module`: source files viewed as separate top-level definitions, which `Module::to_net` encodes
+//!   once each and grafts where they are referenced.
+//! - `data`: builders for terms, and encodings of numerals and lists.
+//! - `rules`: the interaction rules, shown by running `net::rewrite` on each kind of active pair.
+//! - `fans`: tracing of where FAN nodes come from, to catch annihilations the calculus wouldn't do.
+//! - `fuel`: budgets of rewrites for single definitions, set with `%fuel`.
+//! - `waste`: how much of a reduction's work went into what it threw away, as `--waste` prints it.
+//! - `ascii`: drawings of small nets as text, with box-drawing characters, as `sic net --ascii`
+//!   prints them.
+//! - `machine`: an abstract machine that evaluates terms without nets, `term::eval_machine`.
+//! - `eval`: `eval_str`, which evaluates a program with the options the command line offers.
+//! - `report`: HTML reports of a reduction, with charts and drawings of the net, as `--report`
+//!   writes them.
+//! - `error`: the errors each stage can report, and `SicError`, which wraps them all.
+//! - `wasm` (with the `wasm` feature): bindings for running the evaluator in a browser.
+//! - `ffi` (with the `ffi` feature): C bindings, declared in `include/sic.h`.
+//! - `lsp` (with the `lsp` feature): a language server for source files, run by `sic lsp`.
+//! - `testing` (with the `testing` feature): checks of the library's invariants, for tests.
+//!
+//! With the `trace-log` feature, `net::rewrite` prints each interaction to stderr, with the kinds
+//! and addresses of the two nodes. Without it, nothing is logged and nothing is spent on logging.
+//!
+//! With the `compact-nodes` feature, nodes take 3 words of `Net::nodes` rather than 4, their kinds
+//! packed into their first ports. Code that indexes `nodes` should go through `net::slot` and
+//! `net::NODE_SIZE`; serialized nets are the same under both layouts.
+//!
+//! With the `unchecked` feature, the reducer reads and writes nodes without bounds checks, except
+//! in debug builds. Instead, a reduction checks once, as it starts, that every port of the net
+//! points inside it, and panics if one doesn't, as the public functions do on bad arguments.
+//!
+//! The `std` feature, on by default, can be turned off to build for targets without an operating
+//! system: `term`, `net`, `module`, `error` and `data` only need `alloc` (enable the `alloc`
+//! feature instead). Without `std`, `eval`, reports, timing, printing nets and I/O errors are left
+//! out, and so are the bindings, the language server and the `sic` binary (the `cli` feature). The
+//! `cdylib` target needs `std`, so build just the `rlib`:
+//!
+//! ```text
+//! cargo rustc --lib --crate-type rlib --no-default-features --features alloc --target thumbv7m-none-eabi
+//! ```
+//!
+//! Evaluating a program means parsing it, encoding it as a net, reducing the net and reading the
+//! normal form back:
+//!
+//! ```
+//! use sic::term::{from_string, to_net, from_net, to_string};
+//! use sic::net::reduce;
+//!
+//! # fn main() -> Result<(), sic::error::SicError> {
+//! let term = from_string(b":id \\x x /id \\y y")?;
+//! let mut net = to_net(&term)?;
+//! let stats = reduce(&mut net);
+//! let norm = from_net(&net)?;
+//! assert_eq!(to_string(&norm), b"\\a a".to_vec());
+//! assert_eq!(stats.rules, 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The conversions are also methods, `Net::from_term` and `Net::to_term`:
+//!
+//! ```
+//! use sic::net::{reduce, Net};
+//! use sic::term::{from_string, to_string};
+//!
+//! # fn main() -> Result<(), sic::error::SicError> {
+//! let mut net = Net::from_term(&from_string(b"/\\x x \\y y")?)?;
+//! reduce(&mut net);
+//! assert_eq!(to_string(&net.to_term()?), b"\\a a".to_vec());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `term::reduce` does the same in one call, discarding the stats:
+//!
+//! ```
+//! use sic::term::{from_string, reduce, to_string};
+//!
+//! let norm = reduce(&from_string(b"//\\f \\x /f x \\y y \\z z").unwrap()).unwrap();
+//! assert_eq!(to_string(&norm), b"\\a a".to_vec());
+//! ```
+//!
+//! Terms also have it as a method, which allows free variables and can return the stats:
+//!
+//! ```
+//! use sic::term::{from_string, to_string};
+//!
+//! let (norm, stats) = from_string(b"/\\x x y").unwrap().reduce_with_stats().unwrap();
+//! assert_eq!(to_string(&norm), b"y".to_vec());
+//! assert_eq!(stats.rules, 1);
+//! ```
+//!
+//! `term::evaluate` runs the whole pipeline with a limit on rewrites, optionally taking the main
+//! term separately from the definitions:
+//!
+//! ```
+//! use sic::term::{evaluate, to_string};
+//!
+//! let (norm, stats) = evaluate(b":id \\x x", Some(b"/id \\y y"), 100).unwrap();
+//! assert_eq!(to_string(&norm), b"\\a a".to_vec());
+//! assert_eq!(stats.rules, 1);
+//! ```
+//!
+//! `eval_str` does what the `sic` binary does. By default, it evaluates the main term:
+//!
+//! ```
+//! use sic::{eval_str, EvalOptions};
+//!
+//! let outcome = eval_str(":id \\x x /id \\y y", &EvalOptions::default()).unwrap();
+//! assert_eq!(outcome.printed, "\\a a");
+//! assert!(outcome.completed);
+//! ```
+//!
+//! It can instead apply a definition to inputs, and decode the result as data:
+//!
+//! ```
+//! use sic::{eval_str, EvalOptions};
+//! use sic::eval::Decode;
+//!
+//! let src = ":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z";
+//! let opts = EvalOptions {
+//!     entry: Some("add".to_string()),
+//!     inputs: vec!["\\s \\z /s z".to_string(), "\\s \\z = s0 s1 s /s0 /s1 z".to_string()],
+//!     decode: Decode::Nat,
+//!     ..EvalOptions::default()
+//! };
+//! assert_eq!(eval_str(src, &opts).unwrap().printed, "3");
+//! ```
+//!
+//! Limits stop a reduction early, returning the partially reduced term:
+//!
+//! ```
+//! use sic::{eval_str, EvalOptions};
+//!
+//! let opts = EvalOptions { max_rewrites: Some(1), stats: true, ..EvalOptions::default() };
+//! let outcome = eval_str("//\\f \\x /f x \\y y \\z z", &opts).unwrap();
+//! assert!(!outcome.completed);
+//! assert_eq!(outcome.stats.unwrap().rules, 1);
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// What the standard prelude brings in, for the modules that also build without `std`.
+mod prelude {
+    pub use alloc::boxed::Box;
+    pub use alloc::collections::{BTreeMap, BTreeSet};
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+}
+
 pub mod term;
 pub mod net;
+pub mod module;
+pub mod error;
+pub mod data;
+pub mod rules;
+pub mod dot;
+pub mod ascii;
+pub mod fans;
+pub mod fuel;
+pub mod waste;
+pub mod machine;
+#[cfg(feature = "std")]
+pub mod eval;
+#[cfg(feature = "std")]
+pub mod report;
+
+#[cfg(feature = "std")]
+pub use eval::{eval_str, eval_cached, Engine, EvalOptions, EvalOutcome};
+
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "lsp")]
+extern crate lsp_server;
+#[cfg(feature = "lsp")]
+extern crate lsp_types;
+#[cfg(feature = "lsp")]
+extern crate serde_json;
+#[cfg(feature = "lsp")]
+pub mod lsp;
@@ -0,0 +1,404 @@
+// A minimal language server for SIC source files, run by `sic lsp` (with the `lsp` feature). It
+// is synchronous and keeps open documents as text: each change re-checks the whole file and
+// publishes its diagnostics. It also finds and describes `:` definitions, and formats files.
+//
+// Terms carry no positions, so names are located by scanning the source in the order the parser
+// reads them (`tokens`), then walking the parsed module in that same order.
+
+use std::collections::HashMap;
+use std::io;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DiagnosticTag, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextEdit, Uri
+};
+use serde_json;
+use term::*;
+use module::*;
+use error::*;
+use term::Term::{*};
+
+// A problem found in a document, between two byte offsets.
+struct Problem {
+    start: usize,
+    end: usize,
+    severity: DiagnosticSeverity,
+    message: String,
+    unused: bool
+}
+
+// What a document that parses defines and references, by byte offset.
+#[derive(Default)]
+struct Outline {
+    // Each definition, and where its name is.
+    defs: Vec<(Vec<u8>, usize)>,
+    // Each reference to a definition: where it is, and the index of the definition in `defs`.
+    refs: Vec<(usize, usize)>
+}
+
+// Tells whether a byte ends a name, as `parse_name` does.
+fn ends_name(chr : u8) -> bool {
+    chr == b' ' || chr == b'\n' || chr == b'\r' || chr == b'\\' || chr == b'/' || chr == b'|' || chr == b'=' || chr == b'*' ||
+        chr == b'&' || chr == b';' || chr == b'{' || chr == b'}'
+}
+
+// Lists the names in a source file with their offsets, in the order the parser reads them: that
+// is, the order of a pre-order walk of each definition (binders before bodies), then of the main
+// term.
+fn tokens(code : &[u8]) -> Vec<(usize, &[u8])> {
+    let mut tokens = Vec::new();
+    let mut names = 0;
+    let mut comment = 0;
+    // Whether the next name is a definition's, and where the `=` ending its parameters is, if any.
+    let mut def = false;
+    let mut params_end = None;
+    let mut i = 0;
+    while i < code.len() {
+        let chr = code[i];
+        if chr == b' ' || chr == b'\n' || chr == b'\r' {
+            i += 1;
+        } else if names == 0 && (chr == b'(' || comment > 0) {
+            match chr {
+                b'(' => comment += 1,
+                b')' => comment -= 1,
+                _ => {}
+            }
+            i += 1;
+        } else if names == 0 && code[i..].starts_with(b"%fuel") {
+            // A definition's `%fuel n` has no names.
+            i += 5;
+            while i < code.len() && (code[i] == b' ' || code[i] == b'\n' || code[i] == b'\r') {
+                i += 1;
+            }
+            while i < code.len() && code[i].is_ascii_digit() {
+                i += 1;
+            }
+        } else if names == 0 && params_end == Some(i) {
+            // The parameters of a definition are names like any other, and their `=` binds none.
+            i += 1;
+        } else if names == 0 && chr != b'\\' && chr != b'=' && chr != b':' && ends_name(chr) {
+            i += 1;
+        } else if names == 0 && (chr == b'\\' || chr == b'=' || chr == b':') {
+            names = if chr == b'=' { 2 } else { 1 };
+            def = chr == b':';
+            i += 1;
+        } else {
+            let start = i;
+            while i < code.len() && !ends_name(code[i]) {
+                i += 1;
+            }
+            tokens.push((start, &code[start..i]));
+            if def {
+                def = false;
+                params_end = match parse_fuel(&code[i..]).and_then(|(rest, _)| parse_params(rest, &code[start..i])) {
+                    Ok((rest, params)) if !params.is_empty() => Some(code.len() - rest.len() - 1),
+                    _ => None
+                };
+            }
+            // After the first name of a `=`, the parser skips a byte before the second.
+            if names == 2 {
+                i += 1;
+            }
+            names = if names > 0 { names - 1 } else { 0 };
+        }
+    }
+    tokens
+}
+
+// Checks the binders and variables of a definition (or of the main term), whose names come next in
+// `tokens`. `visible` are the definitions it can reference. Returns `None` if the names don't line
+// up with the term, in which case positions can't be trusted.
+fn check_item<'a>(term : &Term, tokens : &mut dyn Iterator<Item = &'a (usize, &'a [u8])>, visible : &[(Vec<u8>, usize)], outline : &mut Outline, problems : &mut Vec<Problem>) -> Option<()> {
+    // A binder or variable, with its position.
+    struct Name<'b> { nam: &'b [u8], at: usize }
+    struct Names<'b> { binders: Vec<Name<'b>>, vars: Vec<Name<'b>>, refs: Vec<(usize, usize)> }
+
+    // Takes the next name, which should be `nam`, returning its position.
+    fn next<'a>(tokens : &mut dyn Iterator<Item = &'a (usize, &'a [u8])>, nam : &[u8]) -> Option<usize> {
+        let (at, tok) = tokens.next()?;
+        if *tok == nam { Some(*at) } else { None }
+    }
+
+    fn walk<'a, 'b>(term : &'b Term, tokens : &mut dyn Iterator<Item = &'a (usize, &'a [u8])>, visible : &[(Vec<u8>, usize)], bound : &mut Vec<&'b [u8]>, names : &mut Names<'b>) -> Option<()> {
+        match term {
+            Lam{nam, bod} => {
+                let at = next(tokens, nam)?;
+                names.binders.push(Name{nam, at});
+                bound.push(nam);
+                walk(bod, tokens, visible, bound, names)?;
+                bound.pop();
+            },
+            App{fun, arg} => {
+                walk(fun, tokens, visible, bound, names)?;
+                walk(arg, tokens, visible, bound, names)?;
+            },
+            Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                walk(a, tokens, visible, bound, names)?;
+                walk(b, tokens, visible, bound, names)?;
+            },
+            Dup{fst, snd, val, nxt} => {
+                let at = next(tokens, fst)?;
+                names.binders.push(Name{nam: fst, at});
+                let at = next(tokens, snd)?;
+                names.binders.push(Name{nam: snd, at});
+                bound.push(snd);
+                bound.push(fst);
+                walk(val, tokens, visible, bound, names)?;
+                walk(nxt, tokens, visible, bound, names)?;
+                bound.pop();
+                bound.pop();
+            },
+            Var{nam} => {
+                let at = next(tokens, nam)?;
+                let def = visible.iter().rposition(|(def, _)| def == nam);
+                match def {
+                    Some(def) if !bound.contains(&&nam[..]) => names.refs.push((at, def)),
+                    _ => names.vars.push(Name{nam, at})
+                }
+            },
+            Ctr{nam} => {
+                next(tokens, nam)?;
+            },
+            Set => {}
+        }
+        Some(())
+    }
+
+    let mut names = Names { binders: Vec::new(), vars: Vec::new(), refs: Vec::new() };
+    walk(term, tokens, visible, &mut Vec::new(), &mut names)?;
+    outline.refs.extend(names.refs);
+    // Binders are global, as in `to_net`: a variable can be bound anywhere in the same term.
+    for (idx, var) in names.vars.iter().enumerate() {
+        let end = var.at + var.nam.len();
+        if !names.binders.iter().any(|binder| binder.nam == var.nam) {
+            let message = format!("unbound variable `{}`", String::from_utf8_lossy(var.nam));
+            problems.push(Problem { start: var.at, end, severity: DiagnosticSeverity::ERROR, message, unused: false });
+        } else if names.vars[..idx].iter().any(|other| other.nam == var.nam) {
+            let message = format!("variable `{}` is used more than once", String::from_utf8_lossy(var.nam));
+            problems.push(Problem { start: var.at, end, severity: DiagnosticSeverity::ERROR, message, unused: false });
+        }
+    }
+    for binder in &names.binders {
+        if binder.nam != b"_" && binder.nam != b"-" && !names.vars.iter().any(|var| var.nam == binder.nam) {
+            let message = format!("unused variable `{}`", String::from_utf8_lossy(binder.nam));
+            let end = binder.at + binder.nam.len();
+            problems.push(Problem { start: binder.at, end, severity: DiagnosticSeverity::WARNING, message, unused: true });
+        }
+    }
+    Some(())
+}
+
+// Checks a document, returning its outline (if it parses) and its problems.
+fn check(code : &[u8]) -> (Option<Outline>, Vec<Problem>) {
+    let error = |at : usize, message : String| Problem { start: at, end: at, severity: DiagnosticSeverity::ERROR, message, unused: false };
+    let module = match parse_module(code) {
+        Ok(module) => module,
+        Err(err) => return (None, vec![error(err.index, err.message)])
+    };
+    let mut outline = Outline::default();
+    let mut problems = Vec::new();
+    let tokens = tokens(code);
+    let mut tokens = tokens.iter();
+    let mut lined_up = true;
+    for (idx, (nam, val)) in module.defs.iter().enumerate() {
+        match tokens.next() {
+            Some((at, tok)) if tok == nam => outline.defs.push((nam.clone(), *at)),
+            _ => lined_up = false
+        }
+        let visible = outline.defs[..idx].to_vec();
+        if !lined_up || check_item(val, &mut tokens, &visible, &mut outline, &mut problems).is_none() {
+            lined_up = false;
+            break;
+        }
+    }
+    if let (true, Some(ref main)) = (lined_up, &module.main) {
+        let visible = outline.defs.clone();
+        lined_up = check_item(main, &mut tokens, &visible, &mut outline, &mut problems).is_some();
+    }
+    // If the scan got lost, its findings are dropped, and whatever `to_net` rejects is reported at
+    // the start of the file instead.
+    if !lined_up {
+        problems.clear();
+    }
+    if !problems.iter().any(|problem| problem.severity == DiagnosticSeverity::ERROR) {
+        let checked = from_string(code).map_err(SicError::from).and_then(|term| Ok(to_net(&term)?));
+        match checked {
+            Err(SicError::Parse(err)) => problems.push(error(err.index, err.message)),
+            Err(err) => problems.push(error(0, err.to_string())),
+            Ok(_) => {}
+        }
+    }
+    problems.sort_by_key(|problem| problem.start);
+    (if lined_up { Some(outline) } else { None }, problems)
+}
+
+// Converts a byte offset into a position, counting characters in UTF-16 units as LSP does.
+fn position(code : &[u8], offset : usize) -> Position {
+    let offset = offset.min(code.len());
+    let line_start = code[..offset].iter().rposition(|chr| *chr == b'\n').map_or(0, |i| i + 1);
+    let line = code[..line_start].iter().filter(|chr| **chr == b'\n').count();
+    let character = String::from_utf8_lossy(&code[line_start..offset]).encode_utf16().count();
+    Position::new(line as u32, character as u32)
+}
+
+// Converts a position into a byte offset.
+fn offset(code : &[u8], pos : Position) -> usize {
+    let line_start = if pos.line == 0 {
+        0
+    } else {
+        match code.iter().enumerate().filter(|(_, chr)| **chr == b'\n').nth(pos.line as usize - 1) {
+            Some((i, _)) => i + 1,
+            None => return code.len()
+        }
+    };
+    let line = String::from_utf8_lossy(&code[line_start..]);
+    let mut units = 0;
+    for (i, chr) in line.char_indices() {
+        if units >= pos.character as usize || chr == '\n' {
+            return line_start + i;
+        }
+        units += chr.len_utf16();
+    }
+    code.len()
+}
+
+fn range(code : &[u8], start : usize, end : usize) -> Range {
+    Range::new(position(code, start), position(code, end))
+}
+
+fn diagnostics(code : &[u8]) -> Vec<Diagnostic> {
+    check(code).1.into_iter().map(|problem| Diagnostic {
+        range: range(code, problem.start, problem.end),
+        severity: Some(problem.severity),
+        source: Some("sic".to_string()),
+        message: problem.message,
+        tags: if problem.unused { Some(vec![DiagnosticTag::UNNECESSARY]) } else { None },
+        ..Diagnostic::default()
+    }).collect()
+}
+
+// Finds the definition named at an offset, either where it's defined or where it's referenced.
+// Returns its index, and where the name under the cursor is.
+fn definition_at(outline : &Outline, at : usize) -> Option<(usize, usize)> {
+    let len = |def : usize| outline.defs[def].0.len();
+    let defined = outline.defs.iter().enumerate().map(|(def, (_, start))| (*start, def));
+    let mut names = defined.chain(outline.refs.iter().cloned());
+    names.find(|(start, def)| *start <= at && at <= start + len(*def)).map(|(start, def)| (def, start))
+}
+
+fn goto_definition(docs : &HashMap<String, Vec<u8>>, params : GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let code = docs.get(uri.as_str())?;
+    let outline = check(code).0?;
+    let (def, _) = definition_at(&outline, offset(code, params.text_document_position_params.position))?;
+    let (ref nam, start) = outline.defs[def];
+    Some(GotoDefinitionResponse::Scalar(Location::new(uri.clone(), range(code, start, start + nam.len()))))
+}
+
+// Describes a definition: its body, laid out by the formatter, and its size in nodes.
+fn hover(docs : &HashMap<String, Vec<u8>>, params : HoverParams) -> Option<Hover> {
+    let code = docs.get(params.text_document_position_params.text_document.uri.as_str())?;
+    let outline = check(code).0?;
+    let (def, start) = definition_at(&outline, offset(code, params.text_document_position_params.position))?;
+    let nam = &outline.defs[def].0;
+    let module = parse_module(code).ok()?;
+    let val = module.defs[def].1.clone();
+    let params = module.params.iter().filter(|(other, _)| other == nam).cloned().collect();
+    let text = format_module(&Module { defs: vec![(nam.clone(), val.clone())], main: None, comments: Vec::new(), notes: Vec::new(), fuel: Vec::new(), params });
+    let value = format!("```sic\n{}\n```\n{} nodes", String::from_utf8_lossy(&text).trim_end(), size(&val));
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+        range: Some(range(code, start, start + nam.len()))
+    })
+}
+
+// Formats a whole document, as `sic fmt` would. Documents that don't parse aren't touched.
+fn formatting(docs : &HashMap<String, Vec<u8>>, params : DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+    let code = docs.get(params.text_document.uri.as_str())?;
+    let formatted = format_module(&parse_module(code).ok()?);
+    if formatted == *code {
+        return Some(Vec::new());
+    }
+    let new_text = String::from_utf8_lossy(&formatted).into_owned();
+    Some(vec![TextEdit::new(range(code, 0, code.len()), new_text)])
+}
+
+fn respond(docs : &HashMap<String, Vec<u8>>, req : Request) -> Response {
+    fn reply<P, R>(req : Request, handle : impl FnOnce(P) -> R) -> Response
+        where P : ::serde::de::DeserializeOwned, R : ::serde::Serialize {
+        match serde_json::from_value(req.params) {
+            Ok(params) => Response::new_ok(req.id, handle(params)),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string())
+        }
+    }
+    match req.method.as_str() {
+        "textDocument/definition" => reply(req, |params| goto_definition(docs, params)),
+        "textDocument/hover" => reply(req, |params| hover(docs, params)),
+        "textDocument/formatting" => reply(req, |params| formatting(docs, params)),
+        _ => Response::new_err(req.id, ErrorCode::MethodNotFound as i32, format!("unknown method {}", req.method))
+    }
+}
+
+// Updates the open documents, kept by URI, returning the one that changed, if any.
+fn update(docs : &mut HashMap<String, Vec<u8>>, not : Notification) -> Option<Uri> {
+    match not.method.as_str() {
+        "textDocument/didOpen" => {
+            let params : DidOpenTextDocumentParams = serde_json::from_value(not.params).ok()?;
+            let doc = params.text_document;
+            docs.insert(doc.uri.to_string(), doc.text.into_bytes());
+            Some(doc.uri)
+        },
+        "textDocument/didChange" => {
+            let params : DidChangeTextDocumentParams = serde_json::from_value(not.params).ok()?;
+            let text = params.content_changes.into_iter().last()?.text;
+            docs.insert(params.text_document.uri.to_string(), text.into_bytes());
+            Some(params.text_document.uri)
+        },
+        "textDocument/didClose" => {
+            let params : DidCloseTextDocumentParams = serde_json::from_value(not.params).ok()?;
+            docs.remove(params.text_document.uri.as_str());
+            Some(params.text_document.uri)
+        },
+        _ => None
+    }
+}
+
+fn failure<E : ToString>(err : E) -> SicError {
+    SicError::Io(io::Error::other(err.to_string()))
+}
+
+// Serves a client over a connection until it shuts the server down.
+pub fn serve(connection : &Connection) -> Result<(), SicError> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities).map_err(failure)?).map_err(failure)?;
+    let mut docs = HashMap::new();
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req).map_err(failure)? {
+                    return Ok(());
+                }
+                connection.sender.send(respond(&docs, req).into()).map_err(failure)?;
+            },
+            Message::Notification(not) => {
+                if let Some(uri) = update(&mut docs, not) {
+                    let diagnostics = docs.get(uri.as_str()).map_or(Vec::new(), |code| diagnostics(code));
+                    let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+                    let not = Notification::new("textDocument/publishDiagnostics".to_string(), params);
+                    connection.sender.send(not.into()).map_err(failure)?;
+                }
+            },
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,615 @@
+// An abstract machine that evaluates terms directly, without nets: a second reference to check the
+// reducer against, and a point of comparison for its speed. A term is loaded into a heap of nodes,
+// where each variable is a cell, filled when its lambda is applied, and each let holds its value,
+// shared by both copies and reduced at most once. Reduction to weak head normal form keeps the
+// spine on an explicit stack; the result is then reduced to normal form, part by part, and read
+// back.
+//
+// It follows what nets do where terms use variables outside of their lambdas, as lets make them
+// do: erasing a copy of a lambda erases its variable's other half, wherever that was used, and
+// binders with no occurrences erase what they're given. A term that ends up containing itself
+// can't be read back, which `ReadbackError::Cyclic` reports.
+
+use prelude::*;
+use net::Stats;
+use term::*;
+use term::Term::*;
+use error::{AffinityError, LimitError, ReadbackError, SicError};
+
+type Ptr = usize;
+
+#[derive(Clone, Copy, Debug)]
+enum Node {
+    Lam{var: usize, bod: Ptr},
+    App{fun: Ptr, arg: Ptr},
+    Par{fst: Ptr, snd: Ptr},
+    Era{val: Ptr, nxt: Ptr},
+    Var{var: usize},
+    // One of the two copies a let makes: the first, or the second.
+    Cop{dup: usize, fst: bool},
+    Ctr{ctr: usize},
+    Set
+}
+
+// What a name stands for: a variable's cell, or one of a let's copies.
+#[derive(Clone, Copy)]
+enum Binding {
+    Var{var: usize},
+    Cop{dup: usize, fst: bool}
+}
+
+// A variable's value: none yet, a term, or none ever, since the variable was erased. A value given
+// to an erased variable is erased.
+#[derive(Clone, Copy)]
+enum Cell {
+    Empty,
+    Full{val: Ptr},
+    Erased
+}
+
+// A let: its value, then the copies made of it, once it's reduced, and which copies were erased
+// before that. A value stuck on a free variable can't be copied, and stays a let.
+struct Let {
+    val: Ptr,
+    copies: Option<(Ptr, Ptr)>,
+    erased: (bool, bool),
+    // Whether the value is being reduced to weak head normal form, or was reduced to normal form,
+    // as a let that's read back.
+    forcing: bool,
+    normal: bool
+}
+
+// What `whnf` has yet to do with the term it reaches: apply it to an argument, or copy it for a
+// let, of which the node `at` is a copy.
+enum Frame {
+    App{arg: Ptr},
+    Cop{dup: usize, fst: bool, at: Ptr}
+}
+
+// Names given to variables and lets on read-back, and the lets found, to be read after the term.
+struct Names {
+    vars: BTreeMap<usize, Vec<u8>>,
+    dups: BTreeMap<usize, (Vec<u8>, Vec<u8>)>,
+    taken: BTreeSet<Vec<u8>>,
+    next: u32,
+    found: Vec<usize>
+}
+
+impl Names {
+    fn fresh(&mut self) -> Vec<u8> {
+        loop {
+            let nam = new_name(self.next);
+            self.next += 1;
+            if !self.taken.contains(&nam) {
+                return nam;
+            }
+        }
+    }
+
+    fn var(&mut self, var : usize) -> Vec<u8> {
+        if let Some(nam) = self.vars.get(&var) {
+            return nam.clone();
+        }
+        let nam = self.fresh();
+        self.vars.insert(var, nam.clone());
+        nam
+    }
+
+    fn cop(&mut self, dup : usize, fst : bool) -> Vec<u8> {
+        if !self.dups.contains_key(&dup) {
+            let names = (self.fresh(), self.fresh());
+            self.dups.insert(dup, names);
+            self.found.push(dup);
+        }
+        let (a, b) = &self.dups[&dup];
+        if fst { a.clone() } else { b.clone() }
+    }
+}
+
+struct Machine {
+    nodes: Vec<Node>,
+    cells: Vec<Cell>,
+    dups: Vec<Let>,
+    ctrs: Vec<Vec<u8>>,
+    stats: Stats,
+    fuel: u32,
+    // The nodes `normal` or `read` are within, to tell a term that contains itself.
+    path: BTreeSet<Ptr>
+}
+
+impl Machine {
+    fn alloc(&mut self, node : Node) -> Ptr {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn cell(&mut self) -> usize {
+        self.cells.push(Cell::Empty);
+        self.cells.len() - 1
+    }
+
+    fn dup(&mut self, val : Ptr) -> usize {
+        self.dups.push(Let { val, copies: None, erased: (false, false), forcing: false, normal: false });
+        self.dups.len() - 1
+    }
+
+    // Gives a variable its value.
+    fn fill(&mut self, var : usize, val : Ptr) -> Result<(), LimitError> {
+        match self.cells[var] {
+            Cell::Erased => self.erase(val),
+            _ => {
+                self.cells[var] = Cell::Full{val};
+                Ok(())
+            }
+        }
+    }
+
+    // Counts a rewrite, failing past the fuel.
+    fn step(&mut self) -> Result<(), LimitError> {
+        if self.stats.rules == self.fuel {
+            return Err(LimitError::Rewrites(self.fuel));
+        }
+        self.stats.rules += 1;
+        self.stats.loops += 1;
+        Ok(())
+    }
+
+    // Gives each binder of a term a cell or a let, in the order `load` meets them. Binders with no
+    // occurrences erase what they're given, as the ERA nodes of nets do.
+    fn bind(&mut self, term : &Term, counts : &BTreeMap<Vec<u8>, u32>, names : &mut BTreeMap<Vec<u8>, Binding>, ids : &mut Vec<usize>) {
+        let unused = |nam : &Vec<u8>| !counts.contains_key(nam) || nam == b"_" || nam == b"-";
+        match term {
+            Lam{nam, bod} => {
+                let var = self.cell();
+                ids.push(var);
+                if unused(nam) {
+                    self.cells[var] = Cell::Erased;
+                } else {
+                    names.insert(nam.clone(), Binding::Var{var});
+                }
+                self.bind(bod, counts, names, ids);
+            },
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                self.bind(a, counts, names, ids);
+                self.bind(b, counts, names, ids);
+            },
+            Dup{fst, snd, val, nxt} => {
+                let dup = self.dup(0);
+                ids.push(dup);
+                self.dups[dup].erased = (unused(fst), unused(snd));
+                for &(nam, fst) in &[(fst, true), (snd, false)] {
+                    if !unused(nam) {
+                        names.insert(nam.clone(), Binding::Cop{dup, fst});
+                    }
+                }
+                self.bind(val, counts, names, ids);
+                self.bind(nxt, counts, names, ids);
+            },
+            Var{..} | Ctr{..} | Set => {}
+        }
+    }
+
+    // Loads a term into the heap, with the binders `bind` numbered. Free variables get cells that
+    // are never filled.
+    fn load(&mut self, term : &Term, names : &mut BTreeMap<Vec<u8>, Binding>, ids : &[usize], order : &mut usize) -> Ptr {
+        let node = match term {
+            Lam{bod, ..} => {
+                let var = ids[*order];
+                *order += 1;
+                let bod = self.load(bod, names, ids, order);
+                Node::Lam{var, bod}
+            },
+            App{fun, arg} => {
+                let fun = self.load(fun, names, ids, order);
+                let arg = self.load(arg, names, ids, order);
+                Node::App{fun, arg}
+            },
+            Par{fst, snd} => {
+                let fst = self.load(fst, names, ids, order);
+                let snd = self.load(snd, names, ids, order);
+                Node::Par{fst, snd}
+            },
+            Dup{val, nxt, ..} => {
+                let dup = ids[*order];
+                *order += 1;
+                self.dups[dup].val = self.load(val, names, ids, order);
+                let nxt = self.load(nxt, names, ids, order);
+                // A let with neither copy used erases one when it's reached, like `&`, and the
+                // other with it.
+                if self.dups[dup].erased != (true, true) {
+                    return nxt;
+                }
+                self.dups[dup].erased = (false, true);
+                let val = self.alloc(Node::Cop{dup, fst: true});
+                Node::Era{val, nxt}
+            },
+            Era{val, nxt} => {
+                let val = self.load(val, names, ids, order);
+                let nxt = self.load(nxt, names, ids, order);
+                Node::Era{val, nxt}
+            },
+            Var{nam} => match names.get(nam) {
+                Some(Binding::Var{var}) => Node::Var{var: *var},
+                Some(Binding::Cop{dup, fst}) => Node::Cop{dup: *dup, fst: *fst},
+                None => {
+                    let var = self.cell();
+                    names.insert(nam.clone(), Binding::Var{var});
+                    Node::Var{var}
+                }
+            },
+            Ctr{nam} => match self.ctrs.iter().position(|ctr| ctr == nam) {
+                Some(ctr) => Node::Ctr{ctr},
+                None => {
+                    self.ctrs.push(nam.clone());
+                    Node::Ctr{ctr: self.ctrs.len() - 1}
+                }
+            },
+            Set => Node::Set
+        };
+        self.alloc(node)
+    }
+
+    // The constructor a term applies, with its arguments, if it's one.
+    fn ctr_spine(&self, at : Ptr) -> Option<(Ptr, Vec<Ptr>)> {
+        let mut args = Vec::new();
+        let mut at = at;
+        loop {
+            match self.nodes[at] {
+                Node::App{fun, arg} => {
+                    args.push(arg);
+                    at = fun;
+                },
+                Node::Ctr{..} => {
+                    args.reverse();
+                    return Some((at, args));
+                },
+                _ => return None
+            }
+        }
+    }
+
+    // Copies a term in weak head normal form for a let, if it can be.
+    fn copy(&mut self, at : Ptr) -> Result<Option<(Ptr, Ptr)>, LimitError> {
+        Ok(Some(match self.nodes[at] {
+            // `= a b \x bod` makes two lambdas, whose variables are superposed in place of `x`,
+            // and whose bodies are the copies of `bod`.
+            Node::Lam{var, bod} => {
+                self.step()?;
+                self.stats.dupls += 1;
+                let (fst, snd) = (self.cell(), self.cell());
+                let (fst_var, snd_var) = (self.alloc(Node::Var{var: fst}), self.alloc(Node::Var{var: snd}));
+                let sup = self.alloc(Node::Par{fst: fst_var, snd: snd_var});
+                self.fill(var, sup)?;
+                let dup = self.dup(bod);
+                let fst_bod = self.alloc(Node::Cop{dup, fst: true});
+                let snd_bod = self.alloc(Node::Cop{dup, fst: false});
+                (self.alloc(Node::Lam{var: fst, bod: fst_bod}), self.alloc(Node::Lam{var: snd, bod: snd_bod}))
+            },
+            Node::Par{fst, snd} => {
+                self.step()?;
+                self.stats.annis += 1;
+                (fst, snd)
+            },
+            Node::Set => {
+                self.step()?;
+                self.stats.erasures += 1;
+                (at, at)
+            },
+            _ => match self.ctr_spine(at) {
+                // A constructor is copied with its fields.
+                Some((ctr, args)) => {
+                    self.step()?;
+                    self.stats.dupls += 1;
+                    let (mut fst, mut snd) = (ctr, ctr);
+                    for arg in args {
+                        let dup = self.dup(arg);
+                        let fst_arg = self.alloc(Node::Cop{dup, fst: true});
+                        let snd_arg = self.alloc(Node::Cop{dup, fst: false});
+                        fst = self.alloc(Node::App{fun: fst, arg: fst_arg});
+                        snd = self.alloc(Node::App{fun: snd, arg: snd_arg});
+                    }
+                    (fst, snd)
+                },
+                None => return Ok(None)
+            }
+        }))
+    }
+
+    // Erases a term: lambdas leave sets in place of their variables, the parts of pairs are erased,
+    // and so are the values variables and copies get later. Applications stuck on a variable are
+    // left alone, as garbage.
+    fn erase(&mut self, at : Ptr) -> Result<(), LimitError> {
+        let mut todo = vec![at];
+        while let Some(at) = todo.pop() {
+            match self.nodes[at] {
+                Node::Lam{var, bod} => {
+                    self.step()?;
+                    self.stats.erasures += 1;
+                    let set = self.alloc(Node::Set);
+                    self.fill(var, set)?;
+                    todo.push(bod);
+                },
+                Node::Par{fst, snd} => {
+                    self.step()?;
+                    self.stats.erasures += 1;
+                    todo.push(fst);
+                    todo.push(snd);
+                },
+                Node::Era{val, nxt} => {
+                    todo.push(val);
+                    todo.push(nxt);
+                },
+                // An application is reduced first, as a net reduces it once its function is a
+                // lambda, even with its result erased.
+                Node::App{..} => {
+                    let val = self.whnf(at)?;
+                    if let Node::App{..} = self.nodes[val] {
+                        continue;
+                    }
+                    todo.push(val);
+                },
+                Node::Var{var} => match self.cells[var] {
+                    Cell::Full{val} => todo.push(val),
+                    _ => self.cells[var] = Cell::Erased
+                },
+                Node::Cop{dup, fst} => match self.dups[dup].copies {
+                    Some((a, b)) => todo.push(if fst { a } else { b }),
+                    None => {
+                        let erased = &mut self.dups[dup].erased;
+                        if fst { erased.0 = true } else { erased.1 = true }
+                        // With both copies erased, the value is still copied, as a net copies it
+                        // once it's reduced, and the copies erased: a lambda's variable becomes a
+                        // pair of sets, rather than a set.
+                        if *erased == (true, true) {
+                            self.whnf(at)?;
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Reduces a term to weak head normal form: a lambda, a pair, a set, or a term stuck on a free
+    // variable, a let that can't be copied or a constructor.
+    fn whnf(&mut self, at : Ptr) -> Result<Ptr, LimitError> {
+        let mut stack = Vec::new();
+        let mut at = at;
+        loop {
+            match self.nodes[at] {
+                Node::App{fun, arg} => {
+                    stack.push(Frame::App{arg});
+                    at = fun;
+                    continue;
+                },
+                Node::Cop{dup, fst} => match self.dups[dup].copies {
+                    Some((a, b)) => {
+                        at = if fst { a } else { b };
+                        continue;
+                    },
+                    // A let whose value is being reduced, further down the stack, is stuck.
+                    None if !self.dups[dup].forcing => {
+                        self.dups[dup].forcing = true;
+                        stack.push(Frame::Cop{dup, fst, at});
+                        at = self.dups[dup].val;
+                        continue;
+                    },
+                    None => {}
+                },
+                Node::Var{var} => if let Cell::Full{val} = self.cells[var] {
+                    at = val;
+                    continue;
+                },
+                Node::Era{val, nxt} => {
+                    self.erase(val)?;
+                    at = nxt;
+                    continue;
+                },
+                _ => {}
+            }
+            let frame = match stack.pop() {
+                Some(frame) => frame,
+                None => return Ok(at)
+            };
+            match (frame, self.nodes[at]) {
+                (Frame::App{arg}, Node::Lam{var, bod}) => {
+                    self.step()?;
+                    self.stats.betas += 1;
+                    self.fill(var, arg)?;
+                    at = bod;
+                },
+                // `/| p q a` copies `a`, and applies each side of the pair to a copy.
+                (Frame::App{arg}, Node::Par{fst, snd}) => {
+                    self.step()?;
+                    self.stats.dupls += 1;
+                    let dup = self.dup(arg);
+                    let fst_arg = self.alloc(Node::Cop{dup, fst: true});
+                    let snd_arg = self.alloc(Node::Cop{dup, fst: false});
+                    let fst = self.alloc(Node::App{fun: fst, arg: fst_arg});
+                    let snd = self.alloc(Node::App{fun: snd, arg: snd_arg});
+                    at = self.alloc(Node::Par{fst, snd});
+                },
+                (Frame::App{arg}, Node::Set) => {
+                    self.step()?;
+                    self.stats.erasures += 1;
+                    self.erase(arg)?;
+                },
+                (Frame::Cop{dup, fst, at: cop}, _) => {
+                    self.dups[dup].forcing = false;
+                    match self.copy(at)? {
+                        Some((a, b)) => {
+                            self.dups[dup].copies = Some((a, b));
+                            let erased = self.dups[dup].erased;
+                            if erased.0 {
+                                self.erase(a)?;
+                            }
+                            if erased.1 {
+                                self.erase(b)?;
+                            }
+                            at = if fst { a } else { b };
+                        },
+                        None => {
+                            self.dups[dup].val = at;
+                            return self.stuck(cop, stack);
+                        }
+                    }
+                },
+                (Frame::App{arg}, _) => {
+                    let app = self.alloc(Node::App{fun: at, arg});
+                    return self.stuck(app, stack);
+                }
+            }
+        }
+    }
+
+    // Rebuilds the spine around a stuck term.
+    fn stuck(&mut self, at : Ptr, mut stack : Vec<Frame>) -> Result<Ptr, LimitError> {
+        let mut at = at;
+        while let Some(frame) = stack.pop() {
+            at = match frame {
+                Frame::App{arg} => self.alloc(Node::App{fun: at, arg}),
+                Frame::Cop{dup, at: cop, ..} => {
+                    self.dups[dup].val = at;
+                    self.dups[dup].forcing = false;
+                    cop
+                }
+            };
+        }
+        Ok(at)
+    }
+
+    // Reduces a term to normal form, in place, with the values of the lets it's stuck on. Parts are
+    // reduced in the order they're reached, and reducing one may give a value to a variable already
+    // reached in another, as erasing a copy of a lambda gives its variable `*`: `read` reduces what
+    // that unblocks.
+    fn normal(&mut self, at : Ptr) -> Result<Ptr, SicError> {
+        let at = self.whnf(at)?;
+        if !self.path.insert(at) {
+            return Err(ReadbackError::Cyclic.into());
+        }
+        match self.nodes[at] {
+            Node::Lam{var, bod} => {
+                let bod = self.normal(bod)?;
+                self.nodes[at] = Node::Lam{var, bod};
+            },
+            Node::App{fun, arg} => {
+                let fun = self.normal(fun)?;
+                let arg = self.normal(arg)?;
+                self.nodes[at] = Node::App{fun, arg};
+            },
+            Node::Par{fst, snd} => {
+                let fst = self.normal(fst)?;
+                let snd = self.normal(snd)?;
+                self.nodes[at] = Node::Par{fst, snd};
+            },
+            Node::Cop{dup, ..} if !self.dups[dup].normal => {
+                self.dups[dup].normal = true;
+                self.dups[dup].val = self.normal(self.dups[dup].val)?;
+            },
+            _ => {}
+        }
+        self.path.remove(&at);
+        Ok(at)
+    }
+
+    // Reads a normal form back. Parts that a later reduction made reducible again, like a let of
+    // a variable that got its value, are reduced as they're reached.
+    fn read(&mut self, at : Ptr, names : &mut Names) -> Result<Term, SicError> {
+        let at = self.whnf(at)?;
+        if !self.path.insert(at) {
+            return Err(ReadbackError::Cyclic.into());
+        }
+        let term = match self.nodes[at] {
+            Node::Lam{var, bod} => {
+                let nam = names.var(var);
+                Lam{nam, bod: Box::new(self.read(bod, names)?)}
+            },
+            Node::App{fun, arg} => {
+                let fun = Box::new(self.read(fun, names)?);
+                App{fun, arg: Box::new(self.read(arg, names)?)}
+            },
+            Node::Par{fst, snd} => {
+                let fst = Box::new(self.read(fst, names)?);
+                Par{fst, snd: Box::new(self.read(snd, names)?)}
+            },
+            Node::Var{var} => Var{nam: names.var(var)},
+            Node::Cop{dup, fst} => Var{nam: names.cop(dup, fst)},
+            Node::Ctr{ctr} => Ctr{nam: self.ctrs[ctr].clone()},
+            Node::Set | Node::Era{..} => Set
+        };
+        self.path.remove(&at);
+        Ok(term)
+    }
+}
+
+// Names the binders no variable refers to `-`, as `from_net` does.
+fn mark_unused(term : &mut Term, counts : &BTreeMap<Vec<u8>, u32>) {
+    let unused = |nam : &mut Vec<u8>| if !counts.contains_key(nam) {
+        *nam = b"-".to_vec();
+    };
+    match term {
+        Lam{nam, bod} => {
+            unused(nam);
+            mark_unused(bod, counts);
+        },
+        App{fun: a, arg: b} | Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+            mark_unused(a, counts);
+            mark_unused(b, counts);
+        },
+        Dup{fst, snd, val, nxt} => {
+            unused(fst);
+            unused(snd);
+            mark_unused(val, counts);
+            mark_unused(nxt, counts);
+        },
+        Var{..} | Ctr{..} | Set => {}
+    }
+}
+
+// Evaluates a term to normal form with the machine, within `fuel` rewrites, returning it with the
+// machine's own stats: each beta reduction, copy, projection and erasure counts as a rewrite, and
+// `max_nodes` is how many nodes the heap grew to. Lets stuck on free variables are placed at the
+// top, as read-back places them, and binders are named as `canonicalize` names them.
+pub fn eval_machine(term : &Term, fuel : u32) -> Result<(Term, Stats), SicError> {
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    if let Some((nam, _)) = counts.iter().find(|(_, count)| **count > 1) {
+        return Err(AffinityError::Duplicated(nam.clone()).into());
+    }
+    let mut machine = Machine {
+        nodes: Vec::new(),
+        cells: Vec::new(),
+        dups: Vec::new(),
+        ctrs: Vec::new(),
+        stats: Stats::default(),
+        fuel,
+        path: BTreeSet::new()
+    };
+    let mut bound = BTreeMap::new();
+    let mut ids = Vec::new();
+    machine.bind(term, &counts, &mut bound, &mut ids);
+    let first_free = machine.cells.len();
+    let root = machine.load(term, &mut bound, &ids, &mut 0);
+    let mut names = Names { vars: BTreeMap::new(), dups: BTreeMap::new(), taken: BTreeSet::new(), next: 0, found: Vec::new() };
+    for (nam, binding) in &bound {
+        if let Binding::Var{var} = binding {
+            if *var >= first_free {
+                names.vars.insert(*var, nam.clone());
+                names.taken.insert(nam.clone());
+            }
+        }
+    }
+    let root = machine.normal(root)?;
+    let mut norm = machine.read(root, &mut names)?;
+    while let Some(dup) = names.found.pop() {
+        let val = machine.read(machine.dups[dup].val, &mut names)?;
+        let (fst, snd) = names.dups[&dup].clone();
+        norm = Dup{fst, snd, val: Box::new(val), nxt: Box::new(norm)};
+    }
+    let mut counts = BTreeMap::new();
+    occurrences(&norm, &mut counts);
+    mark_unused(&mut norm, &counts);
+    machine.stats.max_nodes = machine.nodes.len() as u32;
+    Ok((canonicalize(&norm), machine.stats))
+}
@@ -1,25 +1,125 @@
 extern crate clap;
-use clap::{Arg, App};
+extern crate sic;
+#[cfg(feature = "lsp")]
+extern crate lsp_server;
 
-mod term;
-mod net;
+use clap::{Arg, App, AppSettings, ArgMatches, SubCommand};
 
-use term::*;
+use sic::net;
+use sic::term::*;
+use sic::module::*;
+use sic::error::*;
+use sic::eval::*;
 
+use std::collections::BTreeMap;
 use std::io;
 use std::io::prelude::*;
+use std::fs;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-fn main() -> io::Result<()> {
-    let matches = App::new("Symmetric Interaction Calculus")
+// The stack `run` is given. Terms are walked recursively, so deep ones, like the long lists
+// --stdin-arg reads, need more than the main thread has.
+const STACK_SIZE : usize = 1 << 30;
+
+fn main() {
+    let result = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(run).unwrap().join().unwrap();
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(exit_code(&err));
+    }
+}
+
+// Exit codes follow sysexits.h: bad input is EX_DATAERR, I/O failures are EX_IOERR, and problems
+// inside the evaluator are EX_SOFTWARE. Running out of a resource limit is EX_TEMPFAIL.
+fn exit_code(err : &SicError) -> i32 {
+    match err {
+        SicError::Parse(_) | SicError::Affinity(_) | SicError::Encode(_) | SicError::Export(_) => 65,
+        SicError::Io(_) => 74,
+        SicError::Net(_) | SicError::Readback(_) => 70,
+        SicError::Limit(_) => 75
+    }
+}
+
+fn run() -> Result<(), SicError> {
+    let app = App::new("Symmetric Interaction Calculus")
         .version("0.1.0")
         .author("Victor Maia <srvictormaia@gmail.com>")
         .about("Evaluates SIC programs")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("INPUT")
             .short("i")
             .long("input")
             .value_name("INPUT")
-            .help("Input term")
+            .help("Input term, applied to the entry point (can be repeated)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("STDIN_ARG")
+            .long("stdin-arg")
+            .help("Reads standard input to its end, and applies the entry point to its bytes, as a list of numerals, after the inputs"))
+        .arg(Arg::with_name("OUTPUT_BYTES")
+            .long("output-bytes")
+            .help("Writes the normal form, a list of numerals below 256, to standard output as raw bytes")
+            .conflicts_with_all(&["EVAL", "STATS"]))
+        .arg(Arg::with_name("ENTRY")
+            .short("e")
+            .long("entry")
+            .value_name("ENTRY")
+            .help("Definition to evaluate instead of the main term")
+            .takes_value(true))
+        .arg(Arg::with_name("EVAL")
+            .long("eval")
+            .value_name("NAME,...")
+            .help("Evaluates each of these definitions in turn, printing its name before its result")
+            .conflicts_with("ENTRY")
+            .takes_value(true)
+            .use_delimiter(true))
+        .arg(Arg::with_name("MAX_REWRITES")
+            .long("max-rewrites")
+            .value_name("N")
+            .help("Stops after N rewrites")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("MAX_NODES")
+            .long("max-nodes")
+            .value_name("N")
+            .help("Stops once the net grows past N nodes")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("TIMEOUT")
+            .long("timeout")
+            .value_name("MS")
+            .help("Stops after MS milliseconds")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("TRACE_CSV")
+            .long("trace-csv")
+            .value_name("FILE")
+            .help("Writes the size of the net to FILE as it's reduced, as CSV")
+            .takes_value(true))
+        .arg(Arg::with_name("TRACE_EVERY")
+            .long("trace-every")
+            .value_name("N")
+            .help("Writes a row of --trace-csv every N rewrites [default: 1000]")
+            .requires("TRACE_CSV")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("FUEL")
+            .long("fuel")
+            .value_name("NAME=N")
+            .help("Stops once N rewrites are attributed to definition NAME (can be repeated)")
+            .validator(is_fuel)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("DECODE")
+            .long("decode")
+            .value_name("DECODE")
+            .help("Prints the normal form as data")
+            .possible_values(&["term", "nat", "bits", "string"])
+            .default_value("term")
             .takes_value(true))
         .arg(Arg::with_name("STATS")
             .short("s")
@@ -27,42 +127,921 @@ fn main() -> io::Result<()> {
             .value_name("STATS")
             .help("Show stats")
             .takes_value(false))
+        .arg(Arg::with_name("CHECK_FANS")
+            .long("check-fans")
+            .help("Warns about fans that annihilate where the calculus would have them commute"))
+        .arg(Arg::with_name("ENGINE")
+            .long("engine")
+            .value_name("ENGINE")
+            .help("Reduces the program's net, or the term itself with an abstract machine")
+            .possible_values(&["net", "machine"])
+            .conflicts_with_all(&["CHECK_FANS", "FUEL", "MAX_NODES", "TIMEOUT", "TRACE_CSV"])
+            .takes_value(true))
+        .arg(Arg::with_name("OPEN")
+            .long("open")
+            .help("Allows free variables, which are kept by name through reduction"))
+        .arg(Arg::with_name("LENIENT")
+            .long("lenient")
+            .help("Ignores whatever follows the main term, rather than rejecting anything but comments"))
+        .arg(Arg::with_name("PRUNE")
+            .long("prune")
+            .help("Drops the definitions the entry point doesn't reach before expanding, so those that don't parse don't matter"))
+        .arg(Arg::with_name("VERBOSE")
+            .long("verbose")
+            .help("Reports the definitions --prune dropped, on the standard error")
+            .requires("PRUNE"))
+        .arg(Arg::with_name("SIMPLIFY")
+            .long("simplify")
+            .help("Tidies the result, collapsing identities, erased copies and lets paired back"))
+        .arg(Arg::with_name("EXPAND_DUPS")
+            .long("expand-dups")
+            .help("Prints the result with each let's value written out where its copies are used"))
+        .arg(Arg::with_name("KEEP_DUPS")
+            .long("keep-dups")
+            .help("Prints the result with its lets, which is the default")
+            .conflicts_with("EXPAND_DUPS"))
+        .arg(Arg::with_name("MAX_EXPANSION")
+            .long("max-expansion")
+            .value_name("MAX_EXPANSION")
+            .help("Largest term, in nodes, --expand-dups may print")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("MAX_OUTPUT_DEPTH")
+            .long("max-output-depth")
+            .value_name("MAX_OUTPUT_DEPTH")
+            .help("Prints terms nested deeper than this as `…` and their number of nodes")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("MAX_OUTPUT_SIZE")
+            .long("max-output-size")
+            .value_name("MAX_OUTPUT_SIZE")
+            .help("Prints terms past this many nodes as `…` and their number of nodes")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("CACHE_DIR")
+            .long("cache-dir")
+            .value_name("PATH")
+            .help("Keeps normal forms in a directory, and takes them from there instead of reducing again")
+            .takes_value(true))
+        .arg(Arg::with_name("NO_CACHE")
+            .long("no-cache")
+            .help("Ignores --cache-dir"))
+        .arg(Arg::with_name("DUMP_AST")
+            .long("dump-ast")
+            .help("Prints the parsed term as a tree and exits without reducing it"))
+        .arg(Arg::with_name("DESUGAR")
+            .long("desugar")
+            .help("Prints the program with its sugar and definitions expanded, and exits without reducing it"))
+        .arg(Arg::with_name("REPORT")
+            .long("report")
+            .value_name("FILE")
+            .help("Also writes a report of the reduction to FILE, as a page of HTML")
+            .conflicts_with_all(&["ENGINE", "EVAL", "STDIN_ARG"])
+            .takes_value(true))
+        .arg(Arg::with_name("WASTE")
+            .long("waste")
+            .help("Also prints how much of the reduction's work went into what it threw away, as estimated by tagging the nodes each rewrite makes")
+            .conflicts_with_all(&["ENGINE", "EVAL", "STDIN_ARG", "OUTPUT_BYTES"]))
+        .arg(Arg::with_name("TRACE_TERMS")
+            .long("trace-terms")
+            .help("Prints the term after each rewrite, read back from the net, when it changed")
+            .conflicts_with_all(&["ENGINE", "EVAL", "STDIN_ARG"]))
+        .arg(Arg::with_name("TRACE_TERMS_EVERY")
+            .long("trace-terms-every")
+            .value_name("N")
+            .help("Prints the term of --trace-terms every N rewrites instead")
+            .requires("TRACE_TERMS")
+            .validator(is_number)
+            .takes_value(true))
+        .arg(Arg::with_name("SYNTAX")
+            .long("syntax")
+            .value_name("SYNTAX")
+            .help("Syntax of the input file")
+            .possible_values(&["sic", "hvm"])
+            .default_value("sic")
+            .takes_value(true))
         .arg(Arg::with_name("FILE")
             .help("Sets the input file to use")
             .required(true)
             .index(1))
-        .get_matches();
+        .subcommand(SubCommand::with_name("deps")
+            .about("Shows which definitions reference which")
+            .arg(Arg::with_name("ENTRY")
+                .short("e")
+                .long("entry")
+                .value_name("ENTRY")
+                .help("Definition to start from (defaults to the main term)")
+                .takes_value(true))
+            .arg(Arg::with_name("TOPO")
+                .short("t")
+                .long("topo")
+                .help("Prints definitions in topological order")
+                .conflicts_with("DOT"))
+            .arg(Arg::with_name("DOT")
+                .long("dot")
+                .help("Prints the dependency graph in Graphviz format"))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("bench")
+            .about("Reduces every program in a directory and reports timings, with those of encoding its net")
+            .arg(Arg::with_name("ENGINE")
+                .long("engine")
+                .value_name("ENGINE")
+                .help("Reduces each program's net, or the term itself with an abstract machine")
+                .possible_values(&["net", "machine"])
+                .default_value("net")
+                .takes_value(true))
+            .arg(Arg::with_name("DIR")
+                .help("Directory containing .sic programs")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("cache")
+            .about("Manages a directory of normal forms kept by --cache-dir")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("clear")
+                .about("Removes the normal forms kept in a directory")
+                .arg(Arg::with_name("DIR")
+                    .help("Directory given to --cache-dir")
+                    .required(true)
+                    .index(1))))
+        .subcommand(SubCommand::with_name("canon")
+            .about("Prints the canonical normal form of a program")
+            .arg(Arg::with_name("HASH")
+                .long("hash")
+                .help("Prints only a hash of the canonical normal form"))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("fmt")
+            .about("Formats a SIC source file")
+            .arg(Arg::with_name("WRITE")
+                .short("w")
+                .long("write")
+                .help("Rewrites the file in place")
+                .conflicts_with("CHECK"))
+            .arg(Arg::with_name("CHECK")
+                .long("check")
+                .help("Shows what would change and fails if the file isn't formatted"))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("lift")
+            .about("Evaluates a program and lambda-lifts its normal form into definitions")
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("transform")
+            .about("Transforms the definitions and main term of a program, and prints it")
+            .arg(Arg::with_name("CPS")
+                .long("cps")
+                .help("Translates to continuation-passing style: apply the main term to a continuation, like \\x x")
+                .required(true))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("prune")
+            .about("Writes a program with only the definitions its entry point reaches")
+            .arg(Arg::with_name("ENTRY")
+                .short("e")
+                .long("entry")
+                .value_name("ENTRY")
+                .help("Definition to start from (defaults to the main term, which `main` also names)")
+                .takes_value(true))
+            .arg(Arg::with_name("OUTPUT")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Writes the program to a file instead of the standard output")
+                .takes_value(true))
+            .arg(Arg::with_name("VERBOSE")
+                .long("verbose")
+                .help("Reports the definitions dropped, on the standard error"))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("lint")
+            .about("Reports unbound and duplicated variables, and definitions that are never used")
+            .arg(Arg::with_name("ENTRY")
+                .short("e")
+                .long("entry")
+                .value_name("ENTRY")
+                .help("Definition to start from (defaults to the main term)")
+                .takes_value(true))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("net")
+            .about("Prints the net of a program")
+            .arg(Arg::with_name("REDUCED")
+                .long("reduced")
+                .help("Reduces the net first"))
+            .arg(Arg::with_name("LEAKS")
+                .long("leaks")
+                .help("Prints the nodes the root can't reach, by kind and definition, instead of the net"))
+            .arg(Arg::with_name("DOT")
+                .long("dot")
+                .help("Prints the net in Graphviz format")
+                .conflicts_with("LEAKS"))
+            .arg(Arg::with_name("ASCII")
+                .long("ascii")
+                .help("Draws the net as text, one box per node, main ports on top (refuses large nets)")
+                .conflicts_with_all(&["LEAKS", "DOT"]))
+            .arg(Arg::with_name("RANK")
+                .long("rank")
+                .help("Ranks the nodes of the graph by their distance from the root")
+                .requires("DOT"))
+            .arg(Arg::with_name("COLOR")
+                .long("color")
+                .value_name("KIND=COLOR")
+                .help("Fills the graph's nodes of a kind with a color (can be repeated)")
+                .validator(is_color)
+                .requires("DOT")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("import")
+            .about("Translates a program in HVM's syntax to SIC")
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)))
+        .subcommand(SubCommand::with_name("export")
+            .about("Translates a program to another language")
+            .arg(Arg::with_name("FORMAT")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Language to translate to")
+                .possible_values(&["hvm", "js", "rust"])
+                .required(true)
+                .takes_value(true))
+            .arg(Arg::with_name("OUTPUT")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Writes the translation to a file instead of the standard output")
+                .takes_value(true))
+            .arg(Arg::with_name("NAME")
+                .short("n")
+                .long("name")
+                .value_name("NAME")
+                .help("Names the function a Rust translation defines (the file's name by default)")
+                .takes_value(true))
+            .arg(Arg::with_name("FILE")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1)));
+    let app = app.subcommand(SubCommand::with_name("rules")
+        .about("Shows the interaction rules, as the reducer implements them")
+        .arg(Arg::with_name("PAIR")
+            .long("pair")
+            .value_names(&["KIND", "KIND"])
+            .help("Shows only the rule for this pair of node kinds, where ctr is any constructor")
+            .possible_values(&["era", "con", "fan", "ctr"])
+            .number_of_values(2)
+            .takes_value(true))
+        .arg(Arg::with_name("EXAMPLE")
+            .long("example")
+            .help("Also prints the net of each rule before and after rewriting it")));
+    #[cfg(feature = "lsp")]
+    let app = app.subcommand(SubCommand::with_name("lsp")
+        .about("Runs a language server on the standard input and output"));
+    let matches = app.get_matches();
 
-    let file_name = matches.value_of("FILE").unwrap();
+    match matches.subcommand() {
+        ("deps", Some(matches)) => return deps(matches),
+        ("bench", Some(matches)) => return bench(matches),
+        ("cache", Some(matches)) => return cache(matches),
+        ("canon", Some(matches)) => return canon(matches),
+        ("fmt", Some(matches)) => return fmt(matches),
+        ("lift", Some(matches)) => return lift(matches),
+        ("transform", Some(matches)) => return transform(matches),
+        ("prune", Some(matches)) => return prune(matches),
+        ("lint", Some(matches)) => return lint(matches),
+        ("net", Some(matches)) => return show_net(matches),
+        ("import", Some(matches)) => return import(matches),
+        ("export", Some(matches)) => return export(matches),
+        ("rules", Some(matches)) => return rules(matches),
+        #[cfg(feature = "lsp")]
+        ("lsp", Some(_)) => return lsp(),
+        _ => {}
+    }
+
+    let mut code = read_file(matches.value_of("FILE").unwrap())?;
+
+    if matches.value_of("SYNTAX") == Some("hvm") {
+        code = format_module(&from_hvm(&code)?);
+    }
+
+    let number = |name : &str| matches.value_of(name).map(|value| value.parse::<u32>().unwrap());
+    let opts = EvalOptions {
+        inputs: matches.values_of("INPUT").map_or(Vec::new(), |inputs| inputs.map(String::from).collect()),
+        entry: matches.value_of("ENTRY").map(String::from),
+        max_rewrites: number("MAX_REWRITES"),
+        max_nodes: number("MAX_NODES"),
+        max_time: number("TIMEOUT").map(|ms| Duration::from_millis(ms as u64)),
+        fuel: matches.values_of("FUEL").map_or(Vec::new(), |fuel| fuel.map(|budget| {
+            let (nam, fuel) = budget.split_once('=').unwrap();
+            (nam.to_string(), fuel.parse().unwrap())
+        }).collect()),
+        decode: match matches.value_of("DECODE") {
+            _ if matches.is_present("OUTPUT_BYTES") => Decode::Bytes,
+            Some("nat") => Decode::Nat,
+            Some("bits") => Decode::Bits,
+            Some("string") => Decode::String,
+            _ => Decode::Term
+        },
+        stats: matches.is_present("STATS"),
+        check_fans: matches.is_present("CHECK_FANS"),
+        open: matches.is_present("OPEN"),
+        simplify: matches.is_present("SIMPLIFY"),
+        expand_dups: if matches.is_present("EXPAND_DUPS") {
+            Some(number("MAX_EXPANSION").map_or(MAX_SIZE, |max| max as u64))
+        } else {
+            None
+        },
+        output_limits: OutputLimits {
+            depth: number("MAX_OUTPUT_DEPTH"),
+            size: number("MAX_OUTPUT_SIZE").map(|size| size as u64)
+        },
+        cache_dir: match matches.value_of("CACHE_DIR") {
+            Some(dir) if !matches.is_present("NO_CACHE") => Some(PathBuf::from(dir)),
+            _ => None
+        },
+        engine: if matches.value_of("ENGINE") == Some("machine") { Engine::Machine } else { Engine::Net },
+        trace_csv: matches.value_of("TRACE_CSV").map(|file| (PathBuf::from(file), number("TRACE_EVERY").unwrap_or(1000))),
+        bytes_input: if matches.is_present("STDIN_ARG") {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            Some(bytes)
+        } else {
+            None
+        },
+        strict: !matches.is_present("LENIENT"),
+        prune: matches.is_present("PRUNE")
+    };
+    let code = String::from_utf8_lossy(&code);
+
+    if matches.is_present("DUMP_AST") {
+        println!("{:#?}", program(&code, &opts)?);
+        return Ok(());
+    }
+
+    if matches.is_present("DESUGAR") {
+        println!("{}", String::from_utf8_lossy(&pretty(&program(&code, &opts)?)));
+        return Ok(());
+    }
+
+    // With --trace-terms, each term is printed after a comment with the rewrites that led to it.
+    if matches.is_present("TRACE_TERMS") {
+        let mut net = to_net(&program(&code, &opts)?)?;
+        trace_terms(&mut net, number("TRACE_TERMS_EVERY").unwrap_or(1), &mut |rules, term| match term {
+            Ok(term) => println!("({})\n{}", rules, String::from_utf8_lossy(&pretty(&term))),
+            Err(err) => println!("({}: can't read back, {})", rules, err)
+        })?;
+        return Ok(());
+    }
+
+    // With --report, the program is reduced once more on its own, keeping what the report shows.
+    if let Some(file) = matches.value_of("REPORT") {
+        let net = to_net(&program(&code, &opts)?)?;
+        let run = sic::report::record(&code, net, opts.max_rewrites.unwrap_or(u32::MAX));
+        fs::write(file, sic::report::to_html(&run))?;
+    }
+
+    // With --eval, each goal is the entry point of its own evaluation, printed after its name. They
+    // share a cache, so the definitions are only encoded once.
+    let goals : Vec<Option<String>> = match matches.values_of("EVAL") {
+        Some(names) => names.map(|nam| Some(nam.to_string())).collect(),
+        None => vec![opts.entry.clone()]
+    };
+    let mut cache = NetCache::default();
+    let mut stopped = false;
+    for goal in &goals {
+        let opts = EvalOptions { entry: goal.clone(), ..opts.clone() };
+        if matches.is_present("VERBOSE") {
+            report_pruned(&code, goal.as_deref())?;
+        }
+        let outcome = eval_cached(&code, &opts, &mut cache)?;
+
+        // With --output-bytes, the bytes are written as they're decoded, with nothing after them.
+        match goal {
+            _ if opts.decode == Decode::Bytes && outcome.completed => {
+                let mut out = io::BufWriter::new(io::stdout().lock());
+                let mut failed = None;
+                sic::data::write_bytes(&outcome.term, &mut |byte| if failed.is_none() {
+                    failed = out.write_all(&[byte]).err();
+                })?;
+                if let Some(err) = failed {
+                    return Err(err.into());
+                }
+                out.flush()?;
+            },
+            Some(nam) if matches.is_present("EVAL") => println!("{}: {}", nam, outcome.printed),
+            _ => println!("{}", outcome.printed)
+        }
+        if outcome.elided > 0 {
+            eprintln!("note: {} nodes were left out of the output", outcome.elided);
+        }
+
+        if let Some(stats) = outcome.stats {
+            println!("{}", stats);
+            println!("cache: {} hits, {} misses", cache.hits, cache.misses);
+            if opts.cache_dir.is_some() {
+                println!("cached: {}", outcome.cached);
+            }
+        }
+
+        if !outcome.unsound.is_empty() {
+            warn_unsound(code.as_bytes(), &outcome.unsound);
+        }
+
+        if let Some(err) = outcome.exhausted {
+            eprintln!("error: {}", err);
+            stopped = true;
+        } else if !outcome.completed {
+            eprintln!("error: stopped at a limit before reaching normal form");
+            stopped = true;
+        }
+    }
+
+    if stopped {
+        std::process::exit(75);
+    }
+
+    // With --waste, the program is reduced once more on its own, now that it's known to finish.
+    if matches.is_present("WASTE") {
+        let (_, waste) = sic::waste::reduce_with_waste(&mut to_net(&program(&code, &opts)?)?);
+        println!("{}", waste);
+    }
+
+    Ok(())
+}
+
+// Warns that the result may be wrong, listing each pair of fans that annihilated unsoundly once,
+// with how many times it did.
+fn warn_unsound(code : &[u8], unsound : &[(sic::fans::FanOrigin, sic::fans::FanOrigin)]) {
+    eprintln!("warning: {} fan annihilation(s) may be unsound; the result may not be the normal form", unsound.len());
+    let mut seen : Vec<(String, usize)> = Vec::new();
+    for (fst, snd) in unsound {
+        let line = format!("{} met {}", sic::fans::describe(code, fst), sic::fans::describe(code, snd));
+        match seen.iter_mut().find(|(seen, _)| *seen == line) {
+            Some((_, count)) => *count += 1,
+            None => seen.push((line, 1))
+        }
+    }
+    for (line, count) in seen {
+        if count == 1 {
+            eprintln!("  {}", line);
+        } else {
+            eprintln!("  {} ({} times)", line, count);
+        }
+    }
+}
+
+fn is_number(value : String) -> Result<(), String> {
+    value.parse::<u32>().map(|_| ()).map_err(|_| format!("{} isn't a number", value))
+}
+
+fn is_fuel(value : String) -> Result<(), String> {
+    match value.split_once('=') {
+        Some((nam, fuel)) if !nam.is_empty() => is_number(fuel.to_string()),
+        _ => Err(format!("{} isn't a budget, like name=1000", value))
+    }
+}
+
+// The kind of node a name, like `era`, stands for on the command line.
+fn kind_named(name : &str) -> Option<net::Kind> {
+    match name {
+        "era" => Some(net::Kind::Era),
+        "con" => Some(net::Kind::Con),
+        "fan" => Some(net::Kind::Fan),
+        "free" => Some(net::Kind::Free),
+        "root" => Some(net::Kind::Root),
+        _ => None
+    }
+}
+
+fn is_color(value : String) -> Result<(), String> {
+    match value.split_once('=') {
+        Some((kind, color)) if kind_named(kind).is_some() && !color.is_empty() => Ok(()),
+        _ => Err(format!("{} isn't a kind and a color, like fan=orange", value))
+    }
+}
+
+fn read_file(file_name : &str) -> io::Result<Vec<u8>> {
     let mut file = File::open(file_name)?;
     let mut code = Vec::new();
     file.read_to_end(&mut code)?;
+    Ok(code)
+}
 
-    let input : Option<Vec<u8>> = match matches.value_of("INPUT") {
-            Some(term) => Some(term.as_bytes().to_vec()),
-            None => None
-    };
+// Prints the definition dependency graph of a file.
+fn deps(matches : &ArgMatches) -> Result<(), SicError> {
+    fn print_tree(deps : &[(Vec<u8>, Vec<Vec<u8>>)], refs : &[Vec<u8>], depth : usize, path : &mut Vec<Vec<u8>>, seen : &mut Vec<Vec<u8>>) {
+        for nam in refs {
+            let indent = "  ".repeat(depth);
+            let name = String::from_utf8_lossy(nam);
+            if path.contains(nam) {
+                println!("{}{} (cycle)", indent, name);
+            } else if seen.contains(nam) {
+                println!("{}{} (see above)", indent, name);
+            } else {
+                println!("{}{}", indent, name);
+                seen.push(nam.clone());
+                path.push(nam.clone());
+                let next = &deps.iter().find(|(def, _)| def == nam).unwrap().1;
+                print_tree(deps, next, depth + 1, path, seen);
+                path.pop();
+            }
+        }
+    }
 
-    match input {
-        Some(mut input) => {
-            code.extend_from_slice(b"\n:main ");
-            code.append(&mut input);
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let module = parse_module(&code)?;
+    let entry = matches.value_of("ENTRY").map(|nam| nam.as_bytes());
+    let deps = module.dependencies();
+    let show = |nam : &Vec<u8>| String::from_utf8_lossy(nam).into_owned();
+
+    if matches.is_present("DOT") {
+        println!("digraph deps {{");
+        for (nam, refs) in &deps {
+            println!("  \"{}\";", show(nam));
+            for r in refs {
+                println!("  \"{}\" -> \"{}\";", show(nam), show(r));
+            }
+        }
+        if let Some(ref main) = module.main {
+            for r in references(main, &module.defs) {
+                println!("  main -> \"{}\";", show(&r));
+            }
+        }
+        println!("}}");
+        return Ok(());
+    }
+
+    if matches.is_present("TOPO") {
+        for nam in module.topological_order() {
+            println!("{}", show(&nam));
+        }
+    } else {
+        match entry {
+            Some(nam) => {
+                if module.find(nam).is_none() {
+                    eprintln!("Unknown definition: {}.", String::from_utf8_lossy(nam));
+                    std::process::exit(1);
+                }
+                println!("{}", String::from_utf8_lossy(nam));
+                let refs = &deps.iter().find(|(def, _)| def == nam).unwrap().1;
+                print_tree(&deps, refs, 1, &mut vec![nam.to_vec()], &mut vec![nam.to_vec()]);
+            },
+            None => {
+                println!("main");
+                print_tree(&deps, &module.roots(None), 1, &mut vec![], &mut vec![]);
+            }
+        }
+    }
+
+    let unreachable = module.unreachable(entry);
+    if !unreachable.is_empty() {
+        println!("unreachable: {}", unreachable.iter().map(show).collect::<Vec<_>>().join(", "));
+    }
+    for cycle in module.cycles() {
+        let mut names : Vec<String> = cycle.iter().map(show).collect();
+        println!("recursive group of {}: {}", names.len(), names.join(", "));
+        names.push(names[0].clone());
+        println!("cycle: {}", names.join(" -> "));
+    }
+
+    Ok(())
+}
+
+// Reduces each `.sic` program in a directory and prints a table of stats and timings.
+fn bench(matches : &ArgMatches) -> Result<(), SicError> {
+    let mut paths : Vec<_> = fs::read_dir(Path::new(matches.value_of("DIR").unwrap()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sic"))
+        .collect();
+    paths.sort();
+
+    // Memory is the size of the net's nodes once reduced, which only grow, so it depends on the
+    // layout (see `net::NODE_SIZE`). The machine's heap isn't measured. Encoding is timed both
+    // ways a net is made: by `to_net` on the program with its definitions inlined, and by
+    // `Module::to_net`, which grafts a fragment for each reference instead.
+    let kib = |words : usize| format!("{:.1}", (words * 4) as f64 / 1024.0);
+    let ms = |time : Option<Duration>| time.map_or("-".to_string(), |time| format!("{:.3}", time.as_secs_f64() * 1000.0));
+    let mut total_rules : u64 = 0;
+    let mut total_nodes : u32 = 0;
+    let mut total_words = 0;
+    let mut total_inline = Duration::ZERO;
+    let mut total_graft = Duration::ZERO;
+    let mut total_time = 0.0;
+    println!("{:<24} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}", "program", "rules", "max_nodes", "memory (KiB)", "inline (ms)", "graft (ms)", "time (ms)");
+    for path in &paths {
+        let code = read_file(&path.to_string_lossy())?;
+        let start = Instant::now();
+        let term = from_string(&code)?;
+        let (stats, words, inline, graft, time) = if matches.value_of("ENGINE") == Some("machine") {
+            let start = Instant::now();
+            let (_, stats) = eval_machine(&term, u32::MAX)?;
+            (stats, None, None, None, start.elapsed())
+        } else {
+            let mut net = to_net(&term)?;
+            let inline = start.elapsed();
+            let start = Instant::now();
+            parse_module(&code)?.to_net(None)?;
+            let graft = start.elapsed();
+            let (stats, time) = net::reduce_timed(&mut net);
+            (stats, Some(net.nodes.len()), Some(inline), Some(graft), time)
+        };
+        let time = time.as_secs_f64() * 1000.0;
+        let name = path.file_name().unwrap().to_string_lossy();
+        let memory = words.map_or("-".to_string(), kib);
+        println!("{:<24} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12.3}", name, stats.rules, stats.max_nodes, memory, ms(inline), ms(graft), time);
+        total_rules += stats.rules as u64;
+        total_nodes = total_nodes.max(stats.max_nodes);
+        total_words = total_words.max(words.unwrap_or(0));
+        total_inline += inline.unwrap_or_default();
+        total_graft += graft.unwrap_or_default();
+        total_time += time;
+    }
+    let machine = matches.value_of("ENGINE") == Some("machine");
+    let memory = if machine { "-".to_string() } else { kib(total_words) };
+    let (inline, graft) = if machine { (None, None) } else { (Some(total_inline), Some(total_graft)) };
+    println!("{:<24} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12.3}", "total", total_rules, total_nodes, memory, ms(inline), ms(graft), total_time);
+
+    Ok(())
+}
+
+// Removes the normal forms kept in a cache directory.
+fn cache(matches : &ArgMatches) -> Result<(), SicError> {
+    if let ("clear", Some(matches)) = matches.subcommand() {
+        let count = clear_cache(Path::new(matches.value_of("DIR").unwrap()))?;
+        println!("removed {} normal form(s)", count);
+    }
+    Ok(())
+}
+
+// Evaluates a program and prints its normal form with canonical variable names, or its hash.
+fn canon(matches : &ArgMatches) -> Result<(), SicError> {
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let norm = reduce(&from_string(&code)?)?;
+    if matches.is_present("HASH") {
+        println!("{:016x}", canonical_hash(&norm));
+    } else {
+        println!("{}", canonicalize(&norm));
+    }
+    Ok(())
+}
+
+// Formats a source file, printing the result, writing it back, or checking it's already formatted.
+fn fmt(matches : &ArgMatches) -> Result<(), SicError> {
+    // Prints a line diff between two texts (longest common subsequence of lines).
+    fn print_diff(old : &str, new : &str) {
+        let old : Vec<&str> = old.lines().collect();
+        let new : Vec<&str> = new.lines().collect();
+        let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                println!(" {}", old[i]);
+                i += 1;
+                j += 1;
+            } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                println!("-{}", old[i]);
+                i += 1;
+            } else {
+                println!("+{}", new[j]);
+                j += 1;
+            }
+        }
+    }
+
+    let file_name = matches.value_of("FILE").unwrap();
+    let code = read_file(file_name)?;
+    let formatted = format_module(&parse_module(&code)?);
+
+    if matches.is_present("WRITE") {
+        if formatted != code {
+            fs::write(file_name, &formatted)?;
+        }
+    } else if matches.is_present("CHECK") {
+        if formatted != code {
+            println!("{} is not formatted:", file_name);
+            print_diff(&String::from_utf8_lossy(&code), &String::from_utf8_lossy(&formatted));
+            std::process::exit(1);
+        }
+    } else {
+        print!("{}", String::from_utf8_lossy(&formatted));
+    }
+
+    Ok(())
+}
+
+// Evaluates a program and prints its lambda-lifted normal form as a module.
+fn lift(matches : &ArgMatches) -> Result<(), SicError> {
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let norm = reduce(&from_string(&code)?)?;
+    print!("{}", String::from_utf8_lossy(&format_module(&lambda_lift(&norm))));
+    Ok(())
+}
+
+// Transforms a program's definitions and main term, and prints the module it becomes.
+fn transform(matches : &ArgMatches) -> Result<(), SicError> {
+    let module = parse_module(&read_file(matches.value_of("FILE").unwrap())?)?;
+    print!("{}", String::from_utf8_lossy(&format_module(&cps_module(&module))));
+    Ok(())
+}
+
+// Translates a program from HVM's syntax and prints it as a formatted SIC module.
+fn import(matches : &ArgMatches) -> Result<(), SicError> {
+    let module = from_hvm(&read_file(matches.value_of("FILE").unwrap())?)?;
+    print!("{}", String::from_utf8_lossy(&format_module(&module)));
+    Ok(())
+}
+
+// Translates a program to HVM's syntax, or a term (usually a normal form) to JavaScript or Rust.
+fn export(matches : &ArgMatches) -> Result<(), SicError> {
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let code = match matches.value_of("FORMAT") {
+        Some("js") => to_js(&from_string(&code)?)?,
+        Some("rust") => {
+            let file = Path::new(matches.value_of("FILE").unwrap());
+            let name = matches.value_of("NAME").map(String::from)
+                .unwrap_or_else(|| file.file_stem().map_or("term".into(), |stem| stem.to_string_lossy().into_owned()));
+            to_rust(&from_string(&code)?, &name)?
         },
-        None => {}
+        _ => to_hvm(&parse_module(&code)?)?
+    };
+    match matches.value_of("OUTPUT") {
+        Some(output) => fs::write(output, code)?,
+        None => print!("{}", code)
     }
+    Ok(())
+}
 
-    let term = from_string(&code);
-    let mut net = to_net(&term);
-    let stats = net::reduce(&mut net);
-    let norm = from_net(&net);
+// Writes a program with only the definitions its entry point reaches. Those it doesn't reach
+// needn't parse.
+fn prune(matches : &ArgMatches) -> Result<(), SicError> {
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let entry = matches.value_of("ENTRY").filter(|entry| *entry != "main");
+    let (module, _) = parse_module_pruned(&code, entry.map(str::as_bytes), false)?;
+    if matches.is_present("VERBOSE") {
+        report_pruned(&String::from_utf8_lossy(&code), entry)?;
+    }
+    let pruned = format_module(&module);
+    match matches.value_of("OUTPUT") {
+        Some(output) => fs::write(output, pruned)?,
+        None => print!("{}", String::from_utf8_lossy(&pruned))
+    }
+    Ok(())
+}
 
-    let output = to_string(&norm);
+// Prints the names of the definitions pruning drops for an entry point, if any, on the standard
+// error.
+fn report_pruned(code : &str, entry : Option<&str>) -> Result<(), SicError> {
+    let (_, dropped) = parse_module_pruned(code.as_bytes(), entry.map(str::as_bytes), false)?;
+    if !dropped.is_empty() {
+        let names : Vec<String> = dropped.iter().map(|nam| String::from_utf8_lossy(nam).into_owned()).collect();
+        eprintln!("pruned: {}", names.join(", "));
+    }
+    Ok(())
+}
 
-    println!("{}", String::from_utf8_lossy(&output));
+// Reports the variables `to_net` would reject, all at once, then the definitions not reachable,
+// directly or through other definitions, from the entry point.
+fn lint(matches : &ArgMatches) -> Result<(), SicError> {
+    let file_name = matches.value_of("FILE").unwrap();
+    let code = read_file(file_name)?;
+    let module = parse_module(&code)?;
+    let problems = validate_program(&code);
+    for problem in &problems {
+        println!("{}: {}", file_name, problem);
+    }
+    let unused = module.unreachable(matches.value_of("ENTRY").map(|nam| nam.as_bytes()));
+    for nam in &unused {
+        println!("{}: unused definition `{}`", file_name, String::from_utf8_lossy(nam));
+    }
+    if !problems.is_empty() || !unused.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// Prints the net of a program, as a list of nodes, a graph or a drawing, or a census of its
+// unreachable nodes: how many, of which kinds, and from which definitions, for the nodes that tell.
+fn show_net(matches : &ArgMatches) -> Result<(), SicError> {
+    let code = read_file(matches.value_of("FILE").unwrap())?;
+    let term = program(&String::from_utf8_lossy(&code), &EvalOptions::default())?;
+    let (mut net, _, names) = to_net_named(&term)?;
+    let mut defs = node_defs(&names);
+    if matches.is_present("REDUCED") {
+        reduce_defs(&mut net, &mut defs);
+    }
+    if matches.is_present("DOT") {
+        let mut opts = sic::dot::DotOptions { rank: matches.is_present("RANK"), ..Default::default() };
+        for color in matches.values_of("COLOR").into_iter().flatten() {
+            let (kind, color) = color.split_once('=').unwrap();
+            opts.colors.push((kind_named(kind).unwrap(), color.to_string()));
+        }
+        print!("{}", sic::dot::to_dot(&net, &opts));
+        return Ok(());
+    }
+    if matches.is_present("ASCII") {
+        match sic::ascii::to_ascii(&net) {
+            Some(text) => print!("{}", text),
+            None => {
+                eprintln!("error: the net has {} nodes, too many to draw as text (at most {}); draw it with --dot instead", net::node_count(&net), sic::ascii::MAX_ASCII_NODES);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    if !matches.is_present("LEAKS") {
+        net::print_net(&mut net);
+        return Ok(());
+    }
+    let leaks = net::unreachable_nodes(&net);
+    println!("unreachable: {} of {} nodes", leaks.len(), net::node_count(&net));
+    let mut kinds : BTreeMap<String, u32> = BTreeMap::new();
+    let mut origins : BTreeMap<String, u32> = BTreeMap::new();
+    for node in &leaks {
+        let kind = match net.kind(*node) {
+            net::Kind::Ctr{idx} => String::from_utf8_lossy(&net.ctrs[idx as usize].0).into_owned(),
+            kind => format!("{:?}", kind).to_lowercase()
+        };
+        *kinds.entry(kind).or_insert(0) += 1;
+        if let Some(def) = defs.get(node) {
+            *origins.entry(String::from_utf8_lossy(def).into_owned()).or_insert(0) += 1;
+        }
+    }
+    for (kind, count) in &kinds {
+        println!("  {}: {}", kind, count);
+    }
+    if !origins.is_empty() {
+        println!("from definitions:");
+        for (def, count) in &origins {
+            println!("  {}: {}", def, count);
+        }
+    }
+    Ok(())
+}
 
-    if matches.is_present("STATS") {
-        println!("{:?}", stats);
+// Prints the interaction rules, or the rule of one pair of node kinds.
+fn rules(matches : &ArgMatches) -> Result<(), SicError> {
+    let kind = |name : &str| match name {
+        "era" => net::ERA,
+        "con" => net::CON,
+        "fan" => net::FAN,
+        _ => net::CTR
+    };
+    let pairs : Vec<(u32, u32)> = match matches.values_of("PAIR") {
+        Some(names) => {
+            let kinds : Vec<u32> = names.map(kind).collect();
+            vec![(kinds[0], kinds[1])]
+        },
+        None => sic::rules::pairs()
+    };
+    for (idx, (a, b)) in pairs.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        print!("{}", sic::rules::show_rule(*a, *b));
+        if matches.is_present("EXAMPLE") && net::has_rule(*a, *b) {
+            let (mut net, x, y) = sic::rules::active_pair(*a, *b);
+            println!("  net before:");
+            net::print_net(&mut net);
+            net::rewrite(&mut net, x, y);
+            println!("  net after:");
+            net::print_net(&mut net);
+        }
     }
+    Ok(())
+}
 
+// Serves a language client on the standard input and output until it shuts the server down.
+#[cfg(feature = "lsp")]
+fn lsp() -> Result<(), SicError> {
+    let (connection, threads) = lsp_server::Connection::stdio();
+    sic::lsp::serve(&connection)?;
+    drop(connection);
+    threads.join()?;
     Ok(())
 }
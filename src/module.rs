@@ -0,0 +1,749 @@
+// A module is a source file viewed as a list of top-level definitions plus an optional main term.
+// Unlike `from_string`, which inlines every definition as it parses, this keeps the definitions
+// separate, so tools can inspect how they relate before anything is expanded.
+
+use alloc::fmt;
+use prelude::*;
+use term::*;
+use net::*;
+use error::*;
+use term::Term::{*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+    // Top-level `:name value` definitions, in source order.
+    pub defs: Vec<(Vec<u8>, Term)>,
+
+    // The term after the last definition, if any.
+    pub main: Option<Term>,
+
+    // Top-level comments, each with the index of the item it precedes: a definition, the main
+    // term (`defs.len()`), or the end of the file (`defs.len() + 1`).
+    pub comments: Vec<(usize, Vec<u8>)>,
+
+    // Comments within items, each with the index of its item, as in `comments`, and the position
+    // of the subterm it precedes in the item's value, as `term::parse_commented` gives it.
+    pub notes: Vec<(usize, u64, Vec<u8>)>,
+
+    // The definitions annotated with `%fuel`, with their limit.
+    pub fuel: Vec<(Vec<u8>, u32)>,
+
+    // The definitions written with parameters, as `:name x y = body`, with how many. Their values
+    // start with a lambda for each.
+    pub params: Vec<(Vec<u8>, usize)>
+}
+
+// Skips whitespace and (possibly nested) comments, collecting the comments it skips.
+fn skip_blank<'a>(code : &'a Str, comments : &mut Vec<&'a Str>) -> &'a Str {
+    let mut i : usize = 0;
+    let mut start : usize = 0;
+    let mut comment : u32 = 0;
+    while i < code.len() {
+        match code[i] {
+            b'(' => {
+                if comment == 0 {
+                    start = i;
+                }
+                comment += 1;
+            },
+            b')' if comment > 0 => {
+                comment -= 1;
+                if comment == 0 {
+                    comments.push(&code[start..i + 1]);
+                }
+            },
+            b' ' | b'\n' | b'\r' => {},
+            _ if comment > 0 => {},
+            _ => break
+        }
+        i += 1;
+    }
+    &code[i..]
+}
+
+// Parses a source file into a module, without expanding references to definitions.
+pub fn parse_module(src : &Str) -> Result<Module, ParseError> {
+    parse_items(src, None).map(|(_, module)| module).map_err(|err| err.located(src))
+}
+
+// Parses a source file into a module, like `parse_module`, but only whitespace and comments may
+// follow the main term. Anything else is an error at its first byte.
+pub fn parse_module_all(src : &Str) -> Result<Module, ParseError> {
+    match parse_items(src, None) {
+        Ok((rest, _)) if !rest.is_empty() => Err(ParseError::new(rest, "unexpected input after the main term").located(src)),
+        result => result.map(|(_, module)| module).map_err(|err| err.located(src))
+    }
+}
+
+// Parses a source file into a module, like `parse_module`, keeping only the definitions the entry
+// point reaches (see `Module::prune`), and returns it with the names of the definitions it dropped,
+// in source order. A dropped definition may not even parse: one that doesn't is taken to run to
+// the next line that starts without indentation, as in `format_module`'s layout. If the entry
+// point reaches one, its error is returned. With `strict`, like `parse_module_all`, only blanks
+// may follow the main term.
+pub fn parse_module_pruned(src : &Str, entry : Option<&[u8]>, strict : bool) -> Result<(Module, Vec<Vec<u8>>), ParseError> {
+    let mut broken = Vec::new();
+    let (rest, module) = parse_items(src, Some(&mut broken)).map_err(|err| err.located(src))?;
+    if strict && !rest.is_empty() {
+        return Err(ParseError::new(rest, "unexpected input after the main term").located(src));
+    }
+    let (pruned, dropped) = module.prune(entry);
+    match broken.into_iter().find(|(nam, _)| !dropped.contains(nam)) {
+        Some((_, err)) => Err(err.located(src)),
+        None => Ok((pruned, dropped))
+    }
+}
+
+// Skips the rest of an item that doesn't parse: up to the next line that starts without
+// indentation, or the end of the file.
+fn skip_item(code : &Str) -> &Str {
+    let mut i = 0;
+    while let Some(at) = code[i..].iter().position(|chr| *chr == b'\n') {
+        i += at + 1;
+        if !matches!(code.get(i), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            return &code[i..];
+        }
+    }
+    &code[code.len()..]
+}
+
+// Parses the items of a module, returning what's left after the main term, past any blanks. With
+// `broken`, a definition whose value doesn't parse isn't an error: it's given `*` as its value, and
+// listed in `broken` with its error, and the items after it are parsed.
+fn parse_items<'a>(code : &'a Str, mut broken : Option<&mut Vec<(Vec<u8>, ParseError)>>) -> Result<(&'a Str, Module), ParseError> {
+    // What follows the name of a definition: its fuel, its parameters, its value and the comments
+    // within it.
+    type Parsed<'a> = (&'a Str, Option<u32>, Vec<&'a Str>, Term, Notes);
+    fn value<'a>(code : &'a Str, nam : &Str) -> Result<Parsed<'a>, ParseError> {
+        let (rest, limit) = parse_fuel(code)?;
+        let (rest, names) = parse_params(rest, nam)?;
+        let (rest, val, inner) = parse_commented(rest, &mut Scope::new(), &mut 0)?;
+        Ok((rest, limit, names, val, inner))
+    }
+
+    let mut defs = Vec::new();
+    let mut comments = Vec::new();
+    let mut fuel = Vec::new();
+    let mut params = Vec::new();
+    let mut notes = Vec::new();
+    let mut found = Vec::new();
+    let mut code = skip_blank(code, &mut found);
+    while !code.is_empty() && code[0] == b':' {
+        comments.extend(found.drain(..).map(|text| (defs.len(), text.to_vec())));
+        let (rest, nam) = parse_name(&code[1..])?;
+        let (rest, limit, names, val, inner) = match (value(rest, nam), broken.as_deref_mut()) {
+            (Ok(parsed), _) => parsed,
+            (Err(err), Some(broken)) => {
+                broken.push((nam.to_vec(), err));
+                defs.push((nam.to_vec(), Set));
+                code = skip_blank(skip_item(rest), &mut found);
+                continue;
+            },
+            (Err(err), None) => return Err(err)
+        };
+        fuel.extend(limit.map(|limit| (nam.to_vec(), limit)));
+        if !names.is_empty() {
+            params.push((nam.to_vec(), names.len()));
+        }
+        notes.extend(inner.into_iter().map(|(at, text)| (defs.len(), at, text)));
+        defs.push((nam.to_vec(), lambdas(&names, val)));
+        code = skip_blank(rest, &mut found);
+    }
+    comments.extend(found.drain(..).map(|text| (defs.len(), text.to_vec())));
+    let main = if code.is_empty() {
+        None
+    } else {
+        let (rest, main, inner) = parse_commented(code, &mut Scope::new(), &mut 0)?;
+        notes.extend(inner.into_iter().map(|(at, text)| (defs.len(), at, text)));
+        code = skip_blank(rest, &mut found);
+        comments.extend(found.drain(..).map(|text| (defs.len() + 1, text.to_vec())));
+        Some(main)
+    };
+    Ok((code, Module{defs, main, comments, notes, fuel, params}))
+}
+
+// Prints a module in the canonical layout: each top-level comment on its own line before the item
+// it precedes, definitions separated by blank lines, and terms laid out by `pretty`, with the
+// comments within them before the subterms they precede.
+pub fn format_module(module : &Module) -> Vec<u8> {
+    let mut code = Vec::new();
+    let comments = |code : &mut Vec<u8>, idx : usize| {
+        for (_, text) in module.comments.iter().filter(|(i, _)| *i == idx) {
+            code.extend_from_slice(text);
+            code.push(b'\n');
+        }
+    };
+    let notes = |idx : usize| -> Notes {
+        module.notes.iter().filter(|(i, _, _)| *i == idx).map(|(_, at, text)| (*at, text.clone())).collect()
+    };
+    for (idx, (nam, val)) in module.defs.iter().enumerate() {
+        comments(&mut code, idx);
+        code.push(b':');
+        code.extend_from_slice(nam);
+        if let Some((_, limit)) = module.fuel.iter().find(|(def, _)| def == nam) {
+            code.extend_from_slice(format!(" %fuel {}", limit).as_bytes());
+        }
+        let mut val = val;
+        if let Some((_, count)) = module.params.iter().find(|(def, _)| def == nam) {
+            for _ in 0..*count {
+                if let Lam{nam, bod} = val {
+                    code.push(b' ');
+                    code.extend_from_slice(nam);
+                    val = bod;
+                }
+            }
+            code.extend_from_slice(b" =");
+        }
+        pretty_body_commented(&mut code, val, 0, &notes(idx));
+        code.extend_from_slice(b"\n\n");
+    }
+    comments(&mut code, module.defs.len());
+    if let Some(ref main) = module.main {
+        code.append(&mut pretty_commented(main, &notes(module.defs.len())));
+        code.push(b'\n');
+    }
+    comments(&mut code, module.defs.len() + 1);
+    while code.ends_with(b"\n\n") {
+        code.pop();
+    }
+    code
+}
+
+// Collects the names of definitions referenced by a term, in order of first occurrence. A
+// variable refers to a definition when no enclosing binder of the same name shadows it.
+pub fn references(term : &Term, defs : &[(Vec<u8>, Term)]) -> Vec<Vec<u8>> {
+    fn go<'a>(term : &'a Term, defs : &[(Vec<u8>, Term)], bound : &mut Vec<&'a [u8]>, refs : &mut Vec<Vec<u8>>) {
+        match term {
+            Lam{nam, bod} => {
+                bound.push(nam);
+                go(bod, defs, bound, refs);
+                bound.pop();
+            },
+            App{fun, arg} => {
+                go(fun, defs, bound, refs);
+                go(arg, defs, bound, refs);
+            },
+            Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                go(a, defs, bound, refs);
+                go(b, defs, bound, refs);
+            },
+            Dup{fst, snd, val, nxt} => {
+                bound.push(snd);
+                bound.push(fst);
+                go(val, defs, bound, refs);
+                go(nxt, defs, bound, refs);
+                bound.pop();
+                bound.pop();
+            },
+            Var{nam} => {
+                let is_def = defs.iter().any(|(def, _)| def == nam);
+                let is_bound = bound.iter().any(|var| *var == &nam[..]);
+                if is_def && !is_bound && !refs.contains(nam) {
+                    refs.push(nam.clone());
+                }
+            },
+            Ctr{..} | Set => {}
+        }
+    }
+    let mut refs = Vec::new();
+    go(term, defs, &mut Vec::new(), &mut refs);
+    refs
+}
+
+// A problem `validate_program` found in a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    // The file doesn't parse, so nothing else was checked.
+    Parse(ParseError),
+    // A variable of a definition, or of the main term (`def` is `None`), that is unbound or used
+    // more than once.
+    Affinity{def: Option<Vec<u8>>, error: AffinityError}
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::Parse(err) => write!(f, "{}", err),
+            Diagnostic::Affinity{def: Some(def), error} => write!(f, "in `{}`: {}", String::from_utf8_lossy(def), error),
+            Diagnostic::Affinity{def: None, error} => write!(f, "in the main term: {}", error)
+        }
+    }
+}
+
+// Checks every definition of a source file, and its main term, for the variables `to_net` would
+// reject, reporting all of them rather than the first. As in `to_net`, a variable can be bound
+// anywhere in the same term; one bound nowhere is a reference if it names a definition.
+pub fn validate_program(src : &Str) -> Vec<Diagnostic> {
+    let module = match parse_module(src) {
+        Ok(module) => module,
+        Err(err) => return vec![Diagnostic::Parse(err)]
+    };
+    let items = module.defs.iter().map(|(nam, val)| (Some(nam), val)).chain(module.main.iter().map(|main| (None, main)));
+    let mut found = Vec::new();
+    for (def, term) in items {
+        let mut bound = BTreeSet::new();
+        binders(term, &mut bound);
+        let mut counts = BTreeMap::new();
+        occurrences(term, &mut counts);
+        for (var, count) in counts {
+            let error = if bound.contains(&var) {
+                if count == 1 {
+                    continue;
+                }
+                AffinityError::Duplicated(var)
+            } else if module.defs.iter().any(|(nam, _)| *nam == var) {
+                continue;
+            } else {
+                AffinityError::Unbound(var)
+            };
+            found.push(Diagnostic::Affinity{def: def.cloned(), error});
+        }
+    }
+    found
+}
+
+impl Module {
+    // Finds the value of a definition. Later definitions shadow earlier ones.
+    pub fn find(&self, nam : &[u8]) -> Option<&Term> {
+        self.defs.iter().rev().find(|(def, _)| def == nam).map(|(_, val)| val)
+    }
+
+    // Returns the definitions each definition references, in source order. References to
+    // definitions that come later (or to itself) are kept, so cycles can be reported.
+    pub fn dependencies(&self) -> Vec<(Vec<u8>, Vec<Vec<u8>>)> {
+        let mut deps : Vec<(Vec<u8>, Vec<Vec<u8>>)> = Vec::new();
+        for (nam, _) in &self.defs {
+            if !deps.iter().any(|(def, _)| def == nam) {
+                deps.push((nam.clone(), references(self.find(nam).unwrap(), &self.defs)));
+            }
+        }
+        deps
+    }
+
+    // The definitions referenced directly by the entry point: either a named definition or,
+    // when `entry` is `None`, the main term.
+    pub fn roots(&self, entry : Option<&[u8]>) -> Vec<Vec<u8>> {
+        match entry {
+            Some(nam) => if self.find(nam).is_some() { vec![nam.to_vec()] } else { vec![] },
+            None => match self.main {
+                Some(ref main) => references(main, &self.defs),
+                None => vec![]
+            }
+        }
+    }
+
+    // Returns the definitions reachable from the entry point, in depth-first order.
+    pub fn reachable(&self, entry : Option<&[u8]>) -> Vec<Vec<u8>> {
+        let deps = self.dependencies();
+        let mut seen : Vec<Vec<u8>> = Vec::new();
+        let mut stack = self.roots(entry);
+        stack.reverse();
+        while let Some(nam) = stack.pop() {
+            if !seen.contains(&nam) {
+                if let Some((_, refs)) = deps.iter().find(|(def, _)| *def == nam) {
+                    stack.extend(refs.iter().rev().cloned());
+                }
+                seen.push(nam);
+            }
+        }
+        seen
+    }
+
+    // Returns the definitions that can't be reached from the entry point, in source order.
+    pub fn unreachable(&self, entry : Option<&[u8]>) -> Vec<Vec<u8>> {
+        let reachable = self.reachable(entry);
+        self.dependencies().into_iter()
+            .map(|(nam, _)| nam)
+            .filter(|nam| !reachable.contains(nam))
+            .collect()
+    }
+
+    // Keeps the definitions reachable from the entry point, and the main term unless the entry point
+    // is a definition, with the comments before them and within them. Returns the module and the
+    // names of the definitions it dropped, in source order.
+    pub fn prune(&self, entry : Option<&[u8]>) -> (Module, Vec<Vec<u8>>) {
+        let reachable = self.reachable(entry);
+        let kept : Vec<bool> = self.defs.iter().map(|(nam, _)| reachable.contains(nam)).collect();
+        let count = kept.iter().filter(|kept| **kept).count();
+        // The new index of an item, if it's kept.
+        let place = |idx : usize| match kept.get(idx) {
+            Some(true) => Some(kept[..idx].iter().filter(|kept| **kept).count()),
+            Some(false) => None,
+            None if idx == self.defs.len() && entry.is_some() => None,
+            None => Some(idx - self.defs.len() + count)
+        };
+        let module = Module {
+            defs: self.defs.iter().zip(&kept).filter(|(_, kept)| **kept).map(|(def, _)| def.clone()).collect(),
+            main: if entry.is_some() { None } else { self.main.clone() },
+            comments: self.comments.iter().filter_map(|(idx, text)| place(*idx).map(|idx| (idx, text.clone()))).collect(),
+            notes: self.notes.iter().filter_map(|(idx, at, text)| place(*idx).map(|idx| (idx, *at, text.clone()))).collect(),
+            fuel: self.fuel.iter().filter(|(nam, _)| reachable.contains(nam)).cloned().collect(),
+            params: self.params.iter().filter(|(nam, _)| reachable.contains(nam)).cloned().collect()
+        };
+        (module, self.unreachable(entry))
+    }
+
+    // Groups definitions into strongly connected components (Tarjan's algorithm). Components are
+    // returned dependencies-first, so concatenating them gives a topological order.
+    pub fn groups(&self) -> Vec<Vec<Vec<u8>>> {
+        struct State {
+            index : Vec<Option<usize>>,
+            lowlink : Vec<usize>,
+            on_stack : Vec<bool>,
+            stack : Vec<usize>,
+            count : usize,
+            groups : Vec<Vec<usize>>
+        }
+        fn visit(v : usize, edges : &[Vec<usize>], st : &mut State) {
+            st.index[v] = Some(st.count);
+            st.lowlink[v] = st.count;
+            st.count += 1;
+            st.stack.push(v);
+            st.on_stack[v] = true;
+            for &w in &edges[v] {
+                match st.index[w] {
+                    None => {
+                        visit(w, edges, st);
+                        st.lowlink[v] = st.lowlink[v].min(st.lowlink[w]);
+                    },
+                    Some(idx) => if st.on_stack[w] {
+                        st.lowlink[v] = st.lowlink[v].min(idx);
+                    }
+                }
+            }
+            if Some(st.lowlink[v]) == st.index[v] {
+                let mut group = Vec::new();
+                while let Some(w) = st.stack.pop() {
+                    st.on_stack[w] = false;
+                    group.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                group.sort();
+                st.groups.push(group);
+            }
+        }
+
+        let deps = self.dependencies();
+        let edges : Vec<Vec<usize>> = deps.iter()
+            .map(|(_, refs)| refs.iter().map(|r| deps.iter().position(|(nam, _)| nam == r).unwrap()).collect())
+            .collect();
+        let len = deps.len();
+        let mut st = State {
+            index: vec![None; len],
+            lowlink: vec![0; len],
+            on_stack: vec![false; len],
+            stack: Vec::new(),
+            count: 0,
+            groups: Vec::new()
+        };
+        for v in 0..len {
+            if st.index[v].is_none() {
+                visit(v, &edges, &mut st);
+            }
+        }
+        st.groups.into_iter()
+            .map(|group| group.into_iter().map(|v| deps[v].0.clone()).collect())
+            .collect()
+    }
+
+    // Returns the groups of definitions that reference each other (including a definition that
+    // references itself). Definitions only see earlier ones, so these can only be expanded once
+    // `unfold_recursion` has unfolded them.
+    pub fn cycles(&self) -> Vec<Vec<Vec<u8>>> {
+        let deps = self.dependencies();
+        self.groups().into_iter()
+            .filter(|group| group.len() > 1 || deps.iter().any(|(nam, refs)| *nam == group[0] && refs.contains(nam)))
+            .collect()
+    }
+
+    // Lists definitions so that each one comes after everything it references. Members of a
+    // cycle are listed together, in source order.
+    pub fn topological_order(&self) -> Vec<Vec<u8>> {
+        self.groups().into_iter().flatten().collect()
+    }
+
+    // Unfolds the recursive definitions (see `cycles`), as `@rec` does: each member of a group is
+    // unfolded `REC_DEPTH` times, following its references to the group, with `too_deep_marker` in
+    // place of the innermost ones, and with its binders renamed apart in each unfolding. Evaluating
+    // a program that recurses deeper reports it. Definitions are then listed in topological order,
+    // so each sees what it references, and comments are dropped. A member may reference the group
+    // once at most, or unfolding would copy it exponentially; otherwise, that's reported as a
+    // duplicated variable. A tupled fixpoint would need pairs of the group to be copied with the
+    // lets inside them, which fans don't do soundly (see `fans`).
+    pub fn unfold_recursion(&self) -> Result<Module, AffinityError> {
+        // Renames the binders of a term, and the variables they bind, within `space` at `idx`.
+        // Other variables are passed to `free`, which may replace them.
+        fn rename(term : &Term, bound : &BTreeSet<Vec<u8>>, space : &[u8], idx : u32, free : &mut dyn FnMut(&[u8]) -> Option<Term>) -> Term {
+            let name = |nam : &[u8]| namespace(space, idx, nam);
+            match term {
+                Lam{nam, bod} => Lam{nam: name(nam), bod: Box::new(rename(bod, bound, space, idx, free))},
+                App{fun, arg} => {
+                    let fun = Box::new(rename(fun, bound, space, idx, free));
+                    let arg = Box::new(rename(arg, bound, space, idx, free));
+                    App{fun, arg}
+                },
+                Par{fst, snd} => {
+                    let fst = Box::new(rename(fst, bound, space, idx, free));
+                    let snd = Box::new(rename(snd, bound, space, idx, free));
+                    Par{fst, snd}
+                },
+                Dup{fst, snd, val, nxt} => {
+                    let val = Box::new(rename(val, bound, space, idx, free));
+                    let nxt = Box::new(rename(nxt, bound, space, idx, free));
+                    Dup{fst: name(fst), snd: name(snd), val, nxt}
+                },
+                Era{val, nxt} => {
+                    let val = Box::new(rename(val, bound, space, idx, free));
+                    let nxt = Box::new(rename(nxt, bound, space, idx, free));
+                    Era{val, nxt}
+                },
+                Var{nam} if bound.contains(nam) => Var{nam: name(nam)},
+                Var{nam} => free(nam).unwrap_or_else(|| term.clone()),
+                Ctr{..} | Set => term.clone()
+            }
+        }
+        fn unfold(module : &Module, group : &[Vec<u8>], nam : &[u8], depth : u32, idx : &mut u32) -> Term {
+            if depth == 0 {
+                return too_deep_marker(nam);
+            }
+            let val = module.find(nam).unwrap();
+            let mut bound = BTreeSet::new();
+            binders(val, &mut bound);
+            let at = *idx;
+            *idx += 1;
+            rename(val, &bound, nam, at, &mut |var| {
+                if group.iter().any(|member| member == var) {
+                    Some(unfold(module, group, var, depth - 1, idx))
+                } else {
+                    None
+                }
+            })
+        }
+
+        let cycles = self.cycles();
+        if cycles.is_empty() {
+            return Ok(self.clone());
+        }
+        for group in &cycles {
+            for nam in group {
+                let mut counts = BTreeMap::new();
+                occurrences(self.find(nam).unwrap(), &mut counts);
+                if let Some(member) = group.iter().find(|member| counts.get(*member).is_some_and(|count| *count > 1)) {
+                    return Err(AffinityError::Duplicated(member.clone()));
+                }
+                let uses : u32 = group.iter().filter_map(|member| counts.get(member)).sum();
+                if uses > 1 {
+                    return Err(AffinityError::Duplicated(nam.clone()));
+                }
+            }
+        }
+        let mut idx = 0;
+        let defs = self.topological_order().into_iter()
+            .map(|nam| {
+                let val = match cycles.iter().find(|group| group.contains(&nam)) {
+                    Some(group) => unfold(self, group, &nam, REC_DEPTH, &mut idx),
+                    None => self.find(&nam).unwrap().clone()
+                };
+                (nam, val)
+            })
+            .collect();
+        Ok(Module{defs, main: self.main.clone(), comments: Vec::new(), notes: Vec::new(), fuel: self.fuel.clone(), params: Vec::new()})
+    }
+
+    // Converts the entry point (a named definition, or the main term when `entry` is `None`) to a
+    // net, as `to_net` would convert the program `from_string` expands from the module, but
+    // encoding each definition once, when it's first referenced: its body becomes a `NetFragment`,
+    // and each reference to it grafts a copy. As in source files, a definition sees the ones
+    // before it.
+    pub fn to_net(&self, entry : Option<&[u8]>) -> Result<Net, SicError> {
+        self.encode(entry, false, None)
+    }
+
+    // Converts the entry point to a net like `to_net`, but where a term references a definition
+    // several times, grafts one copy, shared by the references through a chain of FAN nodes, as
+    // `= a b f` would share it. The net starts smaller, and reduction makes the copies as they're
+    // needed. Only definitions whose nets have no FAN nodes are shared: copying a let or a pair
+    // through a FAN would have them annihilate (see `fans`). The normal form is the same.
+    pub fn to_net_shared(&self, entry : Option<&[u8]>) -> Result<Net, SicError> {
+        self.encode(entry, true, None)
+    }
+
+    // Converts the entry point to a net like `to_net`, taking the definitions compiled by earlier
+    // calls from `cache`, and adding those it compiles. A definition is found again if its body,
+    // up to the names of its binders, and the definitions it references are unchanged, so editing
+    // one definition only recompiles it and those that reference it, directly or not.
+    pub fn to_net_cached(&self, entry : Option<&[u8]>, cache : &mut NetCache) -> Result<Net, SicError> {
+        self.encode(entry, false, Some(cache))
+    }
+
+    // Reduces a definition to normal form, or takes it from `cache` if an unchanged definition
+    // (as `to_net_cached` tells) was reduced before.
+    pub fn normal_form_cached(&self, nam : &[u8], cache : &mut NetCache) -> Result<Term, SicError> {
+        let def = match self.defs.iter().rposition(|(def, _)| def == nam) {
+            Some(def) => def,
+            None => return Err(AffinityError::Unbound(nam.to_vec()).into())
+        };
+        let key = self.keys(false)[def];
+        if let Some(norm) = cache.normal.get(&key) {
+            cache.hits += 1;
+            return Ok(norm.clone());
+        }
+        cache.misses += 1;
+        let mut net = self.to_net_cached(Some(nam), cache)?;
+        ::net::reduce(&mut net);
+        let norm = from_net(&net)?;
+        cache.normal.insert(key, norm.clone());
+        Ok(norm)
+    }
+
+    // Keys identifying each definition for `NetCache`: the canonical hash of its body, folded
+    // (as FNV-1a) with the keys of the definitions it references, and with whether they're
+    // shared.
+    fn keys(&self, share : bool) -> Vec<u64> {
+        fn refs(term : &Term, found : &mut Vec<Vec<u8>>) {
+            match term {
+                Lam{bod, ..} => refs(bod, found),
+                App{fun: a, arg: b} | Par{fst: a, snd: b} | Dup{val: a, nxt: b, ..} | Era{val: a, nxt: b} => {
+                    refs(a, found);
+                    refs(b, found);
+                },
+                Var{nam} if nam.first() == Some(&b'*') => found.push(nam[1..].to_vec()),
+                Var{..} | Ctr{..} | Set => {}
+            }
+        }
+        let mut keys : Vec<u64> = Vec::new();
+        for (len, (_, val)) in self.defs.iter().enumerate() {
+            let marked = mark(val, &self.defs[..len], &mut Vec::new());
+            let mut found = Vec::new();
+            refs(&marked, &mut found);
+            let mut key = canonical_hash(&marked);
+            let mut fold = |word : u64| for byte in word.to_le_bytes() {
+                key ^= byte as u64;
+                key = key.wrapping_mul(0x100000001b3);
+            };
+            fold(share as u64);
+            for nam in found {
+                fold(keys[self.defs[..len].iter().rposition(|(def, _)| *def == nam).unwrap()]);
+            }
+            keys.push(key);
+        }
+        keys
+    }
+
+    fn encode(&self, entry : Option<&[u8]>, share : bool, cache : Option<&mut NetCache>) -> Result<Net, SicError> {
+        // Encodes a term that sees the first `len` definitions, grafting those it references and
+        // compiling them into `fragments` first if needed (or taking them from the cache, if any).
+        fn encode(defs : &[(Vec<u8>, Term)], term : &Term, len : usize, share : bool, fragments : &mut Vec<Option<NetFragment>>, cache : &mut Option<(Vec<u64>, &mut NetCache)>) -> Result<Net, AffinityError> {
+            let (mut net, _, free, _) = to_net_with_fans(&mark(term, &defs[..len], &mut Vec::new()), true)?;
+            // The FREE nodes left for the references, by definition.
+            let mut uses : BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+            for (node, nam) in free {
+                match nam.split_first() {
+                    Some((b'*', def)) => uses.entry(defs[..len].iter().rposition(|(nam, _)| nam == def).unwrap()).or_default().push(node),
+                    _ => return Err(AffinityError::Unbound(nam))
+                }
+            }
+            for (def, nodes) in uses {
+                if fragments[def].is_none() {
+                    let cached = cache.as_mut().and_then(|(keys, cache)| match cache.fragments.get(&keys[def]) {
+                        Some(fragment) => {
+                            cache.hits += 1;
+                            Some(fragment.clone())
+                        },
+                        None => {
+                            cache.misses += 1;
+                            None
+                        }
+                    });
+                    fragments[def] = Some(match cached {
+                        Some(fragment) => fragment,
+                        None => {
+                            let fragment = NetFragment::from_net(&encode(defs, &defs[def].1, def, share, fragments, cache)?);
+                            if let Some((keys, cache)) = cache.as_mut() {
+                                cache.fragments.insert(keys[def], fragment.clone());
+                            }
+                            fragment
+                        }
+                    });
+                }
+                let fragment = fragments[def].as_ref().unwrap();
+                for (ctr, arity) in &fragment.ctrs {
+                    match net.ctrs.iter().find(|(nam, _)| nam == ctr) {
+                        Some((_, other)) if other != arity => {
+                            return Err(AffinityError::Arity{nam: ctr.clone(), arity: *other, args: *arity});
+                        },
+                        _ => {}
+                    }
+                }
+                let shared = share && nodes.len() > 1 && fragment.nodes.chunks(4).all(|node| node[3] != FAN);
+                let mut copy = if shared { Some(net.graft(fragment)) } else { None };
+                for (idx, node) in nodes.iter().enumerate() {
+                    let at = enter(&net, link(*node, PORT_MAIN));
+                    net.reuse.push(*node);
+                    let root = match copy {
+                        Some(root) if idx + 1 < nodes.len() => {
+                            let fan = new_node(&mut net, FAN);
+                            connect(&mut net, link(fan, PORT_MAIN), root);
+                            copy = Some(link(fan, PORT_AUX2));
+                            link(fan, PORT_AUX1)
+                        },
+                        Some(root) => root,
+                        None => net.graft(fragment)
+                    };
+                    connect(&mut net, at, root);
+                }
+            }
+            Ok(net)
+        }
+        let term = match (entry, &self.main) {
+            (Some(entry), _) => Var{nam: entry.to_vec()},
+            (None, Some(main)) => main.clone(),
+            (None, None) => return Err(ParseError::new(b"", "expected a main term or an entry").into())
+        };
+        let mut cache = cache.map(|cache| (self.keys(share), cache));
+        Ok(encode(&self.defs, &term, self.defs.len(), share, &mut vec![None; self.defs.len()], &mut cache)?)
+    }
+}
+
+// Renames the variables of a term that refer to the definitions `visible` to `*name`, which no
+// binder can be named, so encoding leaves them free.
+fn mark<'a>(term : &'a Term, visible : &[(Vec<u8>, Term)], bound : &mut Vec<&'a [u8]>) -> Term {
+    match term {
+        Lam{nam, bod} => {
+            bound.push(nam);
+            let bod = Box::new(mark(bod, visible, bound));
+            bound.pop();
+            Lam{nam: nam.clone(), bod}
+        },
+        App{fun, arg} => App{fun: Box::new(mark(fun, visible, bound)), arg: Box::new(mark(arg, visible, bound))},
+        Par{fst, snd} => Par{fst: Box::new(mark(fst, visible, bound)), snd: Box::new(mark(snd, visible, bound))},
+        Dup{fst, snd, val, nxt} => {
+            bound.push(snd);
+            bound.push(fst);
+            let val = Box::new(mark(val, visible, bound));
+            let nxt = Box::new(mark(nxt, visible, bound));
+            bound.pop();
+            bound.pop();
+            Dup{fst: fst.clone(), snd: snd.clone(), val, nxt}
+        },
+        Era{val, nxt} => Era{val: Box::new(mark(val, visible, bound)), nxt: Box::new(mark(nxt, visible, bound))},
+        Var{nam} if !bound.contains(&&nam[..]) && visible.iter().any(|(def, _)| def == nam) => {
+            let mut marked = b"*".to_vec();
+            marked.extend_from_slice(nam);
+            Var{nam: marked}
+        },
+        Var{..} | Ctr{..} | Set => term.clone()
+    }
+}
+
+// Definitions compiled by `Module::to_net_cached`, and normal forms found by
+// `Module::normal_form_cached`, kept from one call to the next, with how many lookups found one.
+#[derive(Clone, Debug, Default)]
+pub struct NetCache {
+    fragments: BTreeMap<u64, NetFragment>,
+    normal: BTreeMap<u64, Term>,
+    pub hits: u32,
+    pub misses: u32
+}
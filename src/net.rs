@@ -1,21 +1,124 @@
 // Implements Interaction Combinators. The Abstract Calculus is directly isomorphic to them, so, to
 // reduce a term, we simply translate to interaction combinators, reduce, then translate back.
 
-#![allow(dead_code)]
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+use alloc::fmt;
+use alloc::sync::Arc;
+use prelude::*;
+use error::*;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
-#[derive(Clone, Debug)]
+// Counters of a reduction. With the `serde` feature, they serialize under their field names, which
+// are kept stable. The default has them all at zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Stats {
     pub loops: u32,
     pub rules: u32,
     pub betas: u32,
     pub dupls: u32,
-    pub annis: u32
+    pub annis: u32,
+    // Rewrites where one of the nodes is an ERA: collecting garbage rather than computing.
+    pub erasures: u32,
+    pub max_nodes: u32
 }
 
-#[derive(Clone, Debug)]
+impl Stats {
+    // Counts a rewrite of an active pair of nodes of kinds `a` and `b` by what it does: a pair with
+    // an ERA is an erasure, two CONs a beta reduction, two FANs an annihilation, and any other pair
+    // a duplication.
+    pub(crate) fn count_pair(&mut self, a : u32, b : u32) {
+        if a == ERA || b == ERA {
+            self.erasures += 1;
+        } else if a == CON && b == CON {
+            self.betas += 1;
+        } else if a == FAN && b == FAN {
+            self.annis += 1;
+        } else {
+            self.dupls += 1;
+        }
+    }
+
+    // Adds the counters of another reduction, like one of a net reduced in parts. Counts are
+    // summed; `max_nodes`, a peak, is the larger of the two.
+    pub fn merge(&mut self, other : &Stats) {
+        self.loops += other.loops;
+        self.rules += other.rules;
+        self.betas += other.betas;
+        self.dupls += other.dupls;
+        self.annis += other.annis;
+        self.erasures += other.erasures;
+        self.max_nodes = self.max_nodes.max(other.max_nodes);
+    }
+
+    // The counters added since `earlier`, stats of the same reduction taken before these, so that
+    // merging it into `earlier` gives these back. `max_nodes` stays the peak so far.
+    pub fn since(&self, earlier : &Stats) -> Stats {
+        Stats {
+            loops: self.loops - earlier.loops,
+            rules: self.rules - earlier.rules,
+            betas: self.betas - earlier.betas,
+            dupls: self.dupls - earlier.dupls,
+            annis: self.annis - earlier.annis,
+            erasures: self.erasures - earlier.erasures,
+            max_nodes: self.max_nodes
+        }
+    }
+}
+
+// A labeled summary for people, as `sic -s` prints it. `loops` counts the reducer's own steps, not
+// work on the term, so it's left to `Debug`.
+impl fmt::Display for Stats {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rules: {}, betas: {}, dupls: {}, annis: {}, erasures: {}, max_nodes: {}",
+            self.rules, self.betas, self.dupls, self.annis, self.erasures, self.max_nodes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "NetFile", into = "NetFile"))]
 pub struct Net {
     pub nodes: Vec<u32>,
-    pub reuse: Vec<u32>
+    pub reuse: Vec<u32>,
+    // The constructors of the net, with their arities. The `idx`th has kind `CTR + idx`.
+    pub ctrs: Vec<(Vec<u8>, u32)>
+}
+
+// Version of the serialized layouts of nets (binary, and serde's). Reading any other version fails.
+pub const NET_VERSION : u32 = 2;
+
+// How a net is serialized: its fields, plus the layout version.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct NetFile {
+    version: u32,
+    nodes: Vec<u32>,
+    reuse: Vec<u32>,
+    #[serde(default)]
+    ctrs: Vec<(Vec<u8>, u32)>
+}
+
+#[cfg(feature = "serde")]
+impl From<Net> for NetFile {
+    fn from(net : Net) -> NetFile {
+        NetFile { version: NET_VERSION, nodes: wide_words(&net), reuse: net.reuse, ctrs: net.ctrs }
+    }
+}
+
+// Deserialized nets are checked, so a malformed file can't make `reduce` index out of bounds.
+#[cfg(feature = "serde")]
+impl TryFrom<NetFile> for Net {
+    type Error = NetError;
+    fn try_from(file : NetFile) -> Result<Net, NetError> {
+        if file.version != NET_VERSION {
+            return Err(NetError::Version(file.version));
+        }
+        let net = Net { nodes: from_wide_words(file.nodes)?, reuse: file.reuse, ctrs: file.ctrs };
+        check_net(&net)?;
+        Ok(net)
+    }
 }
 
 // Node types are consts because those are used in a Vec<u32>.
@@ -23,23 +126,120 @@ pub const ERA : u32 = 0;
 pub const CON : u32 = 1;
 pub const FAN : u32 = 2;
 
+// Kind of the nodes standing for the free variables of an open term (see `term::to_net_open`).
+// They have no rules: one facing another node's principal port is left there, and reduction goes
+// on around it. Their auxiliary ports are looped together, as an ERA's.
+pub const FREE : u32 = 3;
+
+// Kind of the root node, the first of every net, whose port 0 leads to the term.
+pub const ROOT : u32 = 4;
+
+// Kind of the first constructor of a net (see `Net::ctrs`). A constructor
+// holds its fields in its auxiliary ports, as many as its arity: one with a single field has an
+// ERA in the other, and one with none has them looped together. ERAs and FANs erase and copy it
+// by the usual rules, but one applied is left there, like a FREE node.
+pub const CTR : u32 = 5;
+
+// Constructors have at most two fields, as nodes have two auxiliary ports.
+pub const MAX_FIELDS : u32 = 2;
+
+// The kinds above, for users of the library. Nets store kinds as words, and the methods of `Net`
+// convert them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Era,
+    Con,
+    Fan,
+    Free,
+    Root,
+    // The `idx`th constructor of the net.
+    Ctr{idx: u32}
+}
+
+impl From<u32> for Kind {
+    fn from(kind : u32) -> Kind {
+        match kind {
+            ERA => Kind::Era,
+            CON => Kind::Con,
+            FAN => Kind::Fan,
+            FREE => Kind::Free,
+            ROOT => Kind::Root,
+            _ => Kind::Ctr{idx: kind - CTR}
+        }
+    }
+}
+
+impl From<Kind> for u32 {
+    fn from(kind : Kind) -> u32 {
+        match kind {
+            Kind::Era => ERA,
+            Kind::Con => CON,
+            Kind::Fan => FAN,
+            Kind::Free => FREE,
+            Kind::Root => ROOT,
+            Kind::Ctr{idx} => CTR + idx
+        }
+    }
+}
+
+// Whether nodes of kinds `a` and `b` facing each other's principal ports are rewritten. FREE nodes
+// have no rules, and a constructor only has them with ERAs and FANs.
+pub fn has_rule(a : u32, b : u32) -> bool {
+    let inert = |a : u32, b : u32| a == FREE || (a >= CTR && (b == CON || b >= CTR));
+    !inert(a, b) && !inert(b, a)
+}
+
 pub type Link = u32;
 
+// Ports of a node. Nodes interact through their principal port; what the auxiliary ports stand for
+// depends on the kind (see `term::to_net`). Each port is a word of `Net::nodes` (see `slot`).
+pub const PORT_MAIN : u32 = 0;
+pub const PORT_AUX1 : u32 = 1;
+pub const PORT_AUX2 : u32 = 2;
+
+// Words a node takes in `Net::nodes`. By default, a node is its three ports, then its kind. With
+// the `compact-nodes` feature, it's only its three ports, and the kind is kept in the top bits of
+// the first, above the link: that saves a quarter of the memory, but limits nets to 2^22 nodes and
+// 251 constructors. Serialized nets use the default layout either way.
+#[cfg(not(feature = "compact-nodes"))]
+pub const NODE_SIZE : usize = 4;
+#[cfg(feature = "compact-nodes")]
+pub const NODE_SIZE : usize = 3;
+
+// Where the kind starts in a node's first word, with `compact-nodes`.
+#[cfg(feature = "compact-nodes")]
+const KIND_SHIFT : u32 = 24;
+#[cfg(feature = "compact-nodes")]
+const LINK_MASK : u32 = (1 << KIND_SHIFT) - 1;
+
+// A net of only its root node, whose port 0 is looped, waiting for a term.
+pub fn root_net() -> Net {
+    let mut net = Net { nodes: vec![], reuse: vec![], ctrs: vec![] };
+    new_node(&mut net, ROOT);
+    connect(&mut net, link(0, PORT_AUX1), link(0, PORT_AUX2));
+    net
+}
+
 // Allocates a new node, reclaiming a freed space if possible.
 pub fn new_node(net : &mut Net, kind : u32) -> u32 {
     let node : u32 = match net.reuse.pop() {
-        Some(index) => index,
+        Some(index) => {
+            bound(net, last_slot(index));
+            index
+        },
         None => {
             let len = net.nodes.len();
-            net.nodes.resize(len + 4, 0);
-            (len as u32) / 4
+            net.nodes.resize(len + NODE_SIZE, 0);
+            (len / NODE_SIZE) as u32
         }
     };
-    net.nodes[link(node, 0) as usize] = link(node, 0);
-    net.nodes[link(node, 1) as usize] = link(node, 1);
-    net.nodes[link(node, 2) as usize] = link(node, 2);
-    net.nodes[link(node, 3) as usize] = kind;
-    return node;
+    #[cfg(feature = "compact-nodes")]
+    assert!(link(node, 0) <= LINK_MASK && kind >> (32 - KIND_SHIFT) == 0, "net too large for compact-nodes");
+    set_port(net, link(node, PORT_MAIN), link(node, PORT_MAIN));
+    set_port(net, link(node, PORT_AUX1), link(node, PORT_AUX1));
+    set_port(net, link(node, PORT_AUX2), link(node, PORT_AUX2));
+    store_kind(net, node, kind);
+    node
 }
 
 // Builds a link (an address / port pair).
@@ -57,9 +257,101 @@ pub fn port(link : Link) -> u32 {
     link & 3
 }
 
+// Index of the word of `Net::nodes` holding a port. In the default layout, that's the link itself.
+pub fn slot(link : Link) -> usize {
+    addr(link) as usize * NODE_SIZE + port(link) as usize
+}
+
+// Reads and writes words of `nodes`. With the `unchecked` feature, indices aren't checked against
+// the length, except in debug builds. Only the functions below go through these, and the public
+// ones check their arguments first (see `bound`), or the whole net (see `bound_links`).
+#[inline(always)]
+fn load(net : &Net, idx : usize) -> u32 {
+    #[cfg(feature = "unchecked")]
+    {
+        debug_assert!(idx < net.nodes.len(), "word {} is outside of the net", idx);
+        // Safety: `idx` is the slot of a link or node either checked by `bound`, or read from a
+        // net whose links were checked to point inside it by `bound_links` before the walk began.
+        // The walk then only stores links it read, or to nodes `new_node` checked.
+        unsafe { *net.nodes.get_unchecked(idx) }
+    }
+    #[cfg(not(feature = "unchecked"))]
+    net.nodes[idx]
+}
+
+#[inline(always)]
+fn store(net : &mut Net, idx : usize, word : u32) {
+    #[cfg(feature = "unchecked")]
+    {
+        debug_assert!(idx < net.nodes.len(), "word {} is outside of the net", idx);
+        // Safety: as in `load`; `new_node` also stores to the node it just allocated, which is
+        // either pushed onto the net or taken from `reuse` after a `bound`.
+        unsafe { *net.nodes.get_unchecked_mut(idx) = word }
+    }
+    #[cfg(not(feature = "unchecked"))]
+    {
+        net.nodes[idx] = word;
+    }
+}
+
+// Checks an index given to a public function, where `load` and `store` wouldn't.
+#[inline(always)]
+fn bound(net : &Net, idx : usize) {
+    #[cfg(feature = "unchecked")]
+    assert!(idx < net.nodes.len(), "word {} is outside of the net", idx);
+    #[cfg(not(feature = "unchecked"))]
+    let _ = (net, idx);
+}
+
+// Checks that a link read from the net is a port of one of its nodes, where `bound` only checks
+// the word it's in.
+#[inline(always)]
+fn bound_port(net : &Net, to : Link) {
+    bound(net, last_slot(addr(to)));
+    #[cfg(feature = "unchecked")]
+    assert!(port(to) < 3, "link {} points to the kind of node {}, not a port", to, addr(to));
+}
+
+// Checks that every port of a net points to a port of one of its nodes, before a walk follows
+// them without `bound`. The fields of a net are public, so anything may have been written there.
+#[inline(always)]
+fn bound_links(net : &Net) {
+    #[cfg(feature = "unchecked")]
+    {
+        let nodes = (net.nodes.len() / NODE_SIZE) as u32;
+        assert!(nodes > 0, "the net has no root");
+        for node in 0..nodes {
+            for slot in 0..3 {
+                let to = follow(net, link(node, slot));
+                assert!(addr(to) < nodes && port(to) < 3, "port {} points to {}, outside of the net", link(node, slot), to);
+            }
+        }
+    }
+    #[cfg(not(feature = "unchecked"))]
+    let _ = net;
+}
+
+// Index of the last word of a node.
+fn last_slot(node : u32) -> usize {
+    (node as usize + 1) * NODE_SIZE - 1
+}
+
 // Enters a link, returning the link on the other side.
 pub fn enter(net : &Net, link : Link) -> Link {
-    net.nodes[link as usize]
+    bound(net, slot(link));
+    follow(net, link)
+}
+
+#[cfg(not(feature = "compact-nodes"))]
+#[inline]
+fn follow(net : &Net, link : Link) -> Link {
+    load(net, link as usize)
+}
+
+#[cfg(feature = "compact-nodes")]
+#[inline]
+fn follow(net : &Net, link : Link) -> Link {
+    load(net, slot(link)) & LINK_MASK
 }
 
 // Type of the node.
@@ -67,85 +359,969 @@ pub fn enter(net : &Net, link : Link) -> Link {
 // 1 = con (i.e., a lambda or an application)
 // 2 = fan (i.e., a pair or a let)
 pub fn kind(net : &Net, node : u32) -> u32 {
-    net.nodes[link(node, 3) as usize]
+    bound(net, last_slot(node));
+    kind_of(net, node)
+}
+
+#[cfg(not(feature = "compact-nodes"))]
+#[inline]
+fn kind_of(net : &Net, node : u32) -> u32 {
+    load(net, link(node, 3) as usize)
+}
+
+#[cfg(feature = "compact-nodes")]
+#[inline]
+fn kind_of(net : &Net, node : u32) -> u32 {
+    load(net, slot(link(node, 0))) >> KIND_SHIFT
+}
+
+// Sets the kind of a node, leaving its ports as they are.
+pub fn set_kind(net : &mut Net, node : u32, kind : u32) {
+    bound(net, last_slot(node));
+    store_kind(net, node, kind);
+}
+
+#[cfg(not(feature = "compact-nodes"))]
+#[inline]
+fn store_kind(net : &mut Net, node : u32, kind : u32) {
+    store(net, link(node, 3) as usize, kind);
+}
+
+#[cfg(feature = "compact-nodes")]
+#[inline]
+fn store_kind(net : &mut Net, node : u32, kind : u32) {
+    let idx = slot(link(node, 0));
+    let word = load(net, idx);
+    store(net, idx, (word & LINK_MASK) | (kind << KIND_SHIFT));
+}
+
+// Points a port to a link, one way.
+#[cfg(not(feature = "compact-nodes"))]
+#[inline]
+fn set_port(net : &mut Net, port : Link, to : Link) {
+    store(net, port as usize, to);
+}
+
+#[cfg(feature = "compact-nodes")]
+#[inline]
+fn set_port(net : &mut Net, port : Link, to : Link) {
+    let idx = slot(port);
+    let word = load(net, idx);
+    store(net, idx, (word & !LINK_MASK) | to);
+}
+
+// Number of nodes currently in use (allocated and not freed), including the root.
+pub fn node_count(net : &Net) -> u32 {
+    (net.nodes.len() / NODE_SIZE - net.reuse.len()) as u32
 }
 
 // Connect two ports.
 pub fn connect(net : &mut Net, ptr_a : u32, ptr_b : u32) {
-    net.nodes[ptr_a as usize] = ptr_b;
-    net.nodes[ptr_b as usize] = ptr_a;
+    bound(net, slot(ptr_a));
+    bound(net, slot(ptr_b));
+    join(net, ptr_a, ptr_b);
+}
+
+#[inline]
+fn join(net : &mut Net, ptr_a : u32, ptr_b : u32) {
+    set_port(net, ptr_a, ptr_b);
+    set_port(net, ptr_b, ptr_a);
+}
+
+// The words of a net's nodes in the default layout, whatever the build's: each node's three ports,
+// then its kind. Those are what serialized nets hold.
+pub fn wide_words(net : &Net) -> Vec<u32> {
+    let mut words = Vec::with_capacity(net.nodes.len() / NODE_SIZE * 4);
+    for node in 0..(net.nodes.len() / NODE_SIZE) as u32 {
+        for port in 0..3 {
+            words.push(enter(net, link(node, port)));
+        }
+        words.push(kind(net, node));
+    }
+    words
+}
+
+// Lays words from `wide_words` out as this build does. With `compact-nodes`, a link or a kind too
+// large to pack is reported as out of bounds, or unknown.
+#[cfg(not(feature = "compact-nodes"))]
+pub fn from_wide_words(words : Vec<u32>) -> Result<Vec<u32>, NetError> {
+    Ok(words)
+}
+
+#[cfg(feature = "compact-nodes")]
+pub fn from_wide_words(words : Vec<u32>) -> Result<Vec<u32>, NetError> {
+    if !words.len().is_multiple_of(4) {
+        return Err(NetError::Truncated(words.len()));
+    }
+    let mut net = Net { nodes: vec![0; words.len() / 4 * NODE_SIZE], reuse: Vec::new(), ctrs: Vec::new() };
+    for (node, wide) in words.chunks(4).enumerate() {
+        let node = node as u32;
+        for port in 0..3 {
+            if wide[port as usize] > LINK_MASK {
+                return Err(NetError::OutOfBounds{from: link(node, port), to: wide[port as usize]});
+            }
+            set_port(&mut net, link(node, port), wide[port as usize]);
+        }
+        if wide[3] >> (32 - KIND_SHIFT) != 0 {
+            return Err(NetError::UnknownKind{node, kind: wide[3]});
+        }
+        set_kind(&mut net, node, wide[3]);
+    }
+    Ok(net.nodes)
+}
+
+// The functions above as methods, for users of the library. In debug builds, they check that links
+// are ports of nodes of the net (not the word holding a node's kind), and nodes are in the net.
+impl Net {
+    // Reduces the net as `reduce` does, one rewrite per item, each the stats so far. It ends once
+    // the net is in normal form, when the last item holds the stats `reduce` would return, except
+    // for the walk to the end. The iterator borrows the net mutably, so it can't be looked at
+    // between steps; dropping the iterator leaves it partially reduced, and a later `steps` or
+    // `reduce` picks up from there, counting from zero.
+    pub fn steps(&mut self) -> Steps<'_> {
+        let walk = Walk::new(self);
+        Steps { net: self, walk }
+    }
+
+    // Empties the net down to a root node, as `root_net` makes, keeping the room it has.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.reuse.clear();
+        self.ctrs.clear();
+        new_node(self, ROOT);
+        connect(self, link(0, PORT_AUX1), link(0, PORT_AUX2));
+    }
+
+    // Enters a link, returning the link on the other side.
+    pub fn enter(&self, link : Link) -> Link {
+        self.check_link(link);
+        enter(self, link)
+    }
+
+    // Type of a node.
+    pub fn kind(&self, node : u32) -> Kind {
+        debug_assert!((node as usize) < self.nodes.len() / NODE_SIZE, "node {} isn't in the net", node);
+        Kind::from(kind(self, node))
+    }
+
+    // Allocates a new node, with its ports looped.
+    pub fn new_node(&mut self, kind : Kind) -> u32 {
+        new_node(self, u32::from(kind))
+    }
+
+    // Links two ports.
+    pub fn connect(&mut self, a : Link, b : Link) {
+        self.check_link(a);
+        self.check_link(b);
+        connect(self, a, b)
+    }
+
+    fn check_link(&self, link : Link) {
+        debug_assert!(port(link) < 3, "link {} points to the kind of node {}, not a port", link, addr(link));
+        debug_assert!(slot(link) < self.nodes.len(), "link {} points outside of the net", link);
+    }
+
+    // Copies a fragment's nodes to the end of the net, offsetting their links, and returns the
+    // copy of the fragment's root port, which is left looped for the caller to connect.
+    // Constructors are matched by name, and those the net doesn't know are added to it.
+    pub fn graft(&mut self, fragment : &NetFragment) -> Link {
+        let offset = (self.nodes.len() / NODE_SIZE) as u32;
+        let kinds : Vec<u32> = fragment.ctrs.iter().map(|(nam, arity)| {
+            let idx = match self.ctrs.iter().position(|(ctr, _)| ctr == nam) {
+                Some(idx) => idx,
+                None => {
+                    self.ctrs.push((nam.clone(), *arity));
+                    self.ctrs.len() - 1
+                }
+            };
+            CTR + idx as u32
+        }).collect();
+        let len = self.nodes.len();
+        self.nodes.resize(len + fragment.nodes.len() / 4 * NODE_SIZE, 0);
+        for (idx, node) in fragment.nodes.chunks(4).enumerate() {
+            let node_at = offset + idx as u32;
+            for (slot, to) in node[..3].iter().enumerate() {
+                set_port(self, link(node_at, slot as u32), link(addr(*to) + offset, port(*to)));
+            }
+            set_kind(self, node_at, if node[3] >= CTR { kinds[(node[3] - CTR) as usize] } else { node[3] });
+        }
+        link(addr(fragment.root) + offset, port(fragment.root))
+    }
+
+    // Counts the active pairs of the net, as `active_pairs` lists them, but without allocating: a
+    // measure of the work left, cheap enough to take while reducing. Freed nodes are looked up in
+    // `reuse` only when they seem to form a pair, as annihilated ones still do.
+    pub fn interactions(&self) -> usize {
+        (1..(self.nodes.len() / NODE_SIZE) as u32)
+            .map(|node| (node, enter(self, link(node, PORT_MAIN))))
+            .filter(|(node, other)| port(*other) == PORT_MAIN && addr(*other) > *node)
+            .filter(|(node, other)| has_rule(kind(self, *node), kind(self, addr(*other))))
+            .filter(|(node, _)| !self.reuse.contains(node))
+            .count()
+    }
+}
+
+// A net with one free port, `root`, made to be copied into other nets by `Net::graft`. Its nodes
+// are numbered from 0, with no gaps and no root node; the root port is looped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetFragment {
+    pub nodes: Vec<u32>,
+    pub root: Link,
+    // The constructors of the fragment, as in `Net`.
+    pub ctrs: Vec<(Vec<u8>, u32)>
+}
+
+impl NetFragment {
+    // Takes the nodes of a net that aren't its root or freed, renumbering them, with the port
+    // the root leads to as the fragment's root.
+    pub fn from_net(net : &Net) -> NetFragment {
+        let mut number = vec![0; net.nodes.len() / NODE_SIZE];
+        for node in &net.reuse {
+            number[*node as usize] = u32::MAX;
+        }
+        let mut len = 0;
+        for number in number.iter_mut().skip(1) {
+            if *number != u32::MAX {
+                *number = len;
+                len += 1;
+            }
+        }
+        let relink = |link : Link| self::link(number[addr(link) as usize], port(link));
+        let root = relink(enter(net, 0));
+        let mut nodes = Vec::with_capacity(len as usize * 4);
+        for node in (1..number.len() as u32).filter(|node| number[*node as usize] != u32::MAX) {
+            for slot in 0..3 {
+                let to = enter(net, link(node, slot));
+                nodes.push(if to == 0 { root } else { relink(to) });
+            }
+            nodes.push(kind(net, node));
+        }
+        NetFragment { nodes, root, ctrs: net.ctrs.clone() }
+    }
+}
+
+// A net that's cheap to clone, to take snapshots of a large one: clones share its nodes until one
+// of them is changed, which copies them then, as `Arc::make_mut` does. Each clone reads as its own
+// `Net`, and changes it apart from the others, the same as a deep clone would.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedNet(Arc<Net>);
+
+impl SharedNet {
+    pub fn new(net : Net) -> SharedNet {
+        SharedNet(Arc::new(net))
+    }
+
+    // The net, to change it. Copies it first, if a clone still shares it.
+    pub fn make_mut(&mut self) -> &mut Net {
+        Arc::make_mut(&mut self.0)
+    }
+
+    // Whether two clones still share their nodes.
+    pub fn shares(&self, other : &SharedNet) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    // The net, copied only if a clone still shares it.
+    pub fn into_net(self) -> Net {
+        Arc::try_unwrap(self.0).unwrap_or_else(|net| (*net).clone())
+    }
+}
+
+// Nets kept to be reused, for evaluating many programs in a row without allocating each a net:
+// `take` hands out a net of only a root node, as `root_net` makes, but with the room the net had
+// before it was given back. `term::NetPool::to_net` and `from_net` take and give back for you.
+#[derive(Clone, Debug, Default)]
+pub struct NetPool {
+    nets: Vec<Net>
+}
+
+impl NetPool {
+    pub fn new() -> NetPool {
+        NetPool { nets: Vec::new() }
+    }
+
+    // A net of only a root node, from the pool if it has one.
+    pub fn take(&mut self) -> Net {
+        match self.nets.pop() {
+            Some(mut net) => {
+                net.clear();
+                net
+            },
+            None => root_net()
+        }
+    }
+
+    // Puts a net back in the pool, to be cleared and handed out again.
+    pub fn give(&mut self, net : Net) {
+        self.nets.push(net);
+    }
+
+    // How many nets the pool holds.
+    pub fn len(&self) -> usize {
+        self.nets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+}
+
+impl From<Net> for SharedNet {
+    fn from(net : Net) -> SharedNet {
+        SharedNet::new(net)
+    }
+}
+
+impl core::ops::Deref for SharedNet {
+    type Target = Net;
+
+    fn deref(&self) -> &Net {
+        &self.0
+    }
 }
 
 // Reduces a net to normal form lazily and sequentially.
 pub fn reduce(net : &mut Net) -> Stats {
-    let mut stats = Stats { loops: 0, rules: 0, betas: 0, dupls: 0, annis: 0 };
-    let mut schedule : Vec<u32> = Vec::new();
-    let mut exit : Vec<u32> = Vec::new();
-    let mut next : Link = net.nodes[0];
-    let mut prev : Link;
-    let mut back : Link;
-    while next > 0 || schedule.len() > 0 {
-        next = if next == 0 { enter(net, schedule.pop().unwrap()) } else { next };
-        prev = enter(net, next);
-        if port(next) == 0 && port(prev) == 0 && addr(prev) != 0 {
+    reduce_steps(net, u32::MAX, 0, &mut |_| true, &mut |_, _, _, _| {}).0
+}
+
+// Reduces a net like `reduce`, calling `progress` with the stats so far after every `stride`
+// rewrites (never, if `stride` is 0). Cheap enough to leave on for long reductions.
+pub fn reduce_with_progress<F : FnMut(&Stats)>(net : &mut Net, stride : u32, progress : &mut F) -> Stats {
+    reduce_steps(net, u32::MAX, stride, &mut |stats| {
+        progress(stats);
+        true
+    }, &mut |_, _, _, _| {}).0
+}
+
+// Reduces a net like `reduce_with_progress`, but calls `delta` with what changed since its last
+// call rather than the totals, and once more at the end with the rest, if there is any. Merging
+// the deltas in order gives the stats returned.
+pub fn reduce_deltas<F : FnMut(&Stats)>(net : &mut Net, stride : u32, delta : &mut F) -> Stats {
+    let mut last = Stats::default();
+    let stats = reduce_with_progress(net, stride, &mut |stats : &Stats| {
+        delta(&stats.since(&last));
+        last = stats.clone();
+    });
+    if stats != last {
+        delta(&stats.since(&last));
+    }
+    stats
+}
+
+// Reduces a net like `reduce`, but gives up once `max_rules` rewrites have been performed. The net
+// is left in a consistent, partially reduced state.
+pub fn reduce_limited(net : &mut Net, max_rules : u32) -> Result<Stats, LimitError> {
+    match reduce_steps(net, max_rules, 0, &mut |_| true, &mut |_, _, _, _| {}) {
+        (stats, true) => Ok(stats),
+        (_, false) => Err(LimitError::Rewrites(max_rules))
+    }
+}
+
+// Reduces a net like `reduce`, but stops short of a rewrite that would leave more than `max_nodes`
+// nodes in use. Only commutations grow a net, by two nodes each, so a duplication that blows up is
+// caught before it takes the memory. Returns the stats, as an error if it stopped, in which case
+// the net is left in a consistent, partially reduced state, within the bound.
+pub fn reduce_bounded(net : &mut Net, max_nodes : u32) -> Result<Stats, Stats> {
+    let mut walk = Walk::new(net);
+    while walk.find(net) {
+        let (x, y) = (addr(walk.next), addr(follow(net, walk.next)));
+        if kind_of(net, x) != kind_of(net, y) && node_count(net) + 2 > max_nodes {
+            return Err(walk.stats);
+        }
+        walk.rewrite(net);
+    }
+    Ok(walk.stats)
+}
+
+// Reduces a net to normal form eagerly: each pass rewrites every active pair of the net, reachable
+// from the root or not, until none is left. Since it also reduces garbage, it may not terminate
+// where `reduce` does; when it does, it performs the same number of rewrites as any other full
+// reduction.
+pub fn reduce_eager(net : &mut Net) -> Stats {
+    let mut stats = Stats { max_nodes: node_count(net), ..Stats::default() };
+    loop {
+        let pairs = active_pairs(net);
+        stats.loops += 1;
+        if pairs.is_empty() {
+            return stats;
+        }
+        for (x, y) in pairs {
+            stats.count_pair(kind(net, x), kind(net, y));
+            rewrite(net, x, y);
             stats.rules += 1;
-            back = enter(net, link(addr(prev), exit.pop().unwrap()));
-            rewrite(net, addr(prev), addr(next));
-            next = enter(net, back);
-        } else if port(next) == 0 {
-            schedule.push(link(addr(next), 2));
-            next = enter(net, link(addr(next), 1));
-        } else {
-            exit.push(port(next));
-            next = enter(net, link(addr(next), 0));
+            stats.max_nodes = stats.max_nodes.max(node_count(net));
+        }
+    }
+}
+
+// The active pairs of the net, reachable from the root or not, each once, by the address of its
+// first node.
+pub fn active_pairs(net : &Net) -> Vec<(u32, u32)> {
+    let mut freed = vec![false; net.nodes.len() / NODE_SIZE];
+    for node in &net.reuse {
+        freed[*node as usize] = true;
+    }
+    (1..(net.nodes.len() / NODE_SIZE) as u32)
+        .filter(|node| !freed[*node as usize])
+        .map(|node| (node, enter(net, link(node, PORT_MAIN))))
+        .filter(|(node, other)| port(*other) == PORT_MAIN && addr(*other) > *node)
+        .filter(|(node, other)| has_rule(kind(net, *node), kind(net, addr(*other))))
+        .map(|(node, other)| (node, addr(other)))
+        .collect()
+}
+
+// The nodes in use that the root can't reach, through any of their ports, ascending. Lazy
+// reduction only rewrites what the root leads to, so those are garbage it left behind, like an
+// argument that was erased: an ERA facing it, not yet collected. Garbage that should have been
+// used may also be a wiring mistake.
+pub fn unreachable_nodes(net : &Net) -> Vec<u32> {
+    let mut depths = depths(net);
+    for node in &net.reuse {
+        depths[*node as usize] = Some(0);
+    }
+    (0..depths.len() as u32).filter(|node| depths[*node as usize].is_none()).collect()
+}
+
+// The distance of each node from the root, in wires, by address, or `None` if the root can't
+// reach it (freed nodes, say).
+pub fn depths(net : &Net) -> Vec<Option<u32>> {
+    let mut depth = vec![None; net.nodes.len() / NODE_SIZE];
+    let mut level = vec![0];
+    depth[0] = Some(0);
+    let mut dist = 0;
+    while !level.is_empty() {
+        dist += 1;
+        let mut next = Vec::new();
+        for node in level {
+            for slot in 0..3 {
+                let other = addr(enter(net, link(node, slot)));
+                if depth[other as usize].is_none() {
+                    depth[other as usize] = Some(dist);
+                    next.push(other);
+                }
+            }
         }
+        level = next;
+    }
+    depth
+}
+
+// Reduces a net sharing rewrites fairly between its parts, so one that diverges can't starve the
+// others. Each round goes over the active pairs of the net, as `active_pairs` lists them, giving
+// each a budget of `budget` rewrites: the pair, then the pairs its rewrites make next to it. Like
+// `reduce_eager`, it reduces garbage too. Gives up after `max_rules` rewrites, leaving the net
+// consistent, with every part reduced about as far.
+pub fn reduce_fair(net : &mut Net, max_rules : u32, budget : u32) -> Result<Stats, LimitError> {
+    let mut stats = Stats { max_nodes: node_count(net), ..Stats::default() };
+    let mut freed = vec![false; net.nodes.len() / NODE_SIZE];
+    for node in &net.reuse {
+        freed[*node as usize] = true;
+    }
+    // The node `x` faces by its principal port, if they have a rule. Freed nodes face nothing.
+    let partner = |net : &Net, freed : &[bool], x : u32| {
+        let y = enter(net, link(x, PORT_MAIN));
+        let live = x != 0 && addr(y) != 0 && !freed[x as usize] && !freed[addr(y) as usize];
+        if live && port(y) == PORT_MAIN && has_rule(kind(net, x), kind(net, addr(y))) { Some(addr(y)) } else { None }
+    };
+    loop {
+        let pairs = active_pairs(net);
         stats.loops += 1;
+        if pairs.is_empty() {
+            return Ok(stats);
+        }
+        for (x, _) in pairs {
+            let mut local = vec![x];
+            let mut spent = 0;
+            while let Some(x) = local.pop() {
+                let y = match partner(net, &freed, x) {
+                    Some(y) if spent < budget => y,
+                    _ => continue
+                };
+                if stats.rules == max_rules {
+                    return Err(LimitError::Rewrites(max_rules));
+                }
+                let mut near : Vec<u32> = [x, y].iter()
+                    .flat_map(|node| [PORT_AUX1, PORT_AUX2].map(|slot| addr(enter(net, link(*node, slot)))))
+                    .collect();
+                stats.count_pair(kind(net, x), kind(net, y));
+                match rewrite(net, x, y) {
+                    Some((a, b)) => {
+                        freed.resize(net.nodes.len() / NODE_SIZE, false);
+                        freed[a as usize] = false;
+                        freed[b as usize] = false;
+                        near.extend([x, y, a, b]);
+                    },
+                    None => {
+                        freed[x as usize] = true;
+                        freed[y as usize] = true;
+                    }
+                }
+                stats.rules += 1;
+                stats.max_nodes = stats.max_nodes.max(node_count(net));
+                spent += 1;
+                local.extend(near);
+            }
+        }
+    }
+}
+
+// The kind of a node, with constructors told apart by name rather than by number, so nets whose
+// constructors were registered in a different order compare equal.
+fn kind_key(net : &Net, node : u32) -> (u32, &[u8]) {
+    match net.ctrs.get(kind(net, node).wrapping_sub(CTR) as usize) {
+        Some((nam, _)) => (CTR, nam),
+        None => (kind(net, node), &[])
+    }
+}
+
+// Checks that two nets are the same graph, up to the addresses of their nodes. Only the part
+// connected to the root is compared, so garbage and freed space don't matter.
+pub fn nets_equal(a : &Net, b : &Net) -> bool {
+    let mut a_to_b = vec![u32::MAX; a.nodes.len() / NODE_SIZE];
+    let mut b_to_a = vec![u32::MAX; b.nodes.len() / NODE_SIZE];
+    let mut visit = vec![(link(0, PORT_MAIN), link(0, PORT_MAIN))];
+    while let Some((la, lb)) = visit.pop() {
+        let (na, nb) = (addr(la), addr(lb));
+        if port(la) != port(lb) {
+            return false;
+        }
+        if a_to_b[na as usize] == nb && b_to_a[nb as usize] == na {
+            continue;
+        }
+        if a_to_b[na as usize] != u32::MAX || b_to_a[nb as usize] != u32::MAX || kind_key(a, na) != kind_key(b, nb) {
+            return false;
+        }
+        a_to_b[na as usize] = nb;
+        b_to_a[nb as usize] = na;
+        for k in 0..3 {
+            visit.push((enter(a, link(na, k)), enter(b, link(nb, k))));
+        }
+    }
+    true
+}
+
+// A function told of each rewrite: the net after it, the active pair, and the copies made.
+pub(crate) type Rewritten<'a> = dyn FnMut(&Net, u32, u32, Option<(u32, u32)>) + 'a;
+
+// Where a lazy reduction is between rewrites: the stats so far, the link it's about to enter, and
+// the links left to come back to.
+struct Walk {
+    stats: Stats,
+    schedule: Vec<u32>,
+    exit: Vec<u32>,
+    // Nodes of inert pairs, whose auxiliary ports were already visited.
+    stuck: BTreeSet<u32>,
+    next: Link
+}
+
+impl Walk {
+    fn new(net : &Net) -> Walk {
+        bound_links(net);
+        Walk {
+            stats: Stats { max_nodes: node_count(net), ..Stats::default() },
+            schedule: Vec::new(),
+            exit: Vec::new(),
+            stuck: BTreeSet::new(),
+            next: follow(net, 0)
+        }
+    }
+
+    // Walks the net up to the next active pair, leaving `next` on it, or returns false if there
+    // is none left: the net is in normal form.
+    fn find(&mut self, net : &Net) -> bool {
+        // How many auxiliary ports in a row were entered, and how long `exit` was before. Going up
+        // more principal ports than there are nodes means going around a vicious circle: nodes
+        // each pointing their principal port at the next one's auxiliary port. It has no active
+        // pair to reach, and is left as it is.
+        let mut climb = (0, 0);
+        while self.next > 0 || !self.schedule.is_empty() {
+            let next = if self.next == 0 { follow(net, self.schedule.pop().unwrap()) } else { self.next };
+            self.next = next;
+            let prev = follow(net, next);
+            if port(next) == PORT_MAIN && port(prev) == PORT_MAIN && addr(prev) != 0 && !has_rule(kind_of(net, addr(prev)), kind_of(net, addr(next))) {
+                // An inert pair, like a free variable or a constructor applied: the nodes stay, but
+                // their auxiliary ports are reduced, once, as if they had been entered by their
+                // principal ports. Looped ports, as a FREE node's, lead nowhere.
+                self.next = 0;
+                for node in [addr(prev), addr(next)] {
+                    if follow(net, link(node, PORT_AUX1)) != link(node, PORT_AUX2) && self.stuck.insert(node) {
+                        self.schedule.push(link(node, PORT_AUX2));
+                        self.schedule.push(link(node, PORT_AUX1));
+                    }
+                }
+            } else if port(next) == PORT_MAIN && port(prev) == PORT_MAIN && addr(prev) != 0 {
+                return true;
+            } else if port(next) == PORT_MAIN {
+                self.schedule.push(link(addr(next), PORT_AUX2));
+                self.next = follow(net, link(addr(next), PORT_AUX1));
+            } else if climb.0 as usize > net.nodes.len() / NODE_SIZE {
+                self.exit.truncate(climb.1);
+                self.next = 0;
+            } else {
+                if climb.0 == 0 {
+                    climb.1 = self.exit.len();
+                }
+                climb.0 += 1;
+                self.exit.push(port(next));
+                self.next = follow(net, link(addr(next), PORT_MAIN));
+            }
+            if port(self.next) == PORT_MAIN || self.next == 0 {
+                climb.0 = 0;
+            }
+            self.stats.loops += 1;
+        }
+        false
+    }
+
+    // Rewrites the active pair `find` stopped on, returning it, with the copies `rewrite` made.
+    fn rewrite(&mut self, net : &mut Net) -> (u32, u32, Option<(u32, u32)>) {
+        let (x, y) = (addr(follow(net, self.next)), addr(self.next));
+        self.stats.rules += 1;
+        self.stats.count_pair(kind_of(net, x), kind_of(net, y));
+        let back = follow(net, link(x, self.exit.pop().unwrap()));
+        let copies = rewrite_pair(net, x, y);
+        self.stats.max_nodes = self.stats.max_nodes.max(node_count(net));
+        self.stats.loops += 1;
+        self.next = follow(net, back);
+        (x, y, copies)
     }
-    stats
 }
 
-// Rewrites an active pair.
-pub fn rewrite(net : &mut Net, x : Link, y : Link) {
-    if kind(net, x) == kind(net, y) {
-        let p0 = enter(net, link(x, 1));
-        let p1 = enter(net, link(y, 1));
-        connect(net, p0, p1);
-        let p0 = enter(net, link(x, 2));
-        let p1 = enter(net, link(y, 2));
-        connect(net, p0, p1);
+// Reduces a net performing at most `max_rules` rewrites, calling `progress` every `stride` rewrites
+// and stopping if it returns false. Each rewrite is also passed to `rewritten`, with the pair and
+// the copies `rewrite` made. Returns the stats and whether the net reached normal form.
+pub(crate) fn reduce_steps(net : &mut Net, max_rules : u32, stride : u32, progress : &mut dyn FnMut(&Stats) -> bool, rewritten : &mut Rewritten) -> (Stats, bool) {
+    let mut walk = Walk::new(net);
+    while walk.find(net) {
+        if walk.stats.rules == max_rules {
+            return (walk.stats, false);
+        }
+        let (x, y, copies) = walk.rewrite(net);
+        rewritten(net, x, y, copies);
+        if stride > 0 && walk.stats.rules.is_multiple_of(stride) && !progress(&walk.stats) {
+            return (walk.stats, false);
+        }
+    }
+    (walk.stats, true)
+}
+
+// The rewrites of a lazy reduction, one at a time, as `Net::steps` gives them.
+pub struct Steps<'a> {
+    net: &'a mut Net,
+    walk: Walk
+}
+
+impl<'a> Iterator for Steps<'a> {
+    type Item = Stats;
+
+    fn next(&mut self) -> Option<Stats> {
+        if !self.walk.find(self.net) {
+            return None;
+        }
+        self.walk.rewrite(self.net);
+        Some(self.walk.stats.clone())
+    }
+}
+
+// A lazy reduction that owns its net, so it can be stopped, saved with `to_bytes`, and picked up
+// again, even in another process, with `from_bytes`. Unlike a reduction started over on the
+// partially reduced net, it goes on from where it was, so it performs the rewrites an
+// uninterrupted `reduce` would, and ends with the same net and stats.
+pub struct Reducer {
+    net: Net,
+    walk: Walk
+}
+
+impl Reducer {
+    pub fn new(net : Net) -> Reducer {
+        let walk = Walk::new(&net);
+        Reducer { net, walk }
+    }
+
+    // Performs at most `max_rules` more rewrites, returning whether the net reached normal form.
+    pub fn run(&mut self, max_rules : u32) -> bool {
+        for _ in 0..max_rules {
+            if !self.walk.find(&self.net) {
+                return true;
+            }
+            self.walk.rewrite(&mut self.net);
+        }
+        !self.walk.find(&self.net)
+    }
+
+    // The stats so far, counting from `new`, across saves.
+    pub fn stats(&self) -> &Stats {
+        &self.walk.stats
+    }
+
+    pub fn net(&self) -> &Net {
+        &self.net
+    }
+
+    pub fn into_net(self) -> Net {
+        self.net
+    }
+
+    // Encodes the reduction: the magic bytes `SICR`, then `NET_VERSION`, the stats as seven words in
+    // the order of their fields, the link the walk is about to enter, the number of entries in its
+    // schedule, exits and inert nodes, those entries, the net as `net_to_bytes` lays it out, and
+    // finally the CRC-32 of everything before it. All numbers are little-endian u32s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let walk = &self.walk;
+        let stats = &walk.stats;
+        let mut bytes = b"SICR".to_vec();
+        let header = [
+            NET_VERSION, stats.loops, stats.rules, stats.betas, stats.dupls, stats.annis, stats.erasures, stats.max_nodes,
+            walk.next, walk.schedule.len() as u32, walk.exit.len() as u32, walk.stuck.len() as u32
+        ];
+        for word in header.iter().chain(&walk.schedule).chain(&walk.exit).chain(&walk.stuck) {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(&net_to_bytes(&self.net));
+        let crc = crc32(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    // Decodes a reduction written by `to_bytes`, checking the checksum first, then the net, then
+    // that the walk only refers to its nodes and can go on from where it is.
+    pub fn from_bytes(bytes : &[u8]) -> Result<Reducer, NetError> {
+        let word = |idx : usize| {
+            let mut buf = [0; 4];
+            buf.copy_from_slice(&bytes[idx * 4..idx * 4 + 4]);
+            u32::from_le_bytes(buf)
+        };
+        if bytes.len() < 56 || !bytes.len().is_multiple_of(4) {
+            return Err(NetError::BadEncoding("too short, or not made of whole words"));
+        }
+        let end = bytes.len() / 4 - 1;
+        let (stored, computed) = (word(end), crc32(&bytes[..end * 4]));
+        if stored != computed {
+            return Err(NetError::Checksum{stored, computed});
+        }
+        if &bytes[0..4] != b"SICR" {
+            return Err(NetError::BadEncoding("missing the SICR magic bytes"));
+        }
+        if word(1) != NET_VERSION {
+            return Err(NetError::Version(word(1)));
+        }
+        let (schedule, exit, stuck) = (word(10) as usize, word(11) as usize, word(12) as usize);
+        let net_at = schedule.checked_add(exit).and_then(|len| len.checked_add(stuck)).and_then(|len| len.checked_add(13))
+            .filter(|at| *at <= end).ok_or(NetError::BadEncoding("lengths don't match the size of the data"))?;
+        let net = net_from_bytes(&bytes[net_at * 4..end * 4])?;
+        let walk = Walk {
+            stats: Stats { loops: word(2), rules: word(3), betas: word(4), dupls: word(5), annis: word(6), erasures: word(7), max_nodes: word(8) },
+            next: word(9),
+            schedule: (13..13 + schedule).map(word).collect(),
+            exit: (13 + schedule..13 + schedule + exit).map(word).collect(),
+            stuck: (13 + schedule + exit..net_at).map(word).collect()
+        };
+        let nodes = (net.nodes.len() / NODE_SIZE) as u32;
+        let links = walk.schedule.iter().chain(Some(&walk.next)).all(|link| addr(*link) < nodes && port(*link) < 3);
+        if !links || walk.exit.iter().any(|port| *port >= 3) || walk.stuck.iter().any(|node| *node >= nodes) {
+            return Err(NetError::BadEncoding("the walk refers to nodes outside of the net"));
+        }
+        // `find` only reaches an active pair by climbing to it, so a walk on one has a way back.
+        let prev = follow(&net, walk.next);
+        if walk.exit.is_empty() && walk.next != 0 && port(walk.next) == PORT_MAIN && port(prev) == PORT_MAIN && addr(prev) != 0 {
+            return Err(NetError::BadEncoding("the walk is on an active pair it has no way back from"));
+        }
+        Ok(Reducer { net, walk })
+    }
+}
+
+// Checks that a net is consistently wired: it has a root, every port points to a port that points
+// back, every node has a known kind (constructors having at most two fields), and the reuse list
+// only holds nodes. Nets built by `to_net` and `rewrite` always pass; nets from elsewhere should be
+// checked before they're reduced.
+pub fn check_net(net : &Net) -> Result<(), NetError> {
+    let len = net.nodes.len();
+    if !len.is_multiple_of(NODE_SIZE) || len < NODE_SIZE {
+        return Err(NetError::Truncated(len));
+    }
+    let nodes = (len / NODE_SIZE) as u32;
+    for &node in &net.reuse {
+        if node == 0 || node >= nodes {
+            return Err(NetError::BadReuse(node));
+        }
+    }
+    for node in 0..nodes {
+        if net.reuse.contains(&node) {
+            continue;
+        }
+        let kind = kind(net, node);
+        let known = kind <= FREE || net.ctrs.get(kind.wrapping_sub(CTR) as usize).is_some_and(|(_, arity)| *arity <= MAX_FIELDS);
+        if node > 0 && !known {
+            return Err(NetError::UnknownKind{node, kind});
+        }
+        for slot in 0..3 {
+            let from = link(node, slot);
+            let to = enter(net, from);
+            if addr(to) >= nodes || port(to) == 3 {
+                return Err(NetError::OutOfBounds{from, to});
+            }
+            if enter(net, to) != from {
+                return Err(NetError::Asymmetric{from, to});
+            }
+        }
+    }
+    Ok(())
+}
+
+// CRC-32 (IEEE 802.3, reflected polynomial 0xEDB88320), as used by zip and PNG.
+pub fn crc32(bytes : &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// Encodes a net in the binary layout: the magic bytes `SICN`, then `NET_VERSION`, the number of
+// words in `nodes`, the number of entries in `reuse`, the number of constructors, those words and
+// entries, each constructor as its arity, the length of its name and the name's bytes (padded
+// with zeros to whole words), and finally the CRC-32 of everything before it. All numbers are
+// little-endian u32s.
+pub fn net_to_bytes(net : &Net) -> Vec<u8> {
+    let mut bytes = b"SICN".to_vec();
+    let words = wide_words(net);
+    let header = [NET_VERSION, words.len() as u32, net.reuse.len() as u32, net.ctrs.len() as u32];
+    for word in header.iter().chain(&words).chain(&net.reuse) {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    for (nam, arity) in &net.ctrs {
+        bytes.extend_from_slice(&arity.to_le_bytes());
+        bytes.extend_from_slice(&(nam.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(nam);
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+    }
+    let crc = crc32(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+// Decodes a net written by `net_to_bytes`, checking the checksum first and the wiring last.
+pub fn net_from_bytes(bytes : &[u8]) -> Result<Net, NetError> {
+    let word = |idx : usize| {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(&bytes[idx * 4..idx * 4 + 4]);
+        u32::from_le_bytes(buf)
+    };
+    if bytes.len() < 24 || !bytes.len().is_multiple_of(4) {
+        return Err(NetError::BadEncoding("too short, or not made of whole words"));
+    }
+    let end = bytes.len() / 4 - 1;
+    let (stored, computed) = (word(end), crc32(&bytes[..end * 4]));
+    if stored != computed {
+        return Err(NetError::Checksum{stored, computed});
+    }
+    if &bytes[0..4] != b"SICN" {
+        return Err(NetError::BadEncoding("missing the SICN magic bytes"));
+    }
+    if word(1) != NET_VERSION {
+        return Err(NetError::Version(word(1)));
+    }
+    let (nodes, reuse) = (word(2) as usize, word(3) as usize);
+    let short = NetError::BadEncoding("lengths don't match the size of the data");
+    let ctrs_at = nodes.checked_add(reuse).and_then(|len| len.checked_add(5)).filter(|at| *at <= end).ok_or(short.clone())?;
+    let mut ctrs = Vec::new();
+    let mut at = ctrs_at;
+    for _ in 0..word(4) {
+        if at + 2 > end {
+            return Err(short);
+        }
+        let (arity, len) = (word(at), word(at + 1) as usize);
+        let start = (at + 2) * 4;
+        if len > (end - at - 2) * 4 {
+            return Err(short);
+        }
+        ctrs.push((bytes[start..start + len].to_vec(), arity));
+        at += 2 + len.div_ceil(4);
+    }
+    if at != end {
+        return Err(short);
+    }
+    let net = Net {
+        nodes: from_wide_words((5..5 + nodes).map(word).collect())?,
+        reuse: (5 + nodes..ctrs_at).map(word).collect(),
+        ctrs
+    };
+    check_net(&net)?;
+    Ok(net)
+}
+
+// Reduces a net to normal form, also measuring the wall-clock time it took.
+#[cfg(feature = "std")]
+pub fn reduce_timed(net : &mut Net) -> (Stats, Duration) {
+    let start = Instant::now();
+    let stats = reduce(net);
+    (stats, start.elapsed())
+}
+
+// Rewrites an active pair. When the nodes commute, returns the nodes it made: a copy of `x`, and
+// a copy of `y` (`x` and `y` themselves become the other copies).
+pub fn rewrite(net : &mut Net, x : Link, y : Link) -> Option<(u32, u32)> {
+    bound(net, last_slot(x.max(y)));
+    for slot in [PORT_AUX1, PORT_AUX2] {
+        bound_port(net, follow(net, link(x, slot)));
+        bound_port(net, follow(net, link(y, slot)));
+    }
+    rewrite_pair(net, x, y)
+}
+
+// `rewrite`, once the ports of `x` and `y` are known to point inside the net, as in a walk.
+fn rewrite_pair(net : &mut Net, x : u32, y : u32) -> Option<(u32, u32)> {
+    #[cfg(feature = "trace-log")]
+    eprintln!("rewrite: {:?} {} ~ {:?} {}", Kind::from(kind_of(net, x)), x, Kind::from(kind_of(net, y)), y);
+    if kind_of(net, x) == kind_of(net, y) {
+        let p0 = follow(net, link(x, PORT_AUX1));
+        let p1 = follow(net, link(y, PORT_AUX1));
+        join(net, p0, p1);
+        let p0 = follow(net, link(x, PORT_AUX2));
+        let p1 = follow(net, link(y, PORT_AUX2));
+        join(net, p0, p1);
         net.reuse.push(x);
         net.reuse.push(y);
+        None
     } else {
-        let t = kind(net, x);
+        let t = kind_of(net, x);
         let a = new_node(net, t);
-        let t = kind(net, y);
+        let t = kind_of(net, y);
         let b = new_node(net, t);
-        let t = enter(net, link(x, 1));
-        connect(net, link(b, 0), t);
-        let t = enter(net, link(x, 2));
-        connect(net, link(y, 0), t);
-        let t = enter(net, link(y, 1));
-        connect(net, link(a, 0), t);
-        let t = enter(net, link(y, 2));
-        connect(net, link(x, 0), t);
-        connect(net, link(a, 1), link(b, 1));
-        connect(net, link(a, 2), link(y, 1));
-        connect(net, link(x, 1), link(b, 2));
-        connect(net, link(x, 2), link(y, 2));
+        let t = follow(net, link(x, PORT_AUX1));
+        join(net, link(b, PORT_MAIN), t);
+        let t = follow(net, link(x, PORT_AUX2));
+        join(net, link(y, PORT_MAIN), t);
+        let t = follow(net, link(y, PORT_AUX1));
+        join(net, link(a, PORT_MAIN), t);
+        let t = follow(net, link(y, PORT_AUX2));
+        join(net, link(x, PORT_MAIN), t);
+        join(net, link(a, PORT_AUX1), link(b, PORT_AUX1));
+        join(net, link(a, PORT_AUX2), link(y, PORT_AUX1));
+        join(net, link(x, PORT_AUX1), link(b, PORT_AUX2));
+        join(net, link(x, PORT_AUX2), link(y, PORT_AUX2));
+        Some((a, b))
     }
 }
 
+#[cfg(feature = "std")]
 pub fn print_net(net : &mut Net) {
     let mut i = 0;
 
-    while i < net.nodes.len() {
-        println!("{}: {}.{} | {}.{} | {}.{} | K:{}", i >> 2, 
-            port(net.nodes[i]), addr(net.nodes[i]), 
-            port(net.nodes[i+1]), addr(net.nodes[i+1]), 
-            port(net.nodes[i+2]), addr(net.nodes[i+2]), 
-            net.nodes[i+3]
+    while i < net.nodes.len() / NODE_SIZE {
+        let node = i as u32;
+        let (a, b, c) = (enter(net, link(node, 0)), enter(net, link(node, 1)), enter(net, link(node, 2)));
+        println!("{}: {}.{} | {}.{} | {}.{} | K:{}", i, 
+            port(a), addr(a), 
+            port(b), addr(b), 
+            port(c), addr(c), 
+            kind(net, node)
         );
-        i+=4;
+        i+=1;
     }
 
     println!("Empty addresses:");
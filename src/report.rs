@@ -0,0 +1,226 @@
+// Reports of a reduction, as a single HTML file with nothing outside it: the source, the normal
+// form, the stats, how many nodes of each kind the net had before and after, a chart of its size
+// as it was reduced, and, for small runs, a slider through the net at each step. Each part is
+// built by its own function, as a fragment of HTML, so they can be checked apart.
+
+use prelude::*;
+use net::*;
+use term::*;
+use dot::{label, DotOptions};
+
+// Runs whose nets are drawn at each step: those of at most `MAX_SNAPSHOTS` rewrites, on nets of
+// at most `MAX_SNAPSHOT_NODES` nodes. Larger drawings can't be followed anyway.
+pub const MAX_SNAPSHOTS : usize = 64;
+pub const MAX_SNAPSHOT_NODES : u32 = 48;
+
+// The most points the size chart draws. Longer runs are sampled evenly.
+const MAX_POINTS : usize = 512;
+
+// What `record` keeps of a reduction.
+#[derive(Clone, Debug)]
+pub struct Run {
+    pub source: String,
+    // The net before and after the reduction.
+    pub initial: Net,
+    pub net: Net,
+    pub stats: Stats,
+    // Whether the net reached normal form.
+    pub completed: bool,
+    // The nodes in use before the first rewrite and after each.
+    pub sizes: Vec<u32>,
+    // The net before the first rewrite and after each, for small runs; otherwise, none.
+    pub snapshots: Vec<Net>
+}
+
+// Reduces a net like `net::reduce`, giving up after `max_rules` rewrites, and keeps what a report
+// shows of it.
+pub fn record(source : &str, net : Net, max_rules : u32) -> Run {
+    let initial = net.clone();
+    let mut net = net;
+    let mut sizes = vec![node_count(&net)];
+    let mut snapshots = vec![net.clone()];
+    let (stats, completed) = reduce_steps(&mut net, max_rules, 0, &mut |_| true, &mut |net, _, _, _| {
+        sizes.push(node_count(net));
+        if snapshots.len() > MAX_SNAPSHOTS || node_count(net) > MAX_SNAPSHOT_NODES {
+            snapshots.clear();
+        } else if !snapshots.is_empty() {
+            snapshots.push(net.clone());
+        }
+    });
+    if node_count(&initial) > MAX_SNAPSHOT_NODES {
+        snapshots.clear();
+    }
+    Run { source: source.to_string(), initial, net, stats, completed, sizes, snapshots }
+}
+
+// Escapes text to be put in HTML.
+pub fn escape(text : &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for chr in text.chars() {
+        match chr {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            _ => html.push(chr)
+        }
+    }
+    html
+}
+
+// A term laid out by `pretty`, in a `pre`, with its operators and names in spans of classes `op`
+// and `name`, for the style sheet to color.
+pub fn term_html(term : &Term) -> String {
+    let code = String::from_utf8_lossy(&pretty(term)).into_owned();
+    let mut html = String::from("<pre class=\"term\">");
+    let mut name = String::new();
+    let flush = |html : &mut String, name : &mut String| {
+        if !name.is_empty() {
+            html.push_str(&format!("<span class=\"name\">{}</span>", escape(name)));
+            name.clear();
+        }
+    };
+    for chr in code.chars() {
+        match chr {
+            '\\' | '/' | '|' | '=' | '&' | '*' | ':' => {
+                flush(&mut html, &mut name);
+                html.push_str(&format!("<span class=\"op\">{}</span>", escape(&chr.to_string())));
+            },
+            ' ' | '\n' => {
+                flush(&mut html, &mut name);
+                html.push(chr);
+            },
+            _ => name.push(chr)
+        }
+    }
+    flush(&mut html, &mut name);
+    html.push_str("</pre>");
+    html
+}
+
+// The counters of a reduction, as a table.
+pub fn stats_table(stats : &Stats) -> String {
+    let rows = [
+        ("rewrites", stats.rules),
+        ("beta reductions", stats.betas),
+        ("duplications", stats.dupls),
+        ("annihilations", stats.annis),
+        ("erasures", stats.erasures),
+        ("nodes visited", stats.loops),
+        ("most nodes", stats.max_nodes)
+    ];
+    let mut html = String::from("<table class=\"stats\">");
+    for (name, value) in rows {
+        html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>", name, value));
+    }
+    html.push_str("</table>");
+    html
+}
+
+// How many nodes of each kind are in use in a net, by the label `to_dot` gives them.
+pub fn census(net : &Net) -> BTreeMap<String, u32> {
+    let mut counts = BTreeMap::new();
+    for node in 0..(net.nodes.len() / NODE_SIZE) as u32 {
+        if !net.reuse.contains(&node) {
+            *counts.entry(label(net, node)).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+// The nodes of each kind before and after a reduction, as a table.
+pub fn census_table(before : &Net, after : &Net) -> String {
+    let (before, after) = (census(before), census(after));
+    let mut kinds : Vec<&String> = before.keys().chain(after.keys()).collect();
+    kinds.sort();
+    kinds.dedup();
+    let mut html = String::from("<table class=\"census\"><tr><th>kind</th><th>before</th><th>after</th></tr>");
+    for kind in kinds {
+        let count = |counts : &BTreeMap<String, u32>| counts.get(kind).cloned().unwrap_or(0);
+        html.push_str(&format!("<tr><th>{}</th><td>{}</td><td>{}</td></tr>", escape(kind), count(&before), count(&after)));
+    }
+    html.push_str("</table>");
+    html
+}
+
+// The nodes in use at each rewrite, as a line chart in SVG, with the largest count marked.
+pub fn size_chart(sizes : &[u32]) -> String {
+    let (width, height) = (600.0, 200.0);
+    let stride = sizes.len().div_ceil(MAX_POINTS).max(1);
+    let last = sizes.len().saturating_sub(1).max(1) as f64;
+    let most = sizes.iter().cloned().max().unwrap_or(0).max(1) as f64;
+    let points : Vec<String> = sizes.iter().enumerate()
+        .filter(|(idx, _)| idx % stride == 0 || *idx + 1 == sizes.len())
+        .map(|(idx, size)| format!("{:.1},{:.1}", idx as f64 / last * width, height - *size as f64 / most * height))
+        .collect();
+    format!(
+        "<svg class=\"sizes\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"-40 -10 {vw} {vh}\"><polyline fill=\"none\" stroke=\"steelblue\" points=\"{points}\"/><text x=\"-5\" y=\"4\" text-anchor=\"end\">{most}</text><text x=\"-5\" y=\"{h}\" text-anchor=\"end\">0</text><text x=\"{w}\" y=\"{below}\" text-anchor=\"end\">{rules} rewrites</text></svg>",
+        w = width, h = height, vw = width + 50.0, vh = height + 30.0, points = points.join(" "),
+        most = most as u32, below = height + 16.0, rules = sizes.len().saturating_sub(1)
+    )
+}
+
+// A net drawn in SVG: its nodes in use around a circle, by address, filled with the colors
+// `to_dot` gives their kinds, and a line for each wire.
+pub fn net_svg(net : &Net) -> String {
+    let colors = DotOptions::default().colors;
+    let count = net.nodes.len() / NODE_SIZE;
+    let radius = 20.0 + 12.0 * count as f64 / core::f64::consts::PI;
+    let at = |node : u32| {
+        let angle = 2.0 * core::f64::consts::PI * node as f64 / count as f64;
+        (radius * angle.sin(), -radius * angle.cos())
+    };
+    let used : Vec<u32> = (0..count as u32).filter(|node| !net.reuse.contains(node)).collect();
+    let size = radius + 20.0;
+    let mut html = format!("<svg class=\"net\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{s}\" height=\"{s}\" viewBox=\"{o} {o} {s} {s}\">", s = 2.0 * size, o = -size);
+    for node in &used {
+        for slot in 0..3 {
+            let (from, to) = (link(*node, slot), enter(net, link(*node, slot)));
+            if from <= to {
+                let ((x1, y1), (x2, y2)) = (at(*node), at(addr(to)));
+                html.push_str(&format!("<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\"/>", x1, y1, x2, y2));
+            }
+        }
+    }
+    for node in &used {
+        let (x, y) = at(*node);
+        let color = colors.iter().find(|(kind, _)| *kind == net.kind(*node)).map_or("white", |(_, color)| color.as_str());
+        html.push_str(&format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"9\" fill=\"{}\" stroke=\"black\"/>", x, y, color));
+        html.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"6\">{}</text>", x, y + 2.0, escape(&label(net, *node))));
+    }
+    html.push_str("</svg>");
+    html
+}
+
+// A slider through drawings of a net, one per step, showing the first.
+pub fn slider(frames : &[Net]) -> String {
+    let mut html = format!("<div class=\"steps\"><input type=\"range\" min=\"0\" max=\"{}\" value=\"0\" oninput=\"step(this.value)\"> <span id=\"step\">0</span>", frames.len().saturating_sub(1));
+    for (idx, net) in frames.iter().enumerate() {
+        let hidden = if idx == 0 { "" } else { " style=\"display: none\"" };
+        html.push_str(&format!("<div class=\"frame\"{}>{}</div>", hidden, net_svg(net)));
+    }
+    html.push_str("</div><script>function step(idx) { document.querySelectorAll('.frame').forEach(function (frame, at) { frame.style.display = at == idx ? '' : 'none'; }); document.getElementById('step').textContent = idx; }</script>");
+    html
+}
+
+const STYLE : &str = "body { font-family: sans-serif; margin: 2em; } pre { background: #f4f4f4; padding: 1em; } .op { color: #a0522d; } .name { color: #1f4e79; } th { text-align: left; padding-right: 1em; } td { text-align: right; padding-right: 1em; }";
+
+// The whole report of a run, as an HTML page.
+pub fn to_html(run : &Run) -> String {
+    let mut html = format!("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Reduction report</title><style>{}</style></head><body>", STYLE);
+    html.push_str("<h1>Reduction report</h1>");
+    html.push_str(&format!("<h2>Source</h2><pre class=\"source\">{}</pre>", escape(&run.source)));
+    html.push_str(if run.completed { "<h2>Normal form</h2>" } else { "<h2>Partial result</h2>" });
+    match from_net(&run.net) {
+        Ok(term) => html.push_str(&term_html(&term)),
+        Err(err) => html.push_str(&format!("<p>The net can't be read back: {}</p>", escape(&err.to_string())))
+    }
+    html.push_str(&format!("<h2>Stats</h2>{}", stats_table(&run.stats)));
+    html.push_str(&format!("<h2>Nodes by kind</h2>{}", census_table(&run.initial, &run.net)));
+    html.push_str(&format!("<h2>Size of the net</h2>{}", size_chart(&run.sizes)));
+    if !run.snapshots.is_empty() {
+        html.push_str(&format!("<h2>Steps</h2>{}", slider(&run.snapshots)));
+    }
+    html.push_str("</body></html>\n");
+    html
+}
@@ -0,0 +1,100 @@
+// The interaction rules, shown by running them: each rule is drawn from the net `rewrite` leaves
+// when given an active pair, so what `sic rules` prints is what the reducer does.
+
+use prelude::*;
+use net::*;
+
+// The node kinds that have rules, in the order their pairs are listed. `CTR` stands for every
+// constructor a net registers: they follow the same rules, whatever their name.
+pub const KINDS : [u32; 4] = [ERA, CON, FAN, CTR];
+
+pub fn kind_name(kind : u32) -> &'static str {
+    match kind {
+        ERA => "ERA",
+        CON => "CON",
+        FAN => "FAN",
+        FREE | ROOT => "?",
+        _ => "CTR"
+    }
+}
+
+// Builds an active pair of nodes of the given kinds. Their auxiliary ports lead to free wires (FREE
+// nodes), except an ERA's, which are looped together, as `to_net` builds them. Returns the net and the
+// addresses of the pair. A constructor is registered with two fields, as `#Ctr`.
+pub fn active_pair(a : u32, b : u32) -> (Net, u32, u32) {
+    let mut net = root_net();
+    if a >= CTR || b >= CTR {
+        net.ctrs.push((b"#Ctr".to_vec(), MAX_FIELDS));
+    }
+    let x = new_node(&mut net, a);
+    let y = new_node(&mut net, b);
+    connect(&mut net, link(x, PORT_MAIN), link(y, PORT_MAIN));
+    for node in [x, y] {
+        if kind(&net, node) == ERA {
+            connect(&mut net, link(node, PORT_AUX1), link(node, PORT_AUX2));
+        } else {
+            for port in 1..3 {
+                let wire = new_node(&mut net, FREE);
+                connect(&mut net, link(wire, PORT_MAIN), link(node, port));
+            }
+        }
+    }
+    (net, x, y)
+}
+
+// Shows a net built by `active_pair` as a list of nodes, each with where its ports lead. Nodes are
+// named by kind and position, like `CON1`, and the free wires `a`, `b`... in the order they were
+// made; the root and freed nodes are left out.
+pub fn show_wiring(net : &Net) -> String {
+    let nodes : Vec<u32> = (1..(net.nodes.len() / NODE_SIZE) as u32).filter(|node| !net.reuse.contains(node)).collect();
+    let wires : Vec<u32> = nodes.iter().cloned().filter(|node| kind(net, *node) == FREE).collect();
+    let inner : Vec<u32> = nodes.iter().cloned().filter(|node| kind(net, *node) != FREE).collect();
+    let name = |node : u32| match wires.iter().position(|wire| *wire == node) {
+        Some(idx) => ((b'a' + idx as u8) as char).to_string(),
+        None => format!("{}{}", kind_name(kind(net, node)), inner.iter().position(|n| *n == node).unwrap() + 1)
+    };
+    let mut text = String::new();
+    for node in &inner {
+        text.push_str(&format!("    {}", name(*node)));
+        for slot in 0..3 {
+            let other = enter(net, link(*node, slot));
+            if wires.contains(&addr(other)) {
+                text.push_str(&format!("  {}:{}", slot, name(addr(other))));
+            } else {
+                text.push_str(&format!("  {}:{}.{}", slot, name(addr(other)), port(other)));
+            }
+        }
+        text.push('\n');
+    }
+    if inner.is_empty() {
+        text.push_str("    (no nodes)\n");
+    }
+    text
+}
+
+// Shows the rule for an active pair of the given kinds: whether the nodes annihilate or commute,
+// and the wiring before and after `rewrite`. Pairs with no rule, like a constructor and a lambda,
+// are said to be inert.
+pub fn show_rule(a : u32, b : u32) -> String {
+    if !has_rule(a, b) {
+        return format!("{} >< {}: no rule, the pair is inert\n", kind_name(a), kind_name(b));
+    }
+    let (before, x, y) = active_pair(a, b);
+    let mut after = before.clone();
+    let effect = if rewrite(&mut after, x, y).is_some() { "commute" } else { "annihilate" };
+    format!("{} >< {}: {}\n  before:\n{}  after:\n{}",
+        kind_name(a), kind_name(b), effect, show_wiring(&before), show_wiring(&after))
+}
+
+// The pairs of kinds with a rule, one for each unordered pair.
+pub fn pairs() -> Vec<(u32, u32)> {
+    let mut pairs = Vec::new();
+    for (i, a) in KINDS.iter().enumerate() {
+        for b in &KINDS[i..] {
+            if has_rule(*a, *b) {
+                pairs.push((*a, *b));
+            }
+        }
+    }
+    pairs
+}
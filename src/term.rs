@@ -1,11 +1,15 @@
-#![allow(dead_code)]
-
-use std::collections::*;
+use alloc::fmt;
+use prelude::*;
 use net::*;
-use std;
+use module::Module;
+use fans::FanOrigin;
+use error::*;
+
+pub use machine::eval_machine;
 
-// Terms of the Abstract Calculus.
-#[derive(Clone, Debug)]
+// Terms of the Abstract Calculus. Equality and hashing compare names as they are; compare the
+// `canonicalize`d terms to tell terms apart up to renaming.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     // Abstractions (affine functions).
     Lam {nam: Vec<u8>, bod: Box<Term>},                               
@@ -19,438 +23,2789 @@ pub enum Term {
     // Definitions (let).
     Dup {fst: Vec<u8>, snd: Vec<u8>, val: Box<Term>, nxt: Box<Term>},
 
-    // Variable.
-    Var {nam: Vec<u8>}, 
+    // Erasures: `& val nxt` discards `val`, and is `nxt`. Unlike `*`, which is a term that erases
+    // what it meets, this erases a term and stands for another.
+    Era {val: Box<Term>, nxt: Box<Term>},
+
+    // Variable.
+    Var {nam: Vec<u8>}, 
+
+    // Constructor, like `#Nil`, given its fields by application (`//#Cons a b`).
+    Ctr {nam: Vec<u8>},
+
+    // Set.
+    Set
+}
+use self::Term::{*};
+
+// Source code is Ascii-encoded.
+pub type Str = [u8];
+pub type Chr = u8;
+
+// Builds a var name from an index (0="a", 1="b", 26="aa"...).
+pub fn new_name(idx : u32) -> Vec<Chr> {
+    let mut name = Vec::new();
+    let mut idx = idx;
+    while idx > 0 {
+        idx -= 1;
+        name.push((97 + idx % 26) as u8);
+        idx /= 26;
+    }
+    name
+}
+
+pub fn name_idx(name : &[Chr]) -> u32 {
+    let mut idx : u32 = 0;
+    for byte in name.iter().rev() {
+        idx = (idx * 26) + (*byte as u32 - 97) + 1;
+    }
+    idx
+}
+
+// The names in scope while parsing, innermost last, each with a value if it names a definition.
+// A variable whose innermost entry has a value is replaced by a copy of it, with its binders
+// renamed apart (`id#0#x`), as inlined definitions are. One whose innermost entry has none stays a
+// variable: these are binders, such as the parameters of a definition, and they shadow definitions
+// of the same name. Names in no entry stay variables too. Seeding a scope with definitions, like a
+// prelude, lets terms parsed with it refer to them. Entries are only added for the length of a
+// closure (`with_binding`), so parsing leaves a scope as it found it, even when it fails.
+#[derive(Clone, Debug, Default)]
+pub struct Scope<'a> {
+    entries: Vec<(&'a Str, Option<Term>)>
+}
+
+impl<'a> Scope<'a> {
+    pub fn new() -> Scope<'a> {
+        Scope::default()
+    }
+
+    // A scope holding definitions, the later ones shadowing the earlier ones of the same name.
+    pub fn with_definitions<I : IntoIterator<Item = (&'a Str, Term)>>(defs : I) -> Scope<'a> {
+        Scope { entries: defs.into_iter().map(|(nam, val)| (nam, Some(val))).collect() }
+    }
+
+    // How many entries are in scope.
+    pub fn depth(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Whether a name is in scope, as a definition or a binder.
+    pub fn contains(&self, nam : &Str) -> bool {
+        self.entries.iter().any(|(def, _)| *def == nam)
+    }
+
+    // The value of the definition a name refers to, if its innermost entry is one.
+    pub fn find(&self, nam : &Str) -> Option<&Term> {
+        self.entries.iter().rev().find(|(def, _)| *def == nam).and_then(|(_, val)| val.as_ref())
+    }
+
+    // Runs `f` with a name in scope, a definition if it's given a value and a binder otherwise, and
+    // takes it out afterwards, whatever `f` returns.
+    pub fn with_binding<R, F : FnOnce(&mut Scope<'a>) -> R>(&mut self, nam : &'a Str, val : Option<Term>, f : F) -> R {
+        self.with_bindings(Some((nam, val)), f)
+    }
+
+    // Runs `f` with several names in scope, the last innermost, like `with_binding`.
+    pub fn with_bindings<R, I, F>(&mut self, entries : I, f : F) -> R
+        where I : IntoIterator<Item = (&'a Str, Option<Term>)>, F : FnOnce(&mut Scope<'a>) -> R {
+        let depth = self.entries.len();
+        self.entries.extend(entries);
+        let result = f(self);
+        self.entries.truncate(depth);
+        result
+    }
+}
+
+
+// Parses a name, returns the remaining code and the name. Names may contain `#`, which is how the
+// copies of a definition are namespaced (`two#0#s`), so printed terms can be parsed back. They end
+// before the punctuation of terms, including the `;` and braces of `data` and `match`.
+pub(crate) fn parse_name(code : &Str) -> Result<(&Str, &Str), ParseError> {
+    // Ignore whitespace until name is found.
+    let mut j : usize = 0;
+    while j < code.len() && (
+        code[j] == b' ' || code[j] == b'\n' || code[j] == b'\r'
+    ) {
+        j += 1;
+    }
+
+    let mut i : usize = j;
+    while i < code.len() && !(
+        code[i] == b' ' || code[i] == b'\n' || code[i] == b'\r' ||
+        code[i] == b'\\' || code[i] == b'/' || code[i] == b'|' || code[i] == b'=' ||
+        code[i] == b'*' || code[i] == b'&' || code[i] == b';' || code[i] == b'{' || code[i] == b'}'
+    ) {
+        i += 1;
+    }
+
+    if i == j {
+        return Err(ParseError::new(&code[j..], "expected a name"));
+    }
+    if code[j..i].starts_with(ELIDED.as_bytes()) {
+        return Err(ParseError::new(&code[j..], "output with elided terms can't be parsed"));
+    }
+
+    Ok((&code[i..], &code[j..i]))
+}
+
+// Whether a binder name is a wildcard: `_` or `-`, which bind nothing, and are erased. Read-back
+// names every unused binder `-`, and either spelling parses back the same.
+pub(crate) fn is_wildcard(nam : &[u8]) -> bool {
+    nam == b"_" || nam == b"-"
+}
+
+// Names a variable of the `idx`th copy of a definition, `space#idx#var`. Wildcards stay as they are.
+pub(crate) fn namespace(space : &[u8], idx : u32, var : &[u8]) -> Vec<u8> {
+    joined(space, idx, var, b'#')
+}
+
+fn joined(space : &[u8], idx : u32, var : &[u8], sep : u8) -> Vec<u8> {
+    if !is_wildcard(var) {
+        let mut nam = space.to_vec();
+        nam.push(sep);
+        nam.append(&mut idx.to_string().as_bytes().to_vec());
+        nam.push(sep);
+        nam.extend_from_slice(var);
+        nam
+    } else {
+        var.to_vec()
+    }
+}
+
+// Splits a namespaced name, `def#idx#var`, into its definition, number and variable.
+pub(crate) fn split_namespace(nam : &[u8]) -> Option<(&[u8], u32, &[u8])> {
+    for i in 1..nam.len() {
+        let digits = nam[i + 1..].iter().take_while(|chr| chr.is_ascii_digit()).count();
+        if nam[i] == b'#' && digits > 0 && nam.get(i + 1 + digits) == Some(&b'#') {
+            let idx = core::str::from_utf8(&nam[i + 1..i + 1 + digits]).ok()?.parse().ok()?;
+            return Some((&nam[..i], idx, &nam[i + 2 + digits..]));
+        }
+    }
+    None
+}
+
+// While parsing, the copies of definitions name their variables `def;idx;var`. Since `;` ends a
+// name, no name a user writes, not even `def#idx#var`, is taken for one. `name_copies` gives them
+// their printed form once the term is parsed.
+const COPY_SEP : u8 = b';';
+
+fn copy_name(space : &[u8], idx : u32, var : &[u8]) -> Vec<u8> {
+    joined(space, idx, var, COPY_SEP)
+}
+
+fn split_copy_name(nam : &[u8]) -> Option<(&[u8], u32, &[u8])> {
+    let at = nam.iter().position(|chr| *chr == COPY_SEP)?;
+    let digits = nam[at + 1..].iter().position(|chr| *chr == COPY_SEP)?;
+    let idx = core::str::from_utf8(&nam[at + 1..at + 1 + digits]).ok()?.parse().ok()?;
+    Some((&nam[..at], idx, &nam[at + 2 + digits..]))
+}
+
+// Gives the names of copies in a parsed term their printed form, `def#idx#var`. Names the user
+// wrote are kept, and a copy's name that would be the same as one of them, or as another copy's,
+// gets `'`s appended until it's new.
+pub(crate) fn name_copies(term : &Term) -> Term {
+    fn go(term : &Term, names : &BTreeMap<Vec<u8>, Vec<u8>>) -> Term {
+        let name = |nam : &Vec<u8>| names.get(nam).unwrap_or(nam).clone();
+        match term {
+            Lam{nam, bod} => Lam{nam: name(nam), bod: Box::new(go(bod, names))},
+            App{fun, arg} => App{fun: Box::new(go(fun, names)), arg: Box::new(go(arg, names))},
+            Par{fst, snd} => Par{fst: Box::new(go(fst, names)), snd: Box::new(go(snd, names))},
+            Dup{fst, snd, val, nxt} => Dup{fst: name(fst), snd: name(snd), val: Box::new(go(val, names)), nxt: Box::new(go(nxt, names))},
+            Era{val, nxt} => Era{val: Box::new(go(val, names)), nxt: Box::new(go(nxt, names))},
+            Var{nam} => Var{nam: name(nam)},
+            Ctr{..} | Set => term.clone()
+        }
+    }
+    let mut all = BTreeSet::new();
+    binders(term, &mut all);
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    all.extend(counts.into_keys());
+    let (copies, mut taken) : (BTreeSet<Vec<u8>>, BTreeSet<Vec<u8>>) = all.into_iter().partition(|nam| split_copy_name(nam).is_some());
+    if copies.is_empty() {
+        return term.clone();
+    }
+    let mut names = BTreeMap::new();
+    for nam in copies {
+        let (def, idx, var) = split_copy_name(&nam).unwrap();
+        let mut printed = namespace(def, idx, var);
+        while taken.contains(&printed) {
+            printed.push(b'\'');
+        }
+        taken.insert(printed.clone());
+        names.insert(nam, printed);
+    }
+    go(term, &names)
+}
+
+// Makes a namespaced copy of a term, numbered from `idx`, which is advanced past the numbers used.
+// Names from copies inlined in the term, like `d;0;x`, aren't nested (`e;1;d;0;x`), which would
+// grow with each level of inlining: they keep their definition and variable, and each number they
+// have is replaced by a fresh one. So names stay short however deep definitions go.
+pub(crate) fn copy(space : &[u8], idx : &mut u32, term : &Term) -> Term {
+    fn go(space : &[u8], own : u32, idx : &mut u32, fresh : &mut BTreeMap<u32, u32>, term : &Term) -> Term {
+        let mut name = |nam : &[u8]| match split_copy_name(nam) {
+            Some((def, num, var)) => {
+                let num = *fresh.entry(num).or_insert_with(|| {
+                    *idx += 1;
+                    *idx - 1
+                });
+                copy_name(def, num, var)
+            },
+            None => copy_name(space, own, nam)
+        };
+        match term {
+            Lam{nam, bod} => {
+                let nam = name(nam);
+                let bod = Box::new(go(space, own, idx, fresh, bod));
+                Lam{nam, bod}
+            },
+            App{fun, arg} => {
+                let fun = Box::new(go(space, own, idx, fresh, fun));
+                let arg = Box::new(go(space, own, idx, fresh, arg));
+                App{fun, arg}
+            },
+            Par{fst, snd} => {
+                let fst = Box::new(go(space, own, idx, fresh, fst));
+                let snd = Box::new(go(space, own, idx, fresh, snd));
+                Par{fst, snd}
+            },
+            Dup{fst, snd, val, nxt} => {
+                let fst = name(fst);
+                let snd = name(snd);
+                let val = Box::new(go(space, own, idx, fresh, val));
+                let nxt = Box::new(go(space, own, idx, fresh, nxt));
+                Dup{fst, snd, val, nxt}
+            },
+            Var{nam} => {
+                let nam = name(nam);
+                Var{nam}
+            },
+            Era{val, nxt} => {
+                let val = Box::new(go(space, own, idx, fresh, val));
+                let nxt = Box::new(go(space, own, idx, fresh, nxt));
+                Era{val, nxt}
+            },
+            Ctr{nam} => Ctr{nam: nam.clone()},
+            Set => Set
+        }
+    }
+    let own = *idx;
+    *idx += 1;
+    go(space, own, idx, &mut BTreeMap::new(), term)
+}
+
+// Limits that keep `parse_checked` safe on any input: how deeply terms may nest (so recursion
+// can't overflow the stack) and how many nodes a program may expand to once definitions are inlined
+// (so definitions that copy each other can't exhaust memory). Other entry points don't apply them.
+pub const MAX_DEPTH : u32 = 512;
+pub const MAX_SIZE : u64 = 1 << 22;
+
+// Number of nodes in a term.
+pub(crate) fn size(term : &Term) -> u64 {
+    match term {
+        Lam{bod, ..} => 1 + size(bod),
+        App{fun, arg} => 1 + size(fun) + size(arg),
+        Par{fst, snd} => 1 + size(fst) + size(snd),
+        Dup{val, nxt, ..} | Era{val, nxt} => 1 + size(val) + size(nxt),
+        Var{..} | Ctr{..} | Set => 1
+    }
+}
+
+// Parses a term, returns the remaining code and the term. Definitions in `ctx` (see `Scope`)
+// are inlined where the term refers to them. `idx` numbers the copies made, and is advanced past
+// the numbers used: parsing several terms with the same `idx` keeps their copies apart. `comment`
+// is how many comments are open where `code` starts, usually 0. On failure, the error's `index`
+// counts the bytes left unparsed; `ParseError::located` turns it into an offset from the start of
+// the source.
+pub fn parse_term<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, comment : u32) -> Result<(&'a Str, Term), ParseError> {
+    let (rest, term) = parse_bounded(code, ctx, idx, comment, 0, &mut Tally::default())?;
+    Ok((rest, name_copies(&term)))
+}
+
+// Comments within a term, each with the position, in preorder, of the subterm it comes before.
+pub type Notes = Vec<(u64, Vec<u8>)>;
+
+// Parses a term like `parse_term`, also returning the comments in it, as `pretty_commented` takes
+// them. Positions can only be kept for terms read node for node; in one with sugar or definitions
+// to expand, every comment is put before the whole term. Comments between the parts of a `data`
+// or `match` are dropped.
+pub fn parse_commented<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32) -> Result<(&'a Str, Term, Notes), ParseError> {
+    let mut tally = Tally::default();
+    let (rest, term) = parse_bounded(code, ctx, idx, 0, 0, &mut tally)?;
+    let exact = tally.read == size(&term);
+    let notes = tally.comments.into_iter().map(|(at, text)| (if exact { at } else { 0 }, text.to_vec())).collect();
+    Ok((rest, name_copies(&term), notes))
+}
+
+// What parsing a term counts as it goes: the nodes it expands to, which `MAX_SIZE` bounds, the
+// nodes it has read, and the comments it skipped, each after how many nodes were read. Only a
+// `bounded` parse, as `parse_checked`'s, enforces `MAX_DEPTH` and `MAX_SIZE`.
+#[derive(Default)]
+struct Tally<'a> {
+    size: u64,
+    read: u64,
+    comments: Vec<(u64, &'a Str)>,
+    bounded: bool
+}
+
+impl<'a> Tally<'a> {
+    fn too_large(&self) -> bool {
+        self.bounded && self.size > MAX_SIZE
+    }
+}
+
+// Parses a term like `parse_term`, at nesting `depth`, adding the nodes it builds to `size`.
+fn parse_bounded<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, comment : u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    // Skips whitespace and (possibly nested) comments, keeping the comments it started.
+    let mut code = code;
+    let mut comment = comment;
+    let mut start = None;
+    loop {
+        match code.first() {
+            None => {
+                let message = if comment > 0 { "unclosed comment" } else { "unexpected end of input" };
+                return Err(ParseError::new(code, message));
+            },
+            Some(b'(') => {
+                if comment == 0 {
+                    start = Some(code);
+                }
+                comment += 1;
+            },
+            Some(b')') if comment > 0 => {
+                comment -= 1;
+                if let (0, Some(text)) = (comment, start) {
+                    tally.comments.push((tally.read, &text[..text.len() - code.len() + 1]));
+                }
+            },
+            Some(_) if comment > 0 => {},
+            Some(b' ') | Some(b'\n') | Some(b'\r') => {},
+            Some(_) => break
+        }
+        code = &code[1..];
+    }
+    if tally.bounded && depth >= MAX_DEPTH {
+        return Err(ParseError::new(code, "terms are nested too deeply"));
+    }
+    tally.size += 1;
+    tally.read += 1;
+    if tally.too_large() {
+        return Err(ParseError::new(code, "program is too large once definitions are expanded"));
+    }
+    let depth = depth + 1;
+    // Each construct is parsed by its own function, which keeps the frames of this recursion small.
+    match code[0] {
+        b'\\' => parse_lam(&code[1..], ctx, idx, depth, tally),
+        b'/' => parse_app(&code[1..], ctx, idx, depth, tally),
+        b'|' => parse_par(&code[1..], ctx, idx, depth, tally),
+        b'=' => parse_dup(&code[1..], ctx, idx, depth, tally),
+        b':' => parse_def(&code[1..], ctx, idx, depth, tally),
+        b'*' => Ok((&code[1..], Set)),
+        b'&' => parse_era(&code[1..], ctx, idx, depth, tally),
+        b'#' if code.get(1).is_some_and(u8::is_ascii_uppercase) => parse_ctr(code, ctx, idx, tally),
+        // Variables may be named `data` or `match` where they're bound.
+        _ if keyword(code, b"data") && !ctx.contains(b"data") => parse_data(&code[4..], ctx, idx, depth, tally),
+        _ if keyword(code, b"match") && !ctx.contains(b"match") => parse_match(&code[5..], ctx, idx, depth, tally),
+        _ if keyword(code, b"@rec") => parse_rec(&code[4..], ctx, idx, depth, tally),
+        _ => parse_var(code, ctx, idx, tally)
+    }
+}
+
+// Abstraction
+fn parse_lam<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, nam) = parse_name(code)?;
+    let (code, bod) = ctx.with_binding(nam, None, |ctx| parse_bounded(code, ctx, idx, 0, depth, tally))?;
+    let nam = nam.to_vec();
+    let bod = Box::new(bod);
+    Ok((code, Lam{nam,bod}))
+}
+
+// Application
+fn parse_app<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, fun) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let (code, arg) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let fun = Box::new(fun);
+    let arg = Box::new(arg);
+    Ok((code, App{fun,arg}))
+}
+
+// Pair
+fn parse_par<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, fst) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let (code, snd) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let fst = Box::new(fst);
+    let snd = Box::new(snd);
+    Ok((code, Par{fst,snd}))
+}
+
+// Duplication
+fn parse_dup<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, fst) = parse_name(code)?;
+    let (code, snd) = parse_name(code.get(1..).unwrap_or(code))?;
+    let (code, val, nxt) = ctx.with_bindings([(snd, None), (fst, None)], |ctx| {
+        let (code, val) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+        let (code, nxt) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+        Ok((code, val, nxt))
+    })?;
+    let fst = fst.to_vec();
+    let snd = snd.to_vec();
+    let val = Box::new(val);
+    let nxt = Box::new(nxt);
+    Ok((code, Dup{fst, snd, val, nxt}))
+}
+
+// Erasure
+fn parse_era<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, val) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let (code, nxt) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let val = Box::new(val);
+    let nxt = Box::new(nxt);
+    Ok((code, Era{val, nxt}))
+}
+
+// Definition
+fn parse_def<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, nam) = parse_name(code)?;
+    let (code, _) = parse_fuel(code)?;
+    let (code, params) = parse_params(code, nam)?;
+    let binders = params.iter().map(|param| (*param, None));
+    let (code, val) = ctx.with_bindings(binders, |ctx| parse_bounded(code, ctx, idx, 0, depth, tally))?;
+    let val = lambdas(&params, val);
+    ctx.with_binding(nam, Some(val), |ctx| parse_bounded(code, ctx, idx, 0, depth, tally))
+}
+
+// Parses the annotation a definition may have after its name, `%fuel n`, which limits the
+// rewrites attributed to it (see `fuel`). Returns the remaining code and the limit, if any.
+pub(crate) fn parse_fuel(code : &Str) -> Result<(&Str, Option<u32>), ParseError> {
+    let at = skip_blank(code);
+    if !keyword(at, b"%fuel") {
+        return Ok((code, None));
+    }
+    let at = skip_blank(&at[5..]);
+    let digits = at.iter().take_while(|chr| chr.is_ascii_digit()).count();
+    match core::str::from_utf8(&at[..digits]).ok().and_then(|num| num.parse().ok()) {
+        Some(fuel) => Ok((&at[digits..], Some(fuel))),
+        None => Err(ParseError::new(at, "expected a number of rewrites after `%fuel`"))
+    }
+}
+
+// Parses the parameters a definition may list before an `=`, as in `:compose f g x = /f /g x`,
+// which is sugar for `:compose \f \g \x /f /g x`. Returns the code after the `=` and the
+// parameters, or the code as is and none if it doesn't start with such a list. A parameter other
+// than a wildcard can't be listed twice, nor shadow `def`, the definition's own name.
+pub(crate) fn parse_params<'a>(code : &'a Str, def : &Str) -> Result<(&'a Str, Vec<&'a Str>), ParseError> {
+    let mut params : Vec<(&Str, &Str)> = Vec::new();
+    let mut at = skip_blank(code);
+    loop {
+        match at.first() {
+            Some(b'=') if !params.is_empty() => break,
+            Some(chr) if !b"\\/|=*&:#()".contains(chr) && ![&b"data"[..], b"match", b"@rec"].iter().any(|word| keyword(at, word)) => {
+                let (rest, param) = parse_name(at)?;
+                params.push((at, param));
+                at = skip_blank(rest);
+            },
+            _ => return Ok((code, Vec::new()))
+        }
+    }
+    for (idx, (loc, param)) in params.iter().enumerate() {
+        let name = String::from_utf8_lossy(param);
+        if *param == def {
+            return Err(ParseError::new(loc, &format!("parameter `{}` shadows the definition", name)));
+        }
+        if !is_wildcard(param) && params[..idx].iter().any(|(_, prev)| prev == param) {
+            return Err(ParseError::new(loc, &format!("parameter `{}` is listed twice", name)));
+        }
+    }
+    Ok((&at[1..], params.into_iter().map(|(_, param)| param).collect()))
+}
+
+// Wraps a term in a lambda for each parameter, the first outermost.
+pub(crate) fn lambdas(params : &[&Str], body : Term) -> Term {
+    params.iter().rev().fold(body, |bod, nam| Lam{nam: nam.to_vec(), bod: Box::new(bod)})
+}
+
+// Whether the code starts with a keyword, followed by a blank.
+fn keyword(code : &Str, word : &[u8]) -> bool {
+    code.starts_with(word) && matches!(code.get(word.len()), Some(b' ') | Some(b'\n') | Some(b'\r'))
+}
+
+// Skips whitespace and comments between the parts of a `data` or `match`.
+fn skip_blank(code : &Str) -> &Str {
+    let mut code = code;
+    let mut comment : u32 = 0;
+    while let Some(chr) = code.first() {
+        match chr {
+            b'(' => comment += 1,
+            b')' if comment > 0 => comment -= 1,
+            b' ' | b'\n' | b'\r' => {},
+            _ if comment > 0 => {},
+            _ => break
+        }
+        code = &code[1..];
+    }
+    code
+}
+
+// Reads the words of the head of a constructor or branch, up to a byte of `ends`, which is left.
+fn parse_words<'a>(code : &'a Str, ends : &[u8]) -> Result<(&'a Str, Vec<&'a Str>), ParseError> {
+    let mut code = code;
+    let mut words = Vec::new();
+    loop {
+        code = skip_blank(code);
+        match code.first() {
+            None => return Err(ParseError::new(code, "unexpected end of input")),
+            Some(chr) if ends.contains(chr) => return Ok((code, words)),
+            Some(_) => {
+                let len = code.iter().take_while(|chr| !ends.contains(chr) && !matches!(chr, b' ' | b'\n' | b'\r')).count();
+                words.push(&code[..len]);
+                code = &code[len..];
+            }
+        }
+    }
+}
+
+// Constructor: `#` and a capital letter, then the rest of the name. One declared by a `data` is a
+// copy of its definition; any other is a constructor agent.
+fn parse_ctr<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (rest, nam) = parse_name(code)?;
+    if ctx.contains(nam) {
+        return parse_var(code, ctx, idx, tally);
+    }
+    Ok((rest, Ctr{nam: nam.to_vec()}))
+}
+
+// The Scott encoding of a constructor of a data type: it takes its fields, then one case for each
+// constructor of the type, and applies its own case to the fields. Cases are named after their
+// constructors, which is how `match` finds the type of a constructor from its definition.
+fn scott(ctrs : &[(&Str, Vec<&Str>)], ctr : usize) -> Term {
+    let (nam, fields) = &ctrs[ctr];
+    let mut term = Var{nam: nam.to_vec()};
+    for field in fields {
+        term = App{fun: Box::new(term), arg: Box::new(Var{nam: field.to_vec()})};
+    }
+    for (case, _) in ctrs.iter().rev() {
+        term = Lam{nam: case.to_vec(), bod: Box::new(term)};
+    }
+    for field in fields.iter().rev() {
+        term = Lam{nam: field.to_vec(), bod: Box::new(term)};
+    }
+    term
+}
+
+// The arity of a constructor defined by `scott`, and the constructors of its type, in order.
+fn scott_type(term : &Term) -> Option<(usize, Vec<&[u8]>)> {
+    let mut term = term;
+    let mut arity = 0;
+    let mut ctrs = Vec::new();
+    while let Lam{nam, bod} = term {
+        if nam.starts_with(b"#") {
+            ctrs.push(&nam[..]);
+        } else if ctrs.is_empty() {
+            arity += 1;
+        } else {
+            return None;
+        }
+        term = bod;
+    }
+    if ctrs.is_empty() { None } else { Some((arity, ctrs)) }
+}
+
+// Data type: `data List {#Nil; #Cons head tail} rest`. Within `rest`, each constructor is defined
+// by its Scott encoding, so `//#Cons h t` builds a list, and `match` can take lists apart.
+fn parse_data<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, _) = parse_name(code)?;
+    let code = skip_blank(code);
+    if code.first() != Some(&b'{') {
+        return Err(ParseError::new(code, "expected `{`: `data` starts a declaration, unless a variable of that name is bound"));
+    }
+    let mut code = &code[1..];
+    let mut ctrs : Vec<(&'a Str, Vec<&'a Str>)> = Vec::new();
+    loop {
+        let at = skip_blank(code);
+        let (rest, words) = parse_words(code, b";}")?;
+        let (nam, fields) = match words.split_first() {
+            Some((nam, fields)) => (*nam, fields.to_vec()),
+            None => return Err(ParseError::new(at, "expected a constructor"))
+        };
+        if nam.len() < 2 || nam[0] != b'#' || !nam[1].is_ascii_uppercase() {
+            return Err(ParseError::new(at, "constructors are named `#` and a capital letter, like `#Nil`"));
+        }
+        if ctrs.iter().any(|(ctr, _)| *ctr == nam) {
+            return Err(ParseError::new(at, "constructor declared twice"));
+        }
+        for (i, field) in fields.iter().enumerate() {
+            if field.starts_with(b"#") || fields[..i].contains(field) {
+                return Err(ParseError::new(at, "fields must be distinct names, not starting with `#`"));
+            }
+        }
+        ctrs.push((nam, fields));
+        code = &rest[1..];
+        if rest[0] == b'}' {
+            break;
+        }
+    }
+    let defs = (0..ctrs.len()).map(|ctr| (ctrs[ctr].0, Some(scott(&ctrs, ctr))));
+    ctx.with_bindings(defs, |ctx| parse_bounded(code, ctx, idx, 0, depth, tally))
+}
+
+// Pattern matching: `match x {#Nil: nil; #Cons h t: cons}`, on a value of a type declared by a
+// `data`, becomes the application of `x` to one case for each constructor, in the order they were
+// declared, each taking the fields: `//x nil \h \t cons`. Every constructor needs a branch, and a
+// branch's fields can be used once at most.
+fn parse_match<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let (code, val) = parse_bounded(code, ctx, idx, 0, depth, tally)?;
+    let code = skip_blank(code);
+    if code.first() != Some(&b'{') {
+        return Err(ParseError::new(code, "expected `{`: `match` starts a match, unless a variable of that name is bound"));
+    }
+    let mut code = &code[1..];
+    // Each branch: its constructor, fields and body, and where it starts.
+    let mut branches : Vec<(&'a Str, Vec<&'a Str>, Term, &'a Str)> = Vec::new();
+    loop {
+        let at = skip_blank(code);
+        if at.first() == Some(&b'}') && !branches.is_empty() {
+            code = at;
+            break;
+        }
+        let (rest, words) = parse_words(code, b":;}")?;
+        if rest[0] != b':' || words.is_empty() {
+            return Err(ParseError::new(at, "expected a branch, like `#Nil: term`"));
+        }
+        let fields = words[1..].iter().map(|field| (*field, None));
+        let (rest, bod) = ctx.with_bindings(fields, |ctx| parse_bounded(&rest[1..], ctx, idx, 0, depth, tally))?;
+        branches.push((words[0], words[1..].to_vec(), bod, at));
+        code = skip_blank(rest);
+        match code.first() {
+            Some(b';') => code = &code[1..],
+            Some(b'}') => break,
+            _ => return Err(ParseError::new(code, "expected `;` or `}`"))
+        }
+    }
+    let (_, ctrs) = match ctx.find(branches[0].0).and_then(scott_type) {
+        Some(typ) => typ,
+        None => return Err(ParseError::new(branches[0].3, "not a constructor declared by a `data`"))
+    };
+    for (i, (nam, fields, bod, at)) in branches.iter().enumerate() {
+        if !ctrs.contains(nam) {
+            return Err(ParseError::new(at, "constructor of another type"));
+        }
+        if branches[..i].iter().any(|(other, _, _, _)| other == nam) {
+            return Err(ParseError::new(at, "duplicate branch"));
+        }
+        if ctx.find(nam).and_then(scott_type).map(|(arity, _)| arity) != Some(fields.len()) {
+            return Err(ParseError::new(at, "wrong number of fields for the constructor"));
+        }
+        let mut counts = BTreeMap::new();
+        occurrences(bod, &mut counts);
+        if fields.iter().any(|field| counts.get(*field).is_some_and(|count| *count > 1)) {
+            return Err(ParseError::new(at, "a field is used more than once"));
+        }
+    }
+    if let Some(ctr) = ctrs.iter().find(|ctr| !branches.iter().any(|(nam, _, _, _)| nam == *ctr)) {
+        let message = format!("missing a branch for {}", String::from_utf8_lossy(ctr));
+        return Err(ParseError::new(code, &message));
+    }
+    let mut term = val;
+    for ctr in &ctrs {
+        let (_, fields, bod, _) = branches.iter().find(|(nam, _, _, _)| nam == ctr).unwrap();
+        let mut case = bod.clone();
+        for field in fields.iter().rev() {
+            case = Lam{nam: field.to_vec(), bod: Box::new(case)};
+        }
+        term = App{fun: Box::new(term), arg: Box::new(case)};
+    }
+    Ok((&code[1..], term))
+}
+
+// How many times a recursive definition is unfolded.
+pub const REC_DEPTH : u32 = 32;
+
+// What a recursive definition is unfolded to past `REC_DEPTH`: the constructor `#TooDeep@` and the
+// definition's name. Inert, it's left in the normal form of a recursion that went that deep.
+pub fn too_deep_marker(nam : &[u8]) -> Term {
+    let mut ctr = b"#TooDeep@".to_vec();
+    ctr.extend_from_slice(nam);
+    Ctr{nam: ctr}
+}
+
+// The name of the recursive definition whose unfoldings a term ran out of, if it holds the marker
+// `too_deep_marker` leaves.
+pub fn too_deep(term : &Term) -> Option<Vec<u8>> {
+    match term {
+        Lam{bod, ..} => too_deep(bod),
+        App{fun: a, arg: b} | Par{fst: a, snd: b} | Dup{val: a, nxt: b, ..} | Era{val: a, nxt: b} => too_deep(a).or_else(|| too_deep(b)),
+        Ctr{nam} => nam.strip_prefix(b"#TooDeep@").map(|nam| nam.to_vec()),
+        Var{..} | Set => None
+    }
+}
+
+// Recursive definition: `@rec name = val rest`, where `val` may use `name` once. The usual
+// fixpoint, which copies a self-application, would have the lets of its copies meet and annihilate
+// where the calculus would have them commute (see `fans`), so instead `val` is unfolded
+// `REC_DEPTH` times, with `too_deep_marker` in place of the innermost use. Recursion on inputs
+// that don't go that deep gives the same results as a fixpoint would; deeper, the marker is left
+// in the normal form, and evaluation reports it.
+fn parse_rec<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, depth : u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    fn replace(term : &Term, nam : &[u8], val : &Term) -> Term {
+        match term {
+            Lam{nam: var, bod} => Lam{nam: var.clone(), bod: Box::new(replace(bod, nam, val))},
+            App{fun, arg} => App{fun: Box::new(replace(fun, nam, val)), arg: Box::new(replace(arg, nam, val))},
+            Par{fst, snd} => Par{fst: Box::new(replace(fst, nam, val)), snd: Box::new(replace(snd, nam, val))},
+            Dup{fst, snd, val: dup, nxt} => {
+                let dup = Box::new(replace(dup, nam, val));
+                let nxt = Box::new(replace(nxt, nam, val));
+                Dup{fst: fst.clone(), snd: snd.clone(), val: dup, nxt}
+            },
+            Era{val: era, nxt} => Era{val: Box::new(replace(era, nam, val)), nxt: Box::new(replace(nxt, nam, val))},
+            Var{nam: var} if var == nam => val.clone(),
+            Var{..} | Ctr{..} | Set => term.clone()
+        }
+    }
+    let (code, nam) = parse_name(code)?;
+    let code = skip_blank(code);
+    if code.first() != Some(&b'=') {
+        return Err(ParseError::new(code, "expected `=`"));
+    }
+    let at = skip_blank(&code[1..]);
+    let (code, val) = ctx.with_binding(nam, None, |ctx| parse_bounded(&code[1..], ctx, idx, 0, depth, tally))?;
+    let mut counts = BTreeMap::new();
+    occurrences(&val, &mut counts);
+    if counts.get(nam).is_some_and(|count| *count > 1) {
+        return Err(ParseError::new(at, "a recursive definition can use its name once at most"));
+    }
+    tally.size += size(&val) * (REC_DEPTH as u64 - 1);
+    if tally.too_large() {
+        return Err(ParseError::new(at, "program is too large once definitions are expanded"));
+    }
+    let mut rec = too_deep_marker(nam);
+    for _ in 0..REC_DEPTH {
+        let at = *idx;
+        let unfolded = copy(nam, idx, &val);
+        rec = replace(&unfolded, &copy_name(nam, at, nam), &rec);
+    }
+    ctx.with_binding(nam, Some(rec), |ctx| parse_bounded(code, ctx, idx, 0, depth, tally))
+}
+
+// Variable, or a copy of the definition it names.
+fn parse_var<'a>(code : &'a Str, ctx : &mut Scope<'a>, idx : &mut u32, tally : &mut Tally<'a>) -> Result<(&'a Str, Term), ParseError> {
+    let rest = code;
+    let (code, nam) = parse_name(code)?;
+    let mut val : Option<Term> = None;
+    if let Some(term) = ctx.find(nam) {
+        tally.size += size(term);
+        if tally.too_large() {
+            return Err(ParseError::new(rest, "program is too large once definitions are expanded"));
+        }
+        val = Some(copy(nam, idx, term));
+    }
+    let nam = nam.to_vec();
+    Ok((code, match val { Some(term) => term, None => Var{nam} }))
+}
+
+// Converts a source-code to a λ-term. Never panics: on any input, it either returns a term or an
+// error, since nesting and the size of the expanded program are bounded by `MAX_DEPTH` and
+// `MAX_SIZE`. This is the entry point to use on untrusted input, such as a fuzzer's.
+pub fn parse_checked(code : &Str) -> Result<Term, ParseError> {
+    let mut ctx = Scope::new();
+    let mut idx = 0;
+    let mut tally = Tally { bounded: true, ..Tally::default() };
+    parse_bounded(code, &mut ctx, &mut idx, 0, 0, &mut tally).map(|(_, term)| name_copies(&term)).map_err(|err| err.located(code))
+}
+
+// Parses the term at the start of a source-code, like `from_string`, and returns what follows it,
+// unread. Whatever that is, even another term, isn't an error.
+pub fn parse_one(code : &Str) -> Result<(&Str, Term), ParseError> {
+    let mut ctx = Scope::new();
+    let mut idx = 0;
+    parse_term(code, &mut ctx, &mut idx, 0).map_err(|err| err.located(code))
+}
+
+// Parses a source-code that must be a single term: after it, only whitespace and comments may
+// remain. Anything else is an error at its first byte.
+pub fn parse_all(code : &Str) -> Result<Term, ParseError> {
+    let (rest, term) = parse_one(code)?;
+    let rest = skip_blank(rest);
+    if !rest.is_empty() {
+        return Err(ParseError::new(rest, "unexpected input after the term").located(code));
+    }
+    Ok(term)
+}
+
+// Converts a source-code to a λ-term. Unlike `parse_checked`, it doesn't limit how deeply terms
+// nest or how large they get, so input that nests deep enough can overflow the stack.
+pub fn from_string(code : &Str) -> Result<Term, ParseError> {
+    parse_one(code).map(|(_, term)| term)
+}
+
+// Converts a λ-term back to a source-code.
+pub fn to_string(term : &Term) -> Vec<Chr> {
+    fn stringify_term(code : &mut Vec<u8>, term : &Term) {
+        match term {
+            Lam{nam, bod} => {
+                code.extend_from_slice(b"\\");
+                code.append(&mut nam.clone());
+                code.extend_from_slice(b" ");
+                stringify_term(code, bod);
+            },
+            App{fun, arg} => {
+                code.extend_from_slice(b"/");
+                stringify_term(code, fun);
+                code.extend_from_slice(b" ");
+                stringify_term(code, arg);
+            },
+            Par{fst, snd} => {
+                code.extend_from_slice(b"|");
+                code.extend_from_slice(b" ");
+                stringify_term(code, fst);
+                code.extend_from_slice(b" ");
+                stringify_term(code, snd);
+            },
+            Dup{fst, snd, val, nxt} => {
+                code.extend_from_slice(b"=");
+                code.extend_from_slice(b" ");
+                code.append(&mut fst.clone());
+                code.extend_from_slice(b" ");
+                code.append(&mut snd.clone());
+                code.extend_from_slice(b" ");
+                stringify_term(code, val);
+                code.extend_from_slice(b"\n");
+                stringify_term(code, nxt);
+            },
+            Era{val, nxt} => {
+                code.extend_from_slice(b"&");
+                code.extend_from_slice(b" ");
+                stringify_term(code, val);
+                code.extend_from_slice(b"\n");
+                stringify_term(code, nxt);
+            },
+            Set => {
+                code.extend_from_slice(b"*");
+            },
+            Var{nam} | Ctr{nam} => {
+                code.append(&mut nam.clone());
+            },
+        }
+    }
+    let mut code = Vec::new();
+    stringify_term(&mut code, term);
+    code
+}
+
+// Converts a λ-term to source code laid out for reading. Lambdas and applications stay on one
+// line, while each `=` binding goes on its own line, indented under the lambda that holds it.
+pub fn pretty(term : &Term) -> Vec<Chr> {
+    let mut code = Vec::new();
+    pretty_term(&mut code, term, 0);
+    code
+}
+
+// Written by `pretty_limited` in place of the terms it leaves out, followed by how many nodes
+// they have, like `…12`. It isn't ASCII, and the parser rejects names that start with it, so
+// partial output can't be mistaken for a term.
+pub const ELIDED : &str = "…";
+
+// How much of a term `pretty_limited` prints: the first `depth` levels of nesting (the whole term
+// being the first), and the first `size` nodes. The body of a let is at the let's level, since
+// it's printed on the next line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    pub depth: Option<u32>,
+    pub size: Option<u64>
+}
+
+// Converts a λ-term to source code like `pretty`, leaving out what's beyond the limits. Returns
+// the code and how many nodes were left out.
+pub fn pretty_limited(term : &Term, limits : OutputLimits) -> (Vec<Chr>, u64) {
+    let mut code = Vec::new();
+    let mut room = Room { limits, depth: 0, printed: 0, elided: 0, notes: &[] };
+    pretty_within(&mut code, term, 0, &mut room);
+    (code, room.elided)
+}
+
+// Converts a λ-term to source code like `pretty`, with comments put back: each before the subterm
+// at its position in preorder, as `parse_commented` gives them.
+pub fn pretty_commented(term : &Term, notes : &[(u64, Vec<u8>)]) -> Vec<Chr> {
+    let mut code = Vec::new();
+    pretty_within(&mut code, term, 0, &mut Room { notes, ..Room::unlimited() });
+    code
+}
+
+// Appends a term like `pretty_body`, with comments put back like `pretty_commented`.
+pub fn pretty_body_commented(code : &mut Vec<Chr>, term : &Term, indent : usize, notes : &[(u64, Vec<u8>)]) {
+    body_within(code, term, indent, &mut Room { notes, ..Room::unlimited() });
+}
+
+// The limits of a layout, the depth of the term being laid out, the nodes printed and left out so
+// far, and the comments to put back.
+struct Room<'a> {
+    limits: OutputLimits,
+    depth: u32,
+    printed: u64,
+    elided: u64,
+    notes: &'a [(u64, Vec<u8>)]
+}
+
+impl Room<'_> {
+    fn unlimited() -> Room<'static> {
+        Room { limits: OutputLimits::default(), depth: 0, printed: 0, elided: 0, notes: &[] }
+    }
+}
+
+// Appends the layout of a term to `code`, indenting new lines by `indent` spaces.
+pub fn pretty_term(code : &mut Vec<Chr>, term : &Term, indent : usize) {
+    pretty_within(code, term, indent, &mut Room::unlimited());
+}
+
+// Appends a term that follows a binder (a lambda's body or a definition's value): on the same
+// line, or, if it starts with `=` bindings, on the next line with extra indentation.
+pub fn pretty_body(code : &mut Vec<Chr>, term : &Term, indent : usize) {
+    body_within(code, term, indent, &mut Room::unlimited());
+}
+
+// Appends the layout of a term like `pretty_term`, or `ELIDED` if it's beyond the limits.
+fn pretty_within(code : &mut Vec<Chr>, term : &Term, indent : usize, room : &mut Room) {
+    let deep = room.limits.depth.is_some_and(|depth| room.depth >= depth);
+    let full = room.limits.size.is_some_and(|size| room.printed >= size);
+    if deep || full {
+        let len = size(term);
+        code.extend_from_slice(format!("{}{}", ELIDED, len).as_bytes());
+        room.elided += len;
+        return;
+    }
+    for (_, text) in room.notes.iter().filter(|(at, _)| *at == room.printed) {
+        code.extend_from_slice(text);
+        code.push(b' ');
+    }
+    room.printed += 1;
+    match term {
+        Lam{nam, bod} => {
+            code.extend_from_slice(b"\\");
+            code.extend_from_slice(nam);
+            room.depth += 1;
+            body_within(code, bod, indent, room);
+            room.depth -= 1;
+        },
+        App{fun, arg} => {
+            code.extend_from_slice(b"/");
+            room.depth += 1;
+            pretty_within(code, fun, indent, room);
+            code.extend_from_slice(b" ");
+            pretty_within(code, arg, indent, room);
+            room.depth -= 1;
+        },
+        Par{fst, snd} => {
+            code.extend_from_slice(b"| ");
+            room.depth += 1;
+            pretty_within(code, fst, indent, room);
+            code.extend_from_slice(b" ");
+            pretty_within(code, snd, indent, room);
+            room.depth -= 1;
+        },
+        Dup{fst, snd, val, nxt} => {
+            code.extend_from_slice(b"= ");
+            code.extend_from_slice(fst);
+            code.extend_from_slice(b" ");
+            code.extend_from_slice(snd);
+            code.extend_from_slice(b" ");
+            room.depth += 1;
+            pretty_within(code, val, indent, room);
+            room.depth -= 1;
+            code.push(b'\n');
+            code.append(&mut vec![b' '; indent]);
+            pretty_within(code, nxt, indent, room);
+        },
+        Era{val, nxt} => {
+            code.extend_from_slice(b"& ");
+            room.depth += 1;
+            pretty_within(code, val, indent, room);
+            room.depth -= 1;
+            code.push(b'\n');
+            code.append(&mut vec![b' '; indent]);
+            pretty_within(code, nxt, indent, room);
+        },
+        Set => {
+            code.extend_from_slice(b"*");
+        },
+        Var{nam} | Ctr{nam} => {
+            code.extend_from_slice(nam);
+        }
+    }
+}
+
+// Appends a term that follows a binder like `pretty_body`, within the limits.
+fn body_within(code : &mut Vec<Chr>, term : &Term, indent : usize, room : &mut Room) {
+    match term {
+        Dup{..} | Era{..} => {
+            code.push(b'\n');
+            code.append(&mut vec![b' '; indent + 2]);
+            pretty_within(code, term, indent + 2, room);
+        },
+        _ => {
+            code.push(b' ');
+            pretty_within(code, term, indent, room);
+        }
+    }
+}
+
+// Display macro.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&to_string(self)))
+    }
+}
+
+// Debug macro. Like a derived one, but shows names as text rather than byte vectors, so `{:#?}`
+// gives a readable tree of the term.
+impl fmt::Debug for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |nam : &Vec<u8>| String::from_utf8_lossy(nam).into_owned();
+        match self {
+            Lam{nam, bod} => f.debug_struct("Lam").field("nam", &name(nam)).field("bod", bod).finish(),
+            App{fun, arg} => f.debug_struct("App").field("fun", fun).field("arg", arg).finish(),
+            Par{fst, snd} => f.debug_struct("Par").field("fst", fst).field("snd", snd).finish(),
+            Dup{fst, snd, val, nxt} => f.debug_struct("Dup")
+                .field("fst", &name(fst))
+                .field("snd", &name(snd))
+                .field("val", val)
+                .field("nxt", nxt)
+                .finish(),
+            Var{nam} => f.debug_struct("Var").field("nam", &name(nam)).finish(),
+            Era{val, nxt} => f.debug_struct("Era").field("val", val).field("nxt", nxt).finish(),
+            Ctr{nam} => f.debug_struct("Ctr").field("nam", &name(nam)).finish(),
+            Set => write!(f, "Set")
+        }
+    }
+}
+
+// Collects the names bound anywhere in a term, by lambdas or by lets.
+pub(crate) fn binders(term : &Term, names : &mut BTreeSet<Vec<u8>>) {
+    match term {
+        Lam{nam, bod} => {
+            names.insert(nam.clone());
+            binders(bod, names);
+        },
+        App{fun, arg} => {
+            binders(fun, names);
+            binders(arg, names);
+        },
+        Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+            binders(a, names);
+            binders(b, names);
+        },
+        Dup{fst, snd, val, nxt} => {
+            names.insert(fst.clone());
+            names.insert(snd.clone());
+            binders(val, names);
+            binders(nxt, names);
+        },
+        Var{..} | Ctr{..} | Set => {}
+    }
+}
+
+// Counts how many times each variable occurs in a term.
+pub(crate) fn occurrences(term : &Term, counts : &mut BTreeMap<Vec<u8>, u32>) {
+    match term {
+        Lam{bod, ..} => occurrences(bod, counts),
+        App{fun, arg} => {
+            occurrences(fun, counts);
+            occurrences(arg, counts);
+        },
+        Par{fst, snd} => {
+            occurrences(fst, counts);
+            occurrences(snd, counts);
+        },
+        Dup{val, nxt, ..} | Era{val, nxt} => {
+            occurrences(val, counts);
+            occurrences(nxt, counts);
+        },
+        Var{nam} => *counts.entry(nam.clone()).or_insert(0) += 1,
+        Ctr{..} | Set => {}
+    }
+}
+
+// Renames bound variables to `a`, `b`, `c`... in the order they first appear when printed, so
+// alpha-equivalent terms become identical, and equal as `Term`s, with equal hashes, which makes
+// canonical terms fit keys of memo tables. That holds for terms that bind a name more than once
+// too: each binder gets a name of its own, the variables of a name going to its last binder, as
+// `to_net` binds them, and the others binding nothing. Free variables and unused binders (`_`, `-`)
+// keep their names, and generated names skip over the free ones.
+pub fn canonicalize(term : &Term) -> Term {
+    fn free(term : &Term, bound : &BTreeSet<Vec<u8>>, names : &mut BTreeSet<Vec<u8>>) {
+        match term {
+            Lam{bod, ..} => free(bod, bound, names),
+            App{fun, arg} => {
+                free(fun, bound, names);
+                free(arg, bound, names);
+            },
+            Par{fst, snd} => {
+                free(fst, bound, names);
+                free(snd, bound, names);
+            },
+            Dup{val, nxt, ..} | Era{val, nxt} => {
+                free(val, bound, names);
+                free(nxt, bound, names);
+            },
+            Var{nam} => if !bound.contains(nam) {
+                names.insert(nam.clone());
+            },
+            Ctr{..} | Set => {}
+        }
+    }
+    // Where each name is last bound, counting binders in the order they're printed.
+    fn last_binders(term : &Term, seen : &mut usize, last : &mut BTreeMap<Vec<u8>, usize>) {
+        let mut bind = |nam : &[u8], seen : &mut usize| {
+            last.insert(nam.to_vec(), *seen);
+            *seen += 1;
+        };
+        match term {
+            Lam{nam, bod} => {
+                bind(nam, seen);
+                last_binders(bod, seen, last);
+            },
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                last_binders(a, seen, last);
+                last_binders(b, seen, last);
+            },
+            Dup{fst, snd, val, nxt} => {
+                bind(fst, seen);
+                bind(snd, seen);
+                last_binders(val, seen, last);
+                last_binders(nxt, seen, last);
+            },
+            Var{..} | Ctr{..} | Set => {}
+        }
+    }
+    struct Renamer {
+        bound : BTreeSet<Vec<u8>>,
+        free : BTreeSet<Vec<u8>>,
+        last : BTreeMap<Vec<u8>, usize>,
+        seen : usize,
+        names : BTreeMap<Vec<u8>, Vec<u8>>,
+        count : u32
+    }
+    impl Renamer {
+        fn fresh(&mut self) -> Vec<u8> {
+            let mut new = new_name(self.count + 1);
+            self.count += 1;
+            while self.free.contains(&new) {
+                new = new_name(self.count + 1);
+                self.count += 1;
+            }
+            new
+        }
+        fn rename(&mut self, nam : &[u8]) -> Vec<u8> {
+            if nam == b"_" || nam == b"-" || !self.bound.contains(nam) {
+                return nam.to_vec();
+            }
+            if let Some(new) = self.names.get(nam) {
+                return new.clone();
+            }
+            let new = self.fresh();
+            self.names.insert(nam.to_vec(), new.clone());
+            new
+        }
+        // Renames a binder: the last of its name is renamed as its variables are, and any other
+        // gets a name no variable has.
+        fn bind(&mut self, nam : &[u8]) -> Vec<u8> {
+            let at = self.seen;
+            self.seen += 1;
+            if nam == b"_" || nam == b"-" || self.last.get(nam) == Some(&at) {
+                self.rename(nam)
+            } else {
+                self.fresh()
+            }
+        }
+        fn term(&mut self, term : &Term) -> Term {
+            match term {
+                Lam{nam, bod} => {
+                    let nam = self.bind(nam);
+                    let bod = Box::new(self.term(bod));
+                    Lam{nam, bod}
+                },
+                App{fun, arg} => {
+                    let fun = Box::new(self.term(fun));
+                    let arg = Box::new(self.term(arg));
+                    App{fun, arg}
+                },
+                Par{fst, snd} => {
+                    let fst = Box::new(self.term(fst));
+                    let snd = Box::new(self.term(snd));
+                    Par{fst, snd}
+                },
+                Dup{fst, snd, val, nxt} => {
+                    let fst = self.bind(fst);
+                    let snd = self.bind(snd);
+                    let val = Box::new(self.term(val));
+                    let nxt = Box::new(self.term(nxt));
+                    Dup{fst, snd, val, nxt}
+                },
+                Era{val, nxt} => {
+                    let val = Box::new(self.term(val));
+                    let nxt = Box::new(self.term(nxt));
+                    Era{val, nxt}
+                },
+                Var{nam} => Var{nam: self.rename(nam)},
+                Ctr{..} | Set => term.clone()
+            }
+        }
+    }
+    let mut bound = BTreeSet::new();
+    binders(term, &mut bound);
+    let mut names = BTreeSet::new();
+    free(term, &bound, &mut names);
+    let mut last = BTreeMap::new();
+    last_binders(term, &mut 0, &mut last);
+    Renamer{bound, free: names, last, seen: 0, names: BTreeMap::new(), count: 0}.term(term)
+}
+
+// Version of the canonical form. Bump it whenever `canonicalize` or `to_string` change the bytes
+// they produce, so hashes computed by older versions can't be mistaken for current ones.
+pub const CANON_VERSION : u8 = 2;
+
+// Hashes the canonical form of a term: 64-bit FNV-1a (offset basis 0xcbf29ce484222325, prime
+// 0x100000001b3) over the byte `CANON_VERSION` followed by `to_string(&canonicalize(term))`.
+pub fn canonical_hash(term : &Term) -> u64 {
+    let mut hash : u64 = 0xcbf29ce484222325;
+    let mut bytes = vec![CANON_VERSION];
+    bytes.append(&mut to_string(&canonicalize(term)));
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Checks whether two terms are equal up to the names of their bound variables.
+pub fn alpha_eq(a : &Term, b : &Term) -> bool {
+    to_string(&canonicalize(a)) == to_string(&canonicalize(b))
+}
+
+// Tidies a read-back term, for reading. Repeats, until none applies:
+// - `/\x x t` becomes `t`;
+// - `/* t` becomes `*`, and `= - - t n` and `& t n` become `n`, when erasing `t` leaves no
+//   variable dangling;
+// - `= a b * n` becomes `n`, with `*` for `a` and `b`;
+// - `= a b |c d |a b` becomes `|c d`.
+// All but the last are rewrites reduction would do. The last is what a let does to a pair; any
+// other value, as a lambda, would be copied instead, so it's left.
+pub fn simplify(term : &Term) -> Term {
+    // Whether a term binds all the variables it uses, and uses all those it binds.
+    fn closed(term : &Term, counts : &BTreeMap<Vec<u8>, u32>) -> bool {
+        let mut inner = BTreeMap::new();
+        occurrences(term, &mut inner);
+        let mut bound = BTreeSet::new();
+        binders(term, &mut bound);
+        inner.keys().all(|nam| bound.contains(nam)) && bound.iter().all(|nam| inner.get(nam) == counts.get(nam))
+    }
+    fn pass(term : &Term, counts : &BTreeMap<Vec<u8>, u32>, erased : &mut BTreeSet<Vec<u8>>) -> Term {
+        match term {
+            Lam{nam, bod} => Lam{nam: nam.clone(), bod: Box::new(pass(bod, counts, erased))},
+            App{fun, arg} => match (pass(fun, counts, erased), pass(arg, counts, erased)) {
+                (Lam{nam, bod}, arg) if *bod == Var{nam: nam.clone()} => arg,
+                (Set, ref arg) if closed(arg, counts) => Set,
+                (fun, arg) => App{fun: Box::new(fun), arg: Box::new(arg)}
+            },
+            Par{fst, snd} => Par{fst: Box::new(pass(fst, counts, erased)), snd: Box::new(pass(snd, counts, erased))},
+            Dup{fst, snd, val, nxt} => match (pass(val, counts, erased), pass(nxt, counts, erased)) {
+                (Set, nxt) => {
+                    erased.insert(fst.clone());
+                    erased.insert(snd.clone());
+                    nxt
+                },
+                (ref val, nxt) if !counts.contains_key(fst) && !counts.contains_key(snd) && closed(val, counts) => nxt,
+                (val @ Par{..}, Par{fst: ref a, snd: ref b}) if **a == Var{nam: fst.clone()} && **b == Var{nam: snd.clone()} => val,
+                (val, nxt) => Dup{fst: fst.clone(), snd: snd.clone(), val: Box::new(val), nxt: Box::new(nxt)}
+            },
+            Era{val, nxt} => match (pass(val, counts, erased), pass(nxt, counts, erased)) {
+                (ref val, nxt) if closed(val, counts) => nxt,
+                (val, nxt) => Era{val: Box::new(val), nxt: Box::new(nxt)}
+            },
+            Var{nam} if erased.contains(nam) => Set,
+            Var{..} | Ctr{..} | Set => term.clone()
+        }
+    }
+    let mut term = term.clone();
+    loop {
+        let mut counts = BTreeMap::new();
+        occurrences(&term, &mut counts);
+        // Copies of `*` may be used before their let is reached, so they're replaced by a second pass.
+        let mut erased = BTreeSet::new();
+        let next = pass(&term, &counts, &mut erased);
+        let next = pass(&next, &counts, &mut erased);
+        if next == term {
+            return term;
+        }
+        term = next;
+    }
+}
+
+// Expands the lets of a term, for reading or for tools without them: each copy a let makes is
+// replaced by its value, written out again, with its binders renamed apart. Variables bound
+// outside of the value are then used once for each copy, so the result isn't affine. Expansion can
+// double the term once for each let, so it stops with an error once the term is larger than `max`
+// nodes.
+pub fn expand_dups(term : &Term, max : u64) -> Result<Term, LimitError> {
+    // Takes out the first let found, leaving its body in its place.
+    fn take(term : &mut Term) -> Option<(Vec<u8>, Vec<u8>, Term)> {
+        match term {
+            Dup{..} => match core::mem::replace(term, Set) {
+                Dup{fst, snd, val, nxt} => {
+                    *term = *nxt;
+                    Some((fst, snd, *val))
+                },
+                _ => unreachable!()
+            },
+            Lam{bod, ..} => take(bod),
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Era{val: a, nxt: b} => take(a).or_else(|| take(b)),
+            Var{..} | Ctr{..} | Set => None
+        }
+    }
+    // Names not taken yet, in the order of `new_name`.
+    fn fresh(taken : &mut BTreeSet<Vec<u8>>, next : &mut u32) -> Vec<u8> {
+        loop {
+            let nam = new_name(*next);
+            *next += 1;
+            if taken.insert(nam.clone()) {
+                return nam;
+            }
+        }
+    }
+    // Copies a value, renaming the binders in it.
+    fn rename(term : &Term, names : &mut BTreeMap<Vec<u8>, Vec<u8>>, taken : &mut BTreeSet<Vec<u8>>, next : &mut u32) -> Term {
+        fn bind(nam : &[u8], names : &mut BTreeMap<Vec<u8>, Vec<u8>>, taken : &mut BTreeSet<Vec<u8>>, next : &mut u32) -> Vec<u8> {
+            if nam == b"-" {
+                return nam.to_vec();
+            }
+            let new = fresh(taken, next);
+            names.insert(nam.to_vec(), new.clone());
+            new
+        }
+        match term {
+            Lam{nam, bod} => {
+                let nam = bind(nam, names, taken, next);
+                Lam{nam, bod: Box::new(rename(bod, names, taken, next))}
+            },
+            Dup{fst, snd, val, nxt} => {
+                let fst = bind(fst, names, taken, next);
+                let snd = bind(snd, names, taken, next);
+                let val = Box::new(rename(val, names, taken, next));
+                Dup{fst, snd, val, nxt: Box::new(rename(nxt, names, taken, next))}
+            },
+            App{fun, arg} => App{fun: Box::new(rename(fun, names, taken, next)), arg: Box::new(rename(arg, names, taken, next))},
+            Par{fst, snd} => Par{fst: Box::new(rename(fst, names, taken, next)), snd: Box::new(rename(snd, names, taken, next))},
+            Era{val, nxt} => Era{val: Box::new(rename(val, names, taken, next)), nxt: Box::new(rename(nxt, names, taken, next))},
+            Var{nam} => Var{nam: names.get(nam).unwrap_or(nam).clone()},
+            Ctr{..} | Set => term.clone()
+        }
+    }
+    // How many variables are named `fst` or `snd`.
+    fn uses(term : &Term, fst : &[u8], snd : &[u8]) -> u64 {
+        match term {
+            Var{nam} => (nam == fst || nam == snd) as u64,
+            Lam{bod, ..} => uses(bod, fst, snd),
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Dup{val: a, nxt: b, ..} | Era{val: a, nxt: b} => uses(a, fst, snd) + uses(b, fst, snd),
+            Ctr{..} | Set => 0
+        }
+    }
+    // Replaces the variables named `fst` or `snd` by copies of `val`.
+    fn substitute(term : &mut Term, fst : &[u8], snd : &[u8], val : &Term, taken : &mut BTreeSet<Vec<u8>>, next : &mut u32) {
+        match term {
+            Var{nam} if nam == fst || nam == snd => *term = rename(val, &mut BTreeMap::new(), taken, next),
+            Lam{bod, ..} => substitute(bod, fst, snd, val, taken, next),
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Dup{val: a, nxt: b, ..} | Era{val: a, nxt: b} => {
+                substitute(a, fst, snd, val, taken, next);
+                substitute(b, fst, snd, val, taken, next);
+            },
+            Var{..} | Ctr{..} | Set => {}
+        }
+    }
+    let mut term = term.clone();
+    let mut taken = BTreeSet::new();
+    binders(&term, &mut taken);
+    let mut counts = BTreeMap::new();
+    occurrences(&term, &mut counts);
+    taken.extend(counts.into_keys());
+    let mut next = 1;
+    let mut len = size(&term);
+    while let Some((fst, snd, val)) = take(&mut term) {
+        // The let and its value go, and each copy takes the place of a variable.
+        let copies = uses(&term, &fst, &snd);
+        len = (len + copies * size(&val)).saturating_sub(1 + size(&val) + copies);
+        if len > max {
+            return Err(LimitError::Expansion(max));
+        }
+        substitute(&mut term, &fst, &snd, &val, &mut taken, &mut next);
+    }
+    Ok(term)
+}
+
+// Lambda-lifts a term: every maximal chain of lambdas that uses variables bound outside of it
+// becomes a definition `:gN` taking those variables as extra leading parameters, and is replaced by
+// `gN` applied to them. Since each captured variable occurs once in the chain, it now occurs once
+// at the call site instead, so affinity is preserved. Chains whose own binders are used outside of
+// them (which read-back can produce) stay in place. Binder names are assumed to be distinct, as
+// they are in read-back terms and in expanded programs.
+pub fn lambda_lift(term : &Term) -> Module {
+    fn lift(term : &Term, counts : &BTreeMap<Vec<u8>, u32>, defs : &mut Vec<(Vec<u8>, Term)>) -> Term {
+        match term {
+            Lam{..} => {
+                let mut nams = Vec::new();
+                let mut bod = term;
+                while let Lam{nam, bod: next} = bod {
+                    nams.push(nam.clone());
+                    bod = next;
+                }
+                let mut lam = lift(bod, counts, defs);
+                for nam in nams.into_iter().rev() {
+                    lam = Lam{nam, bod: Box::new(lam)};
+                }
+
+                let mut bound = BTreeSet::new();
+                binders(&lam, &mut bound);
+                let mut inner = BTreeMap::new();
+                occurrences(&lam, &mut inner);
+                let escapes = bound.iter().any(|nam| inner.get(nam) != counts.get(nam));
+                let mut free : Vec<Vec<u8>> = Vec::new();
+                for nam in variables(&lam) {
+                    let is_def = defs.iter().any(|(def, _)| *def == nam);
+                    if !bound.contains(&nam) && !is_def && !free.contains(&nam) {
+                        free.push(nam);
+                    }
+                }
+                if free.is_empty() || escapes {
+                    return lam;
+                }
+
+                let mut def = lam;
+                for nam in free.iter().rev() {
+                    def = Lam{nam: nam.clone(), bod: Box::new(def)};
+                }
+                let mut name = Vec::new();
+                for idx in defs.len().. {
+                    name = format!("g{}", idx).into_bytes();
+                    if !counts.contains_key(&name) && !defs.iter().any(|(def, _)| *def == name) {
+                        break;
+                    }
+                }
+                defs.push((name.clone(), def));
+                let mut site = Var{nam: name};
+                for nam in free {
+                    site = App{fun: Box::new(site), arg: Box::new(Var{nam})};
+                }
+                site
+            },
+            App{fun, arg} => {
+                let fun = Box::new(lift(fun, counts, defs));
+                let arg = Box::new(lift(arg, counts, defs));
+                App{fun, arg}
+            },
+            Par{fst, snd} => {
+                let fst = Box::new(lift(fst, counts, defs));
+                let snd = Box::new(lift(snd, counts, defs));
+                Par{fst, snd}
+            },
+            Dup{fst, snd, val, nxt} => {
+                let val = Box::new(lift(val, counts, defs));
+                let nxt = Box::new(lift(nxt, counts, defs));
+                Dup{fst: fst.clone(), snd: snd.clone(), val, nxt}
+            },
+            Era{val, nxt} => {
+                let val = Box::new(lift(val, counts, defs));
+                let nxt = Box::new(lift(nxt, counts, defs));
+                Era{val, nxt}
+            },
+            Var{..} | Ctr{..} | Set => term.clone()
+        }
+    }
+    // Lists variable occurrences in printing order.
+    fn variables(term : &Term) -> Vec<Vec<u8>> {
+        fn go(term : &Term, vars : &mut Vec<Vec<u8>>) {
+            match term {
+                Lam{bod, ..} => go(bod, vars),
+                App{fun, arg} => {
+                    go(fun, vars);
+                    go(arg, vars);
+                },
+                Par{fst, snd} => {
+                    go(fst, vars);
+                    go(snd, vars);
+                },
+                Dup{val, nxt, ..} | Era{val, nxt} => {
+                    go(val, vars);
+                    go(nxt, vars);
+                },
+                Var{nam} => vars.push(nam.clone()),
+                Ctr{..} | Set => {}
+            }
+        }
+        let mut vars = Vec::new();
+        go(term, &mut vars);
+        vars
+    }
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    let mut defs = Vec::new();
+    let main = lift(term, &counts, &mut defs);
+    Module{defs, main: Some(main), comments: Vec::new(), notes: Vec::new(), fuel: Vec::new(), params: Vec::new()}
+}
+
+// Translates a term to continuation-passing style, call-by-value, after Plotkin: the translation
+// `[t]` of a term is a function of a continuation `k`, which it calls with the value of `t`.
+// - `[x]` is `\k /k x`, and `[\x t]` is `\k /k \x [t]`: a function takes its argument and then a
+//   continuation. `*` and constructors are values too, like variables.
+// - `[/f a]` is `\k /[f] \g /[a] \b //g b k`: the function, then the argument, are evaluated first.
+//   A constructor applied to all its fields is a value once they are: `[//#C a b]` is
+//   `\k /[a] \x /[b] \y /k //#C x y`.
+// - `[| a b]` evaluates both sides, then passes the pair: `\k /[a] \x /[b] \y /k | x y`.
+// - `[= a b v t]` evaluates `v` first and copies its value: `\k /[v] \x = a b x /[t] k`. Copying
+//   a value only ever copies a function, a pair or a constructor, never a pending computation.
+// - `[& v t]` discards `v` without evaluating it: `\k & [v] /[t] k`.
+// Each continuation, and each binder it introduces, is used exactly once, so affine terms stay
+// affine. The binders it introduces are named `k0`, `g1`, `x2`... skipping the names the term
+// already uses, so they never capture the user's variables.
+pub fn cps(term : &Term) -> Term {
+    cps_with(term, &BTreeSet::new())
+}
+
+// Translates each definition and the main term of a module to continuation-passing style, like
+// `cps`. A reference to a definition stands for its translation, a computation, so `[d]` is `d`
+// rather than `\k /k d`. Parameters and comments within terms are dropped, as the terms change
+// shape.
+pub fn cps_module(module : &Module) -> Module {
+    let defs : BTreeSet<Vec<u8>> = module.defs.iter().map(|(nam, _)| nam.clone()).collect();
+    Module {
+        defs: module.defs.iter().map(|(nam, val)| (nam.clone(), cps_with(val, &defs))).collect(),
+        main: module.main.as_ref().map(|main| cps_with(main, &defs)),
+        comments: module.comments.clone(),
+        notes: Vec::new(),
+        fuel: module.fuel.clone(),
+        params: Vec::new()
+    }
+}
+
+// Translates a term like `cps`, with the variables named in `defs` and bound nowhere in it being
+// references to translated definitions.
+fn cps_with(term : &Term, defs : &BTreeSet<Vec<u8>>) -> Term {
+    struct Names<'a> {
+        bound: BTreeSet<Vec<u8>>,
+        taken: BTreeSet<Vec<u8>>,
+        defs: &'a BTreeSet<Vec<u8>>,
+        next: u32
+    }
+    impl<'a> Names<'a> {
+        fn fresh(&mut self, prefix : &str) -> Vec<u8> {
+            loop {
+                let nam = format!("{}{}", prefix, self.next).into_bytes();
+                self.next += 1;
+                if !self.taken.contains(&nam) && !self.defs.contains(&nam) {
+                    return nam;
+                }
+            }
+        }
+    }
+    fn lam(nam : &[u8], bod : Term) -> Term {
+        Lam{nam: nam.to_vec(), bod: Box::new(bod)}
+    }
+    fn app(fun : Term, arg : Term) -> Term {
+        App{fun: Box::new(fun), arg: Box::new(arg)}
+    }
+    fn var(nam : &[u8]) -> Term {
+        Var{nam: nam.to_vec()}
+    }
+    // `\k /[a] \x /[b] \y ... /k (make x y ...)`.
+    fn values(terms : &[&Term], make : &dyn Fn(Vec<Term>) -> Term, names : &mut Names) -> Term {
+        let k = names.fresh("k");
+        let xs : Vec<Vec<u8>> = terms.iter().map(|_| names.fresh("x")).collect();
+        let mut bod = app(var(&k), make(xs.iter().map(|x| var(x)).collect()));
+        for (term, x) in terms.iter().zip(&xs).rev() {
+            bod = app(go(term, names), lam(x, bod));
+        }
+        lam(&k, bod)
+    }
+    fn go(term : &Term, names : &mut Names) -> Term {
+        match term {
+            Var{nam} if names.defs.contains(nam) && !names.bound.contains(nam) => term.clone(),
+            Var{..} | Ctr{..} | Set => {
+                let k = names.fresh("k");
+                lam(&k, app(var(&k), term.clone()))
+            },
+            Lam{nam, bod} => {
+                let k = names.fresh("k");
+                lam(&k, app(var(&k), lam(nam, go(bod, names))))
+            },
+            App{fun, arg} => match ctr_spine(term) {
+                Some((nam, args)) => values(&args, &|xs| xs.into_iter().fold(Ctr{nam: nam.to_vec()}, app), names),
+                None => {
+                    let (k, g, b) = (names.fresh("k"), names.fresh("g"), names.fresh("x"));
+                    let fun = go(fun, names);
+                    let call = app(app(var(&g), var(&b)), var(&k));
+                    lam(&k, app(fun, lam(&g, app(go(arg, names), lam(&b, call)))))
+                }
+            },
+            Par{fst, snd} => values(&[fst, snd], &|mut xs| {
+                let snd = xs.pop().unwrap();
+                Par{fst: Box::new(xs.pop().unwrap()), snd: Box::new(snd)}
+            }, names),
+            Dup{fst, snd, val, nxt} => {
+                let (k, x) = (names.fresh("k"), names.fresh("x"));
+                let val = go(val, names);
+                let nxt = app(go(nxt, names), var(&k));
+                let dup = Dup{fst: fst.clone(), snd: snd.clone(), val: Box::new(var(&x)), nxt: Box::new(nxt)};
+                lam(&k, app(val, lam(&x, dup)))
+            },
+            Era{val, nxt} => {
+                let k = names.fresh("k");
+                let val = go(val, names);
+                lam(&k, Era{val: Box::new(val), nxt: Box::new(app(go(nxt, names), var(&k)))})
+            }
+        }
+    }
+    let mut bound = BTreeSet::new();
+    binders(term, &mut bound);
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    let taken = bound.iter().cloned().chain(counts.into_keys()).collect();
+    go(term, &mut Names{bound, taken, defs, next: 0})
+}
+
+// Translates a module to HVM's syntax. Each definition `:name val` becomes `@name = val`, and the
+// main term becomes `@main`. Within terms:
+// - `\x bod` becomes `λx bod`, and `/f a` becomes `(f a)`.
+// - `| a b` becomes the superposition `&0{a b}`, and `= a b val nxt` the duplication
+//   `! &0{a b} = val; nxt`. SIC has a single kind of fan, so every label is 0.
+// - Erased binders (`_`, `-`) become `_`, and references to definitions become `@name`.
+// Sets, constructors, names HVM can't spell, variables bound nowhere around them, and repeated definitions
+// (including one named `main` next to a main term) are reported as errors.
+pub fn to_hvm(module : &Module) -> Result<String, ExportError> {
+    fn name(nam : &[u8]) -> Result<String, ExportError> {
+        if nam == b"_" || nam == b"-" {
+            return Ok("_".to_string());
+        }
+        if nam.iter().all(|chr| chr.is_ascii_alphanumeric() || *chr == b'_' || *chr == b'.') {
+            Ok(String::from_utf8_lossy(nam).into_owned())
+        } else {
+            Err(ExportError::Name(nam.to_vec()))
+        }
+    }
+    fn go<'a>(term : &'a Term, module : &Module, bound : &mut Vec<&'a [u8]>, out : &mut String) -> Result<(), ExportError> {
+        match term {
+            Lam{nam, bod} => {
+                out.push_str(&format!("λ{} ", name(nam)?));
+                bound.push(nam);
+                go(bod, module, bound, out)?;
+                bound.pop();
+            },
+            App{fun, arg} => {
+                out.push('(');
+                go(fun, module, bound, out)?;
+                out.push(' ');
+                go(arg, module, bound, out)?;
+                out.push(')');
+            },
+            Par{fst, snd} => {
+                out.push_str("&0{");
+                go(fst, module, bound, out)?;
+                out.push(' ');
+                go(snd, module, bound, out)?;
+                out.push('}');
+            },
+            Dup{fst, snd, val, nxt} => {
+                out.push_str(&format!("! &0{{{} {}}} = ", name(fst)?, name(snd)?));
+                bound.push(fst);
+                bound.push(snd);
+                go(val, module, bound, out)?;
+                out.push_str("; ");
+                go(nxt, module, bound, out)?;
+                bound.pop();
+                bound.pop();
+            },
+            // Erasing a term is copying it to nowhere.
+            Era{val, nxt} => {
+                out.push_str("! &0{_ _} = ");
+                go(val, module, bound, out)?;
+                out.push_str("; ");
+                go(nxt, module, bound, out)?;
+            },
+            Set => return Err(ExportError::Set),
+            Ctr{nam} => return Err(ExportError::Ctr(nam.clone())),
+            Var{nam} => {
+                if bound.contains(&&nam[..]) {
+                    out.push_str(&name(nam)?);
+                } else if module.find(nam).is_some() {
+                    out.push_str(&format!("@{}", name(nam)?));
+                } else {
+                    return Err(ExportError::Unbound(nam.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+    let mut out = String::new();
+    let mut seen : Vec<&[u8]> = Vec::new();
+    for (nam, val) in &module.defs {
+        if seen.contains(&&nam[..]) || (nam == b"main" && module.main.is_some()) {
+            return Err(ExportError::Redefined(nam.clone()));
+        }
+        seen.push(nam);
+        out.push_str(&format!("@{} = ", name(nam)?));
+        go(val, module, &mut Vec::new(), &mut out)?;
+        out.push('\n');
+    }
+    if let Some(ref main) = module.main {
+        out.push_str("@main = ");
+        go(main, module, &mut Vec::new(), &mut out)?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// How `to_js` and `to_rust` spell the parts of a term. Binders that are never used are given as
+// `None`, and code on new lines is indented by `indent` spaces.
+trait Target {
+    // Spaces to indent a block by.
+    fn step(&self) -> usize;
+    fn name(&self, nam : &[u8]) -> Result<String, ExportError>;
+    // A function, with the variables it uses from outside, the lets declared in it and its body.
+    fn lam(&self, param : Option<&str>, captures : &[String], lets : &str, body : &str, indent : usize) -> String;
+    fn app(&self, fun : &str, fun_lam : bool, arg : &str) -> String;
+    fn par(&self, fst : &str, snd : &str) -> String;
+    fn var(&self, nam : &str) -> String;
+    fn set(&self) -> String;
+    // A let and an erasure, as statements.
+    fn dup(&self, fst : Option<&str>, snd : Option<&str>, val : &str) -> String;
+    fn era(&self, val : &str, val_lam : bool) -> String;
+}
+
+// A term laid out as code: its free variables, which the caller binds, then the lets declared
+// before its body, and its body.
+struct Program {
+    params: Vec<String>,
+    lets: String,
+    body: String
+}
+
+// Lays a term out as code for a target whose functions have lexical scope. Each let is declared
+// in the outermost block where the variables of its value are in scope, which undoes read-back's
+// floating lets to the top. Constructors, and variables used outside the scope of their binders,
+// are reported as errors.
+fn lay_out(term : &Term, target : &dyn Target, indent : usize) -> Result<Program, ExportError> {
+    // A let, or an erasure, which has no binders. Each is declared once.
+    struct Let<'a> {
+        binders: Option<(&'a [u8], &'a [u8])>,
+        val: &'a Term,
+        done: bool
+    }
+    struct State<'a, 'b> {
+        target: &'b dyn Target,
+        counts: BTreeMap<Vec<u8>, u32>,
+        lets: Vec<Let<'a>>,
+        // Variables in scope: free ones, then binders of the enclosing functions and lets.
+        scope: Vec<&'a [u8]>,
+        // For each enclosing function, how much of the scope is outside it, and the variables from
+        // there it uses.
+        frames: Vec<(usize, Vec<&'a [u8]>)>
+    }
+
+    fn collect<'a>(term : &'a Term, lets : &mut Vec<Let<'a>>) {
+        match term {
+            Lam{bod, ..} => collect(bod, lets),
+            App{fun: a, arg: b} | Par{fst: a, snd: b} => {
+                collect(a, lets);
+                collect(b, lets);
+            },
+            Dup{fst, snd, val, nxt} => {
+                lets.push(Let { binders: Some((fst, snd)), val, done: false });
+                collect(val, lets);
+                collect(nxt, lets);
+            },
+            Era{val, nxt} => {
+                lets.push(Let { binders: None, val, done: false });
+                collect(val, lets);
+                collect(nxt, lets);
+            },
+            Var{..} | Ctr{..} | Set => {}
+        }
+    }
+    // Lists the variables a term needs in scope, leaving out its lets, which are declared apart.
+    fn needs<'a>(term : &'a Term, bound : &mut Vec<&'a [u8]>, vars : &mut Vec<&'a [u8]>) {
+        match term {
+            Lam{nam, bod} => {
+                bound.push(nam);
+                needs(bod, bound, vars);
+                bound.pop();
+            },
+            App{fun: a, arg: b} | Par{fst: a, snd: b} => {
+                needs(a, bound, vars);
+                needs(b, bound, vars);
+            },
+            Dup{nxt, ..} | Era{nxt, ..} => needs(nxt, bound, vars),
+            Var{nam} => if !bound.contains(&&nam[..]) {
+                vars.push(nam);
+            },
+            Ctr{..} | Set => {}
+        }
+    }
+    // Whether a term, without its lets, is a function.
+    fn is_lam(term : &Term) -> bool {
+        match term {
+            Dup{nxt, ..} | Era{nxt, ..} => is_lam(nxt),
+            Lam{..} => true,
+            _ => false
+        }
+    }
+    fn binder(st : &State, nam : &[u8]) -> Result<Option<String>, ExportError> {
+        if nam == b"_" || nam == b"-" || !st.counts.contains_key(nam) {
+            Ok(None)
+        } else {
+            st.target.name(nam).map(Some)
+        }
+    }
+    // Declares the lets that can be, adding their binders to the scope.
+    fn declare<'a>(st : &mut State<'a, '_>, indent : usize) -> Result<String, ExportError> {
+        let mut code = String::new();
+        loop {
+            let scope = &st.scope;
+            let ready = st.lets.iter().position(|lt| !lt.done && {
+                let mut vars = Vec::new();
+                needs(lt.val, &mut Vec::new(), &mut vars);
+                vars.iter().all(|var| scope.contains(var))
+            });
+            let idx = match ready {
+                Some(idx) => idx,
+                None => return Ok(code)
+            };
+            st.lets[idx].done = true;
+            let (binders, val) = (st.lets[idx].binders, st.lets[idx].val);
+            code.push_str(&" ".repeat(indent));
+            let stmt = match binders {
+                Some((fst, snd)) => {
+                    let (fst_name, snd_name) = (binder(st, fst)?, binder(st, snd)?);
+                    let val = expr(val, st, indent)?;
+                    st.scope.push(fst);
+                    st.scope.push(snd);
+                    st.target.dup(fst_name.as_deref(), snd_name.as_deref(), &val)
+                },
+                None => st.target.era(&expr(val, st, indent)?, is_lam(val))
+            };
+            code.push_str(&stmt);
+            code.push('\n');
+        }
+    }
+    fn expr<'a>(term : &'a Term, st : &mut State<'a, '_>, indent : usize) -> Result<String, ExportError> {
+        Ok(match term {
+            Lam{nam, bod} => {
+                let len = st.scope.len();
+                st.scope.push(nam);
+                st.frames.push((len, Vec::new()));
+                let step = st.target.step();
+                let lets = declare(st, indent + step)?;
+                let body = expr(bod, st, indent + step)?;
+                let (_, captured) = st.frames.pop().unwrap();
+                st.scope.truncate(len);
+                let captures = captured.iter().map(|nam| st.target.name(nam)).collect::<Result<Vec<_>, _>>()?;
+                st.target.lam(binder(st, nam)?.as_deref(), &captures, &lets, &body, indent)
+            },
+            App{fun, arg} => {
+                let (fun_code, arg_code) = (expr(fun, st, indent)?, expr(arg, st, indent)?);
+                st.target.app(&fun_code, is_lam(fun), &arg_code)
+            },
+            Par{fst, snd} => {
+                let (fst, snd) = (expr(fst, st, indent)?, expr(snd, st, indent)?);
+                st.target.par(&fst, &snd)
+            },
+            Dup{nxt, ..} | Era{nxt, ..} => expr(nxt, st, indent)?,
+            Set => st.target.set(),
+            Ctr{nam} => return Err(ExportError::Ctr(nam.clone())),
+            Var{nam} => match st.scope.iter().rposition(|var| *var == &nam[..]) {
+                Some(at) => {
+                    for (len, captured) in st.frames.iter_mut() {
+                        if at < *len && !captured.contains(&&nam[..]) {
+                            captured.push(nam);
+                        }
+                    }
+                    st.target.var(&st.target.name(nam)?)
+                },
+                None => return Err(ExportError::Unbound(nam.clone()))
+            }
+        })
+    }
+
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    let mut bound = BTreeSet::new();
+    binders(term, &mut bound);
+    let free : Vec<&[u8]> = counts.keys().filter(|nam| !bound.contains(*nam)).map(|nam| &nam[..]).collect();
+    let mut st = State { target, counts: counts.clone(), lets: Vec::new(), scope: free.clone(), frames: Vec::new() };
+    collect(term, &mut st.lets);
+    let lets = declare(&mut st, indent)?;
+    let body = expr(term, &mut st, indent)?;
+    if let Some(lt) = st.lets.iter().find(|lt| !lt.done) {
+        let mut vars = Vec::new();
+        needs(lt.val, &mut Vec::new(), &mut vars);
+        let var = vars.into_iter().find(|var| !free.contains(var)).unwrap();
+        return Err(ExportError::Unbound(var.to_vec()));
+    }
+    let params = free.iter().map(|nam| target.name(nam)).collect::<Result<Vec<_>, _>>()?;
+    Ok(Program { params, lets, body })
+}
 
-    // Set.
-    Set
+// Checks that a name is an identifier, and not one of `reserved`.
+fn identifier(nam : &[u8], reserved : &[&str]) -> Result<String, ExportError> {
+    let spelled = nam.first().is_some_and(|chr| !chr.is_ascii_digit())
+        && nam.iter().all(|chr| chr.is_ascii_alphanumeric() || *chr == b'_');
+    match core::str::from_utf8(nam) {
+        Ok(text) if spelled && !reserved.contains(&text) => Ok(text.to_string()),
+        _ => Err(ExportError::Name(nam.to_vec()))
+    }
 }
-use self::Term::{*};
 
-// Source code is Ascii-encoded.
-pub type Str = [u8];
-pub type Chr = u8;
+struct Js;
 
-// Builds a var name from an index (0="a", 1="b", 26="aa"...).
-pub fn new_name(idx : u32) -> Vec<Chr> {
-    let mut name = Vec::new();
-    let mut idx = idx;
-    while idx > 0 {
-        idx = idx - 1;
-        name.push((97 + idx % 26) as u8);
-        idx = idx / 26;
+impl Target for Js {
+    fn step(&self) -> usize {
+        2
+    }
+    fn name(&self, nam : &[u8]) -> Result<String, ExportError> {
+        identifier(nam, &[
+            "SET", "dup", "main", "arguments", "await", "break", "case", "catch", "class", "const",
+            "continue", "debugger", "default", "delete", "do", "else", "enum", "eval", "export",
+            "extends", "false", "finally", "for", "function", "if", "implements", "import", "in",
+            "instanceof", "interface", "let", "new", "null", "package", "private", "protected",
+            "public", "return", "static", "super", "switch", "this", "throw", "true", "try",
+            "typeof", "undefined", "var", "void", "while", "with", "yield"
+        ])
+    }
+    fn lam(&self, param : Option<&str>, _ : &[String], lets : &str, body : &str, indent : usize) -> String {
+        let param = param.unwrap_or("_");
+        if lets.is_empty() {
+            format!("{} => {}", param, body)
+        } else {
+            format!("{} => {{\n{}{}return {};\n{}}}", param, lets, " ".repeat(indent + 2), body, " ".repeat(indent))
+        }
+    }
+    fn app(&self, fun : &str, fun_lam : bool, arg : &str) -> String {
+        if fun_lam { format!("({})({})", fun, arg) } else { format!("{}({})", fun, arg) }
+    }
+    fn par(&self, fst : &str, snd : &str) -> String {
+        format!("[{}, {}]", fst, snd)
+    }
+    fn var(&self, nam : &str) -> String {
+        nam.to_string()
+    }
+    fn set(&self) -> String {
+        "SET".to_string()
+    }
+    fn dup(&self, fst : Option<&str>, snd : Option<&str>, val : &str) -> String {
+        format!("const [{}, {}] = dup({});", fst.unwrap_or(""), snd.unwrap_or(""), val)
+    }
+    fn era(&self, val : &str, val_lam : bool) -> String {
+        if val_lam { format!("void ({});", val) } else { format!("void {};", val) }
     }
-    return name;
 }
 
-pub fn name_idx(name : &Vec<Chr>) -> u32 {
-    let mut idx : u32 = 0;
-    for byte in name.iter().rev() {
-        idx = (idx * 26) + (*byte as u32 - 97) + 1;
+// Translates a term, usually a normal form, to a JavaScript program that defines it as `main`.
+// Within terms:
+// - `\x bod` becomes the arrow function `x => bod`, and `/f a` the call `f(a)`.
+// - `| a b` becomes the array `[a, b]`, and `= a b val nxt` the declaration
+//   `const [a, b] = dup(val);`. `dup` splits an array, as a let does with a pair, and gives
+//   anything else twice: functions are shared rather than copied, which is the same on the affine
+//   fragment.
+// - `& val nxt` becomes `void val;`, and `*` the sentinel `SET`.
+// - Variables bound nowhere become parameters of `main`.
+// Lets are placed as `lay_out` does. Names JavaScript can't spell, or that the program defines
+// itself, are reported as errors.
+pub fn to_js(term : &Term) -> Result<String, ExportError> {
+    let free = {
+        let mut counts = BTreeMap::new();
+        occurrences(term, &mut counts);
+        let mut bound = BTreeSet::new();
+        binders(term, &mut bound);
+        counts.keys().any(|nam| !bound.contains(nam))
+    };
+    let Program { params, lets, body } = lay_out(term, &Js, if free { 2 } else { 0 })?;
+    let mut out = "const SET = Symbol(\"*\");\nconst dup = val => Array.isArray(val) ? val : [val, val];\n".to_string();
+    if params.is_empty() {
+        out.push_str(&lets);
+        out.push_str(&format!("const main = {};\n", body));
+    } else if lets.is_empty() {
+        out.push_str(&format!("const main = ({}) => {};\n", params.join(", "), body));
+    } else {
+        out.push_str(&format!("const main = ({}) => {{\n{}  return {};\n}};\n", params.join(", "), lets, body));
     }
-    return idx;
+    Ok(out)
 }
 
-// A context is a vector of (name, value) assignments.
-type Context<'a> = Vec<(&'a Str, Option<Term>)>;
+struct Rust;
 
-// Extends a context with a (name, value) assignments.
-fn extend<'a,'b>(nam : &'a Str, val : Option<Term>, ctx : &'b mut Context<'a>) -> &'b mut Context<'a> {
-    ctx.push((nam,val));
-    ctx
+impl Target for Rust {
+    fn step(&self) -> usize {
+        4
+    }
+    fn name(&self, nam : &[u8]) -> Result<String, ExportError> {
+        identifier(nam, &[
+            "Value", "apply", "drop", "dup", "fun", "as", "async", "await", "break", "const",
+            "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for", "if",
+            "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+            "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+            "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+            "override", "priv", "try", "typeof", "unsized", "virtual", "yield", "_"
+        ])
+    }
+    fn lam(&self, param : Option<&str>, captures : &[String], lets : &str, body : &str, indent : usize) -> String {
+        let param = param.unwrap_or("_");
+        let fun = if lets.is_empty() {
+            format!("fun(move |{}| {})", param, body)
+        } else {
+            format!("fun(move |{}| {{\n{}{}{}\n{}}})", param, lets, " ".repeat(indent + 4), body, " ".repeat(indent))
+        };
+        // The function is called any number of times, so it keeps copies of what it uses.
+        let clones : String = captures.iter().map(|nam| format!("let {} = {}.clone(); ", nam, nam)).collect();
+        if captures.is_empty() { fun } else { format!("{{ {}{} }}", clones, fun) }
+    }
+    fn app(&self, fun : &str, _ : bool, arg : &str) -> String {
+        format!("{}.apply({})", fun, arg)
+    }
+    fn par(&self, fst : &str, snd : &str) -> String {
+        format!("Value::Pair(Box::new({}), Box::new({}))", fst, snd)
+    }
+    fn var(&self, nam : &str) -> String {
+        format!("{}.clone()", nam)
+    }
+    fn set(&self) -> String {
+        "Value::Unit".to_string()
+    }
+    fn dup(&self, fst : Option<&str>, snd : Option<&str>, val : &str) -> String {
+        format!("let ({}, {}) = dup({});", fst.unwrap_or("_"), snd.unwrap_or("_"), val)
+    }
+    fn era(&self, val : &str, _ : bool) -> String {
+        format!("drop({});", val)
+    }
 }
 
-// Removes an assignment from a context.
-fn narrow<'a,'b>(ctx : &'b mut Context<'a>) -> &'b mut Context<'a> {
-    ctx.pop();
-    ctx
+// Translates a term, usually a normal form, to Rust: a `Value` type, with what it takes to build
+// and call values, and a function `name` that builds the term's. Meant to be a module of its own,
+// since each translation defines `Value`. Within terms:
+// - `\x bod` becomes a closure, in `Value::Fun`, and `/f a` the call `f.apply(a)`.
+// - `| a b` becomes `Value::Pair(a, b)`, and `*` becomes `Value::Unit`.
+// - `= a b val nxt` becomes `let (a, b) = dup(val);`, which splits a pair, as a let does, and
+//   clones anything else. Clones of a function don't share the work done in it, so each copy
+//   redoes it when called.
+// - `& val nxt` drops `val`.
+// - Variables bound nowhere become parameters of `name`.
+// Lets are placed as `lay_out` does. Names Rust can't spell, or that the program defines itself,
+// are reported as errors.
+pub fn to_rust(term : &Term, name : &str) -> Result<String, ExportError> {
+    let name = Rust.name(name.as_bytes())?;
+    let Program { params, lets, body } = lay_out(term, &Rust, 4)?;
+    let params : Vec<String> = params.iter().map(|param| format!("{} : Value", param)).collect();
+    let mut out = String::new();
+    out.push_str("use std::rc::Rc;\n\n");
+    out.push_str("#[derive(Clone)]\n#[allow(dead_code)]\npub enum Value {\n");
+    out.push_str("    Fun(Rc<dyn Fn(Value) -> Value>),\n    Pair(Box<Value>, Box<Value>),\n    Unit\n}\n\n");
+    out.push_str("#[allow(dead_code)]\nimpl Value {\n");
+    out.push_str("    // Calls a function. Applying a unit erases the argument, as applying `*` does.\n");
+    out.push_str("    pub fn apply(self, arg : Value) -> Value {\n        match self {\n");
+    out.push_str("            Value::Fun(fun) => fun(arg),\n            Value::Pair(..) => panic!(\"a pair was applied\"),\n");
+    out.push_str("            Value::Unit => Value::Unit\n        }\n    }\n}\n\n");
+    out.push_str("#[allow(dead_code)]\nfn fun(fun : impl Fn(Value) -> Value + 'static) -> Value {\n    Value::Fun(Rc::new(fun))\n}\n\n");
+    out.push_str("#[allow(dead_code)]\nfn dup(val : Value) -> (Value, Value) {\n    match val {\n");
+    out.push_str("        Value::Pair(fst, snd) => (*fst, *snd),\n        val => (val.clone(), val)\n    }\n}\n\n");
+    out.push_str(&format!("pub fn {}({}) -> Value {{\n{}    {}\n}}\n", name, params.join(", "), lets, body));
+    Ok(out)
 }
 
+// Reads a program in the subset of HVM's syntax that `to_hvm` produces: `@name = term`
+// definitions, with `@main` as the main term, made of `λx bod`, `(f a b ...)`, `&L{a b}`,
+// `! &L{a b} = val; nxt`, `@name`, `*` and variables. HVM1's `{a b}` and `dup a b = val; nxt` are
+// also accepted, and `//` starts a line comment. Binders are renamed apart, since SIC's are
+// global. Fans here are unlabeled, so the program may use one label only. Constructors, numbers,
+// pattern-matching rules and recursive definitions are rejected, and so are variables used twice.
+// Definitions are reordered so each comes after the ones it references.
+pub fn from_hvm(code : &Str) -> Result<Module, SicError> {
+    struct State<'a> {
+        // Lexical scope, from HVM names to the names given to their binders.
+        scope : Vec<(&'a Str, Vec<u8>)>,
+        // Names given to binders in the current definition, and how often each is used.
+        uses : BTreeMap<Vec<u8>, u32>,
+        // Names that aren't bound, which must be definitions.
+        free : Vec<&'a Str>,
+        // The first label seen, if any.
+        label : Option<&'a Str>
+    }
 
-// Parses a name, returns the remaining code and the name.
-fn parse_name(code : &Str) -> (&Str, &Str) {
-    // Ignore whitespace until name is found. 
-    let mut j : usize = 0;
-    while j < code.len() && (
-        code[j] == b' ' || code[j] == b'\n' || code[j] == b'\r'
-    ) {
-        if code[j] == b'\\' || code[j] == b'/' || code[j] == b'|' || code[j] == b'=' ||
-           code[j] == b'#' || code[j] == b'*'
-        { panic!("Valid name not found: {}.", std::str::from_utf8(code).unwrap()) };
-        
-        j += 1;
+    fn skip(code : &Str) -> &Str {
+        let mut code = code;
+        loop {
+            match code.first() {
+                Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') => code = &code[1..],
+                Some(b'/') if code.get(1) == Some(&b'/') => {
+                    while !code.is_empty() && code[0] != b'\n' {
+                        code = &code[1..];
+                    }
+                },
+                _ => return code
+            }
+        }
     }
 
-    let mut i : usize = j;
-    while i < code.len() && !(
-        code[i] == b' ' || code[i] == b'\n' || code[i] == b'\r' ||
-        code[i] == b'\\' || code[i] == b'/' || code[i] == b'|' || code[i] == b'=' ||
-        code[i] == b'#' || code[i] == b'*'
-    ) {
-        i += 1;
+    fn expect<'a>(code : &'a Str, text : &str) -> Result<&'a Str, ParseError> {
+        let code = skip(code);
+        if code.starts_with(text.as_bytes()) {
+            Ok(&code[text.len()..])
+        } else {
+            Err(ParseError::new(code, &format!("expected `{}`", text)))
+        }
     }
 
-    (&code[i..], &code[j..i])
-}
+    fn name(code : &Str) -> Result<(&Str, &Str), ParseError> {
+        let code = skip(code);
+        let len = code.iter().position(|chr| !(chr.is_ascii_alphanumeric() || *chr == b'_' || *chr == b'.')).unwrap_or(code.len());
+        if len == 0 {
+            return Err(ParseError::new(code, "expected a name"));
+        }
+        Ok((&code[len..], &code[..len]))
+    }
 
-pub fn namespace(space : &Vec<u8>, idx : u32, var : &Vec<u8>) -> Vec<u8> {
-    if var != b"-" {
-        let mut nam = space.clone();
-        nam.extend_from_slice(b"#");
-        nam.append(&mut idx.to_string().as_bytes().to_vec());
-        nam.extend_from_slice(b"#");
-        nam.append(&mut var.clone());
-        nam
-    } else {
-        var.clone()
+    // Binds an HVM name, returning the SIC name of the binder. `erased` names `_` binders.
+    fn bind<'a>(nam : &'a Str, erased : &[u8], st : &mut State<'a>) -> Vec<u8> {
+        if nam == b"_" {
+            return erased.to_vec();
+        }
+        let mut new = nam.to_vec();
+        let mut idx = 0;
+        while st.uses.contains_key(&new) || new == b"_" || new == b"-" {
+            new = format!("{}{}", String::from_utf8_lossy(nam), idx).into_bytes();
+            idx += 1;
+        }
+        st.uses.insert(new.clone(), 0);
+        st.scope.push((nam, new.clone()));
+        new
     }
-}
 
-// Makes a namespaced copy of a term
-pub fn copy(space : &Vec<u8>, idx : u32, term : &Term) -> Term {
-    match term {
-        Lam{nam, bod} => {
-            let nam = namespace(space, idx, nam);
-            let bod = Box::new(copy(space, idx, bod));
-            Lam{nam, bod}
-        },
-        App{fun, arg} => {
-            let fun = Box::new(copy(space, idx, fun));
-            let arg = Box::new(copy(space, idx, arg));
-            App{fun, arg}
-        },
-        Par{fst, snd} => {
-            let fst = Box::new(copy(space, idx, fst));
-            let snd = Box::new(copy(space, idx, snd));
-            Par{fst, snd}
-        },
-        Dup{fst, snd, val, nxt} => {
-            let fst = namespace(space, idx, fst);
-            let snd = namespace(space, idx, snd);
-            let val = Box::new(copy(space, idx, val));
-            let nxt = Box::new(copy(space, idx, nxt));
-            Dup{fst, snd, val, nxt}
-        },
-        Var{nam} => {
-            let nam = namespace(space, idx, nam);
-            Var{nam}
-        },
-        Set => Set
+    fn unbind(nam : &Str, st : &mut State) {
+        if nam != b"_" {
+            st.scope.pop();
+        }
     }
-}
 
-// Parses a term, returns the remaining code and the term.
-pub fn parse_term<'a>(code : &'a Str, ctx : &mut Context<'a>, idx : &mut u32, comment : u32) -> (&'a Str, Term) {
-    if comment > 0 {
-        match code[0] {
-            b'(' => {
-                parse_term(&code[1..], ctx, idx, comment + 1)
-            },
-            b')' => {
-                parse_term(&code[1..], ctx, idx, comment - if comment == 0 { 0 } else { 1 })
-            },
-            _    => {
-                parse_term(&code[1..], ctx, idx, comment)
+    fn label<'a>(code : &'a Str, st : &mut State<'a>) -> Result<&'a Str, ParseError> {
+        let (rest, lab) = name(code)?;
+        match st.label {
+            Some(seen) if seen != lab => Err(ParseError::new(skip(code), &format!(
+                "label `{}` differs from `{}`; fans are unlabeled here, so only one label can be used",
+                String::from_utf8_lossy(lab), String::from_utf8_lossy(seen)))),
+            _ => {
+                st.label = Some(lab);
+                Ok(rest)
             }
         }
-    } else {
-        match code[0] {
-            // Whitespace
-            b' ' => {
-                parse_term(&code[1..], ctx, idx, comment)
-            },
-            // Newline
-            b'\n' => {
-                parse_term(&code[1..], ctx, idx, comment)
-            },
-            // Carriage return
-            b'\r' => {
-                parse_term(&code[1..], ctx, idx, comment)
-            },
-            // Comment
-            b'(' => {
-                parse_term(&code[1..], ctx, idx, comment + 1)
-            },
-            // Abstraction
-            b'\\' => {
-                let (code, nam) = parse_name(&code[1..]);
-                extend(nam, None, ctx);
-                let (code, bod) = parse_term(code, ctx, idx, comment);
-                narrow(ctx);
-                let nam = nam.to_vec();
-                let bod = Box::new(bod);
-                (code, Lam{nam,bod})
-            },
-            // Application
-            b'/' => {
-                let (code, fun) = parse_term(&code[1..], ctx, idx, comment);
-                let (code, arg) = parse_term(code, ctx, idx, comment);
-                let fun = Box::new(fun);
-                let arg = Box::new(arg);
-                (code, App{fun,arg})
-            },
-            // Pair
-            b'|' => {
-                let (code, fst) = parse_term(code, ctx, idx, comment);
-                let (code, snd) = parse_term(code, ctx, idx, comment);
-                let fst = Box::new(fst);
-                let snd = Box::new(snd);
-                (code, Par{fst,snd})
-            },
-            // Duplication
-            b'=' => {
-                let (code, fst) = parse_name(&code[1..]);
-                let (code, snd) = parse_name(&code[1..]);
-                extend(snd, None, ctx);
-                extend(fst, None, ctx);
-                let (code, val) = parse_term(code, ctx, idx, comment);
-                let (code, nxt) = parse_term(code, ctx, idx, comment);
-                narrow(ctx);
-                narrow(ctx);
-                let fst = fst.to_vec();
-                let snd = snd.to_vec();
-                let val = Box::new(val);
-                let nxt = Box::new(nxt);
-                (code, Dup{fst, snd, val, nxt})
-            },
-            // Definition
-            b':' => {
-                let (code, nam) = parse_name(&code[1..]);
-                let (code, val) = parse_term(code, ctx, idx, comment);
-                extend(nam, Some(val), ctx);
-                let (code, bod) = parse_term(code, ctx, idx, comment);
-                narrow(ctx);
-                (code, bod)
-            },
-            // Set
-            b'*' => {
-                (&code[1..], Set)
-            },
-            // Variable
-            _ => {
-                let (code, nam) = parse_name(code);
-                let mut val : Option<Term> = None;
-                for i in (0..ctx.len()).rev() {
-                    if ctx[i].0 == nam {
-                        match ctx[i].1 {
-                            Some(ref term) => {
-                                let name = nam.clone().to_vec();
-                                val = Some(copy(&name, *idx, term));
-                                *idx += 1;
-                                break;
-                            },
-                            None => {
-                                break;
-                            }
-                        }
+    }
+
+    // Parses `a b} = val; nxt` (or `a b = val; nxt` when `close` is false), the end of a dup.
+    fn dup<'a>(code : &'a Str, close : bool, st : &mut State<'a>) -> Result<(&'a Str, Term), ParseError> {
+        let (code, fst) = name(code)?;
+        let (code, snd) = name(code)?;
+        let code = if close { expect(code, "}")? } else { code };
+        let code = expect(code, "=")?;
+        let (code, val) = term(code, st)?;
+        let code = expect(code, ";")?;
+        let fst_new = bind(fst, b"-", st);
+        let snd_new = bind(snd, b"-", st);
+        let (code, nxt) = term(code, st)?;
+        unbind(snd, st);
+        unbind(fst, st);
+        Ok((code, Dup{fst: fst_new, snd: snd_new, val: Box::new(val), nxt: Box::new(nxt)}))
+    }
+
+    fn term<'a>(code : &'a Str, st : &mut State<'a>) -> Result<(&'a Str, Term), ParseError> {
+        let code = skip(code);
+        match code.first() {
+            None => Err(ParseError::new(code, "unexpected end of input")),
+            Some(_) if code.starts_with("λ".as_bytes()) => {
+                let (rest, nam) = name(&code[2..])?;
+                let new = bind(nam, b"_", st);
+                let (rest, bod) = term(rest, st)?;
+                unbind(nam, st);
+                Ok((rest, Lam{nam: new, bod: Box::new(bod)}))
+            },
+            Some(b'(') => {
+                let head = skip(&code[1..]);
+                if head.first().is_some_and(|chr| chr.is_ascii_uppercase()) {
+                    return Err(ParseError::new(head, "constructors and pattern-matching rules aren't supported"));
+                }
+                let (mut rest, mut fun) = term(head, st)?;
+                loop {
+                    rest = skip(rest);
+                    if rest.first() == Some(&b')') {
+                        return Ok((&rest[1..], fun));
+                    }
+                    let (next, arg) = term(rest, st)?;
+                    fun = App{fun: Box::new(fun), arg: Box::new(arg)};
+                    rest = next;
+                }
+            },
+            Some(b'&') | Some(b'{') => {
+                let rest = if code[0] == b'&' { expect(label(&code[1..], st)?, "{")? } else { &code[1..] };
+                let (rest, fst) = term(rest, st)?;
+                let (rest, snd) = term(rest, st)?;
+                Ok((expect(rest, "}")?, Par{fst: Box::new(fst), snd: Box::new(snd)}))
+            },
+            Some(b'!') => {
+                let rest = expect(&code[1..], "&")?;
+                let rest = expect(label(rest, st)?, "{")?;
+                dup(rest, true, st)
+            },
+            Some(b'd') if code.starts_with(b"dup ") => dup(&code[4..], false, st),
+            Some(b'*') => Ok((&code[1..], Set)),
+            Some(b'#') => Err(ParseError::new(code, "constructors aren't supported")),
+            Some(chr) if chr.is_ascii_digit() => Err(ParseError::new(code, "numbers aren't supported")),
+            Some(b'@') => {
+                let (rest, nam) = name(&code[1..])?;
+                st.free.push(nam);
+                Ok((rest, Var{nam: nam.to_vec()}))
+            },
+            Some(_) => {
+                let (rest, nam) = name(code)?;
+                match st.scope.iter().rev().find(|(var, _)| *var == nam) {
+                    Some((_, new)) => {
+                        *st.uses.get_mut(new).unwrap() += 1;
+                        Ok((rest, Var{nam: new.clone()}))
+                    },
+                    None => {
+                        st.free.push(nam);
+                        Ok((rest, Var{nam: nam.to_vec()}))
                     }
                 }
-                let nam = nam.to_vec();
-                (code, match val { Some(term) => term, None => Var{nam} })
             }
         }
     }
-}
 
-// Converts a source-code to a λ-term.
-pub fn from_string<'a>(code : &'a Str) -> Term {
-    let mut ctx = Vec::new();
-    let mut idx = 0;
-    parse_term(code, &mut ctx, &mut idx, 0).1
+    // Parses `@name = term`.
+    fn definition<'a>(code : &'a Str, st : &mut State<'a>) -> Result<(&'a Str, &'a Str, Term), ParseError> {
+        if code[0] == b'(' {
+            return Err(ParseError::new(code, "pattern-matching rules aren't supported; only `@name = term` definitions are"));
+        }
+        if code.starts_with(b"data ") || code.starts_with(b"type ") {
+            return Err(ParseError::new(code, "constructors aren't supported"));
+        }
+        let (code, nam) = name(expect(code, "@")?)?;
+        let (code, val) = term(expect(code, "=")?, st)?;
+        Ok((code, nam, val))
+    }
+
+    let mut defs : Vec<(Vec<u8>, Term)> = Vec::new();
+    let mut main = None;
+    let mut st = State { scope: Vec::new(), uses: BTreeMap::new(), free: Vec::new(), label: None };
+    let mut rest = skip(code);
+    while !rest.is_empty() {
+        st.uses.clear();
+        let (after, nam, val) = definition(rest, &mut st).map_err(|err| err.located(code))?;
+        if let Some((var, _)) = st.uses.iter().find(|(_, count)| **count > 1) {
+            return Err(AffinityError::Duplicated(var.clone()).into());
+        }
+        if defs.iter().any(|(def, _)| def == nam) || (nam == b"main" && main.is_some()) {
+            return Err(ParseError::new(rest, "definition given twice").located(code).into());
+        } else if nam == b"main" {
+            main = Some(val);
+        } else {
+            defs.push((nam.to_vec(), val));
+        }
+        rest = skip(after);
+    }
+    if let Some(nam) = st.free.iter().find(|nam| !defs.iter().any(|(def, _)| def == *nam)) {
+        return Err(AffinityError::Unbound(nam.to_vec()).into());
+    }
+
+    let module = Module { defs, main, comments: Vec::new(), notes: Vec::new(), fuel: Vec::new(), params: Vec::new() };
+    if let Some(cycle) = module.cycles().first() {
+        let names : Vec<String> = cycle.iter().map(|nam| String::from_utf8_lossy(nam).into_owned()).collect();
+        return Err(ParseError { index: 0, message: format!("recursive definitions can't be expanded: {}", names.join(", ")) }.into());
+    }
+    let defs = module.topological_order().into_iter()
+        .map(|nam| (nam.clone(), module.find(&nam).unwrap().clone()))
+        .collect();
+    Ok(Module { defs, main: module.main, comments: Vec::new(), notes: Vec::new(), fuel: Vec::new(), params: Vec::new() })
 }
 
-// Converts a λ-term back to a source-code.
-pub fn to_string(term : &Term) -> Vec<Chr> {
-    fn stringify_term(code : &mut Vec<u8>, term : &Term) {
+// Checks that a closed term is affine: every variable is bound once, by a lambda or a let, and used
+// at most once. Wildcards bind nothing, so they can repeat. This is what `to_net` requires,
+// checked on the term alone.
+pub fn check_affine(term : &Term) -> Result<(), AffinityError> {
+    fn bind(nam : &[u8], bound : &mut BTreeSet<Vec<u8>>) -> Result<(), AffinityError> {
+        if !is_wildcard(nam) && !bound.insert(nam.to_vec()) {
+            return Err(AffinityError::Duplicated(nam.to_vec()));
+        }
+        Ok(())
+    }
+    fn go(term : &Term, bound : &mut BTreeSet<Vec<u8>>) -> Result<(), AffinityError> {
         match term {
-            &Lam{ref nam, ref bod} => {
-                code.extend_from_slice(b"\\");
-                code.append(&mut nam.clone());
-                code.extend_from_slice(b" ");
-                stringify_term(code, &bod);
-            },
-            &App{ref fun, ref arg} => {
-                code.extend_from_slice(b"/");
-                stringify_term(code, &fun);
-                code.extend_from_slice(b" ");
-                stringify_term(code, &arg);
-            },
-            &Par{ref fst, ref snd} => {
-                code.extend_from_slice(b"|");
-                code.extend_from_slice(b" ");
-                stringify_term(code, &fst);
-                code.extend_from_slice(b" ");
-                stringify_term(code, &snd);
-            },
-            &Dup{ref fst, ref snd, ref val, ref nxt} => {
-                code.extend_from_slice(b"=");
-                code.extend_from_slice(b" ");
-                code.append(&mut fst.clone());
-                code.extend_from_slice(b" ");
-                code.append(&mut snd.clone());
-                code.extend_from_slice(b" ");
-                stringify_term(code, &val);
-                code.extend_from_slice(b"\n");
-                stringify_term(code, &nxt);
+            Lam{nam, bod} => {
+                bind(nam, bound)?;
+                go(bod, bound)
             },
-            &Set => {
-                code.extend_from_slice(b"*");
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Era{val: a, nxt: b} => {
+                go(a, bound)?;
+                go(b, bound)
             },
-            &Var{ref nam} => {
-                code.append(&mut nam.clone());
+            Dup{fst, snd, val, nxt} => {
+                bind(fst, bound)?;
+                bind(snd, bound)?;
+                go(val, bound)?;
+                go(nxt, bound)
             },
+            Var{..} | Ctr{..} | Set => Ok(())
         }
     }
-    let mut code = Vec::new();
-    stringify_term(&mut code, &term);
-    return code;
-}
-
-// Display macro.
-impl std::fmt::Display for Term {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", String::from_utf8_lossy(&to_string(&self)))
+    let mut bound = BTreeSet::new();
+    go(term, &mut bound)?;
+    let mut counts = BTreeMap::new();
+    occurrences(term, &mut counts);
+    for (nam, count) in counts {
+        if !bound.contains(&nam) {
+            return Err(AffinityError::Unbound(nam));
+        } else if count > 1 {
+            return Err(AffinityError::Duplicated(nam));
+        }
     }
+    Ok(())
 }
 
 // Converts a term to an Interaction Combinator net. Both systems are directly isomorphic, so,
 // each node of the Abstract Calculus correspond to a single Interaction Combinator node.
-pub fn to_net(term : &Term) -> Net {
+pub fn to_net(term : &Term) -> Result<Net, AffinityError> {
+    to_net_with_fans(term, false).map(|(net, _, _, _)| net)
+}
+
+// Converts a term to a net like `to_net`, filling `net`, which is cleared first, so the room it
+// has is reused. On an error, `net` is left cleared.
+pub fn to_net_in(term : &Term, net : &mut Net) -> Result<(), AffinityError> {
+    net.clear();
+    let encoded = encode(term, false, net).map(|_| ());
+    if encoded.is_err() {
+        net.clear();
+    }
+    encoded
+}
+
+// Encoding and reading back through a pool, so the nets of many programs reuse each other's room.
+impl NetPool {
+    // Converts a term to a net like `to_net`, in a net from the pool.
+    pub fn to_net(&mut self, term : &Term) -> Result<Net, AffinityError> {
+        let mut net = self.take();
+        match to_net_in(term, &mut net) {
+            Ok(()) => Ok(net),
+            Err(err) => {
+                self.give(net);
+                Err(err)
+            }
+        }
+    }
+
+    // Reads a net back like `from_net`, then puts it back in the pool.
+    pub fn from_net(&mut self, net : Net) -> Result<Term, ReadbackError> {
+        let term = from_net(&net);
+        self.give(net);
+        term
+    }
+}
+
+// The names of the free variables of a net, by the address of their FREE nodes.
+pub type FreeNames = BTreeMap<u32, Vec<u8>>;
+
+// Converts an open term to a net. Where `to_net` fails on a free variable, this gives each of its
+// occurrences a FREE node, and returns their names; `from_net_open` reads them back.
+pub fn to_net_open(term : &Term) -> Result<(Net, FreeNames), AffinityError> {
+    to_net_with_fans(term, true).map(|(net, _, free, _)| (net, free))
+}
+
+// The names of binders, by the port their variable is linked to: port 1 of a lambda's CON node,
+// and ports 1 and 2 of a let's FAN node.
+pub type BinderNames = BTreeMap<u32, Vec<u8>>;
+
+// Converts a term to a net like `to_net_open`, also returning the names of its binders, which
+// `from_net_named` can give back to those that survive reduction (see `reduce_named`).
+pub fn to_net_named(term : &Term) -> Result<(Net, FreeNames, BinderNames), AffinityError> {
+    to_net_with_fans(term, true).map(|(net, _, free, binders)| (net, free, binders))
+}
+
+// Reduces a net like `net::reduce`, keeping its binder names up to date: a node that takes part in
+// a rewrite, and the copies it makes, lose theirs. So names are only kept by binders that reduction
+// left in place, and copied or merged ones get new names on read-back.
+pub fn reduce_named(net : &mut Net, names : &mut BinderNames) -> Stats {
+    reduce_steps(net, u32::MAX, 0, &mut |_| true, &mut |_, x, y, copies| {
+        let (a, b) = copies.unwrap_or((x, y));
+        for node in [x, y, a, b] {
+            names.remove(&link(node, PORT_AUX1));
+            names.remove(&link(node, PORT_AUX2));
+        }
+    }).0
+}
+
+// The definitions the nodes of a net were inlined from, by address. Only lambdas and lets have
+// names to tell (`f#0#x`), so other nodes, and those of the main term, have none.
+pub type NodeDefs = BTreeMap<u32, Vec<u8>>;
+
+// The definitions of the binders of a net, as `to_net_named` names them.
+pub fn node_defs(names : &BinderNames) -> NodeDefs {
+    names.iter().filter_map(|(port, nam)| split_namespace(nam).map(|(def, _, _)| (addr(*port), def.to_vec()))).collect()
+}
+
+// Reduces a net like `net::reduce`, keeping the definitions of its nodes up to date: the copies
+// made when a node commutes come from its definition, and annihilated nodes from none.
+pub fn reduce_defs(net : &mut Net, defs : &mut NodeDefs) -> Stats {
+    reduce_steps(net, u32::MAX, 0, &mut |_| true, &mut |_, x, y, copies| {
+        match copies {
+            Some((a, b)) => {
+                for (copy, node) in [(a, x), (b, y)] {
+                    match defs.get(&node).cloned() {
+                        Some(def) => defs.insert(copy, def),
+                        None => defs.remove(&copy)
+                    };
+                }
+            },
+            None => {
+                defs.remove(&x);
+                defs.remove(&y);
+            }
+        }
+    }).0
+}
+
+// The most nodes `trace_terms` lets a net have, since it reads the whole net back at each step.
+pub const MAX_TRACE_NODES : u32 = 4096;
+
+// Reduces a net like `net::reduce`, reading it back before the first rewrite and after every
+// `every`, and passing each term that isn't the same as the one before to `step`, with the
+// rewrites so far. A net midway through may not read back, in which case the error is passed
+// instead. Reading back takes time in the size of the net, so a net with more than
+// `MAX_TRACE_NODES` nodes is refused, and one that grows past them is left partially reduced.
+pub fn trace_terms(net : &mut Net, every : u32, step : &mut dyn FnMut(u32, Result<Term, ReadbackError>)) -> Result<Stats, LimitError> {
+    if node_count(net) > MAX_TRACE_NODES {
+        return Err(LimitError::Traced(MAX_TRACE_NODES));
+    }
+    let every = every.max(1);
+    let mut last = from_net(net);
+    step(0, last.clone());
+    let mut rules : u32 = 0;
+    let grown = core::cell::Cell::new(false);
+    let (stats, _) = reduce_steps(net, u32::MAX, 1, &mut |_| !grown.get(), &mut |net, _, _, _| {
+        rules += 1;
+        grown.set(node_count(net) > MAX_TRACE_NODES);
+        if !grown.get() && rules.is_multiple_of(every) {
+            let term = from_net(net);
+            if term != last {
+                step(rules, term.clone());
+                last = term;
+            }
+        }
+    });
+    if grown.get() {
+        return Err(LimitError::Traced(MAX_TRACE_NODES));
+    }
+    // The normal form, if the last rewrite fell between steps.
+    let term = from_net(net);
+    if term != last {
+        step(stats.rules, term);
+    }
+    Ok(stats)
+}
+
+// A net, the FAN nodes made with where each came from, the names of the FREE nodes, and the names
+// of the binders.
+pub(crate) type Encoded = (Net, Vec<(u32, FanOrigin)>, FreeNames, BinderNames);
+
+// Converts a term to a net like `to_net`, also returning the FAN nodes made, with where each came
+// from, and the binders. Pairs and lets are numbered in the order they're encoded. If `open`, free
+// variables become FREE nodes, as in `to_net_open`.
+pub(crate) fn to_net_with_fans(term : &Term, open : bool) -> Result<Encoded, AffinityError> {
+    let mut net = root_net();
+    let (fans, free, binders) = encode(term, open, &mut net)?;
+    Ok((net, fans, free, binders))
+}
+
+// What `encode` returns besides the net it fills.
+type EncodedNames = (Vec<(u32, FanOrigin)>, FreeNames, BinderNames);
+
+// Encodes a term into a net of only a root node, as `to_net_with_fans` does, returning the rest.
+fn encode(term : &Term, open : bool, net : &mut Net) -> Result<EncodedNames, AffinityError> {
+    // Registers the constructors of a term in `ctrs`, each with the number of arguments it's
+    // applied to where it first occurs, and checks every other occurrence has as many.
+    fn register(term : &Term, ctrs : &mut Vec<(Vec<u8>, u32)>) -> Result<(), AffinityError> {
+        if let Some((nam, args)) = ctr_spine(term) {
+            let arity = match ctrs.iter().find(|(ctr, _)| ctr == nam) {
+                Some((_, arity)) => *arity,
+                None if args.len() as u32 <= MAX_FIELDS => {
+                    ctrs.push((nam.to_vec(), args.len() as u32));
+                    args.len() as u32
+                },
+                None => MAX_FIELDS
+            };
+            if args.len() as u32 != arity {
+                return Err(AffinityError::Arity{nam: nam.to_vec(), arity, args: args.len() as u32});
+            }
+            return args.iter().try_for_each(|arg| register(arg, ctrs));
+        }
+        match term {
+            Lam{bod, ..} => register(bod, ctrs),
+            App{fun: a, arg: b} | Par{fst: a, snd: b} | Dup{val: a, nxt: b, ..} | Era{val: a, nxt: b} => {
+                register(a, ctrs)?;
+                register(b, ctrs)
+            },
+            Var{..} | Ctr{..} | Set => Ok(())
+        }
+    }
+
     fn encode_term
         ( net   : &mut Net
         , term  : &Term
         , up    : Link
-        , scope : &mut HashMap<Vec<u8>,u32>
+        , scope : &mut BTreeMap<Vec<u8>,u32>
         , vars  : &mut Vec<(Vec<u8>,u32)>
+        , fans  : &mut Vec<(u32, FanOrigin)>
         ) -> Link {
         match term {
             // A lambda becomes to a con node. Ports:
             // - 0: points to where the lambda occurs.
             // - 1: points to the lambda variable.
             // - 2: points to the lambda body.
-            &Lam{ref nam, ref bod} => {
+            Lam{nam, bod} => {
                 let fun = new_node(net, CON);
-                scope.insert(nam.to_vec(), link(fun, 1));
-                // Also, if the variable is unused, crease an erase node.
-                if nam == b"_" {
+                // Also, if the variable is a wildcard, create an erase node. Wildcards aren't in
+                // scope, so that several can't clash.
+                if !is_wildcard(nam) {
+                    scope.insert(nam.to_vec(), link(fun, PORT_AUX1));
+                } else {
                     let era = new_node(net, ERA);
-                    connect(net, link(era, 1), link(era, 2));
-                    connect(net, link(fun, 1), link(era, 0));
+                    connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+                    connect(net, link(fun, PORT_AUX1), link(era, PORT_MAIN));
+                }
+                let bod = encode_term(net, bod, link(fun, PORT_AUX2), scope, vars, fans);
+                connect(net, link(fun, PORT_AUX2), bod);
+                link(fun, PORT_MAIN)
+            },
+            // A constructor given its fields becomes a node of its kind. Ports:
+            // - 0: points to where the constructor occurs.
+            // - 1: points to the first field, if any.
+            // - 2: points to the second field, if any.
+            App{..} | Ctr{..} if ctr_spine(term).is_some() => {
+                let (nam, args) = ctr_spine(term).unwrap();
+                let idx = net.ctrs.iter().position(|(ctr, _)| ctr == nam).unwrap() as u32;
+                let ctr = new_node(net, CTR + idx);
+                for (slot, arg) in args.iter().enumerate() {
+                    let slot = slot as u32 + 1;
+                    let arg = encode_term(net, arg, link(ctr, slot), scope, vars, fans);
+                    connect(net, link(ctr, slot), arg);
+                }
+                // Unused ports hold an erase node, or are looped if there are no fields.
+                match args.len() {
+                    0 => connect(net, link(ctr, PORT_AUX1), link(ctr, PORT_AUX2)),
+                    1 => {
+                        let era = new_node(net, ERA);
+                        connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+                        connect(net, link(ctr, PORT_AUX2), link(era, PORT_MAIN));
+                    },
+                    _ => {}
                 }
-                let bod = encode_term(net, bod, link(fun, 2), scope, vars);
-                connect(net, link(fun, 2), bod);
-                link(fun, 0)
+                link(ctr, PORT_MAIN)
             },
             // An application becomes to a con node too. Ports:
             // - 0: points to the function being applied.
             // - 1: points to the function's argument.
             // - 2: points to where the application occurs.
-            &App{ref fun, ref arg} => {
+            App{fun, arg} => {
                 let app = new_node(net, CON);
-                let fun = encode_term(net, fun, link(app, 0), scope, vars);
-                connect(net, link(app, 0), fun);
-                let arg = encode_term(net, arg, link(app, 1), scope, vars);
-                connect(net, link(app, 1), arg);
-                link(app, 2)
+                let fun = encode_term(net, fun, link(app, PORT_MAIN), scope, vars, fans);
+                connect(net, link(app, PORT_MAIN), fun);
+                let arg = encode_term(net, arg, link(app, PORT_AUX1), scope, vars, fans);
+                connect(net, link(app, PORT_AUX1), arg);
+                link(app, PORT_AUX2)
             },
             // A pair becomes a dup node. Ports:
             // - 0: points to where the pair occurs.
             // - 1: points to the first value.
             // - 2: points to the second value.
-            &Par{ref fst, ref snd} => {
+            Par{fst, snd} => {
                 let dup = new_node(net, FAN);
-                let fst = encode_term(net, fst, link(dup, 1), scope, vars);
-                connect(net, link(dup, 1), fst);
-                let snd = encode_term(net, snd, link(dup, 2), scope, vars);
-                connect(net, link(dup, 2), snd);
-                link(dup, 0)
+                fans.push((dup, FanOrigin::Par{idx: fans.len() as u32}));
+                let fst = encode_term(net, fst, link(dup, PORT_AUX1), scope, vars, fans);
+                connect(net, link(dup, PORT_AUX1), fst);
+                let snd = encode_term(net, snd, link(dup, PORT_AUX2), scope, vars, fans);
+                connect(net, link(dup, PORT_AUX2), snd);
+                link(dup, PORT_MAIN)
             },
             // A duplication becomes a dup node too. Ports:
             // - 0: points to the value projected.
             // - 1: points to the occurrence of the first variable.
             // - 2: points to the occurrence of the second variable.
-            &Dup{ref fst, ref snd, ref val, ref nxt} => {
+            Dup{fst, snd, val, nxt} => {
                 let dup = new_node(net, FAN);
-                scope.insert(fst.to_vec(), link(dup, 1));
-                scope.insert(snd.to_vec(), link(dup, 2));
-                // If the first variable is unused, create an erase node.
-                if fst == b"-" {
+                fans.push((dup, FanOrigin::Dup{idx: fans.len() as u32, fst: fst.to_vec(), snd: snd.to_vec()}));
+                // If the first variable is a wildcard, create an erase node.
+                if !is_wildcard(fst) {
+                    scope.insert(fst.to_vec(), link(dup, PORT_AUX1));
+                } else {
                     let era = new_node(net, ERA);
-                    connect(net, link(era, 1), link(era, 2));
-                    connect(net, link(dup, 1), link(era, 0));
+                    connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+                    connect(net, link(dup, PORT_AUX1), link(era, PORT_MAIN));
                 }
-                // If the second variable is unused, create an erase node.
-                if snd == b"-" {
+                // Same for the second.
+                if !is_wildcard(snd) {
+                    scope.insert(snd.to_vec(), link(dup, PORT_AUX2));
+                } else {
                     let era = new_node(net, ERA);
-                    connect(net, link(era, 1), link(era, 2));
-                    connect(net, link(dup, 2), link(era, 0));
+                    connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+                    connect(net, link(dup, PORT_AUX2), link(era, PORT_MAIN));
                 }
-                let val = encode_term(net, &val, link(dup, 0), scope, vars);
-                connect(net, val, link(dup, 0));
-                encode_term(net, &nxt, up, scope, vars)
+                let val = encode_term(net, val, link(dup, PORT_MAIN), scope, vars, fans);
+                connect(net, val, link(dup, PORT_MAIN));
+                encode_term(net, nxt, up, scope, vars, fans)
+            },
+            // An erasure becomes an era node, facing the value it erases. Ports:
+            // - 0: points to the value erased.
+            // - 1 and 2: point to each other.
+            Era{val, nxt} => {
+                let era = new_node(net, ERA);
+                connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+                let val = encode_term(net, val, link(era, PORT_MAIN), scope, vars, fans);
+                connect(net, val, link(era, PORT_MAIN));
+                encode_term(net, nxt, up, scope, vars, fans)
             },
             // A set is just an erase node stored in a place.
-            &Set => {
+            Set => {
                 let set = new_node(net, ERA);
-                connect(net, link(set, 1), link(set, 2));
-                link(set, 0)
+                connect(net, link(set, PORT_AUX1), link(set, PORT_AUX2));
+                link(set, PORT_MAIN)
             },
             Var{ref nam} => {
                 vars.push((nam.to_vec(), up));
                 up
-            }
+            },
+            Ctr{..} => unreachable!()
         }
     }
 
-    // Initializes net with a root node.
-    let mut net = Net { nodes: vec![0,2,1,4], reuse: vec![] };
+    // Adds the constructors of the term to the net.
+    register(term, &mut net.ctrs)?;
     let mut vars = Vec::new();
-    let mut scope = HashMap::new();
+    let mut scope = BTreeMap::new();
+    let mut fans = Vec::new();
+    let mut free = BTreeMap::new();
 
     // Encodes the main term.
-    let main = encode_term(&mut net, &term, 0, &mut scope, &mut vars);
+    let main = encode_term(net, term, 0, &mut scope, &mut vars, &mut fans);
+    let binders = scope.iter().map(|(nam, at)| (*at, nam.clone())).collect();
 
     // Links bound variables.
-    for i in 0..vars.len() {
-        let (ref nam, var) = vars[i];
+    for (nam, var) in &vars {
+        let var = *var;
         match scope.get(nam) {
             Some(next) => {
                 let next = *next;
-                if enter(&net, next) == next {
-                    connect(&mut net, var, next);
+                if enter(net, next) == next {
+                    connect(net, var, next);
                 } else {
-                    panic!("Variable used more than once: {}.", std::str::from_utf8(nam).unwrap());
+                    return Err(AffinityError::Duplicated(nam.clone()));
                 }
             },
-            None => panic!("Unbound variable: {}.", std::str::from_utf8(nam).unwrap())
+            None if open => {
+                let node = new_node(net, FREE);
+                connect(net, link(node, PORT_AUX1), link(node, PORT_AUX2));
+                connect(net, var, link(node, PORT_MAIN));
+                free.insert(node, nam.clone());
+            },
+            None => return Err(AffinityError::Unbound(nam.clone()))
         }
     }
 
     // Connects unbound variables to erase nodes
     for (_, addr) in scope {
-        if enter(&net, addr) == addr {
-            let era = new_node(&mut net, ERA);
-            connect(&mut net, link(era, 1), link(era, 2));
-            connect(&mut net, addr, link(era, 0));
+        if enter(net, addr) == addr {
+            let era = new_node(net, ERA);
+            connect(net, link(era, PORT_AUX1), link(era, PORT_AUX2));
+            connect(net, addr, link(era, PORT_MAIN));
         }
     }
 
     // Links the term to the net's root.
-    connect(&mut net, 0, main);
+    connect(net, 0, main);
+
+    Ok((fans, free, binders))
+}
+
+// If a term is a constructor applied to arguments (possibly none), returns its name and them.
+fn ctr_spine(term : &Term) -> Option<(&[u8], Vec<&Term>)> {
+    let mut args = Vec::new();
+    let mut head = term;
+    while let App{fun, arg} = head {
+        args.push(&**arg);
+        head = fun;
+    }
+    args.reverse();
+    match head {
+        Ctr{nam} => Some((nam, args)),
+        _ => None
+    }
+}
+
+// The conversions between terms and nets, as methods.
+impl Net {
+    // Converts a term to a net, like `to_net`.
+    pub fn from_term(term : &Term) -> Result<Net, AffinityError> {
+        to_net(term)
+    }
 
-    net
+    // Converts a net to a term, like `from_net`.
+    pub fn to_term(&self) -> Result<Term, ReadbackError> {
+        from_net(self)
+    }
 }
 
 // Converts an Interaction-Net node to an Abstract Calculus term.
-pub fn from_net(net : &Net) -> Term {
-    // Given a link, returns its name, or assigns one if it wasn't named yet.
-    fn name_of(net : &Net, var_port : Link, var_name : &mut HashMap<u32, Vec<u8>>) -> Vec<u8> {
+pub fn from_net(net : &Net) -> Result<Term, ReadbackError> {
+    from_net_open(net, &FreeNames::new())
+}
+
+// Converts a net made by `to_net_open` to a term, reading each FREE node as a variable with its
+// name in `free`. Binders are named so as not to capture them.
+pub fn from_net_open(net : &Net, free : &FreeNames) -> Result<Term, ReadbackError> {
+    from_net_named(net, free, &BinderNames::new())
+}
+
+// Converts a net made by `to_net_named` to a term like `from_net_open`, giving binders the names
+// in `names`, when they're still there. A name two binders would get, or a free variable has, is
+// left for a new one.
+pub fn from_net_named(net : &Net, free : &FreeNames, names : &BinderNames) -> Result<Term, ReadbackError> {
+    // Names that new ones must not clash with: the indices of those that `new_name` could make,
+    // in order, and the binder names kept.
+    struct Taken {
+        idxs : Vec<u32>,
+        kept : BinderNames,
+        // The ports read back as variables, once known: other binders are unused.
+        used : Option<BTreeSet<u32>>
+    }
+
+    // Given a link, returns its name, or assigns one if it wasn't named yet. Indices of names in
+    // `taken`, in order, are skipped.
+    fn name_of(net : &Net, var_port : Link, var_name : &mut BTreeMap<u32, Vec<u8>>, taken : &Taken) -> Vec<u8> {
         // If link is linked to an erase node, return an unused variable
         if kind(net, addr(enter(net, var_port))) == ERA {
             return b"-".to_vec();
         }
+        if taken.used.as_ref().is_some_and(|used| !used.contains(&var_port)) {
+            return b"-".to_vec();
+        }
+        if let Some(nam) = taken.kept.get(&var_port) {
+            return nam.clone();
+        }
         if !var_name.contains_key(&var_port) {
-            let nam = new_name(var_name.len() as u32 + 1);
+            let mut idx = var_name.len() as u32 + 1;
+            for skip in &taken.idxs {
+                if *skip <= idx {
+                    idx += 1;
+                }
+            }
+            let nam = new_name(idx);
             var_name.insert(var_port, nam.clone());
         }
         var_name.get(&var_port).unwrap().to_vec()
@@ -460,44 +2815,49 @@ pub fn from_net(net : &Net) -> Term {
     fn read_term
         ( net      : &Net
         , next     : Link
-        , var_name : &mut HashMap<u32, Vec<u8>>
+        , var_name : &mut BTreeMap<u32, Vec<u8>>
         , lets_vec : &mut Vec<u32>
-        , lets_set : &mut HashSet<u32>
-        ) -> Term {
-        match kind(net, addr(next)) {
+        , lets_set : &mut BTreeSet<u32>
+        , free     : &FreeNames
+        , taken    : &Taken
+        ) -> Result<Term, ReadbackError> {
+        Ok(match kind(net, addr(next)) {
             // If we're visiting a set...
             ERA => Set,
             // If we're visiting a con node...
             CON => match port(next) {
                 // If we're visiting a link 0, then it is a lambda.
-                0 => {
-                    let nam = name_of(net, link(addr(next),1), var_name);
-                    let prt = enter(net, link(addr(next), 2));
-                    let bod = read_term(net, prt, var_name, lets_vec, lets_set);
-                    let lam = Lam{nam: nam, bod: Box::new(bod)};
-                    lam
+                PORT_MAIN => {
+                    let nam = name_of(net, link(addr(next), PORT_AUX1), var_name, taken);
+                    let prt = enter(net, link(addr(next), PORT_AUX2));
+                    let bod = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
+                    Lam{nam, bod: Box::new(bod)}
                 },
                 // If we're visiting a link 1, then it is a variable.
-                1 => {
-                    Var{nam: name_of(net, next, var_name)}
+                PORT_AUX1 => {
+                    Var{nam: name_of(net, next, var_name, taken)}
                 },
                 // If we're visiting a link 2, then it is an application.
                 _ => {
-                    let prt = enter(net, link(addr(next), 0));
-                    let fun = read_term(net, prt, var_name, lets_vec, lets_set);
-                    let prt = enter(net, link(addr(next), 1));
-                    let arg = read_term(net, prt, var_name, lets_vec, lets_set);
+                    let prt = enter(net, link(addr(next), PORT_MAIN));
+                    let fun = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
+                    let prt = enter(net, link(addr(next), PORT_AUX1));
+                    let arg = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
                     App{fun: Box::new(fun), arg: Box::new(arg)}
                 }
             },
-            // If we're visiting a fan node...
+            // If we're visiting a fan node... Whether it's a pair or a let depends on the port we
+            // enter by, and reduction keeps that consistent: a pair's values are only ever read
+            // from the pair, and a let's value from the let. So a pair being applied (its port 0
+            // facing an application's function port) reads as an application of a pair, and a
+            // pair being copied (facing a let's port 0) as the let's value.
             FAN => match port(next) {
                 // If we're visiting a link 0, then it is a pair.
-                0 => {
-                    let prt = enter(net, link(addr(next), 1));
-                    let fst = read_term(net, prt, var_name, lets_vec, lets_set);
-                    let prt = enter(net, link(addr(next), 2));
-                    let snd = read_term(net, prt, var_name, lets_vec, lets_set);
+                PORT_MAIN => {
+                    let prt = enter(net, link(addr(next), PORT_AUX1));
+                    let fst = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
+                    let prt = enter(net, link(addr(next), PORT_AUX2));
+                    let snd = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
                     Par{fst: Box::new(fst), snd: Box::new(snd)}
                 },
                 // If we're visiting a link 1 or 2, then it is a variable.
@@ -507,43 +2867,133 @@ pub fn from_net(net : &Net) -> Term {
                         lets_set.insert(addr(next));
                         lets_vec.push(addr(next));
                     }
-                    let nam = name_of(net, next, var_name);
+                    let nam = name_of(net, next, var_name, taken);
                     Var{nam}
                 }
             },
-            _ => panic!("Unknown kind of node"),
-        }
+            // If we're visiting a free variable...
+            FREE => match free.get(&addr(next)) {
+                Some(nam) => Var{nam: nam.clone()},
+                None => return Err(ReadbackError::Unnamed(addr(next)))
+            },
+            // If we're visiting a constructor, by its link 0, it's applied to its fields.
+            kind => match net.ctrs.get(kind.wrapping_sub(CTR) as usize) {
+                Some(_) if port(next) != PORT_MAIN => return Err(ReadbackError::Field(addr(next))),
+                Some((nam, arity)) => {
+                    let mut term = Ctr{nam: nam.clone()};
+                    for slot in 1..arity + 1 {
+                        let prt = enter(net, link(addr(next), slot));
+                        let arg = read_term(net, prt, var_name, lets_vec, lets_set, free, taken)?;
+                        term = App{fun: Box::new(term), arg: Box::new(arg)};
+                    }
+                    term
+                },
+                None => return Err(ReadbackError::UnknownKind{node: addr(next), kind})
+            }
+        })
     }
 
     // A hashmap linking ports to binder names. Those ports have names:
     // Link 1 of a con node (λ), ports 1 and 2 of a fan node (let).
-    let mut binder_name = HashMap::new();
+    let mut binder_name = BTreeMap::new();
 
     // Lets aren't scoped. We find them when we read one of the variables
-    // introduced by them. Thus, we must store the lets we find to read later.
-    // We have a vec for .pop(). and a set to avoid storing duplicates.
-    let mut lets_vec = Vec::new();
-    let mut lets_set = HashSet::new();
+    // introduced by them. Thus, we must store the lets we find to read later,
+    // in a vec for .pop(), and a set to avoid storing duplicates (see `read`).
+
+    // The binder names kept: those of binders still in the net, that no other binder or free
+    // variable has.
+    let live = |at : &u32| !net.reuse.contains(&addr(*at)) && match kind(net, addr(*at)) {
+        CON => port(*at) == PORT_AUX1,
+        FAN => true,
+        _ => false
+    };
+    let mut kept = BinderNames::new();
+    for (at, nam) in names {
+        let clash = names.iter().any(|(other, same)| other != at && same == nam) || free.values().any(|other| other == nam);
+        if live(at) && nam != b"_" && nam != b"-" && !clash {
+            kept.insert(*at, nam.clone());
+        }
+    }
 
-    // Reads the main term from the net
-    let mut main = read_term(net, enter(net, 0), &mut binder_name, &mut lets_vec, &mut lets_set);
+    // The indices of free and kept names that binder names could clash with.
+    let mut idxs : Vec<u32> = free.values().chain(kept.values())
+        .filter(|nam| !nam.is_empty() && nam.iter().all(|chr| chr.is_ascii_lowercase()))
+        .map(|nam| name_idx(nam))
+        .collect();
+    idxs.sort_unstable();
+    idxs.dedup();
+    let mut taken = Taken { idxs, kept, used: None };
 
-    // Reads let founds by starting the read_term function from their 0 ports.
-    while lets_vec.len() > 0 {
-        let dup = lets_vec.pop().unwrap();
-        let val = read_term(net, enter(net,link(dup,0)), &mut binder_name, &mut lets_vec, &mut lets_set);
-        let fst = name_of(net, link(dup,1), &mut binder_name);
-        let snd = name_of(net, link(dup,2), &mut binder_name);
-        let val = Box::new(val);
-        let nxt = Box::new(main);
-        main = Dup{fst, snd, val, nxt};
+    // Reads the main term from the net, then the lets found, from their 0 ports.
+    let read = |taken : &Taken, binder_name : &mut BTreeMap<u32, Vec<u8>>| -> Result<Term, ReadbackError> {
+        let (mut lets_vec, mut lets_set) = (Vec::new(), BTreeSet::new());
+        let mut main = read_term(net, enter(net, 0), binder_name, &mut lets_vec, &mut lets_set, free, taken)?;
+        while let Some(dup) = lets_vec.pop() {
+            let val = read_term(net, enter(net, link(dup, PORT_MAIN)), binder_name, &mut lets_vec, &mut lets_set, free, taken)?;
+            let fst = name_of(net, link(dup, PORT_AUX1), binder_name, taken);
+            let snd = name_of(net, link(dup, PORT_AUX2), binder_name, taken);
+            let val = Box::new(val);
+            let nxt = Box::new(main);
+            main = Dup{fst, snd, val, nxt};
+        }
+        Ok(main)
+    };
+    let main = read(&taken, &mut binder_name)?;
+
+    // A binder whose variable is never read, like one tied to a let whose copies are all erased,
+    // got a name all the same. If there's one, the net is read again, with those named `-` (as
+    // `to_net` would have erased them), so reading the term back a second time gives it again.
+    let mut counts = BTreeMap::new();
+    occurrences(&main, &mut counts);
+    let mut bound = BTreeSet::new();
+    binders(&main, &mut bound);
+    if bound.iter().all(|nam| is_wildcard(nam) || counts.contains_key(nam)) {
+        return Ok(main);
     }
-    main
+    let used = binder_name.iter().chain(taken.kept.iter()).filter(|(_, nam)| counts.contains_key(*nam)).map(|(port, _)| *port).collect();
+    taken.used = Some(used);
+    read(&taken, &mut BTreeMap::new())
 }
 
 // Reduces an Abstract Calculus term through Interaction Combinators.
-pub fn reduce(term : &Term) -> Term {
-    let mut net : Net = to_net(&term);
-    ::net::reduce(&mut net);
-    from_net(&net)
+pub fn reduce(term : &Term) -> Result<Term, SicError> {
+    reduce_to_normal_form(term).map(|(norm, _, _)| norm)
+}
+
+// Reduces a term like `reduce`, also returning the net in normal form and the stats, so the net
+// can be inspected without encoding the term again.
+pub fn reduce_to_normal_form(term : &Term) -> Result<(Term, Net, Stats), SicError> {
+    let mut net : Net = to_net(term)?;
+    let stats = ::net::reduce(&mut net);
+    let norm = from_net(&net)?;
+    Ok((norm, net, stats))
+}
+
+// Reduction as methods. Free variables are allowed, and kept as they are (see `to_net_open`).
+impl Term {
+    // Reduces the term to normal form.
+    pub fn reduce(&self) -> Result<Term, SicError> {
+        self.reduce_with_stats().map(|(norm, _)| norm)
+    }
+
+    // Reduces the term to normal form, also returning the stats.
+    pub fn reduce_with_stats(&self) -> Result<(Term, Stats), SicError> {
+        let (mut net, free) = to_net_open(self)?;
+        let stats = ::net::reduce(&mut net);
+        Ok((from_net_open(&net, &free)?, stats))
+    }
+}
+
+// Evaluates a program, reducing its net with at most `max_rewrites` rewrites. If `input` is given,
+// `code` holds only definitions and `input` is the main term. Returns the normal form and stats.
+pub fn evaluate(code : &Str, input : Option<&Str>, max_rewrites : u32) -> Result<(Term, Stats), SicError> {
+    let mut code = code.to_vec();
+    if let Some(input) = input {
+        code.push(b'\n');
+        code.extend_from_slice(input);
+    }
+    let mut net = to_net(&from_string(&code)?)?;
+    let stats = reduce_limited(&mut net, max_rewrites)?;
+    Ok((from_net(&net)?, stats))
 }
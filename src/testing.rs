@@ -0,0 +1,185 @@
+// Checks of the library's invariants, for the tests of this crate and of crates built on it.
+// Enabled by the `testing` feature. This module is a stable API: the names and signatures of its
+// functions, and what each one checks, only change with a major version. Failure messages aren't
+// part of it.
+//
+// Each check comes in two forms: `check_*` returns a description of the first violation found, for
+// tests that go over a corpus and report every failure, and `assert_*` panics with it. Messages
+// include the printed terms involved and, for reductions, the stats.
+
+use std::collections::HashSet;
+use net::*;
+use term::*;
+use error::LimitError;
+
+// Parses a program, panicking with the parse error.
+pub fn term(code : &str) -> Term {
+    match from_string(code.as_bytes()) {
+        Ok(term) => term,
+        Err(err) => panic!("{}\n  in: {}", err, code)
+    }
+}
+
+fn show(term : &Term) -> String {
+    String::from_utf8_lossy(&to_string(term)).into_owned()
+}
+
+fn show_stats(stats : &Stats) -> String {
+    format!("{} rewrites, {} nodes at most", stats.rules, stats.max_nodes)
+}
+
+fn encode(term : &Term) -> Result<Net, String> {
+    to_net(term).map_err(|err| format!("term can't be encoded: {}\n  term: {}", err, show(term)))
+}
+
+fn read_back(net : &Net, term : &Term) -> Result<Term, String> {
+    from_net(net).map_err(|err| format!("net can't be read back: {}\n  term: {}", err, show(term)))
+}
+
+// Checks that printing a term and parsing it back gives an alpha-equivalent term.
+pub fn check_round_trip_parse(term : &Term) -> Result<(), String> {
+    let again = from_string(&to_string(term))
+        .map_err(|err| format!("printed term doesn't parse: {}\n  printed: {}", err, show(term)))?;
+    if !alpha_eq(term, &again) {
+        return Err(format!("printed term parses differently\n  term:     {}\n  reparsed: {}", show(term), show(&again)));
+    }
+    Ok(())
+}
+
+pub fn assert_round_trip_parse(term : &Term) {
+    if let Err(msg) = check_round_trip_parse(term) {
+        panic!("{}", msg);
+    }
+}
+
+// Checks that encoding a term as a net and reading it back, without reducing, gives an
+// alpha-equivalent term. Readback puts every `=` at the top of the term, so this is meant for
+// terms already laid out that way, like the normal forms `reduce` returns.
+pub fn check_round_trip_net(term : &Term) -> Result<(), String> {
+    let again = read_back(&encode(term)?, term)?;
+    if !alpha_eq(term, &again) {
+        return Err(format!("term reads back differently\n  term:      {}\n  read back: {}", show(term), show(&again)));
+    }
+    Ok(())
+}
+
+pub fn assert_round_trip_net(term : &Term) {
+    if let Err(msg) = check_round_trip_net(term) {
+        panic!("{}", msg);
+    }
+}
+
+// Finds the active pair with the lowest address, skipping freed nodes, the root, and inert pairs.
+fn active_pair(net : &Net) -> Option<(u32, u32)> {
+    let freed : HashSet<u32> = net.reuse.iter().cloned().collect();
+    (1..(net.nodes.len() / NODE_SIZE) as u32)
+        .filter(|node| !freed.contains(node))
+        .map(|node| (node, enter(net, link(node, PORT_MAIN))))
+        .filter(|(node, other)| has_rule(kind(net, *node), kind(net, addr(*other))))
+        .find(|(node, other)| port(*other) == PORT_MAIN && addr(*other) != 0 && addr(*other) > *node)
+        .map(|(node, other)| (node, addr(other)))
+}
+
+// Rewrites the active pair `x`, `y` the textbook way, independently of `net::rewrite`: nodes of
+// the same kind annihilate, wiring their auxiliary ports together pairwise; nodes of different
+// kinds commute, each being replaced by two copies of the other.
+fn interact(net : &mut Net, x : u32, y : u32) {
+    let old = [link(x, PORT_AUX1), link(x, PORT_AUX2), link(y, PORT_AUX1), link(y, PORT_AUX2)];
+    let next : Vec<Link> = old.iter().map(|port| enter(net, *port)).collect();
+    let inner = |port : Link| old.iter().position(|old| *old == port);
+    if kind(net, x) == kind(net, y) {
+        // A wire entering port `k` leaves through its partner on the other node, and may loop
+        // back into the pair before reaching the outside.
+        let partner = |k : usize| (k + 2) % 4;
+        for k in 0..4 {
+            if inner(next[k]).is_none() {
+                let mut out = next[partner(k)];
+                while let Some(j) = inner(out) {
+                    out = next[partner(j)];
+                }
+                connect(net, next[k], out);
+            }
+        }
+    } else {
+        let (kx, ky) = (kind(net, x), kind(net, y));
+        let copies = [new_node(net, ky), new_node(net, ky), new_node(net, kx), new_node(net, kx)];
+        for i in 0..2 {
+            for j in 0..2 {
+                connect(net, link(copies[i], j as u32 + 1), link(copies[2 + j], i as u32 + 1));
+            }
+        }
+        for k in 0..4 {
+            let out = match inner(next[k]) {
+                Some(j) => link(copies[j], PORT_MAIN),
+                None => next[k]
+            };
+            connect(net, link(copies[k], PORT_MAIN), out);
+        }
+    }
+    net.reuse.push(x);
+    net.reuse.push(y);
+}
+
+// A reducer to check the others against: it rewrites the active pair with the lowest address,
+// wherever it is, until none is left. It's slow, and it may not terminate where `net::reduce`
+// does, since it also reduces garbage; so it gives up after `max_rules` rewrites.
+pub fn reduce_reference(net : &mut Net, max_rules : u32) -> Result<Stats, LimitError> {
+    let mut stats = Stats { max_nodes: node_count(net), ..Stats::default() };
+    while let Some((x, y)) = active_pair(net) {
+        if stats.rules == max_rules {
+            return Err(LimitError::Rewrites(max_rules));
+        }
+        stats.count_pair(kind(net, x), kind(net, y));
+        interact(net, x, y);
+        stats.rules += 1;
+        stats.loops += 1;
+        stats.max_nodes = stats.max_nodes.max(node_count(net));
+    }
+    Ok(stats)
+}
+
+// Checks that `net::reduce` and `reduce_reference` give alpha-equivalent normal forms, each within
+// `fuel` rewrites.
+pub fn check_reduction_matches_reference(term : &Term, fuel : u32) -> Result<(), String> {
+    let reference = |net : &mut Net| reduce_reference(net, fuel);
+    let lazy = |net : &mut Net| reduce_limited(net, fuel);
+    check_confluent(term, &[("net::reduce", &lazy), ("reference", &reference)])
+}
+
+pub fn assert_reduction_matches_reference(term : &Term, fuel : u32) {
+    if let Err(msg) = check_reduction_matches_reference(term, fuel) {
+        panic!("{}", msg);
+    }
+}
+
+// A named way of reducing a net, as `check_confluent` takes them. It may fail, for example by
+// running out of rewrites.
+pub type Strategy<'a> = (&'a str, &'a dyn Fn(&mut Net) -> Result<Stats, LimitError>);
+
+// Checks that reducing a term's net with each of the named strategies gives alpha-equivalent
+// normal forms. A strategy that fails fails the check.
+pub fn check_confluent(term : &Term, strategies : &[Strategy]) -> Result<(), String> {
+    let net = encode(term)?;
+    let mut results : Vec<(&str, Term, Stats)> = Vec::new();
+    for (name, strategy) in strategies {
+        let mut net = net.clone();
+        let stats = strategy(&mut net).map_err(|err| format!("{} failed: {}\n  term: {}", name, err, show(term)))?;
+        results.push((*name, read_back(&net, term)?, stats));
+    }
+    if let Some((_, first, _)) = results.first() {
+        if results.iter().any(|(_, norm, _)| !alpha_eq(first, norm)) {
+            let mut msg = format!("strategies disagree\n  term: {}", show(term));
+            for (name, norm, stats) in &results {
+                msg.push_str(&format!("\n  {} ({}): {}", name, show_stats(stats), show(norm)));
+            }
+            return Err(msg);
+        }
+    }
+    Ok(())
+}
+
+pub fn assert_confluent(term : &Term, strategies : &[Strategy]) {
+    if let Err(msg) = check_confluent(term, strategies) {
+        panic!("{}", msg);
+    }
+}
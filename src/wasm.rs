@@ -0,0 +1,37 @@
+// Bindings for running the evaluator in a browser, enabled by the `wasm` feature.
+
+use std::panic;
+use wasm_bindgen::prelude::*;
+use js_sys::{Object, Reflect};
+use term;
+
+fn set(obj : &Object, key : &str, val : JsValue) {
+    Reflect::set(obj, &JsValue::from_str(key), &val).unwrap();
+}
+
+// Evaluates `source`, with `input` as the main term if given. Returns an object with `result`
+// (the normal form, or null), `stats` (rules, loops and max_nodes, or null) and `error` (a
+// message, or null). Panics are caught and reported as errors when the target supports it.
+#[wasm_bindgen]
+pub fn evaluate(source : &str, input : Option<String>, max_rewrites : u32) -> JsValue {
+    let out = Object::new();
+    let run = panic::catch_unwind(|| {
+        term::evaluate(source.as_bytes(), input.as_ref().map(|input| input.as_bytes()), max_rewrites)
+    });
+    let (result, stats, error) = match run {
+        Ok(Ok((norm, stats))) => {
+            let obj = Object::new();
+            set(&obj, "rules", JsValue::from(stats.rules));
+            set(&obj, "loops", JsValue::from(stats.loops));
+            set(&obj, "max_nodes", JsValue::from(stats.max_nodes));
+            let norm = String::from_utf8_lossy(&term::to_string(&norm)).into_owned();
+            (JsValue::from(norm), obj.into(), JsValue::NULL)
+        },
+        Ok(Err(err)) => (JsValue::NULL, JsValue::NULL, JsValue::from(err.to_string())),
+        Err(_) => (JsValue::NULL, JsValue::NULL, JsValue::from("internal error: the evaluator panicked"))
+    };
+    set(&out, "result", result);
+    set(&out, "stats", stats);
+    set(&out, "error", error);
+    out.into()
+}
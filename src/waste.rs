@@ -0,0 +1,128 @@
+// Measures of the work a reduction spends on what it throws away. Affine programs discard
+// arguments, and a net can copy or erase a structure before it turns out not to be needed.
+//
+// The measure is an approximation, by tagging: each node a commutation makes or moves (the two
+// copies and the two nodes it rewired) is tagged with that rewrite. A rewrite is counted as wasted
+// if any node tagged with it is thrown away: met by an ERA later, or left in use but out of the
+// root's reach when the reduction ends. So copying a structure of which one copy is dropped counts
+// each copying step as wasted, though it also made the copy that was kept, and erasing a structure
+// counts each erasing step. Annihilations make nothing, so they're never counted, even when they
+// happened in a part of the net thrown away later; the lazy reducer only rewrites pairs the root
+// reaches, so that's rare, and most of its waste is copies and erasures, which are counted.
+
+use alloc::fmt;
+use prelude::*;
+use net::*;
+
+// The waste of a reduction, as `reduce_with_waste` measures it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Waste {
+    // Nodes freed by annihilations of two ERAs, and by annihilations of other kinds.
+    pub erased: u32,
+    pub annihilated: u32,
+    // The rewrites performed, and how many of them were spent on nodes that were thrown away.
+    pub rules: u32,
+    pub wasted: u32
+}
+
+impl Waste {
+    // The share of the rewrites that were wasted, as a percentage (0 without any rewrites).
+    pub fn percent(&self) -> f64 {
+        if self.rules == 0 { 0.0 } else { 100.0 * self.wasted as f64 / self.rules as f64 }
+    }
+}
+
+// A summary for people, as `sic --waste` prints it.
+impl fmt::Display for Waste {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wasted: {:.1}% ({} of {} rewrites), freed: {} by erasure, {} by annihilation",
+            self.percent(), self.wasted, self.rules, self.erased, self.annihilated)
+    }
+}
+
+// A table kept beside a net during a reduction, giving each node the rewrite that last made or
+// moved it, if any.
+#[derive(Clone, Debug, Default)]
+pub struct WasteTrace {
+    made_by: Vec<Option<u32>>,
+    // Whether each rewrite, by number, was wasted.
+    wasted: Vec<bool>,
+    erased: u32,
+    annihilated: u32
+}
+
+impl WasteTrace {
+    pub fn new() -> WasteTrace {
+        WasteTrace::default()
+    }
+
+    // The rewrite a node is tagged with, if any.
+    fn tag(&self, node : u32) -> Option<u32> {
+        self.made_by.get(node as usize).cloned().flatten()
+    }
+
+    fn set_tag(&mut self, node : u32, tag : Option<u32>) {
+        if self.made_by.len() <= node as usize {
+            self.made_by.resize(node as usize + 1, None);
+        }
+        self.made_by[node as usize] = tag;
+    }
+
+    // Updates the table after `rewrite` was given `x` and `y` and made `copies`.
+    pub fn rewritten(&mut self, net : &Net, x : u32, y : u32, copies : Option<(u32, u32)>) {
+        let rule = self.wasted.len() as u32;
+        self.wasted.push(false);
+        let erasing = kind(net, x) == ERA || kind(net, y) == ERA;
+        for node in [x, y] {
+            match self.tag(node) {
+                Some(tag) if erasing => self.wasted[tag as usize] = true,
+                _ => {}
+            }
+        }
+        match copies {
+            Some((a, b)) => {
+                for node in [x, y, a, b] {
+                    self.set_tag(node, Some(rule));
+                }
+            },
+            None => {
+                if erasing {
+                    self.erased += 2;
+                } else {
+                    self.annihilated += 2;
+                }
+                self.set_tag(x, None);
+                self.set_tag(y, None);
+            }
+        }
+    }
+
+    // The waste of the reduction, counting the nodes the root can't reach in the net it ended
+    // with as thrown away.
+    pub fn finish(&self, net : &Net) -> Waste {
+        let mut freed = vec![false; net.nodes.len() / NODE_SIZE];
+        for node in &net.reuse {
+            freed[*node as usize] = true;
+        }
+        let mut wasted = self.wasted.clone();
+        for (node, depth) in depths(net).iter().enumerate() {
+            match self.tag(node as u32) {
+                Some(tag) if depth.is_none() && !freed[node] => wasted[tag as usize] = true,
+                _ => {}
+            }
+        }
+        Waste {
+            erased: self.erased,
+            annihilated: self.annihilated,
+            rules: wasted.len() as u32,
+            wasted: wasted.iter().filter(|wasted| **wasted).count() as u32
+        }
+    }
+}
+
+// Reduces a net like `net::reduce`, measuring the work it wasted.
+pub fn reduce_with_waste(net : &mut Net) -> (Stats, Waste) {
+    let mut trace = WasteTrace::new();
+    let stats = reduce_steps(net, u32::MAX, 0, &mut |_| true, &mut |net, x, y, copies| trace.rewritten(net, x, y, copies)).0;
+    (stats, trace.finish(net))
+}
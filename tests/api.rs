@@ -0,0 +1,63 @@
+extern crate sic;
+
+use sic::net;
+use sic::net::Kind;
+use sic::term::*;
+
+#[test]
+fn evaluation_through_the_public_api() {
+    let code = b":two \\s \\z = s0 s1 s /s0 /s1 z :id \\x x //two id \\y y";
+    let term = from_string(code).unwrap();
+    let mut net = to_net(&term).unwrap();
+    let stats = net::reduce(&mut net);
+    let norm = from_net(&net).unwrap();
+    assert_eq!(to_string(&norm), b"\\a a".to_vec());
+    assert!(stats.rules > 0);
+    assert_eq!(norm, reduce(&term).unwrap());
+}
+
+#[test]
+fn evaluation_with_an_input_and_a_limit() {
+    let defs = b":two \\s \\z = s0 s1 s /s0 /s1 z :id \\x x";
+    let (norm, stats) = evaluate(defs, Some(b"//two id \\y y"), 1000).unwrap();
+    assert_eq!(to_string(&norm), b"\\a a".to_vec());
+    assert!(stats.rules > 0);
+    assert!(evaluate(defs, Some(b"//two id \\y y"), 1).is_err());
+}
+
+#[test]
+fn nets_through_methods() {
+    let mut net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let app = net::addr(net.enter(0));
+    assert_eq!(net.kind(app), Kind::Con);
+    let lam = net::addr(net.enter(net::link(app, 0)));
+    assert_eq!(net.kind(lam), Kind::Con);
+    net.connect(net::link(app, 0), net::link(app, 1));
+    assert_eq!(net.enter(net::link(app, 1)), net::link(app, 0));
+    assert_eq!(net.enter(net::link(app, 0)), net::enter(&net, net::link(app, 0)));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "not a port")]
+fn methods_reject_the_kind_word() {
+    let net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    net.enter(net::link(1, 3));
+}
+
+#[test]
+fn kinds_convert_to_and_from_words() {
+    let kinds = [Kind::Era, Kind::Con, Kind::Fan, Kind::Free, Kind::Root, Kind::Ctr{idx: 0}, Kind::Ctr{idx: 7}];
+    let words = [net::ERA, net::CON, net::FAN, net::FREE, net::ROOT, net::CTR, net::CTR + 7];
+    for (kind, word) in kinds.iter().zip(words.iter()) {
+        assert_eq!(u32::from(*kind), *word);
+        assert_eq!(Kind::from(*word), *kind);
+    }
+    let mut net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    for kind in &kinds {
+        let node = net.new_node(*kind);
+        assert_eq!(net.kind(node), *kind);
+        assert_eq!(net::kind(&net, node), u32::from(*kind));
+    }
+    assert_eq!(net.kind(0), Kind::Root);
+}
@@ -0,0 +1,58 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::ascii::*;
+use sic::net::*;
+use sic::term::{from_string, to_net};
+
+// Checks the drawing of a term's net against the golden file of that name, under
+// `tests/fixtures/ascii`.
+fn golden(code : &str, name : &str) {
+    let net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    let expected = std::fs::read_to_string(format!("tests/fixtures/ascii/{}.txt", name)).unwrap();
+    assert_eq!(to_ascii(&net).unwrap(), expected, "drawing of {}", code);
+}
+
+#[test]
+fn nets_match_their_drawings() {
+    golden("\\x x", "identity");
+    golden("\\x \\y x", "erase");
+    // The two CONs of the redex are drawn with double lines.
+    golden("/\\x x \\y y", "application");
+}
+
+#[test]
+fn every_node_and_wire_is_drawn() {
+    let mut net = to_net(&from_string(b"= a b \\x x |a b").unwrap()).unwrap();
+    let text = to_ascii(&net).unwrap();
+    for node in ["ROOT 0", "FAN 1", "CON 2", "FAN 3"] {
+        assert_eq!(text.matches(node).count(), 1, "{} in\n{}", node, text);
+    }
+    // Each wire meets the boxes at both of its ends; the CON's loop is drawn, the root's isn't.
+    assert_eq!(text.matches(['┴', '┬', '╧', '╤']).count(), 2 * 5);
+    assert_eq!(text.matches('╔').count(), 2);
+    reduce(&mut net);
+    let text = to_ascii(&net).unwrap();
+    assert!(!text.contains('╔'));
+    assert!(text.lines().all(|line| !line.ends_with(' ')));
+}
+
+#[test]
+fn large_nets_are_refused() {
+    let code = format!("{}*", "|* ".repeat(MAX_ASCII_NODES as usize));
+    let net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    assert!(node_count(&net) > MAX_ASCII_NODES);
+    assert_eq!(to_ascii(&net), None);
+    let dir = std::env::temp_dir().join("sic-ascii-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("large.sic");
+    std::fs::write(&file, &code).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["net", "--ascii"]).arg(&file).output().unwrap();
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("too many to draw as text (at most 40); draw it with --dot instead"));
+    // Small nets are printed as `to_ascii` draws them.
+    std::fs::write(&file, "\\x x").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["net", "--ascii"]).arg(&file).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), std::fs::read_to_string("tests/fixtures/ascii/identity.txt").unwrap());
+}
@@ -0,0 +1,19 @@
+use std::process::Command;
+
+#[test]
+fn bench_reports_one_row_per_program() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["bench", "tests/fixtures/bench"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines : Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].contains("rules") && lines[0].contains("max_nodes") && lines[0].contains("memory"));
+    assert!(lines[0].contains("inline") && lines[0].contains("graft"));
+    assert!(lines[1].starts_with("church.sic"));
+    assert!(lines[2].starts_with("pairs.sic"));
+    assert!(lines[3].starts_with("ski.sic"));
+    assert!(lines[4].starts_with("total"));
+    assert_eq!(lines.len(), 5);
+}
@@ -0,0 +1,105 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_cached, eval_str, EvalOptions};
+use sic::eval::cache_path;
+use sic::module::*;
+
+// `b` references `a`, `d` references `c`, and the main term references `b` and `d`.
+fn program(c : &str) -> String {
+    format!(":a \\x x\n:b \\y /a y\n:c {}\n:d /c \\w w\n//#Pair /b \\p p d", c)
+}
+
+#[test]
+fn only_changed_definitions_are_recompiled() {
+    let opts = EvalOptions::default();
+    let mut cache = NetCache::default();
+    let first = eval_cached(&program("\\z \\q z"), &opts, &mut cache).unwrap();
+    assert_eq!(first, eval_str(&program("\\z \\q z"), &opts).unwrap());
+    assert_eq!((cache.hits, cache.misses), (0, 4));
+    // Editing `c` recompiles it and `d`, which references it. `b` is found, with `a` grafted in.
+    let second = eval_cached(&program("\\z \\q q"), &opts, &mut cache).unwrap();
+    assert_eq!(second, eval_str(&program("\\z \\q q"), &opts).unwrap());
+    assert_eq!((cache.hits, cache.misses), (1, 6));
+    // Renaming binders changes nothing.
+    eval_cached(&program("\\u \\t t"), &opts, &mut cache).unwrap();
+    assert_eq!((cache.hits, cache.misses), (3, 6));
+}
+
+#[test]
+fn normal_forms_are_kept() {
+    let mut cache = NetCache::default();
+    let module = parse_module(program("\\z \\q z").as_bytes()).unwrap();
+    let norm = module.normal_form_cached(b"d", &mut cache).unwrap();
+    assert_eq!(sic::term::to_string(&norm), b"\\- \\a a".to_vec());
+    let misses = cache.misses;
+    assert_eq!(module.normal_form_cached(b"d", &mut cache).unwrap(), norm);
+    assert_eq!((cache.hits, cache.misses), (1, misses));
+}
+
+#[test]
+fn stats_show_the_cache() {
+    let file = std::env::temp_dir().join(format!("sic_cache_{}.sic", std::process::id()));
+    std::fs::write(&file, program("\\z \\q z")).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).arg("--stats").output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("cache: 0 hits, 4 misses"));
+}
+
+const TWO : &str = ":two \\s \\z = s0 s1 s /s0 /s1 z\n//two \\x x \\y y";
+
+// A new directory for a test, numbered by the process so runs side by side don't share it.
+fn empty_dir(nam : &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", nam, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn normal_forms_are_kept_on_disk() {
+    let opts = EvalOptions { stats: true, cache_dir: Some(empty_dir("sic_disk_kept")), ..EvalOptions::default() };
+    let first = eval_str(TWO, &opts).unwrap();
+    assert!(!first.cached && first.stats.as_ref().unwrap().rules > 0);
+    let second = eval_str(TWO, &opts).unwrap();
+    assert!(second.cached && second.completed);
+    assert_eq!(second.stats.unwrap().rules, 0);
+    assert_eq!((second.term, second.printed), (first.term, first.printed));
+    // Stats are only given when asked for, kept normal form or not.
+    let quiet = eval_str(TWO, &EvalOptions { stats: false, ..opts.clone() }).unwrap();
+    assert!(quiet.cached && quiet.stats.is_none());
+    // A limit the reduction went past isn't met by the kept normal form.
+    let limited = eval_str(TWO, &EvalOptions { max_rewrites: Some(1), ..opts.clone() }).unwrap();
+    assert!(!limited.cached && !limited.completed);
+}
+
+#[test]
+fn unreadable_files_are_reduced_again() {
+    let opts = EvalOptions { cache_dir: Some(empty_dir("sic_disk_unreadable")), ..EvalOptions::default() };
+    let first = eval_str(TWO, &opts).unwrap();
+    let path = cache_path(TWO, &opts).unwrap().unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    std::fs::write(&path, bytes).unwrap();
+    let second = eval_str(TWO, &opts).unwrap();
+    assert!(!second.cached);
+    assert_eq!(second.term, first.term);
+    assert!(eval_str(TWO, &opts).unwrap().cached);
+}
+
+#[test]
+fn cache_dir_from_the_command_line() {
+    let dir = empty_dir("sic_disk_cli");
+    let file = std::env::temp_dir().join(format!("sic_disk_cli_{}.sic", std::process::id()));
+    std::fs::write(&file, TWO).unwrap();
+    let run = |args : &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(args).output().unwrap();
+        String::from_utf8(out.stdout).unwrap()
+    };
+    let eval = |flags : &[&str]| run(&[&[file.to_str().unwrap(), "-s", "--cache-dir", dir.to_str().unwrap()], flags].concat());
+    assert!(eval(&[]).contains("cached: false"));
+    let second = eval(&[]);
+    assert!(second.starts_with("\\a a\nrules: 0,") && second.contains("cached: true"));
+    assert!(!eval(&["--no-cache"]).contains("cached:"));
+    assert_eq!(run(&["cache", "clear", dir.to_str().unwrap()]), "removed 1 normal form(s)\n");
+    assert!(eval(&[]).contains("cached: false"));
+}
@@ -0,0 +1,74 @@
+extern crate sic;
+
+use std::collections::HashMap;
+use sic::term::*;
+
+fn hash(code : &str) -> u64 {
+    canonical_hash(&reduce(&from_string(code.as_bytes()).unwrap()).unwrap())
+}
+
+const ADD : &str = "
+    :two \\s \\z = s0 s1 s /s0 /s1 z
+    :three \\s \\z = s0 s1 s = s2 s3 s0 /s1 /s2 /s3 z
+    :add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+    //add two three";
+
+const ADD_RENAMED : &str = "
+    :dos \\f \\x = f0 f1 f /f0 /f1 x
+    :tres \\f \\x = f0 f1 f = f2 f3 f0 /f1 /f2 /f3 x
+    :plus \\a \\b \\f \\x = f0 f1 f //a f0 //b f1 x
+    //plus dos tres";
+
+const ADD_TWO : &str = "
+    :two \\s \\z = s0 s1 s /s0 /s1 z
+    :add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+    //add two two";
+
+#[test]
+fn alpha_equivalent_programs_hash_equal() {
+    assert_eq!(hash(ADD), hash(ADD_RENAMED));
+    assert_eq!(hash("\\x x"), hash("\\y y"));
+    let norm = canonicalize(&from_string(b"\\p \\q /q p").unwrap());
+    assert_eq!(to_string(&norm), b"\\a \\b /b a".to_vec());
+}
+
+#[test]
+fn shadowed_names_are_bound_by_the_last_binder() {
+    let canon = |code : &str| canonicalize(&from_string(code.as_bytes()).unwrap());
+    assert_eq!(canon("\\x \\x x"), canon("\\x \\y y"));
+    assert_eq!(to_string(&canon("\\x \\x x")), b"\\a \\b b".to_vec());
+    assert_ne!(canon("\\x \\x x"), canon("\\x \\y x"));
+    assert_eq!(hash("\\x \\x x"), hash("\\x \\y y"));
+    assert_eq!(canon("= x y \\z z \\x \\y | x y"), canon("= p q \\z z \\a \\b | a b"));
+}
+
+#[test]
+fn alpha_equivalent_terms_canonicalize_equal() {
+    let terms = [
+        "\\f \\x = f0 f1 f /f0 /f1 x",
+        "\\s \\z = a b s /a /b z",
+        "\\b \\a = b0 b1 b /b0 /b1 a"
+    ];
+    // Names bound twice, whose variables the last binder binds.
+    let shadowed = ["\\x \\x x", "\\y \\x x", "\\x \\y y"];
+    let canon = |terms : &[&str]| -> Vec<Term> { terms.iter().map(|code| canonicalize(&from_string(code.as_bytes()).unwrap())).collect() };
+    let (canon, shadowed) = (canon(&terms), canon(&shadowed));
+    assert!(canon.windows(2).all(|pair| pair[0] == pair[1]));
+    assert!(shadowed.windows(2).all(|pair| pair[0] == pair[1]));
+    // So each group is one key of a memo table.
+    let table : HashMap<Term, usize> = canon.into_iter().chain(shadowed).zip(0..).collect();
+    assert_eq!(table.len(), 2);
+    assert_ne!(canonicalize(&from_string(b"\\x \\y x").unwrap()), canonicalize(&from_string(b"\\x \\y y").unwrap()));
+}
+
+#[test]
+fn different_programs_hash_different() {
+    assert_ne!(hash(ADD), hash(ADD_TWO));
+    assert_ne!(hash("\\x x"), hash("\\x \\y x"));
+}
+
+#[test]
+fn hash_is_stable() {
+    assert_eq!(hash("\\x x"), 0x36600abd724a3c91);
+    assert_eq!(hash(ADD), 0x4bd9e0b93d40172e);
+}
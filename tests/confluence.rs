@@ -0,0 +1,70 @@
+// Property: reduction is confluent, so the lazy and the eager reducers reach the same normal form.
+
+extern crate proptest;
+extern crate sic;
+
+use proptest::prelude::*;
+use sic::term::{from_string, to_net, from_net, to_string};
+use sic::net::*;
+use sic::testing::reduce_reference;
+
+mod generate;
+use generate::*;
+
+// Rewrites a term's net may take before it's considered not to normalize.
+const FUEL : u32 = 2000;
+
+proptest! {
+    #[test]
+    fn lazy_and_eager_agree(raw in raw()) {
+        let net = to_net(&affine(&raw)).unwrap();
+        // Only terms whose whole net normalizes: `reduce_eager` reduces garbage too.
+        let mut full = net.clone();
+        let reference = reduce_reference(&mut full, FUEL);
+        prop_assume!(reference.is_ok());
+        let mut lazy = net.clone();
+        reduce(&mut lazy);
+        let mut eager = net.clone();
+        let stats = reduce_eager(&mut eager);
+        prop_assert!(nets_equal(&lazy, &eager), "{}", affine(&raw));
+        prop_assert!(nets_equal(&eager, &full));
+        prop_assert_eq!(stats.rules, reference.unwrap().rules);
+    }
+}
+
+#[test]
+fn eager_reduces_garbage() {
+    let mut lazy = to_net(&from_string(b"/\\x \\y y //\\f \\z /f z \\a a \\b b").unwrap()).unwrap();
+    let mut eager = lazy.clone();
+    let lazy_stats = reduce(&mut lazy);
+    let eager_stats = reduce_eager(&mut eager);
+    assert!(nets_equal(&lazy, &eager));
+    assert!(eager_stats.rules > lazy_stats.rules);
+    assert_eq!(to_string(&from_net(&eager).unwrap()), b"\\a a".to_vec());
+}
+
+// Vicious circles, nodes each pointing their principal port at the next one's auxiliary port,
+// have no active pair: reduction leaves them, rather than walking around them forever.
+#[test]
+fn vicious_circles_are_left() {
+    let net = to_net(&from_string(b"/\\x = a b x /a b \\y = c d y /c d").unwrap()).unwrap();
+    let mut full = net.clone();
+    assert!(reduce_reference(&mut full, FUEL).is_ok());
+    let mut lazy = net.clone();
+    reduce(&mut lazy);
+    let mut eager = net;
+    reduce_eager(&mut eager);
+    assert!(nets_equal(&lazy, &eager));
+    assert!(nets_equal(&eager, &full));
+}
+
+#[test]
+fn nets_differ() {
+    let id = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    let era = to_net(&from_string(b"\\x *").unwrap()).unwrap();
+    let app = to_net(&from_string(b"\\x \\y /x y").unwrap()).unwrap();
+    let flip = to_net(&from_string(b"\\x \\y /y x").unwrap()).unwrap();
+    assert!(nets_equal(&id, &id.clone()));
+    assert!(!nets_equal(&id, &era));
+    assert!(!nets_equal(&app, &flip));
+}
@@ -0,0 +1,50 @@
+extern crate sic;
+
+use sic::error::*;
+use sic::net::*;
+use sic::term::*;
+
+fn normal(src : &str) -> String {
+    let term = from_string(src.as_bytes()).unwrap();
+    String::from_utf8(to_string(&sic::term::reduce(&term).unwrap())).unwrap()
+}
+
+#[test]
+fn copied_lists_print_constructors() {
+    let src = ":dup \\l = a b l /\\p p //#Pair a b\n/dup //#Cons #Zero //#Cons /#Succ #Zero #Nil";
+    let list = "//#Cons #Zero //#Cons /#Succ #Zero #Nil";
+    assert_eq!(normal(src), format!("//#Pair {} {}", list, list));
+    assert_eq!(normal("/\\x * //#Cons #Nil #Nil"), "*");
+}
+
+#[test]
+fn applied_constructors_are_left_applied() {
+    // `#Succ` is registered with no fields, so it can't be applied in the source, but it can be
+    // once reduced: the application stays there.
+    assert_eq!(normal("/\\f //#Cons /f #Nil #Nil #Succ"), "//#Cons /#Succ #Nil #Nil");
+}
+
+#[test]
+fn arities_are_checked() {
+    let arity = |src : &str| match to_net(&from_string(src.as_bytes()).unwrap()) {
+        Err(AffinityError::Arity{arity, args, ..}) => Some((arity, args)),
+        _ => None
+    };
+    assert_eq!(arity("///#Cons #Nil #Nil #Nil"), Some((2, 3)));
+    assert_eq!(arity("//#Cons #Nil /#Nil #Nil"), Some((0, 1)));
+    assert_eq!(arity("//#Cons /#Cons #Nil #Nil"), Some((2, 1)));
+    assert_eq!(arity("//#Cons /#Wrap #Nil /#Wrap #Nil"), None);
+}
+
+#[test]
+fn nets_keep_their_constructors() {
+    let net = to_net(&from_string(b"//#Cons /#Wrap #Nil #Nil").unwrap()).unwrap();
+    assert_eq!(net.ctrs, vec![(b"#Cons".to_vec(), 2), (b"#Wrap".to_vec(), 1), (b"#Nil".to_vec(), 0)]);
+    assert_eq!(net_from_bytes(&net_to_bytes(&net)), Ok(net.clone()));
+    let mut renumbered = net.clone();
+    renumbered.ctrs.swap(0, 2);
+    assert!(!nets_equal(&net, &renumbered));
+    let mut unknown = net.clone();
+    unknown.ctrs.pop();
+    assert!(matches!(check_net(&unknown), Err(NetError::UnknownKind{..})));
+}
@@ -0,0 +1,49 @@
+extern crate sic;
+
+use sic::term::*;
+
+#[test]
+fn seeded_definitions_are_inlined() {
+    let mut ctx = Scope::with_definitions([(&b"id"[..], from_string(b"\\x x").unwrap())]);
+    let mut idx = 0;
+    let (rest, term) = parse_term(b"/id \\y y rest", &mut ctx, &mut idx, 0).unwrap();
+    assert_eq!(rest, b" rest");
+    assert_eq!(term.to_string(), "/\\id#0#x id#0#x \\y y");
+    assert_eq!(reduce(&term).unwrap().to_string(), "\\a a");
+    assert_eq!(ctx.depth(), 1);
+    // Another term parsed with the same counter gets copies of its own.
+    let (_, term) = parse_term(b"id", &mut ctx, &mut idx, 0).unwrap();
+    assert_eq!(term.to_string(), "\\id#1#x id#1#x");
+}
+
+#[test]
+fn binders_shadow_definitions() {
+    let mut ctx = Scope::with_definitions([(&b"id"[..], from_string(b"\\x x").unwrap())]);
+    let (_, term) = ctx.with_binding(b"id", None, |ctx| parse_term(b"/id id", ctx, &mut 0, 0)).unwrap();
+    assert_eq!(term.to_string(), "/id id");
+    assert!(ctx.find(b"id").is_some());
+}
+
+#[test]
+fn open_comments_and_error_offsets() {
+    let (rest, term) = parse_term(b"still a comment) \\x x", &mut Scope::new(), &mut 0, 1).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(term.to_string(), "\\x x");
+    let err = parse_term(b"/a", &mut Scope::new(), &mut 0, 0).unwrap_err();
+    assert_eq!(err.located(b"/a").index, 2);
+}
+
+#[test]
+fn scopes_return_to_their_depth() {
+    let mut ctx = Scope::with_definitions([(&b"id"[..], from_string(b"\\x x").unwrap())]);
+    parse_term(b"\\a \\b = c d a /id |c d", &mut ctx, &mut 0, 0).unwrap();
+    assert_eq!(ctx.depth(), 1);
+    parse_term(b":k \\x \\y x data B {#T; #F} match #T {#T: k; #F: id}", &mut ctx, &mut 0, 0).unwrap();
+    assert_eq!(ctx.depth(), 1);
+    // Even when parsing fails within binders.
+    assert!(parse_term(b"\\a \\b :k x x = x /a", &mut ctx, &mut 0, 0).is_err());
+    assert!(parse_term(b"\\a = b c a", &mut ctx, &mut 0, 0).is_err());
+    assert_eq!(ctx.depth(), 1);
+    assert_eq!(ctx.with_binding(b"x", None, |ctx| ctx.depth()), 2);
+    assert_eq!(ctx.depth(), 1);
+}
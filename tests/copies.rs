@@ -0,0 +1,73 @@
+extern crate sic;
+
+use sic::term::*;
+
+fn printed(code : &str) -> String {
+    String::from_utf8(to_string(&from_string(code.as_bytes()).unwrap())).unwrap()
+}
+
+#[test]
+fn nested_definitions_keep_names_short() {
+    fn longest(term : &Term) -> usize {
+        match term {
+            Term::Lam{nam, bod} => nam.len().max(longest(bod)),
+            Term::App{fun, arg} => longest(fun).max(longest(arg)),
+            Term::Par{fst: a, snd: b} | Term::Era{val: a, nxt: b} => longest(a).max(longest(b)),
+            Term::Dup{fst, snd, val, nxt} => fst.len().max(snd.len()).max(longest(val)).max(longest(nxt)),
+            Term::Var{nam} | Term::Ctr{nam} => nam.len(),
+            Term::Set => 0
+        }
+    }
+    let mut code = b":a0 \\x x\n".to_vec();
+    for idx in 1..100 {
+        code.extend_from_slice(format!(":a{} \\x = y z x /a{} //\\w w y z\n", idx, idx - 1).as_bytes());
+    }
+    code.extend_from_slice(b"/a99 \\v v");
+    let term = parse_checked(&code).unwrap();
+    // Nesting would have made names of about 700 bytes.
+    assert!(longest(&term) <= "a99#99999#x".len());
+    assert!(alpha_eq(&reduce(&term).unwrap(), &parse_checked(b"\\v v").unwrap()));
+}
+
+#[test]
+fn copies_of_inner_definitions_stay_apart() {
+    fn binders(term : &Term, names : &mut Vec<Vec<u8>>) {
+        match term {
+            Term::Lam{nam, bod} => {
+                names.push(nam.clone());
+                binders(bod, names);
+            },
+            Term::App{fun: a, arg: b} | Term::Par{fst: a, snd: b} | Term::Era{val: a, nxt: b} => {
+                binders(a, names);
+                binders(b, names);
+            },
+            Term::Dup{fst, snd, val, nxt} => {
+                names.push(fst.clone());
+                names.push(snd.clone());
+                binders(val, names);
+                binders(nxt, names);
+            },
+            Term::Var{..} | Term::Ctr{..} | Term::Set => {}
+        }
+    }
+    let code = b":outer :inner \\x x \\y = a b y //inner a /inner b\n//outer outer \\z z";
+    let term = parse_checked(code).unwrap();
+    let mut names = Vec::new();
+    binders(&term, &mut names);
+    // Each name has one suffix at most, like `inner#3#x`, and no two copies share one.
+    assert!(names.iter().all(|nam| nam.iter().filter(|chr| **chr == b'#').count() <= 2));
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
+    assert!(names.contains(&b"inner#3#x".to_vec()));
+}
+
+#[test]
+fn written_names_never_pass_for_copies() {
+    // A copy's name is kept apart from the same name written by hand.
+    assert_eq!(printed(":d \\x x\n\\d#0#x /d d#0#x"), "\\d#0#x /\\d#0#x' d#0#x' d#0#x");
+    // Inside a definition, it's namespaced like any other name, rather than renumbered.
+    assert_eq!(printed(":e \\a#1#b a#1#b\n/e e"), "/\\e#0#a#1#b e#0#a#1#b \\e#1#a#1#b e#1#a#1#b");
+    assert!(alpha_eq(&reduce(&from_string(b":d \\x x\n\\d#0#x /d d#0#x").unwrap()).unwrap(), &from_string(b"\\y y").unwrap()));
+}
@@ -0,0 +1,89 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::term::*;
+use sic::term::Term::*;
+use sic::module::*;
+use sic::error::AffinityError;
+
+const NUMS : &str = "
+:two \\s \\z = s0 s1 s /s0 /s1 z
+:three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z
+:add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+";
+
+fn app(fun : Term, arg : &str) -> Term {
+    App{fun: Box::new(fun), arg: Box::new(from_string(arg.as_bytes()).unwrap())}
+}
+
+// Normalizes a module with its main term replaced.
+fn run(module : &Module, main : Term) -> Term {
+    let module = Module { main: Some(main), ..module.clone() };
+    reduce(&from_string(&format_module(&module)).unwrap()).unwrap()
+}
+
+// Applies a numeral to constructors, directly and after the translation. The translated program
+// is given the identity as its continuation. Its value, a translated numeral, takes a successor
+// that takes a continuation, then a continuation, given the function of `z` it returns.
+fn both(main : &str) -> (Term, Term) {
+    let module = parse_module(format!("{}{}", NUMS, main).as_bytes()).unwrap();
+    let direct = run(&module, app(app(module.main.clone().unwrap(), "#S"), "#Z"));
+    let cps = cps_module(&module);
+    check_affine(&from_string(&format_module(&cps)).unwrap()).unwrap();
+    let value = app(cps.main.clone().unwrap(), "\\r r");
+    let translated = run(&cps, app(app(value, "\\x \\k /k /#S x"), "\\h //h #Z \\q q"));
+    (direct, translated)
+}
+
+#[test]
+fn church_arithmetic_keeps_its_answers() {
+    for main in ["//add two three", "//add three //add two two", "//add two \\s \\z z", "//add //add two two //add three three", "& two three"] {
+        let (direct, translated) = both(main);
+        assert!(alpha_eq(&direct, &translated), "{}: {} became {}", main, direct, translated);
+    }
+    let (_, translated) = both("//add two three");
+    assert_eq!(translated.to_string(), "/#S /#S /#S /#S /#S #Z");
+}
+
+#[test]
+fn copies_pairs_and_erasures() {
+    for code in ["= a b | #A #B //#P a b", "& two //#P #A #B", "//#P | #A #B *"] {
+        let direct = reduce(&from_string(format!("{}{}", NUMS, code).as_bytes()).unwrap()).unwrap();
+        let module = cps_module(&parse_module(format!("{}{}", NUMS, code).as_bytes()).unwrap());
+        check_affine(&from_string(&format_module(&module)).unwrap()).unwrap();
+        let translated = run(&module, app(module.main.clone().unwrap(), "\\r r"));
+        assert_eq!(canonicalize(&translated).to_string(), canonicalize(&direct).to_string(), "{}", code);
+    }
+}
+
+#[test]
+fn continuations_are_fresh() {
+    assert_eq!(cps(&from_string(b"\\x x").unwrap()).to_string(), "\\k0 /k0 \\x \\k1 /k1 x");
+    let term = from_string(b"\\k0 \\x1 \\g2 //k0 x1 g2").unwrap();
+    let translated = cps(&term);
+    check_affine(&translated).unwrap();
+    assert!(translated.to_string().starts_with("\\k1 /k1 \\k0 \\k2 /k2 \\x1 \\k3 /k3 \\g2 \\k4 "));
+}
+
+#[test]
+fn affinity_is_checked() {
+    let var = |nam : &str| Var{nam: nam.as_bytes().to_vec()};
+    let lam = |nam : &str, bod : Term| Lam{nam: nam.as_bytes().to_vec(), bod: Box::new(bod)};
+    assert_eq!(check_affine(&lam("x", Par{fst: Box::new(var("x")), snd: Box::new(var("x"))})), Err(AffinityError::Duplicated(b"x".to_vec())));
+    assert_eq!(check_affine(&Par{fst: Box::new(lam("x", var("x"))), snd: Box::new(lam("x", var("x")))}), Err(AffinityError::Duplicated(b"x".to_vec())));
+    assert_eq!(check_affine(&lam("x", var("y"))), Err(AffinityError::Unbound(b"y".to_vec())));
+    assert_eq!(check_affine(&from_string(b"\\_ \\_ = a - \\x x a").unwrap()), Ok(()));
+}
+
+#[test]
+fn command_line_prints_the_module() {
+    let dir = std::env::temp_dir().join("sic-cps-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("add.sic");
+    let code = format!("{}//add two three", NUMS);
+    std::fs::write(&file, &code).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["transform", "--cps"]).arg(&file).output().unwrap();
+    assert!(out.status.success());
+    let expected = format_module(&cps_module(&parse_module(code.as_bytes()).unwrap()));
+    assert_eq!(out.stdout, expected);
+}
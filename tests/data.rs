@@ -0,0 +1,86 @@
+extern crate sic;
+
+use sic::data::*;
+use sic::term::*;
+use sic::error::EncodeError;
+
+fn add() -> Term {
+    from_string(b"\\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z").unwrap()
+}
+
+#[test]
+fn numerals_round_trip() {
+    for n in 0..6 {
+        assert_eq!(nat_to(&nat_of(n)), Some(n));
+        assert_eq!(nat_to(&reduce(&nat_of(n)).unwrap()), Some(n));
+    }
+}
+
+#[test]
+fn fold_sums_a_list() {
+    let list = list_of(vec![nat_of(1), nat_of(2), nat_of(3)]);
+    let sum = reduce(&church_fold(add(), nat_of(0), list)).unwrap();
+    assert_eq!(nat_to(&sum), Some(6));
+}
+
+#[test]
+fn map_over_a_list() {
+    let wrap = from_string(b"\\x //#Box x #Tag").unwrap();
+    let list = list_of(vec![nat_of(1), nat_of(2), nat_of(3)]);
+    let cons = from_string(b"\\h \\t //#Cons h t").unwrap();
+    let nil = from_string(b"#Nil").unwrap();
+    let mapped = reduce(&app(app(church_map(wrap, list), cons), nil)).unwrap();
+    let boxed = from_string(b":one \\s \\z /s z
+        :two \\s \\z = s0 s1 s /s0 /s1 z
+        :three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z
+        //#Cons //#Box one #Tag //#Cons //#Box two #Tag //#Cons //#Box three #Tag #Nil").unwrap();
+    assert!(alpha_eq(&mapped, &reduce(&boxed).unwrap()));
+}
+
+#[test]
+fn bytes_round_trip() {
+    let bytes = [0, 1, 7, 128, 254, 255];
+    let items = list_to(&chars_of(&bytes)).unwrap();
+    let decoded : Vec<Option<u64>> = items.iter().map(nat_to).collect();
+    assert_eq!(decoded, bytes.iter().map(|byte| Some(*byte as u64)).collect::<Vec<_>>());
+    assert_eq!(string_of("hi"), chars_of(b"hi"));
+}
+
+#[test]
+fn ascii_lists() {
+    let items = list_to(&ascii_list("Hi!").unwrap()).unwrap();
+    let decoded : Vec<Option<u64>> = items.iter().map(nat_to).collect();
+    assert_eq!(decoded, vec![Some(72), Some(105), Some(33)]);
+    assert_eq!(ascii_list("caf\u{e9}"), Err(EncodeError::NonAscii{index: 3, chr: '\u{e9}'}));
+}
+
+#[test]
+fn bytes_survive_reduction() {
+    let data = [0, 42, 255, 3];
+    assert_eq!(bytes_of(&reduce(&chars_of(&data)).unwrap()), Some(data.to_vec()));
+    assert_eq!(string_to(&reduce(&string_of("ok")).unwrap()), Some("ok".to_string()));
+    assert_eq!(bytes_of(&list_of(vec![nat_of(256)])), None);
+    assert_eq!(bytes_of(&nat_of(1)), None);
+}
+
+#[test]
+fn binary_numbers_round_trip() {
+    assert_eq!(to_string(&bits_of(0)), b"\\o \\i \\e e".to_vec());
+    assert_eq!(to_string(&bits_of(6)), b"\\o \\i \\e = i0 i1 i\n/o /i0 /i1 e".to_vec());
+    for n in &[0, 1, 2, 5, 6, 255, 1 << 40, u64::MAX - 1, u64::MAX] {
+        assert_eq!(bits_to(&bits_of(*n)), Some(*n));
+    }
+    for n in &[0, 13, 1000] {
+        assert_eq!(bits_to(&reduce(&bits_of(*n)).unwrap()), Some(*n));
+    }
+    assert_eq!(bits_to(&nat_of(3)), None);
+}
+
+#[test]
+fn pairs_of_numerals_round_trip() {
+    assert_eq!(pair_to_nats(&pair_of_nats(7, 0)), Some((7, 0)));
+    assert_eq!(pair_to_nats(&reduce(&pair_of_nats(2, 3)).unwrap()), Some((2, 3)));
+    let swap = from_string(b"\\q \\r /q \\a \\b //r b a").unwrap();
+    assert_eq!(pair_to_nats(&reduce(&app(swap, pair_of_nats(4, 1))).unwrap()), Some((1, 4)));
+    assert_eq!(pair_to_nats(&nat_of(2)), None);
+}
@@ -0,0 +1,82 @@
+extern crate sic;
+
+use sic::module::*;
+use sic::error::{AffinityError, LimitError, SicError};
+use sic::term::REC_DEPTH;
+use sic::{eval_str, EvalOptions};
+use std::fs;
+
+fn names(names : &[Vec<u8>]) -> Vec<String> {
+    names.iter().map(|nam| String::from_utf8_lossy(nam).into_owned()).collect()
+}
+
+fn fixture() -> Module {
+    parse_module(&fs::read("tests/fixtures/deps.sic").unwrap()).unwrap()
+}
+
+#[test]
+fn dependency_edges() {
+    let edges : Vec<(String, Vec<String>)> = fixture().dependencies().iter()
+        .map(|(nam, refs)| (String::from_utf8_lossy(nam).into_owned(), names(refs)))
+        .collect();
+    let expected = vec![
+        ("id", vec![]),
+        ("const", vec![]),
+        ("twice", vec![]),
+        ("unused", vec!["id"]),
+        ("ping", vec!["pong"]),
+        ("pong", vec!["ping"]),
+    ];
+    let expected : Vec<(String, Vec<String>)> = expected.into_iter()
+        .map(|(nam, refs)| (nam.to_string(), refs.into_iter().map(String::from).collect()))
+        .collect();
+    assert_eq!(edges, expected);
+    assert_eq!(names(&fixture().roots(None)), vec!["twice", "const", "id"]);
+}
+
+#[test]
+fn unreachable_definitions() {
+    let module = fixture();
+    assert_eq!(names(&module.unreachable(None)), vec!["unused", "ping", "pong"]);
+    assert_eq!(names(&module.unreachable(Some(b"unused"))), vec!["const", "twice", "ping", "pong"]);
+}
+
+#[test]
+fn cycles_and_order() {
+    let module = fixture();
+    let cycles : Vec<Vec<String>> = module.cycles().iter().map(|group| names(group)).collect();
+    assert_eq!(cycles, vec![vec!["ping", "pong"]]);
+    let order = names(&module.topological_order());
+    let pos = |nam : &str| order.iter().position(|n| n == nam).unwrap();
+    assert!(pos("id") < pos("unused"));
+}
+
+#[test]
+fn mutual_recursion_is_unfolded() {
+    let src = fs::read_to_string("tests/fixtures/even_odd.sic").unwrap();
+    let module = parse_module(src.as_bytes()).unwrap();
+    let cycles : Vec<Vec<String>> = module.cycles().iter().map(|group| names(group)).collect();
+    assert_eq!(cycles, vec![vec!["even", "odd"]]);
+    assert_eq!(eval_str(&src, &EvalOptions::default()).unwrap().printed, "\\- \\a a");
+    let opts = EvalOptions { entry: Some("even".to_string()), inputs: vec!["//\\s \\z = s0 s1 s /s0 /s1 z succ zero".to_string()], ..EvalOptions::default() };
+    assert_eq!(eval_str(&src, &opts).unwrap().printed, "\\a \\- a");
+}
+
+#[test]
+fn mutual_recursion_deeper_than_the_unfoldings_is_an_error() {
+    let src = fs::read_to_string("tests/fixtures/even_odd.sic").unwrap();
+    let defs = &src[..src.rfind("/even").unwrap()];
+    let eval = |n : u32| eval_str(&format!("{}/even {}zero", defs, "/succ ".repeat(n as usize)), &EvalOptions::default());
+    let parity = if (REC_DEPTH - 1).is_multiple_of(2) { "\\a \\- a" } else { "\\- \\a a" };
+    assert_eq!(eval(REC_DEPTH - 1).unwrap().printed, parity);
+    match eval(REC_DEPTH) {
+        Err(SicError::Limit(LimitError::Recursion(nam))) => assert_eq!(nam, b"even"),
+        other => panic!("expected a recursion error, got {:?}", other.map(|outcome| outcome.printed))
+    }
+}
+
+#[test]
+fn recursion_uses_its_group_once_at_most() {
+    let module = parse_module(b":f \\x //g x f :g \\y /f y").unwrap();
+    assert_eq!(module.unfold_recursion(), Err(AffinityError::Duplicated(b"f".to_vec())));
+}
@@ -0,0 +1,83 @@
+extern crate sic;
+
+use std::process::Command;
+
+use sic::dot::{to_dot, write_dot_animation, DotOptions};
+use sic::net::{reduce, Kind};
+use sic::term::{from_string, to_net};
+
+#[test]
+fn nodes_and_wires_are_drawn() {
+    let net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    let dot = to_dot(&net, &DotOptions::default());
+    assert_eq!(dot, "graph net {\n  n0 [label=\"ROOT\"];\n  n1 [label=\"CON\", style=filled, fillcolor=\"lightblue\"];\n  n0 -- n1 [label=\"0-0\"];\n  n0 -- n0 [label=\"1-2\"];\n  n1 -- n1 [label=\"1-2\"];\n}\n");
+}
+
+#[test]
+fn edges_are_labeled_with_ports_once() {
+    let net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let dot = to_dot(&net, &DotOptions::default());
+    // The application's result goes to the root, its function is `\x x`, its argument `\y y`.
+    assert!(dot.contains("  n0 -- n1 [label=\"0-2\"];\n"));
+    assert!(dot.contains("  n1 -- n2 [label=\"0-0\"];\n"));
+    assert!(dot.contains("  n1 -- n3 [label=\"1-0\"];\n"));
+    assert_eq!(dot.matches(" -- ").count(), 6);
+}
+
+#[test]
+fn nodes_are_colored_by_kind() {
+    let net = to_net(&from_string(b"\\x = a b x |a &b *").unwrap()).unwrap();
+    let lines = |dot : String, label : &str| -> Vec<String> {
+        dot.lines().filter(|line| line.contains(&format!("[label=\"{}\"", label))).map(String::from).collect()
+    };
+    for (label, color) in [("ERA", "gray"), ("CON", "lightblue"), ("FAN", "orange")] {
+        let found = lines(to_dot(&net, &DotOptions::default()), label);
+        assert!(!found.is_empty());
+        assert!(found.iter().all(|line| line.contains(&format!("style=filled, fillcolor=\"{}\"", color))), "{:?}", found);
+    }
+    assert!(lines(to_dot(&net, &DotOptions::default()), "ROOT").iter().all(|line| !line.contains("fillcolor")));
+    let mut opts = DotOptions::default();
+    opts.colors.push((Kind::Fan, "pink".to_string()));
+    assert!(lines(to_dot(&net, &opts), "FAN").iter().all(|line| line.contains("fillcolor=\"pink\"")));
+}
+
+#[test]
+fn nodes_are_ranked_by_depth() {
+    let mut net = to_net(&from_string(b":id \\x x\n:const \\a \\_ a\n//const id id").unwrap()).unwrap();
+    assert!(!to_dot(&net, &DotOptions::default()).contains("rank="));
+    let dot = to_dot(&net, &DotOptions { rank: true, ..DotOptions::default() });
+    assert!(dot.contains("{ rank=same; n0; }\n  { rank=same; n1; }\n"));
+    // After reducing, the erased argument is left out of the ranks.
+    reduce(&mut net);
+    let dot = to_dot(&net, &DotOptions { rank: true, ..DotOptions::default() });
+    assert_eq!(dot.matches("rank=same").count(), 2);
+    assert!(dot.contains("n5 [label=\"ERA\"") && !dot.contains("rank=same; n5;"));
+}
+
+#[test]
+fn net_command_draws_ranks() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["net", "--dot", "--rank", "tests/fixtures/leaks.sic"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("graph net {") && stdout.contains("rank=same"));
+}
+
+#[test]
+fn animation_has_a_frame_per_stride() {
+    let net = to_net(&from_string(b"//\\f \\x = f0 f1 f /f0 /f1 x \\y y \\z z").unwrap()).unwrap();
+    let mut normal = net.clone();
+    let rules = reduce(&mut normal).rules as usize;
+    assert_eq!(rules, 6);
+    for (stride, frames) in [(1, rules + 1), (2, 4), (4, 3), (6, 2), (10, 2)] {
+        let dir = std::env::temp_dir().join(format!("sic-dot-animation-{}", stride));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(write_dot_animation(&mut net.clone(), &dir, stride, &DotOptions::default()).unwrap(), frames);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), frames);
+        assert_eq!(std::fs::read_to_string(dir.join("step_000.dot")).unwrap(), to_dot(&net, &DotOptions::default()));
+        let last = dir.join(format!("step_{:03}.dot", frames - 1));
+        assert_eq!(std::fs::read_to_string(last).unwrap(), to_dot(&normal, &DotOptions::default()));
+    }
+}
@@ -0,0 +1,44 @@
+// Validates a small corpus of programs the way a crate built on this one would: only through the
+// `testing` module.
+
+extern crate sic;
+
+use sic::testing::*;
+
+const PROGRAMS : &[&str] = &[
+    "\\x x",
+    "//\\f \\x /f x \\y y \\z z",
+    ":k \\a \\_ a :i \\x x //k i *",
+    ":two \\s \\z = s0 s1 s /s0 /s1 z :id \\x x //two id \\y y",
+    ":two \\s \\z = s0 s1 s /s0 /s1 z :add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z //add two two",
+    "= a b \\x x //a b \\y y"
+];
+
+#[test]
+fn programs_round_trip_through_the_printer() {
+    for code in PROGRAMS {
+        assert_round_trip_parse(&term(code));
+    }
+}
+
+#[test]
+fn programs_reduce_like_the_reference() {
+    for code in PROGRAMS {
+        assert_reduction_matches_reference(&term(code), 10000);
+    }
+}
+
+#[test]
+fn programs_are_confluent() {
+    let lazy = |net : &mut sic::net::Net| Ok(sic::net::reduce(net));
+    let reference = |net : &mut sic::net::Net| reduce_reference(net, 10000);
+    for code in PROGRAMS {
+        assert_confluent(&term(code), &[("lazy", &lazy), ("reference", &reference)]);
+    }
+}
+
+#[test]
+fn failures_show_the_terms() {
+    let msg = check_round_trip_net(&term("\\y = p q y /p q")).unwrap_err();
+    assert!(msg.contains("\\y = p q y"), "{}", msg);
+}
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+#[test]
+fn dump_ast_prints_the_parsed_tree() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--dump-ast", "library/test3.sic"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for label in ["Lam {", "Dup {", "App {", "Var {", "nam: \"d#0#x\""].iter() {
+        assert!(stdout.contains(label), "missing {} in:\n{}", label, stdout);
+    }
+    // The term is printed as parsed, not reduced.
+    assert!(!stdout.contains("\\a"));
+}
@@ -0,0 +1,44 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::term::{from_string, pretty, pretty_limited, OutputLimits};
+
+fn limited(src : &str, depth : Option<u32>, size : Option<u64>) -> (String, u64) {
+    let (code, elided) = pretty_limited(&from_string(src.as_bytes()).unwrap(), OutputLimits { depth, size });
+    (String::from_utf8(code).unwrap(), elided)
+}
+
+const APPLY : &str = "\\f \\x /f /x *";
+
+#[test]
+fn deep_terms_are_left_out() {
+    assert_eq!(limited(APPLY, Some(0), None), ("…7".to_string(), 7));
+    assert_eq!(limited(APPLY, Some(1), None), ("\\f …6".to_string(), 6));
+    assert_eq!(limited(APPLY, Some(3), None), ("\\f \\x /…1 …3".to_string(), 4));
+    assert_eq!(limited(APPLY, Some(100), None), (APPLY.to_string(), 0));
+    // The body of a let is as deep as the let.
+    assert_eq!(limited("\\f = a b f \\x /a /b x", Some(2), None), ("\\f\n  = a b …1\n  \\x …5".to_string(), 6));
+}
+
+#[test]
+fn large_terms_are_left_out() {
+    assert_eq!(limited(APPLY, None, Some(3)), ("\\f \\x /…1 …3".to_string(), 4));
+    assert_eq!(limited(APPLY, None, Some(7)), (APPLY.to_string(), 0));
+    assert_eq!(limited(APPLY, None, None).0, String::from_utf8(pretty(&from_string(APPLY.as_bytes()).unwrap())).unwrap());
+}
+
+#[test]
+fn partial_output_is_not_a_term() {
+    let (code, _) = limited(APPLY, Some(1), None);
+    let err = from_string(code.as_bytes()).unwrap_err();
+    assert_eq!(err.message, "output with elided terms can't be parsed");
+}
+
+#[test]
+fn elided_output_from_the_command_line() {
+    let file = std::env::temp_dir().join(format!("sic_elide_{}.sic", std::process::id()));
+    std::fs::write(&file, format!("/\\y y {}", APPLY)).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(["--max-output-depth", "2"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "\\a \\b …5\n");
+    assert!(String::from_utf8_lossy(&out.stderr).ends_with("note: 5 nodes were left out of the output\n"));
+}
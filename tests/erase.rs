@@ -0,0 +1,36 @@
+extern crate sic;
+
+use sic::net::{ERA, NODE_SIZE, kind};
+use sic::term::{alpha_eq, from_string, reduce, to_net, to_string, Term};
+
+fn parse(src : &str) -> Term {
+    from_string(src.as_bytes()).unwrap()
+}
+
+fn eras(src : &str) -> usize {
+    let net = to_net(&parse(src)).unwrap();
+    (0..(net.nodes.len() / NODE_SIZE) as u32).filter(|node| kind(&net, *node) == ERA).count()
+}
+
+#[test]
+fn erasures_are_parsed() {
+    match parse("\\x & x \\y y") {
+        Term::Lam{bod, ..} => assert!(matches!(*bod, Term::Era{..})),
+        term => panic!("not a lambda: {:?}", term)
+    }
+    let src = "\\x & x \\y = a b y //a b x";
+    assert!(alpha_eq(&parse(&String::from_utf8(to_string(&parse(src))).unwrap()), &parse(src)));
+}
+
+#[test]
+fn erasures_become_era_nodes() {
+    assert_eq!(eras("& \\y y \\z z"), eras("\\z z") + 1);
+    assert_eq!(eras("& * *"), eras("*") + 2);
+}
+
+#[test]
+fn erased_values_are_gone() {
+    assert!(alpha_eq(&reduce(&parse("/\\x & x \\y y \\z z")).unwrap(), &parse("\\a a")));
+    assert_eq!(to_string(&reduce(&parse("\\x & x \\y y")).unwrap()), b"\\- \\a a");
+    assert!(alpha_eq(&reduce(&parse("& /\\x x \\y y \\z z")).unwrap(), &parse("\\z z")));
+}
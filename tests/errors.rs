@@ -0,0 +1,84 @@
+extern crate sic;
+
+use sic::error::*;
+use sic::net::*;
+use sic::term::*;
+
+// Runs the whole pipeline, so each failure has to come out as a `SicError`.
+fn eval(code : &str) -> Result<Term, SicError> {
+    let term = from_string(code.as_bytes())?;
+    let mut net = to_net(&term)?;
+    reduce_limited(&mut net, 1000)?;
+    Ok(from_net(&net)?)
+}
+
+#[test]
+fn parse_errors() {
+    match eval("\\x") {
+        Err(SicError::Parse(err)) => assert_eq!(err.index, 2),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+    match eval("/\\x x (unclosed") {
+        Err(SicError::Parse(err)) => assert_eq!(err.message, "unclosed comment"),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+    match eval("= a") {
+        Err(SicError::Parse(err)) => assert_eq!(err.message, "expected a name"),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+}
+
+#[test]
+fn affinity_errors() {
+    match eval("\\x /x x") {
+        Err(SicError::Affinity(AffinityError::Duplicated(nam))) => assert_eq!(nam, b"x".to_vec()),
+        other => panic!("expected an affinity error, got {:?}", other)
+    }
+    match eval("\\x /f x") {
+        Err(SicError::Affinity(AffinityError::Unbound(nam))) => assert_eq!(nam, b"f".to_vec()),
+        other => panic!("expected an affinity error, got {:?}", other)
+    }
+}
+
+#[test]
+fn limit_errors() {
+    let two = ":two \\s \\z = s0 s1 s /s0 /s1 z";
+    let code = format!("{} //two \\x x \\y y", two);
+    assert!(eval(&code).is_ok());
+    let mut net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    match reduce_limited(&mut net, 2) {
+        Err(LimitError::Rewrites(2)) => {},
+        other => panic!("expected a limit error, got {:?}", other)
+    }
+}
+
+#[test]
+fn net_errors() {
+    let net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    assert_eq!(check_net(&net), Ok(()));
+
+    let mut broken = net.clone();
+    broken.nodes.pop();
+    assert_eq!(check_net(&broken), Err(NetError::Truncated(net.nodes.len() - 1)));
+
+    let mut broken = net.clone();
+    broken.nodes[slot(link(1, PORT_AUX1))] = 100;
+    assert_eq!(check_net(&broken), Err(NetError::OutOfBounds{from: 5, to: 100}));
+
+    let mut broken = net.clone();
+    set_kind(&mut broken, 1, 9);
+    assert_eq!(check_net(&broken), Err(NetError::UnknownKind{node: 1, kind: 9}));
+
+    let err : SicError = check_net(&broken).unwrap_err().into();
+    assert!(err.to_string().contains("unknown kind 9"));
+}
+
+#[test]
+fn readback_errors() {
+    let mut net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    set_kind(&mut net, 1, 9);
+    match from_net(&net) {
+        Err(ReadbackError::UnknownKind{node: 1, kind: 9}) => {},
+        other => panic!("expected a readback error, got {:?}", other)
+    }
+}
@@ -0,0 +1,113 @@
+extern crate sic;
+
+use std::process::Command;
+use std::time::Duration;
+use sic::{eval_str, EvalOptions};
+use sic::eval::Decode;
+use sic::error::*;
+
+const NATS : &str = "
+    :two \\s \\z = s0 s1 s /s0 /s1 z
+    :add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+    //add two two";
+
+fn nat(src : &str, opts : EvalOptions) -> String {
+    eval_str(src, &EvalOptions { decode: Decode::Nat, ..opts }).unwrap().printed
+}
+
+#[test]
+fn entries_and_inputs() {
+    assert_eq!(nat(NATS, EvalOptions::default()), "4");
+    let inputs = vec!["two".to_string(), "\\s \\z /s z".to_string()];
+    assert_eq!(nat(NATS, EvalOptions { entry: Some("add".to_string()), inputs, ..EvalOptions::default() }), "3");
+    // The main term is applied to the inputs, and binders of different inputs don't clash.
+    let src = "\\f \\x /f x";
+    let inputs = vec!["\\x x".to_string(), "\\x x".to_string()];
+    let outcome = eval_str(src, &EvalOptions { inputs, ..EvalOptions::default() }).unwrap();
+    assert_eq!(outcome.printed, "\\a a");
+}
+
+#[test]
+fn limits_stop_early() {
+    let opts = EvalOptions { max_rewrites: Some(2), ..EvalOptions::default() };
+    let outcome = eval_str(NATS, &opts).unwrap();
+    assert!(!outcome.completed);
+    assert!(outcome.stats.is_none());
+    let opts = EvalOptions { max_nodes: Some(10), stats: true, ..EvalOptions::default() };
+    let outcome = eval_str(NATS, &opts).unwrap();
+    assert!(!outcome.completed);
+    assert!(outcome.stats.unwrap().max_nodes > 10);
+    let opts = EvalOptions { max_time: Some(Duration::from_secs(60)), ..EvalOptions::default() };
+    assert!(eval_str(NATS, &opts).unwrap().completed);
+}
+
+#[test]
+fn decoding() {
+    let opts = EvalOptions { decode: Decode::String, ..EvalOptions::default() };
+    let src = ":one \\s \\z /s z :zero \\s \\z z \\c \\n = c0 c1 c //c0 one //c1 zero n";
+    assert_eq!(eval_str(src, &opts).unwrap().printed, "\u{1}\u{0}");
+    match eval_str("\\x x", &EvalOptions { decode: Decode::Nat, ..EvalOptions::default() }) {
+        Err(SicError::Readback(ReadbackError::Decode(_))) => {},
+        other => panic!("expected a decoding error, got {:?}", other)
+    }
+    match eval_str(":id \\x x", &EvalOptions { inputs: vec!["id".to_string()], ..EvalOptions::default() }) {
+        Err(SicError::Parse(err)) => assert_eq!(err.message, "expected a main term or an entry"),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+}
+
+#[test]
+fn command_line() {
+    let dir = std::env::temp_dir().join("sic-eval-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("nats.sic");
+    std::fs::write(&file, NATS).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .arg(&file).args(["-e", "add", "-i", "two", "-i", "two", "--decode", "nat"])
+        .output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "4\n");
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(["--max-rewrites", "2"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(75));
+}
+
+#[test]
+fn several_goals() {
+    let dir = std::env::temp_dir().join("sic-eval-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("goals.sic");
+    std::fs::write(&file, NATS.replace("//add two two", ":four //add two two\n:six //add two four")).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .arg(&file).args(["--eval", "four,six", "--decode", "nat"])
+        .output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "four: 4\nsix: 6\n");
+}
+
+#[test]
+fn size_trace() {
+    let src = ":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+        :three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z
+        :six //add three three
+        //add six //add six three";
+    let rules = eval_str(src, &EvalOptions { stats: true, ..EvalOptions::default() }).unwrap().stats.unwrap().rules;
+    let dir = std::env::temp_dir().join("sic-eval-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let (file, csv) = (dir.join("fifteen.sic"), dir.join("fifteen.csv"));
+    std::fs::write(&file, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .arg(&file).args(["--decode", "nat", "--trace-every", "3", "--trace-csv"]).arg(&csv)
+        .output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "15\n");
+    let text = std::fs::read_to_string(&csv).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("rewrites,nodes,free_list,era,con,fan,free,ctr"));
+    let rows : Vec<Vec<u32>> = lines.map(|line| line.split(',').map(|num| num.parse().unwrap()).collect()).collect();
+    assert!(rules > 20);
+    assert_eq!(rows.len() as u32, 1 + rules / 3);
+    for (idx, row) in rows.iter().enumerate() {
+        assert_eq!(row[0], idx as u32 * 3);
+        // The census counts every node in use but the root.
+        assert_eq!(row[3..].iter().sum::<u32>() + 1, row[1]);
+    }
+}
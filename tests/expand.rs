@@ -0,0 +1,40 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::error::LimitError;
+use sic::term::{expand_dups, from_string, to_string, MAX_SIZE};
+
+fn expanded(src : &str, max : u64) -> Result<String, LimitError> {
+    expand_dups(&from_string(src.as_bytes()).unwrap(), max).map(|term| String::from_utf8(to_string(&term)).unwrap())
+}
+
+#[test]
+fn lets_are_written_out() {
+    assert_eq!(expanded("\\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z", MAX_SIZE), Ok("\\s \\z /s /s /s z".to_string()));
+    // Each copy of a lambda gets binders of its own.
+    assert_eq!(expanded("= a b \\x x /a b", MAX_SIZE), Ok("/\\c c \\d d".to_string()));
+    assert_eq!(expanded("\\x x", MAX_SIZE), Ok("\\x x".to_string()));
+}
+
+#[test]
+fn expansion_is_bounded() {
+    // Each let copies the one before it twice.
+    let mut src = "\\f \\x = a0 b0 f ".to_string();
+    for idx in 1..32 {
+        src.push_str(&format!("= a{} b{} \\y{} /a{} /b{} y{} ", idx, idx, idx, idx - 1, idx - 1, idx));
+    }
+    src.push_str("/a31 /b31 x");
+    assert_eq!(expanded(&src, 1 << 16), Err(LimitError::Expansion(1 << 16)));
+}
+
+#[test]
+fn expanding_from_the_command_line() {
+    let file = std::env::temp_dir().join(format!("sic_expand_{}.sic", std::process::id()));
+    std::fs::write(&file, "\\f \\x = a b f = c d a = e g c /b /d /e /g x").unwrap();
+    let run = |args : &[&str]| Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(args).output().unwrap();
+    assert_eq!(run(&["--keep-dups"]).stdout, b"= h c a\n= g d h\n= e f g\n\\a \\b /c /d /e /f b\n".to_vec());
+    assert_eq!(run(&["--expand-dups"]).stdout, b"\\a \\b /a /a /a /a b\n".to_vec());
+    let out = run(&["--expand-dups", "--max-expansion", "5"]);
+    assert_eq!(out.status.code(), Some(75));
+    assert!(String::from_utf8_lossy(&out.stderr).ends_with("error: expanding the lets would take more than 5 nodes\n"));
+}
@@ -0,0 +1,189 @@
+extern crate sic;
+
+use std::fs;
+use std::process::Command;
+use sic::data::*;
+use sic::error::ExportError;
+use sic::module::*;
+use sic::term::*;
+
+// Checks that `code` follows the subset of HVM's grammar the exporter produces:
+//   program := ("@" name " = " term "\n")*
+//   term    := "λ" name " " term | "(" term " " term ")" | "&0{" term " " term "}"
+//            | "! &0{" name " " name "} = " term "; " term | "@" name | name
+fn well_formed(code : &str) -> bool {
+    fn name(code : &str) -> Option<&str> {
+        let len = code.find(|chr : char| !(chr.is_ascii_alphanumeric() || chr == '_' || chr == '.')).unwrap_or(code.len());
+        if len == 0 { None } else { Some(&code[len..]) }
+    }
+    fn term(code : &str) -> Option<&str> {
+        if let Some(rest) = code.strip_prefix('λ') {
+            term(name(rest)?.strip_prefix(' ')?)
+        } else if let Some(rest) = code.strip_prefix('(') {
+            term(term(rest)?.strip_prefix(' ')?)?.strip_prefix(')')
+        } else if let Some(rest) = code.strip_prefix("&0{") {
+            term(term(rest)?.strip_prefix(' ')?)?.strip_prefix('}')
+        } else if let Some(rest) = code.strip_prefix("! &0{") {
+            let rest = name(name(rest)?.strip_prefix(' ')?)?.strip_prefix("} = ")?;
+            term(term(rest)?.strip_prefix("; ")?)
+        } else {
+            name(code.strip_prefix('@').unwrap_or(code))
+        }
+    }
+    let mut code = code;
+    while !code.is_empty() {
+        let rest = name(match code.strip_prefix('@') { Some(rest) => rest, None => return false });
+        match rest.and_then(|rest| rest.strip_prefix(" = ")).and_then(term).and_then(|rest| rest.strip_prefix('\n')) {
+            Some(rest) => code = rest,
+            None => return false
+        }
+    }
+    true
+}
+
+#[test]
+fn exports_match_golden_files() {
+    for name in &["id", "add", "twice"] {
+        let src = fs::read(format!("tests/fixtures/export/{}.sic", name)).unwrap();
+        let golden = fs::read_to_string(format!("tests/fixtures/export/{}.hvm", name)).unwrap();
+        let code = to_hvm(&parse_module(&src).unwrap()).unwrap();
+        assert_eq!(code, golden, "{}", name);
+        assert!(well_formed(&code), "{}", name);
+    }
+}
+
+#[test]
+fn exports_superpositions() {
+    let module = Module { defs: vec![], main: Some(par(lam("x", var("x")), lam("y", var("y")))), comments: vec![], notes: vec![], fuel: vec![], params: vec![] };
+    let code = to_hvm(&module).unwrap();
+    assert_eq!(code, "@main = &0{λx x λy y}\n");
+    assert!(well_formed(&code));
+    assert!(!well_formed("@main = (λx x\n"));
+}
+
+#[test]
+fn reports_what_doesnt_translate() {
+    let export = |src : &[u8]| to_hvm(&parse_module(src).unwrap());
+    assert_eq!(export(b"\\x *"), Err(ExportError::Set));
+    assert_eq!(export(b"\\x y"), Err(ExportError::Unbound(b"y".to_vec())));
+    assert_eq!(export(b":a \\x x :a \\y y a"), Err(ExportError::Redefined(b"a".to_vec())));
+    assert_eq!(export(b":main \\x x main"), Err(ExportError::Redefined(b"main".to_vec())));
+    assert_eq!(export(b"\\x' x'"), Err(ExportError::Name(b"x'".to_vec())));
+}
+
+#[test]
+fn cli_writes_the_export() {
+    let out = std::env::temp_dir().join(format!("sic-export-test-{}.hvm", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["export", "--format", "hvm", "tests/fixtures/export/add.sic", "-o"])
+        .arg(&out)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&out).unwrap(), fs::read_to_string("tests/fixtures/export/add.hvm").unwrap());
+    fs::remove_file(&out).unwrap();
+}
+
+#[test]
+fn js_matches_golden_file() {
+    let src = fs::read("tests/fixtures/export/four.sic").unwrap();
+    let golden = fs::read_to_string("tests/fixtures/export/four.js").unwrap();
+    assert_eq!(to_js(&from_string(&src).unwrap()).unwrap(), golden);
+}
+
+#[test]
+fn js_runs_church_arithmetic() {
+    let src = fs::read("tests/fixtures/fans/church.sic").unwrap();
+    let mut code = to_js(&sic::term::reduce(&from_string(&src).unwrap()).unwrap()).unwrap();
+    code.push_str("console.log(main(n => n + 1)(0));\n");
+    let path = std::env::temp_dir().join(format!("sic-export-test-{}.js", std::process::id()));
+    fs::write(&path, code).unwrap();
+    // Without a JavaScript engine, the golden file is all that's checked.
+    if let Ok(out) = Command::new("node").arg(&path).output() {
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(String::from_utf8(out.stdout).unwrap(), "10\n");
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn js_takes_free_variables_as_parameters() {
+    let js = |src : &[u8]| to_js(&from_string(src).unwrap());
+    let term = app(var("f"), par(var("x"), lam("y", var("y"))));
+    assert!(to_js(&term).unwrap().ends_with("const main = (f, x) => f([x, y => y]);\n"));
+    assert!(js(b"= a b f /a b").unwrap().ends_with("const main = (f) => {\n  const [a, b] = dup(f);\n  return a(b);\n};\n"));
+    assert!(js(b"/\\x x & * \\y y").unwrap().ends_with("void SET;\nconst main = (x => x)(y => y);\n"));
+}
+
+#[test]
+fn js_reports_what_doesnt_translate() {
+    let js = |src : &[u8]| to_js(&from_string(src).unwrap());
+    assert_eq!(js(b"\\x /x #Nil"), Err(ExportError::Ctr(b"#Nil".to_vec())));
+    assert_eq!(js(b"\\in in"), Err(ExportError::Name(b"in".to_vec())));
+    assert_eq!(js(b"\\x' x'"), Err(ExportError::Name(b"x'".to_vec())));
+    assert_eq!(js(b"/\\x y \\y x"), Err(ExportError::Unbound(b"y".to_vec())));
+}
+
+// Builds `add` as Rust, with a program that adds two and three through `Value`.
+const RUST_MAIN : &str = "
+mod add {
+    include!(\"add.rs\");
+}
+
+use add::Value;
+use std::rc::Rc;
+
+fn church(n : u32) -> Value {
+    Value::Fun(Rc::new(move |s : Value| Value::Fun(Rc::new(move |z : Value| {
+        (0..n).fold(z, |x, _| s.clone().apply(x))
+    }))))
+}
+
+fn count(n : Value) -> u32 {
+    let succ = Value::Fun(Rc::new(|x : Value| Value::Pair(Box::new(Value::Unit), Box::new(x))));
+    let mut n = n.apply(succ).apply(Value::Unit);
+    let mut count = 0;
+    while let Value::Pair(_, rest) = n {
+        count += 1;
+        n = *rest;
+    }
+    count
+}
+
+fn main() {
+    println!(\"{}\", count(add::add().apply(church(2)).apply(church(3))));
+}
+";
+
+#[test]
+fn rust_adds_church_numerals() {
+    let add = from_string(b"\\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z").unwrap();
+    let dir = std::env::temp_dir().join(format!("sic-export-rust-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("add.rs"), to_rust(&add, "add").unwrap()).unwrap();
+    fs::write(dir.join("main.rs"), RUST_MAIN).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let out = Command::new(rustc)
+        .args(["--edition", "2021", "-D", "warnings", "-o"])
+        .arg(dir.join("main"))
+        .arg(dir.join("main.rs"))
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = Command::new(dir.join("main")).output().unwrap();
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "5\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rust_takes_free_variables_as_parameters() {
+    let code = to_rust(&from_string(b"= a b f /a \\x /b *").unwrap(), "twice").unwrap();
+    let expected = "pub fn twice(f : Value) -> Value {
+    let (a, b) = dup(f.clone());
+    a.clone().apply({ let b = b.clone(); fun(move |_| b.clone().apply(Value::Unit)) })
+}
+";
+    assert!(code.ends_with(expected), "{}", code);
+    assert_eq!(to_rust(&from_string(b"\\x x").unwrap(), "fn"), Err(ExportError::Name(b"fn".to_vec())));
+    assert_eq!(to_rust(&from_string(b"\\self self").unwrap(), "id"), Err(ExportError::Name(b"self".to_vec())));
+}
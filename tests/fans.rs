@@ -0,0 +1,58 @@
+extern crate sic;
+
+use std::fs;
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::eval::Decode;
+use sic::fans::*;
+use sic::term::{from_string, to_string, from_net};
+
+fn fixture(name : &str) -> String {
+    fs::read_to_string(format!("tests/fixtures/fans/{}.sic", name)).unwrap()
+}
+
+#[test]
+fn nested_lets_are_reported() {
+    let opts = EvalOptions { check_fans: true, ..EvalOptions::default() };
+    let outcome = eval_str(&fixture("unsound"), &opts).unwrap();
+    assert_eq!(outcome.unsound.len(), 1);
+    let (fst, snd) = &outcome.unsound[0];
+    assert_eq!(describe(fixture("unsound").as_bytes(), fst), "let `= c d` at 3:10");
+    assert_eq!(describe(fixture("unsound").as_bytes(), snd), "let `= a b` at 4:10");
+    // Without the flag, nothing is traced.
+    assert!(eval_str(&fixture("unsound"), &EvalOptions::default()).unwrap().unsound.is_empty());
+}
+
+#[test]
+fn church_arithmetic_is_faithful() {
+    let opts = EvalOptions { check_fans: true, decode: Decode::Nat, ..EvalOptions::default() };
+    let outcome = eval_str(&fixture("church"), &opts).unwrap();
+    assert_eq!(outcome.printed, "10");
+    assert!(outcome.unsound.is_empty());
+}
+
+#[test]
+fn copies_meet_their_own_let() {
+    let term = from_string(b"= a b \\x x /a /b \\y y").unwrap();
+    let (mut net, mut trace) = to_net_traced(&term).unwrap();
+    assert_eq!(trace.origins, vec![FanOrigin::Dup { idx: 0, fst: b"a".to_vec(), snd: b"b".to_vec() }]);
+    reduce_traced(&mut net, &mut trace);
+    assert!(trace.unsound.is_empty());
+    assert_eq!(to_string(&from_net(&net).unwrap()), b"\\a a".to_vec());
+}
+
+#[test]
+fn cli_warns_with_locations() {
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--check-fans", "tests/fixtures/fans/unsound.sic"])
+        .output().unwrap();
+    assert!(out.status.success());
+    let err = String::from_utf8(out.stderr).unwrap();
+    assert!(err.contains("warning: 1 fan annihilation(s) may be unsound"));
+    assert!(err.contains("  let `= c d` at 3:10 met let `= a b` at 4:10\n"));
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--check-fans", "--decode", "nat", "tests/fixtures/fans/church.sic"])
+        .output().unwrap();
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "10\n");
+    assert!(!String::from_utf8(out.stderr).unwrap().contains("warning"));
+}
@@ -0,0 +1,31 @@
+// Compiles `tests/ffi/main.c` against the cdylib and runs it. Needs a C compiler.
+#![cfg(feature = "ffi")]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_round_trip() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Cargo leaves the library of the crate under test in `deps`, next to the test binaries.
+    let lib_dir = Path::new(env!("CARGO_BIN_EXE_sic")).parent().unwrap().join("deps");
+    let exe = lib_dir.join("ffi_main");
+    let status = Command::new("cc")
+        .arg(root.join("tests/ffi/main.c"))
+        .arg("-I").arg(root.join("include"))
+        .arg("-L").arg(&lib_dir)
+        .arg("-lsic")
+        .arg("-o").arg(&exe)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = Command::new(&exe).env("LD_LIBRARY_PATH", &lib_dir).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let lines = String::from_utf8(output.stdout).unwrap();
+    let lines : Vec<&str> = lines.lines().collect();
+    assert_eq!(lines, vec![
+        "= c d h", "= h g a", "= e f g", "\\a \\b /c /d /e /f b",
+        "parse error at byte 2: unexpected end of input",
+        "6"
+    ]);
+}
@@ -0,0 +1,35 @@
+#include <stdio.h>
+#include <string.h>
+#include "sic.h"
+
+static const char *ADD =
+    ":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z\n"
+    ":two \\s \\z = s0 s1 s /s0 /s1 z\n";
+
+int main(void) {
+    SicOptions opts = { 1000 };
+    SicResult res;
+
+    int code = sic_eval(ADD, "//add two two", &opts, &res);
+    if (code != SIC_OK || res.error != NULL) {
+        fprintf(stderr, "unexpected error %d: %s\n", code, res.error);
+        return 1;
+    }
+    printf("%s\n", res.output);
+    sic_free_result(&res);
+    sic_free_result(&res);
+
+    code = sic_eval("\\x", NULL, NULL, &res);
+    if (code != SIC_ERR_PARSE || res.output != NULL) {
+        fprintf(stderr, "expected a parse error, got %d\n", code);
+        return 1;
+    }
+    printf("%s\n", res.error);
+    sic_free_result(&res);
+
+    opts.max_rewrites = 1;
+    code = sic_eval(ADD, "//add two two", &opts, &res);
+    printf("%d\n", code);
+    sic_free_result(&res);
+    return 0;
+}
@@ -0,0 +1,8 @@
+const SET = Symbol("*");
+const dup = val => Array.isArray(val) ? val : [val, val];
+const main = a => {
+  const [h, g] = dup(a);
+  const [c, d] = dup(h);
+  const [e, f] = dup(g);
+  return b => c(d(e(f(b))));
+};
@@ -0,0 +1,52 @@
+extern crate sic;
+
+use sic::module::*;
+use std::fs;
+
+fn format(code : &[u8]) -> Vec<u8> {
+    format_module(&parse_module(code).unwrap())
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let files = [
+        "library/test.sic",
+        "library/test2.sic",
+        "library/test3.sic",
+        "library/test4.sic",
+        "tests/fixtures/deps.sic",
+        "tests/fixtures/bench/church.sic",
+    ];
+    for file in files.iter() {
+        let once = format(&fs::read(file).unwrap());
+        let twice = format(&once);
+        assert_eq!(String::from_utf8_lossy(&once), String::from_utf8_lossy(&twice), "{}", file);
+    }
+}
+
+#[test]
+fn formatting_keeps_definitions_and_comments() {
+    let code = b"(identity)\n:i\\x x (unused (nested))\n:k\\y\\- y\n//k i i (the end)";
+    let expected = "(identity)\n:i \\x x\n\n(unused (nested))\n:k \\y \\- y\n\n//k i i\n(the end)\n";
+    assert_eq!(String::from_utf8_lossy(&format(code)), expected);
+}
+
+#[test]
+fn comments_within_terms_are_kept() {
+    let code = b"(two arguments)\n:k (first)\\a (then) \\b (body) a\n:t x y = (pair) |(left) x y\n= p q (value) /k *\n(next) /p q";
+    let expected = "(two arguments)\n:k (first) \\a (then) \\b (body) a\n\n:t x y = (pair) | (left) x y\n\n= p q (value) /k *\n(next) /p q\n";
+    let once = format(code);
+    assert_eq!(String::from_utf8_lossy(&once), expected);
+    assert_eq!(format(&once), once);
+    // Where sugar is expanded, the comments go before the whole value.
+    let code = b":f \\x (local) :g \\y y (use) /g x";
+    assert_eq!(String::from_utf8_lossy(&format(code)), ":f (local) (use) \\x /\\g#0#y g#0#y x\n");
+}
+
+#[test]
+fn terms_print_their_comments() {
+    let (rest, term, notes) = sic::term::parse_commented(b"/(fun) \\x x (arg) * (after)", &mut sic::term::Scope::new(), &mut 0).unwrap();
+    assert_eq!(rest, b" (after)");
+    assert_eq!(notes, vec![(1, b"(fun)".to_vec()), (3, b"(arg)".to_vec())]);
+    assert_eq!(sic::term::pretty_commented(&term, &notes), b"/(fun) \\x x (arg) *".to_vec());
+}
@@ -0,0 +1,63 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::data::nat_of;
+use sic::error::*;
+use sic::fuel::*;
+use sic::module::*;
+use sic::term::to_string;
+
+// A program whose helper, `spin`, makes most of the rewrites: it applies a large numeral to the
+// identity.
+fn program(fuel : &str) -> String {
+    let big = String::from_utf8(to_string(&nat_of(200))).unwrap();
+    format!(":id \\a a\n:spin{} //{} \\x x \\y y\n/id spin", fuel, big)
+}
+
+#[test]
+fn a_helper_runs_out_of_its_fuel() {
+    let opts = EvalOptions { max_rewrites: Some(1 << 24), stats: true, ..EvalOptions::default() };
+    let outcome = eval_str(&program(" %fuel 100"), &opts).unwrap();
+    assert_eq!(outcome.exhausted, Some(LimitError::Fuel{nam: b"spin".to_vec(), fuel: 100}));
+    assert!(!outcome.completed);
+    assert!(outcome.stats.unwrap().rules < 200);
+    assert_eq!(outcome.exhausted.unwrap().to_string(), "definition `spin` exceeded its fuel of 100 interactions");
+    // The rest of the program has no budget of its own, and a generous one doesn't stop it.
+    let outcome = eval_str(&program(""), &EvalOptions { fuel: vec![("id".to_string(), 100)], ..opts.clone() }).unwrap();
+    assert!(outcome.completed && outcome.exhausted.is_none());
+    assert_eq!(outcome.printed, "\\a a");
+    assert!(outcome.stats.unwrap().rules > 200);
+    assert!(eval_str(&program(" %fuel 100000"), &opts).unwrap().completed);
+}
+
+#[test]
+fn fuel_on_the_command_line() {
+    let file = std::env::temp_dir().join("sic_fuel.sic");
+    std::fs::write(&file, program("")).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(["--fuel", "spin=100"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(75));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("definition `spin` exceeded its fuel of 100 interactions"));
+    assert!(!out.stdout.is_empty());
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(["--fuel", "spin"]).output().unwrap();
+    assert!(!out.status.success());
+}
+
+#[test]
+fn annotations_are_kept() {
+    let src = ":id %fuel 7 \\a a\n\n/id \\b b\n";
+    let module = parse_module(src.as_bytes()).unwrap();
+    assert_eq!(module.fuel, vec![(b"id".to_vec(), 7)]);
+    assert_eq!(format_module(&module), src.as_bytes().to_vec());
+    assert_eq!(parse_module(b":id %fuel \\a a").unwrap_err().message, "expected a number of rewrites after `%fuel`");
+}
+
+#[test]
+fn nets_with_fuel() {
+    let term = sic::term::from_string(program("").as_bytes()).unwrap();
+    let (mut net, mut fuel) = to_net_fueled(&term, &[(b"spin".to_vec(), 100)]).unwrap();
+    assert_eq!(reduce_fueled(&mut net, &mut fuel), Err(LimitError::Fuel{nam: b"spin".to_vec(), fuel: 100}));
+    assert_eq!(fuel.budgets, vec![(b"spin".to_vec(), 100, 101)]);
+    let (mut net, mut fuel) = to_net_fueled(&term, &[(b"id".to_vec(), 100)]).unwrap();
+    assert!(reduce_fueled(&mut net, &mut fuel).is_ok());
+}
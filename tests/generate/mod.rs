@@ -0,0 +1,115 @@
+// Generators of random terms, shared by the property tests, and what they need to tell which terms
+// to skip.
+
+use proptest::prelude::*;
+use sic::net::{addr, enter, link, port, Net, NODE_SIZE};
+use sic::term::Term;
+use sic::term::Term::*;
+
+// A term whose variables are indices into the enclosing lambdas, and may be used any number of
+// times. `affine` turns it into a `Term`.
+#[derive(Clone, Debug)]
+pub enum Raw {
+    Lam(Box<Raw>),
+    App(Box<Raw>, Box<Raw>),
+    Par(Box<Raw>, Box<Raw>),
+    Var(usize),
+    Set
+}
+
+pub fn raw() -> impl Strategy<Value = Raw> {
+    let leaf = prop_oneof![4 => (0..8usize).prop_map(Raw::Var), 1 => Just(Raw::Set)];
+    leaf.prop_recursive(8, 64, 2, |inner| prop_oneof![
+        3 => inner.clone().prop_map(|bod| Raw::Lam(Box::new(bod))),
+        2 => (inner.clone(), inner.clone()).prop_map(|(fun, arg)| Raw::App(Box::new(fun), Box::new(arg))),
+        1 => (inner.clone(), inner).prop_map(|(fst, snd)| Raw::Par(Box::new(fst), Box::new(snd)))
+    ])
+}
+
+// Names the binders of a raw term apart and makes it affine: a variable used more than once is
+// copied by a chain of lets right under its lambda, and each use gets its own copy. Variables with
+// no enclosing lambda become sets.
+pub fn affine(raw : &Raw) -> Term {
+    fn count(raw : &Raw, scope : &mut Vec<usize>, uses : &mut Vec<u32>) {
+        match raw {
+            Raw::Lam(bod) => {
+                scope.push(uses.len());
+                uses.push(0);
+                count(bod, scope, uses);
+                scope.pop();
+            },
+            Raw::App(fst, snd) | Raw::Par(fst, snd) => {
+                count(fst, scope, uses);
+                count(snd, scope, uses);
+            },
+            Raw::Var(idx) => if !scope.is_empty() {
+                uses[scope[scope.len() - 1 - idx % scope.len()]] += 1;
+            },
+            Raw::Set => {}
+        }
+    }
+    fn build(raw : &Raw, scope : &mut Vec<usize>, uses : &[u32], next : &mut Vec<u32>) -> Term {
+        match raw {
+            Raw::Lam(bod) => {
+                let id = next.len();
+                next.push(0);
+                scope.push(id);
+                let mut bod = build(bod, scope, uses, next);
+                scope.pop();
+                let nam = format!("v{}", id);
+                // `= v0c0 v0r0 v0`, `= v0c1 v0r1 v0r0`... and the last copy takes the rest.
+                let len = uses[id] as usize;
+                for idx in (0..len.saturating_sub(1)).rev() {
+                    let src = if idx == 0 { nam.clone() } else { format!("{}r{}", nam, idx - 1) };
+                    let snd = if idx == len - 2 { format!("{}c{}", nam, len - 1) } else { format!("{}r{}", nam, idx) };
+                    let fst = format!("{}c{}", nam, idx);
+                    bod = Dup{fst: fst.into_bytes(), snd: snd.into_bytes(), val: Box::new(Var{nam: src.into_bytes()}), nxt: Box::new(bod)};
+                }
+                Lam{nam: nam.into_bytes(), bod: Box::new(bod)}
+            },
+            Raw::App(fun, arg) => {
+                let fun = Box::new(build(fun, scope, uses, next));
+                let arg = Box::new(build(arg, scope, uses, next));
+                App{fun, arg}
+            },
+            Raw::Par(fst, snd) => {
+                let fst = Box::new(build(fst, scope, uses, next));
+                let snd = Box::new(build(snd, scope, uses, next));
+                Par{fst, snd}
+            },
+            Raw::Var(idx) => {
+                if scope.is_empty() {
+                    return Set;
+                }
+                let id = scope[scope.len() - 1 - idx % scope.len()];
+                let nam = if uses[id] == 1 { format!("v{}", id) } else { format!("v{}c{}", id, next[id]) };
+                next[id] += 1;
+                Var{nam: nam.into_bytes()}
+            },
+            Raw::Set => Set
+        }
+    }
+    let mut uses = Vec::new();
+    count(raw, &mut Vec::new(), &mut uses);
+    build(raw, &mut Vec::new(), &uses, &mut Vec::new())
+}
+
+// Whether a net has a vicious circle: nodes each pointing their principal port at the next one's
+// auxiliary port. Reduction leaves them, and read-back turns them into lets of each other, which
+// terms reduced without nets never have.
+#[allow(dead_code)]
+pub fn vicious_circle(net : &Net) -> bool {
+    let len = (net.nodes.len() / NODE_SIZE) as u32;
+    let freed = |node : u32| net.reuse.contains(&node);
+    (1..len).filter(|node| !freed(*node)).any(|start| {
+        let mut node = start;
+        for _ in 0..len {
+            let next = enter(net, link(node, 0));
+            if port(next) == 0 || addr(next) == 0 {
+                return false;
+            }
+            node = addr(next);
+        }
+        true
+    })
+}
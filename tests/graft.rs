@@ -0,0 +1,91 @@
+extern crate sic;
+
+use sic::error::*;
+use sic::module::*;
+use sic::net::*;
+use sic::term::{alpha_eq, from_net, from_string, to_net, to_string};
+
+// Encodes a module both ways: by grafting its definitions, and by expanding it as a term.
+fn both(src : &str) -> (Net, Net) {
+    let grafted = parse_module(src.as_bytes()).unwrap().to_net(None).unwrap();
+    let expanded = to_net(&from_string(src.as_bytes()).unwrap()).unwrap();
+    (grafted, expanded)
+}
+
+#[test]
+fn grafting_matches_expansion() {
+    let src = "
+        :id \\x x
+        :two \\s \\z = s0 s1 s /s0 /s1 z
+        :app \\f \\x /f x
+        :id \\y /id y
+        :pair \\p //p #Zero /#Succ #Zero
+        //app two id /\\two two pair";
+    let (mut grafted, mut expanded) = both(src);
+    assert_eq!(check_net(&grafted), Ok(()));
+    assert!(nets_equal(&grafted, &expanded));
+    reduce(&mut grafted);
+    reduce(&mut expanded);
+    assert!(alpha_eq(&from_net(&grafted).unwrap(), &from_net(&expanded).unwrap()));
+}
+
+#[test]
+fn many_references() {
+    // A tree of pairs with 1024 copies of `m` as leaves, nested only 10 deep.
+    let mut tree = "m".to_string();
+    for _ in 0..10 {
+        tree = format!("//#Pair {} {}", tree, tree);
+    }
+    let src = format!(":m \\y = a b y //\\c \\d /c d a /\\e e b\n{}", tree);
+    let (mut grafted, mut expanded) = both(&src);
+    assert!(nets_equal(&grafted, &expanded));
+    reduce(&mut grafted);
+    reduce(&mut expanded);
+    assert!(alpha_eq(&from_net(&grafted).unwrap(), &from_net(&expanded).unwrap()));
+}
+
+#[test]
+fn grafts_are_fresh() {
+    let fragment = NetFragment::from_net(&to_net(&from_string(b"\\x /#Wrap x").unwrap()).unwrap());
+    let mut net = root_net();
+    net.ctrs.push((b"#Nil".to_vec(), 0));
+    let fst = net.graft(&fragment);
+    let snd = net.graft(&fragment);
+    assert_ne!(addr(fst), addr(snd));
+    assert_eq!(net.enter(fst), fst);
+    assert_eq!(net.ctrs, vec![(b"#Nil".to_vec(), 0), (b"#Wrap".to_vec(), 1)]);
+    let app = new_node(&mut net, CON);
+    net.connect(link(app, 0), fst);
+    net.connect(link(app, 1), snd);
+    net.connect(link(app, 2), 0);
+    assert_eq!(check_net(&net), Ok(()));
+    reduce(&mut net);
+    assert_eq!(to_string(&from_net(&net).unwrap()), b"/#Wrap \\a /#Wrap a".to_vec());
+}
+
+#[test]
+fn entries_and_errors() {
+    let module = parse_module(b":id \\x x\n:k \\a \\b //#Pair a b\n:bad /#Pair k").unwrap();
+    assert!(nets_equal(&module.to_net(Some(b"id")).unwrap(), &to_net(&from_string(b"\\x x").unwrap()).unwrap()));
+    assert!(matches!(module.to_net(None), Err(SicError::Parse(_))));
+    assert!(matches!(module.to_net(Some(b"bad")), Err(SicError::Affinity(AffinityError::Arity{..}))));
+    assert!(matches!(module.to_net(Some(b"nope")), Err(SicError::Affinity(AffinityError::Unbound(_)))));
+}
+
+#[test]
+fn sharing_shrinks_nets() {
+    let live = |net : &Net| net.nodes.len() / NODE_SIZE - net.reuse.len();
+    let src = ":m \\y //\\c \\d /c d \\e e y\n\\x //#Pair /m x //#Pair m /m m";
+    let module = parse_module(src.as_bytes()).unwrap();
+    let (mut shared, mut copied) = (module.to_net_shared(None).unwrap(), module.to_net(None).unwrap());
+    assert_eq!(check_net(&shared), Ok(()));
+    // Four copies of `m`, against one and the three FAN nodes that share it.
+    let size = live(&to_net(&from_string(b"\\y //\\c \\d /c d \\e e y").unwrap()).unwrap()) - 1;
+    assert_eq!(live(&copied) - live(&shared), 3 * size - 3);
+    reduce(&mut shared);
+    reduce(&mut copied);
+    assert!(alpha_eq(&from_net(&shared).unwrap(), &from_net(&copied).unwrap()));
+    // Definitions with lets are copied anyway.
+    let module = parse_module(b":two \\s \\z = s0 s1 s /s0 /s1 z\n//#Pair two two").unwrap();
+    assert_eq!(live(&module.to_net_shared(None).unwrap()), live(&module.to_net(None).unwrap()));
+}
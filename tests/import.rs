@@ -0,0 +1,81 @@
+extern crate sic;
+
+use std::fs;
+use std::process::Command;
+use sic::data::nat_to;
+use sic::error::*;
+use sic::module::*;
+use sic::term::*;
+
+fn error(code : &str) -> SicError {
+    from_hvm(code.as_bytes()).unwrap_err()
+}
+
+#[test]
+fn export_then_import_is_alpha_equal() {
+    for name in &["id", "add", "twice"] {
+        let module = parse_module(&fs::read(format!("tests/fixtures/export/{}.sic", name)).unwrap()).unwrap();
+        let back = from_hvm(to_hvm(&module).unwrap().as_bytes()).unwrap();
+        assert_eq!(back.defs.len(), module.defs.len(), "{}", name);
+        for ((nam, val), (back_nam, back_val)) in module.defs.iter().zip(&back.defs) {
+            assert_eq!(nam, back_nam);
+            assert!(alpha_eq(val, back_val), "{}", String::from_utf8_lossy(nam));
+        }
+        assert!(alpha_eq(module.main.as_ref().unwrap(), back.main.as_ref().unwrap()), "{}", name);
+    }
+}
+
+#[test]
+fn hand_written_program_evaluates() {
+    let module = from_hvm(&fs::read("tests/fixtures/export/church.hvm").unwrap()).unwrap();
+    let names : Vec<&[u8]> = module.defs.iter().map(|(nam, _)| &nam[..]).collect();
+    assert_eq!(names, vec![&b"add"[..], b"two", b"three"]);
+    let norm = reduce(&from_string(&format_module(&module)).unwrap()).unwrap();
+    assert_eq!(nat_to(&norm), Some(5));
+}
+
+#[test]
+fn binders_are_renamed_apart() {
+    let module = from_hvm("@main = (λx x λx x)".as_bytes()).unwrap();
+    assert_eq!(to_string(module.main.as_ref().unwrap()), b"/\\x x \\x0 x0".to_vec());
+}
+
+#[test]
+fn rejects_what_doesnt_translate() {
+    let message = |err : SicError| match err {
+        SicError::Parse(err) => err.message,
+        err => panic!("unexpected error: {}", err)
+    };
+    assert!(message(error("(Foo a) = a")).contains("pattern-matching rules"));
+    assert!(message(error("@main = #Foo{}")).contains("constructors"));
+    assert!(message(error("@main = (Foo λx x)")).contains("constructors"));
+    assert!(message(error("@main = 42")).contains("numbers"));
+    assert!(message(error("@main = λs ! &A{a b} = s; &B{a b}")).contains("only one label"));
+    assert!(message(error("@f = λx (@f x)\n@main = @f")).contains("recursive"));
+    assert!(message(error("@a = λx x\n@a = λy y")).contains("twice"));
+    match error("@main = λx (x x)") {
+        SicError::Affinity(AffinityError::Duplicated(nam)) => assert_eq!(nam, b"x".to_vec()),
+        err => panic!("unexpected error: {}", err)
+    }
+    match error("@main = (@id λx x)") {
+        SicError::Affinity(AffinityError::Unbound(nam)) => assert_eq!(nam, b"id".to_vec()),
+        err => panic!("unexpected error: {}", err)
+    }
+}
+
+#[test]
+fn cli_imports_and_evaluates() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["import", "tests/fixtures/export/church.hvm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().ends_with("//add two three\n"));
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--syntax", "hvm", "tests/fixtures/export/church.hvm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let norm = from_string(&output.stdout).unwrap();
+    assert_eq!(nat_to(&norm), Some(5));
+}
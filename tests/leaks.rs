@@ -0,0 +1,35 @@
+extern crate sic;
+
+use std::process::Command;
+
+use sic::net::{reduce, unreachable_nodes, Kind};
+use sic::term::{from_string, to_net};
+
+#[test]
+fn erased_arguments_are_left_unreachable() {
+    let mut net = to_net(&from_string(b":id \\x x\n:const \\a \\_ a\n//const id id").unwrap()).unwrap();
+    assert!(unreachable_nodes(&net).is_empty());
+    reduce(&mut net);
+    let leaks = unreachable_nodes(&net);
+    let mut kinds : Vec<Kind> = leaks.iter().map(|node| net.kind(*node)).collect();
+    kinds.sort_by_key(|kind| u32::from(*kind));
+    assert_eq!(kinds, vec![Kind::Era, Kind::Con]);
+}
+
+#[test]
+fn used_arguments_leave_nothing() {
+    let mut net = to_net(&from_string(b":id \\x x\n:apply \\f \\x /f x\n//apply id \\y y").unwrap()).unwrap();
+    reduce(&mut net);
+    assert!(unreachable_nodes(&net).is_empty());
+}
+
+#[test]
+fn leaks_are_counted_by_kind_and_definition() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["net", "--reduced", "--leaks", "tests/fixtures/leaks.sic"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "unreachable: 2 of 4 nodes\n  con: 1\n  era: 1\nfrom definitions:\n  id: 1\n");
+}
@@ -0,0 +1,30 @@
+extern crate sic;
+
+use sic::term::*;
+use sic::module::*;
+use sic::testing::*;
+
+fn relift(code : &str) -> (Term, Module) {
+    let norm = reduce(&from_string(code.as_bytes()).unwrap()).unwrap();
+    let lifted = lambda_lift(&norm);
+    assert_round_trip_net(&norm);
+    let again = reduce(&from_string(&format_module(&lifted)).unwrap()).unwrap();
+    assert!(alpha_eq(&norm, &again), "{} became {}", norm, again);
+    assert_reduction_matches_reference(&from_string(&format_module(&lifted)).unwrap(), 10000);
+    (norm, lifted)
+}
+
+#[test]
+fn lifting_captured_variables() {
+    let (_, lifted) = relift("\\f \\g /f \\x = a b g /a /b x");
+    assert_eq!(lifted.defs.len(), 1);
+    let (_, lifted) = relift("\\f \\x /f \\y /x \\z /y z");
+    assert_eq!(lifted.defs.len(), 2);
+}
+
+#[test]
+fn lifting_preserves_normal_forms() {
+    relift("\\x x");
+    relift(":k \\a \\_ a :i \\x x /k i");
+    relift(":two \\s \\z = s0 s1 s /s0 /s1 z :add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z //add two two");
+}
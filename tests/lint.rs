@@ -0,0 +1,51 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::error::AffinityError;
+use sic::module::{validate_program, Diagnostic};
+
+#[test]
+fn lint_reports_only_unused_definitions() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["lint", "tests/fixtures/lint.sic"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "tests/fixtures/lint.sic: unused definition `const`\ntests/fixtures/lint.sic: unused definition `unused`\n");
+}
+
+#[test]
+fn lint_follows_transitive_uses() {
+    let output = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["lint", "--entry", "unused", "tests/fixtures/lint.sic"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("`const`"));
+    assert!(stdout.contains("`id`") && stdout.contains("`apply`"));
+}
+
+#[test]
+fn every_affinity_problem_is_reported() {
+    let src = b":twice \\f \\x /f /f x\n:id \\x x\n//twice id y";
+    let name = |nam : &str| nam.as_bytes().to_vec();
+    assert_eq!(validate_program(src), vec![
+        Diagnostic::Affinity{def: Some(name("twice")), error: AffinityError::Duplicated(name("f"))},
+        Diagnostic::Affinity{def: None, error: AffinityError::Unbound(name("y"))}
+    ]);
+    assert_eq!(validate_program(b":id \\x x\n/id id"), vec![]);
+}
+
+#[test]
+fn lint_reports_affinity_problems() {
+    let dir = std::env::temp_dir().join("sic-lint-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("affine.sic");
+    std::fs::write(&file, ":dup \\x //x x x\n:erase \\a \\b b\n/dup /erase y").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_sic")).arg("lint").arg(&file).output().unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("in `dup`: variable used more than once: x"));
+    assert!(stdout.contains("in the main term: unbound variable: y"));
+}
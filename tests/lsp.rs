@@ -0,0 +1,135 @@
+// Drives the language server with raw JSON-RPC messages over in-memory pipes.
+
+#![cfg(feature = "lsp")]
+
+extern crate lsp_server;
+extern crate serde_json;
+extern crate sic;
+
+use std::thread;
+use lsp_server::{Connection, Message};
+use serde_json::Value;
+
+const URI : &str = "file:///tmp/main.sic";
+
+// A client talking to a server running on another thread.
+struct Client {
+    connection: Connection,
+    server: Option<thread::JoinHandle<()>>,
+    id: u32
+}
+
+impl Client {
+    fn start() -> Client {
+        let (server, connection) = Connection::memory();
+        let server = thread::spawn(move || sic::lsp::serve(&server).unwrap());
+        let mut client = Client { connection, server: Some(server), id: 0 };
+        client.request("initialize", r#"{"capabilities": {}}"#);
+        client.notify("initialized", "{}");
+        client
+    }
+
+    fn send(&self, json : String) {
+        self.connection.sender.send(serde_json::from_str::<Message>(&json).unwrap()).unwrap();
+    }
+
+    fn notify(&self, method : &str, params : &str) {
+        self.send(format!(r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}}}"#, method, params));
+    }
+
+    // Sends a request and returns the result of its response.
+    fn request(&mut self, method : &str, params : &str) -> Value {
+        self.id += 1;
+        self.send(format!(r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {}}}"#, self.id, method, params));
+        match self.connection.receiver.recv().unwrap() {
+            Message::Response(resp) => {
+                assert!(resp.error.is_none(), "{:?}", resp.error);
+                resp.result.unwrap_or(Value::Null)
+            },
+            msg => panic!("expected a response, got {:?}", msg)
+        }
+    }
+
+    // Waits for the diagnostics the server publishes.
+    fn diagnostics(&self) -> Vec<Value> {
+        match self.connection.receiver.recv().unwrap() {
+            Message::Notification(not) => {
+                assert_eq!(not.method, "textDocument/publishDiagnostics");
+                not.params["diagnostics"].as_array().unwrap().clone()
+            },
+            msg => panic!("expected diagnostics, got {:?}", msg)
+        }
+    }
+
+    fn open(&self, text : &str) -> Vec<Value> {
+        let doc = serde_json::json!({"uri": URI, "languageId": "sic", "version": 1, "text": text});
+        self.notify("textDocument/didOpen", &serde_json::json!({"textDocument": doc}).to_string());
+        self.diagnostics()
+    }
+
+    fn change(&self, text : &str) -> Vec<Value> {
+        let params = serde_json::json!({"textDocument": {"uri": URI, "version": 2}, "contentChanges": [{"text": text}]});
+        self.notify("textDocument/didChange", &params.to_string());
+        self.diagnostics()
+    }
+
+    fn at(&mut self, method : &str, line : u32, character : u32) -> Value {
+        let params = serde_json::json!({"textDocument": {"uri": URI}, "position": {"line": line, "character": character}});
+        self.request(method, &params.to_string())
+    }
+
+    fn stop(mut self) {
+        self.request("shutdown", "null");
+        self.notify("exit", "null");
+        self.server.take().unwrap().join().unwrap();
+    }
+}
+
+// The messages of some diagnostics, with the position each starts at.
+fn summary(diagnostics : &[Value]) -> Vec<(u64, u64, String)> {
+    diagnostics.iter().map(|diagnostic| {
+        let start = &diagnostic["range"]["start"];
+        (start["line"].as_u64().unwrap(), start["character"].as_u64().unwrap(), diagnostic["message"].as_str().unwrap().to_string())
+    }).collect()
+}
+
+#[test]
+fn diagnostics_for_a_broken_file() {
+    let client = Client::start();
+    let found = summary(&client.open(":id \\x x\n:dup \\y /y y\n/id \\z w\n"));
+    assert_eq!(found, vec![
+        (1, 11, "variable `y` is used more than once".to_string()),
+        (2, 5, "unused variable `z`".to_string()),
+        (2, 7, "unbound variable `w`".to_string())
+    ]);
+    let found = summary(&client.change(":id \\x x\n/id \\"));
+    assert_eq!(found, vec![(1, 5, "expected a name".to_string())]);
+    assert!(client.change(":id \\x x\n/id \\z z\n").is_empty());
+    client.stop();
+}
+
+#[test]
+fn definitions_and_hover() {
+    let mut client = Client::start();
+    assert!(client.open(":id \\x x\n(the identity)\n/id \\z z\n").is_empty());
+    let location = client.at("textDocument/definition", 2, 2);
+    assert_eq!(location["uri"], URI);
+    assert_eq!(location["range"]["start"], serde_json::json!({"line": 0, "character": 1}));
+    assert_eq!(location["range"]["end"], serde_json::json!({"line": 0, "character": 3}));
+    let hover = client.at("textDocument/hover", 2, 1);
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains(":id \\x x"), "{}", text);
+    assert!(text.contains("2 nodes"), "{}", text);
+    assert_eq!(client.at("textDocument/definition", 2, 5), Value::Null);
+    client.stop();
+}
+
+#[test]
+fn formatting_a_document() {
+    let mut client = Client::start();
+    client.open(":id   \\x x /id \\z z");
+    let params = serde_json::json!({"textDocument": {"uri": URI}, "options": {"tabSize": 4, "insertSpaces": true}});
+    let edits = client.request("textDocument/formatting", &params.to_string());
+    assert_eq!(edits[0]["newText"], ":id \\x x\n\n/id \\z z\n");
+    client.stop();
+}
@@ -0,0 +1,131 @@
+// The abstract machine agrees with the net reducer, and with the reference reducer, on the terms
+// all of them normalize.
+
+extern crate proptest;
+extern crate sic;
+
+use std::process::Command;
+
+use proptest::prelude::*;
+use sic::data::{app, dup, lam, par, var};
+use sic::error::{AffinityError, LimitError, SicError};
+use sic::net::reduce_limited;
+use sic::term::{alpha_eq, eval_machine, from_net, from_string, to_net, to_string, Term};
+use sic::testing::reduce_reference;
+
+mod generate;
+use generate::*;
+
+// Rewrites a term may take before it's considered not to normalize.
+const FUEL : u32 = 2000;
+
+fn parse(src : &str) -> Term {
+    from_string(src.as_bytes()).unwrap()
+}
+
+fn machine(term : &Term) -> Term {
+    eval_machine(term, FUEL).unwrap().0
+}
+
+// Names `-` the binders no variable refers to. Nets don't always read them back as `-`: the ERA
+// may be reached through another node.
+fn unused_erased(term : &Term) -> Term {
+    fn uses(term : &Term, names : &mut Vec<Vec<u8>>) {
+        match term {
+            Term::Lam{bod, ..} => uses(bod, names),
+            Term::App{fun: a, arg: b} | Term::Par{fst: a, snd: b} | Term::Dup{val: a, nxt: b, ..} | Term::Era{val: a, nxt: b} => {
+                uses(a, names);
+                uses(b, names);
+            },
+            Term::Var{nam} => names.push(nam.clone()),
+            Term::Ctr{..} | Term::Set => {}
+        }
+    }
+    fn erase(term : &Term, names : &[Vec<u8>]) -> Term {
+        let binder = |nam : &Vec<u8>| if names.contains(nam) { nam.clone() } else { b"-".to_vec() };
+        let go = |term : &Term| Box::new(erase(term, names));
+        match term {
+            Term::Lam{nam, bod} => Term::Lam{nam: binder(nam), bod: go(bod)},
+            Term::App{fun, arg} => Term::App{fun: go(fun), arg: go(arg)},
+            Term::Par{fst, snd} => Term::Par{fst: go(fst), snd: go(snd)},
+            Term::Dup{fst, snd, val, nxt} => Term::Dup{fst: binder(fst), snd: binder(snd), val: go(val), nxt: go(nxt)},
+            Term::Era{val, nxt} => Term::Era{val: go(val), nxt: go(nxt)},
+            term => term.clone()
+        }
+    }
+    let mut names = Vec::new();
+    uses(term, &mut names);
+    erase(term, &names)
+}
+
+proptest! {
+    #[test]
+    fn machine_agrees_with_nets(raw in raw()) {
+        let term = affine(&raw);
+        let net = to_net(&term).unwrap();
+        // Only terms whose whole net normalizes, without a vicious circle, which the machine
+        // doesn't build: nets read one back as a term of its own.
+        let mut full = net.clone();
+        prop_assume!(reduce_reference(&mut full, FUEL).is_ok() && !vicious_circle(&full));
+        let mut lazy = net.clone();
+        prop_assume!(reduce_limited(&mut lazy, FUEL).is_ok());
+        let evaluated = eval_machine(&term, FUEL);
+        prop_assume!(evaluated.is_ok());
+        let norm = evaluated.unwrap().0;
+        for net in [&lazy, &full] {
+            prop_assert!(alpha_eq(&norm, &unused_erased(&from_net(net).unwrap())), "{}", term);
+        }
+    }
+}
+
+#[test]
+fn machine_reduces_to_normal_form() {
+    assert_eq!(to_string(&machine(&parse("/\\x x \\y y"))), b"\\a a");
+    assert_eq!(to_string(&machine(&parse("\\x & x \\y y"))), b"\\- \\a a");
+    let twice = parse("//\\s \\z = a b s /a /b z \\x x \\y y");
+    assert_eq!(to_string(&machine(&twice)), b"\\a a");
+    // Pairs are copied and applied side by side.
+    let applied = app(par(lam("x", var("x")), lam("y", var("y"))), lam("z", var("z")));
+    assert!(alpha_eq(&machine(&applied), &par(lam("a", var("a")), lam("b", var("b")))));
+}
+
+#[test]
+fn machine_keeps_free_variables() {
+    assert_eq!(to_string(&machine(&parse("/\\x /x y z"))), b"/z y");
+    // A let of a free variable can't be copied, and stays.
+    assert_eq!(to_string(&machine(&dup("a", "b", var("x"), par(var("a"), var("b"))))), b"= a b x\n| a b");
+}
+
+#[test]
+fn machine_reports_limits_and_duplicates() {
+    let (_, stats) = eval_machine(&parse("/\\x x \\y y"), FUEL).unwrap();
+    assert_eq!((stats.rules, stats.betas), (1, 1));
+    match eval_machine(&parse("/\\x /x x \\y y"), FUEL) {
+        Err(SicError::Affinity(AffinityError::Duplicated(nam))) => assert_eq!(nam, b"x"),
+        res => panic!("not rejected: {:?}", res.map(|(term, _)| term))
+    }
+    let twice = parse("//\\s \\z = a b s /a /b z \\x x \\y y");
+    match eval_machine(&twice, 2) {
+        Err(SicError::Limit(LimitError::Rewrites(2))) => {},
+        res => panic!("not limited: {:?}", res.map(|(term, _)| term))
+    }
+}
+
+#[test]
+fn engines_agree_on_the_command_line() {
+    let run = |engine : &str| Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--engine", engine, "tests/fixtures/even_odd.sic"])
+        .output().unwrap();
+    let (net, machine) = (run("net"), run("machine"));
+    assert!(machine.status.success(), "{}", String::from_utf8_lossy(&machine.stderr));
+    assert_eq!(net.stdout, machine.stdout);
+}
+
+#[test]
+fn bench_compares_engines() {
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["bench", "--engine", "machine", "tests/fixtures/bench"])
+        .output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(!out.stdout.is_empty());
+}
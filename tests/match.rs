@@ -0,0 +1,51 @@
+extern crate sic;
+
+use sic::term::*;
+
+const LIST : &str = "data List {#Nil; #Cons head tail}
+:zero \\s \\z z
+:succ \\n \\s \\z = s0 s1 s /s0 //n s1 z
+";
+
+fn normal(src : &str) -> Term {
+    reduce(&from_string(format!("{}{}", LIST, src).as_bytes()).unwrap()).unwrap()
+}
+
+fn error(src : &str) -> (usize, String) {
+    let src = format!("{}{}", LIST, src);
+    let err = from_string(src.as_bytes()).unwrap_err();
+    (err.index - LIST.len(), err.message)
+}
+
+#[test]
+fn length_of_a_list() {
+    let len = "@rec len = \\l match l {#Nil: zero; #Cons _ t: /succ /len t}\n";
+    let three = normal("/succ /succ /succ zero");
+    assert!(alpha_eq(&normal(&format!("{}/len //#Cons \\x x //#Cons \\y y //#Cons \\z z #Nil", len)), &three));
+    assert!(alpha_eq(&normal(&format!("{}/len #Nil", len)), &normal("zero")));
+}
+
+#[test]
+fn map_over_a_list() {
+    let map = "@rec map = \\f \\l = f0 f1 f match l {#Nil: #Nil; #Cons h t: //#Cons /f0 h //map f1 t}\n";
+    let mapped = normal(&format!("{}//map \\x //#Cons x #Nil //#Cons \\a a //#Cons \\b b //#Cons \\c c #Nil", map));
+    let expected = normal("//#Cons //#Cons \\a a #Nil //#Cons //#Cons \\b b #Nil //#Cons //#Cons \\c c #Nil #Nil");
+    assert!(alpha_eq(&mapped, &expected));
+}
+
+#[test]
+fn keywords_can_be_bound() {
+    assert_eq!(to_string(&from_string(b"\\data \\match /data match").unwrap()), b"\\data \\match /data match".to_vec());
+    assert_eq!(to_string(&from_string(b":match \\x x /match *").unwrap()), b"/\\match#0#x match#0#x *".to_vec());
+    let err = from_string(b"\\f \\x //f data x").unwrap_err();
+    assert!(err.message.contains("`data` starts a declaration"));
+}
+
+#[test]
+fn branches_are_checked() {
+    assert_eq!(error("\\l match l {#Cons h t: h}"), (24, "missing a branch for #Nil".to_string()));
+    assert_eq!(error("\\l match l {#Nil: *; #Nil: *}").0, 21);
+    assert_eq!(error("\\l match l {#Nil: *; #Cons h t: //h h t}").1, "a field is used more than once");
+    assert_eq!(error("\\l match l {#Nil: *; #Cons h: h}").1, "wrong number of fields for the constructor");
+    assert_eq!(error("\\l match l {#Leaf: *}").1, "not a constructor declared by a `data`");
+}
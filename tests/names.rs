@@ -0,0 +1,30 @@
+extern crate sic;
+
+use sic::term::{from_net_named, from_string, reduce_named, to_net_named, to_string};
+
+fn named(src : &str) -> String {
+    let (mut net, free, mut names) = to_net_named(&from_string(src.as_bytes()).unwrap()).unwrap();
+    reduce_named(&mut net, &mut names);
+    String::from_utf8(to_string(&from_net_named(&net, &free, &names).unwrap())).unwrap()
+}
+
+#[test]
+fn names_survive_a_round_trip() {
+    assert_eq!(named("\\f \\x /f x"), "\\f \\x /f x");
+    assert_eq!(named("\\f \\x = y z f /y /z x"), "= y z f\n\\f \\x /y /z x");
+    assert_eq!(named("\\a \\b /b a"), "\\a \\b /b a");
+}
+
+#[test]
+fn names_survive_reduction_where_binders_do() {
+    assert_eq!(named("\\y /\\z z y"), "\\y y");
+    assert_eq!(named("\\c \\k /k /\\z z c"), "\\c \\k /k c");
+}
+
+#[test]
+fn copied_binders_get_new_names() {
+    // Both copies of `\x x` are made by reduction, so neither is `\x x`.
+    assert_eq!(named("= a b \\x x //#Pair a b"), "//#Pair \\a a \\b b");
+    // New names don't clash with the kept ones.
+    assert_eq!(named("\\a = f g \\x x //#Pair /f a g"), "\\a //#Pair a \\b b");
+}
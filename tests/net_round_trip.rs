@@ -0,0 +1,153 @@
+// Property: encoding a term as a net and reading it back, without reducing, gives the same term,
+// up to the names of variables and to where lets are placed (read-back puts them all at the top).
+
+extern crate proptest;
+extern crate sic;
+
+use std::collections::HashMap;
+use proptest::prelude::*;
+use sic::term::*;
+use sic::term::Term::*;
+use sic::data::*;
+use sic::net::{reduce_limited, nets_equal};
+
+mod generate;
+use generate::*;
+
+// Places lets where read-back does. Read-back reads the term without its lets, noting each let the
+// first time one of its variables shows up. Then, repeatedly, it takes the let noted last, reads
+// its value (noting more lets), and wraps the term read so far in it. Lets it never notes are
+// dropped, and unused binders are named `-`.
+fn float(term : &Term) -> Term {
+    // A let, with its value stripped of lets.
+    type Let = (Vec<u8>, Vec<u8>, Term);
+
+    fn uses(term : &Term, counts : &mut HashMap<Vec<u8>, u32>) {
+        match term {
+            Lam{bod, ..} => uses(bod, counts),
+            App{fun: fst, arg: snd} | Par{fst, snd} | Dup{val: fst, nxt: snd, ..} | Era{val: fst, nxt: snd} => {
+                uses(fst, counts);
+                uses(snd, counts);
+            },
+            Var{nam} => *counts.entry(nam.clone()).or_insert(0) += 1,
+            Ctr{..} | Set => {}
+        }
+    }
+    fn strip(term : &Term, used : &HashMap<Vec<u8>, u32>, lets : &mut Vec<Let>) -> Term {
+        let binder = |nam : &Vec<u8>| if used.contains_key(nam) { nam.clone() } else { b"-".to_vec() };
+        match term {
+            Lam{nam, bod} => Lam{nam: binder(nam), bod: Box::new(strip(bod, used, lets))},
+            App{fun, arg} => App{fun: Box::new(strip(fun, used, lets)), arg: Box::new(strip(arg, used, lets))},
+            Par{fst, snd} => Par{fst: Box::new(strip(fst, used, lets)), snd: Box::new(strip(snd, used, lets))},
+            Dup{fst, snd, val, nxt} => {
+                let val = strip(val, used, lets);
+                lets.push((binder(fst), binder(snd), val));
+                strip(nxt, used, lets)
+            },
+            Era{nxt, ..} => strip(nxt, used, lets),
+            Var{..} | Ctr{..} | Set => term.clone()
+        }
+    }
+    fn note(term : &Term, lets : &[Let], seen : &mut Vec<bool>, noted : &mut Vec<usize>) {
+        match term {
+            Lam{bod, ..} => note(bod, lets, seen, noted),
+            App{fun: fst, arg: snd} | Par{fst, snd} | Dup{val: fst, nxt: snd, ..} | Era{val: fst, nxt: snd} => {
+                note(fst, lets, seen, noted);
+                note(snd, lets, seen, noted);
+            },
+            Var{nam} => if let Some(idx) = lets.iter().position(|(fst, snd, _)| fst == nam || snd == nam) {
+                if !seen[idx] {
+                    seen[idx] = true;
+                    noted.push(idx);
+                }
+            },
+            Ctr{..} | Set => {}
+        }
+    }
+    let mut used = HashMap::new();
+    uses(term, &mut used);
+    let mut lets = Vec::new();
+    let mut main = strip(term, &used, &mut lets);
+    let mut seen = vec![false; lets.len()];
+    let mut noted = Vec::new();
+    note(&main, &lets, &mut seen, &mut noted);
+    while let Some(idx) = noted.pop() {
+        let (fst, snd, val) = lets[idx].clone();
+        note(&val, &lets, &mut seen, &mut noted);
+        main = Dup{fst, snd, val: Box::new(val), nxt: Box::new(main)};
+    }
+    main
+}
+
+proptest! {
+    #[test]
+    fn generated_terms_are_affine(raw in raw()) {
+        prop_assert!(to_net(&affine(&raw)).is_ok());
+    }
+
+    #[test]
+    fn net_round_trip(raw in raw()) {
+        let term = affine(&raw);
+        let back = from_net(&to_net(&term).unwrap()).unwrap();
+        prop_assert!(alpha_eq(&back, &float(&term)), "{}\nread back as\n{}", term, back);
+    }
+}
+
+#[test]
+fn lets_are_floated_to_the_top() {
+    let term = from_string(b"\\f \\x = a b f /a /b x").unwrap();
+    assert_eq!(to_string(&float(&term)), b"= a b f\n\\f \\x /a /b x".to_vec());
+    assert!(alpha_eq(&from_net(&to_net(&term).unwrap()).unwrap(), &float(&term)));
+}
+
+// Reduces a term's net by at most `rules` rewrites, checks that reading it back and encoding the
+// result gives the same net, and returns what was read.
+fn partial(term : &Term, rules : u32) -> Vec<u8> {
+    let mut net = to_net(term).unwrap();
+    let _ = reduce_limited(&mut net, rules);
+    let back = from_net(&net).unwrap();
+    assert!(nets_equal(&to_net(&back).unwrap(), &net), "{}", back);
+    to_string(&back)
+}
+
+#[test]
+fn superposed_applications_read_back() {
+    // `\f \g \x /\p /p x |f g`: after the beta, a pair is applied, its FAN facing the CON's
+    // function port. Then the application commutes, copying `x`, and the net is normal.
+    let term = lam("f", lam("g", lam("x", app(lam("p", app(var("p"), var("x"))), par(var("f"), var("g"))))));
+    assert_eq!(partial(&term, 1), b"\\a \\b \\c /| a b c".to_vec());
+    assert_eq!(partial(&term, 2), b"= d e c\n\\a \\b \\c | /a d /b e".to_vec());
+}
+
+#[test]
+fn pairs_under_lets_read_back() {
+    // `\a \b /\p = c d p |c d |a b`: after the beta, a let's value is a pair, their FANs facing.
+    let dup = Dup{fst: b"c".to_vec(), snd: b"d".to_vec(), val: Box::new(var("p")), nxt: Box::new(par(var("c"), var("d")))};
+    let term = lam("a", lam("b", app(lam("p", dup), par(var("a"), var("b")))));
+    assert_eq!(partial(&term, 1), b"= c d | a b\n\\a \\b | c d".to_vec());
+}
+
+// Unused binders read back as `-`, which encodes as `_` does, so a second round trip changes
+// nothing.
+#[test]
+fn unused_binders_round_trip_stably() {
+    let corpus : &[&[u8]] = &[
+        b"\\_ \\x x",
+        b"\\- \\x x",
+        b"\\_ \\_ \\x x",
+        b"\\- \\_ \\- \\x x",
+        b"\\x \\y x",
+        b"\\x = _ b x b",
+        b"\\x = a - x a",
+        b"\\x = - _ x \\y y",
+        b"\\x = a b x \\_ /a b",
+        b"\\f = - b f /\\- b \\_ \\- *",
+        b":k \\a \\_ a\n:e \\_ \\- \\x x\n//k e \\_ \\- \\y y"
+    ];
+    let trip = |term : &Term| from_net(&to_net(term).unwrap()).unwrap();
+    for src in corpus {
+        let once = trip(&from_string(src).unwrap());
+        let twice = trip(&once);
+        assert_eq!(to_string(&once), to_string(&twice), "{}", String::from_utf8_lossy(src));
+    }
+}
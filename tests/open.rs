@@ -0,0 +1,61 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::error::*;
+use sic::net::*;
+use sic::term::{from_string, to_string, to_net, to_net_open, from_net, from_net_open};
+
+// Reduces an open term, returning its normal form as text.
+fn normalize(code : &str) -> String {
+    let (mut net, free) = to_net_open(&from_string(code.as_bytes()).unwrap()).unwrap();
+    reduce(&mut net);
+    String::from_utf8(to_string(&from_net_open(&net, &free).unwrap())).unwrap()
+}
+
+#[test]
+fn free_variables_survive_reduction() {
+    assert_eq!(normalize("/f /f x"), "/f /f x");
+    assert_eq!(normalize("/\\x /f /f x /\\y y a"), "/f /f a");
+    // Reduction goes on under an application of a free variable.
+    assert_eq!(normalize("\\a /f /\\x \\y /y x a"), "\\a /f \\b /b a");
+    // A free variable copied by a let stays under it.
+    assert_eq!(normalize(":two \\s \\z = s0 s1 s /s0 /s1 z\n//two f x"), "= a b f\n/a /b x");
+}
+
+#[test]
+fn erased_open_subterms_vanish() {
+    assert_eq!(normalize("//\\x \\y y /g a f"), "f");
+    // Erasing an open lambda leaves its body's application of `g`, stuck, as garbage.
+    let (mut net, free) = to_net_open(&from_string(b"//\\x \\y y \\z /g z f").unwrap()).unwrap();
+    let stats = reduce_eager(&mut net);
+    assert!(stats.erasures > 0);
+    assert_eq!(to_string(&from_net_open(&net, &free).unwrap()), b"f".to_vec());
+}
+
+#[test]
+fn binders_avoid_free_names() {
+    assert_eq!(normalize("\\x /a x"), "\\b /a b");
+    assert_eq!(normalize("\\x \\y //b a /x y"), "\\c \\d //b a /c d");
+}
+
+#[test]
+fn closed_encoding_still_rejects_free_variables() {
+    let term = from_string(b"/f x").unwrap();
+    assert_eq!(to_net(&term), Err(AffinityError::Unbound(b"f".to_vec())));
+    let (net, _) = to_net_open(&term).unwrap();
+    assert!(matches!(from_net(&net), Err(ReadbackError::Unnamed(_))));
+}
+
+#[test]
+fn open_flag() {
+    let opts = EvalOptions { open: true, ..EvalOptions::default() };
+    assert_eq!(eval_str("/\\x /f x y", &opts).unwrap().printed, "/f y");
+    assert!(eval_str("/\\x /f x y", &EvalOptions::default()).is_err());
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["--open", "tests/fixtures/open.sic"]).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "/f /f a\n");
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg("tests/fixtures/open.sic").output().unwrap();
+    assert_eq!(out.status.code(), Some(65));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unbound variable: f"));
+}
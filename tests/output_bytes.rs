@@ -0,0 +1,56 @@
+extern crate sic;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use sic::data::*;
+use sic::error::ReadbackError;
+use sic::term::from_string;
+
+// Runs `sic --output-bytes` on a program, with `input` on standard input.
+fn run(name : &str, code : &str, args : &[&str], input : &[u8]) -> std::process::Output {
+    let dir = std::env::temp_dir().join("sic-output-bytes-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join(name);
+    std::fs::write(&file, code).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).arg("--output-bytes").args(args)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn bytes_are_written_as_decoded() {
+    let mut written = Vec::new();
+    assert_eq!(write_bytes(&chars_of(b"\0a\xff"), &mut |byte| written.push(byte)), Ok(3));
+    assert_eq!(written, b"\0a\xff");
+    // Where the shape diverges, after the bytes before it.
+    let mut written = Vec::new();
+    let term = from_string(b"\\c \\n = c0 c1 c //c0 \\s \\z z //c1 \\a a n").unwrap();
+    let err = write_bytes(&term, &mut |byte| written.push(byte)).unwrap_err();
+    assert_eq!(written, b"\0");
+    assert_eq!(err.to_string(), "the normal form isn't a list of bytes: byte 1 should be a numeral below 256, but is `\\a a`");
+    let term = from_string(b"\\c \\n = c0 c1 c //c0 \\s \\z z /c1 n").unwrap();
+    match write_bytes(&term, &mut |_| {}) {
+        Err(ReadbackError::Shape{at, ..}) => assert_eq!(at, "the rest of the list after byte 0"),
+        other => panic!("decoded {:?}", other)
+    }
+}
+
+#[test]
+fn identity_filter_round_trips() {
+    let bytes : Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+    let out = run("id.sic", ":id l = l\nid", &["--stdin-arg"], &bytes);
+    assert!(out.status.success());
+    assert_eq!(out.stdout, bytes);
+    let out = run("id.sic", ":id l = l\nid", &["--stdin-arg"], b"");
+    assert!(out.status.success());
+    assert!(out.stdout.is_empty());
+}
+
+#[test]
+fn lambdas_are_refused() {
+    let out = run("lam.sic", "\\x x", &[], b"");
+    assert_eq!(out.status.code(), Some(70));
+    assert!(out.stdout.is_empty());
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "error: the normal form isn't a list of bytes: the term should be a list, `\\c \\n ...`, but is `\\a a`\n");
+}
@@ -0,0 +1,59 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::module::*;
+use sic::term::*;
+
+// Definitions written with parameters, and with the lambdas they stand for.
+const SUGARED : &str = ":compose f g x = /f /g x\n:two s z = = s0 s1 s /s0 /s1 z\n:k x _ = x\n///compose two two \\y y";
+const EXPLICIT : &str = ":compose \\f \\g \\x /f /g x\n:two \\s \\z = s0 s1 s /s0 /s1 z\n:k \\x \\_ x\n///compose two two \\y y";
+
+#[test]
+fn parameters_are_lambdas() {
+    assert_eq!(from_string(SUGARED.as_bytes()).unwrap(), from_string(EXPLICIT.as_bytes()).unwrap());
+    assert_eq!(parse_module(SUGARED.as_bytes()).unwrap().defs, parse_module(EXPLICIT.as_bytes()).unwrap().defs);
+    // A definition's value may still be a variable, even one followed by another term.
+    assert_eq!(eval_str(":id \\x x :i id /i i", &EvalOptions::default()).unwrap().printed, "\\a a");
+    assert_eq!(eval_str(":f %fuel 3 x = x /f \\y y", &EvalOptions::default()).unwrap().printed, "\\a a");
+}
+
+#[test]
+fn sugared_definitions_evaluate_alike() {
+    let sugared = eval_str(SUGARED, &EvalOptions::default()).unwrap();
+    assert_eq!(sugared.printed, eval_str(EXPLICIT, &EvalOptions::default()).unwrap().printed);
+    assert_eq!(sugared.printed, "\\a a");
+    let opts = EvalOptions { entry: Some("k".to_string()), inputs: vec!["\\a a".to_string(), "*".to_string()], ..EvalOptions::default() };
+    assert_eq!(eval_str(SUGARED, &opts).unwrap().printed, "\\a a");
+}
+
+#[test]
+fn formatting_keeps_parameters() {
+    let formatted = format_module(&parse_module(SUGARED.as_bytes()).unwrap());
+    let expected = ":compose f g x = /f /g x\n\n:two s z =\n  = s0 s1 s\n  /s0 /s1 z\n\n:k x _ = x\n\n///compose two two \\y y\n";
+    assert_eq!(String::from_utf8_lossy(&formatted), expected);
+    assert_eq!(format_module(&parse_module(&formatted).unwrap()), formatted);
+    let explicit = format_module(&parse_module(EXPLICIT.as_bytes()).unwrap());
+    assert!(String::from_utf8_lossy(&explicit).starts_with(":compose \\f \\g \\x /f /g x\n"));
+}
+
+#[test]
+fn bad_parameters_are_diagnosed() {
+    let err = from_string(b":compose f g f = /f g\n*").unwrap_err();
+    assert_eq!((err.index, err.message.as_str()), (13, "parameter `f` is listed twice"));
+    let err = parse_module(b":loop x loop = /loop x\n*").unwrap_err();
+    assert_eq!((err.index, err.message.as_str()), (8, "parameter `loop` shadows the definition"));
+    // Wildcards may be repeated.
+    assert!(parse_module(b":snd _ _ y = y\n*").is_ok());
+}
+
+#[test]
+fn desugar_shows_the_lambdas() {
+    let dir = std::env::temp_dir().join("sic-params-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("compose.sic");
+    std::fs::write(&file, ":compose f g x = /f /g x\ncompose").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg("--desugar").arg(&file).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "\\compose#0#f \\compose#0#g \\compose#0#x /compose#0#f /compose#0#g compose#0#x\n");
+}
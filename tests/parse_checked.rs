@@ -0,0 +1,86 @@
+extern crate sic;
+
+use sic::term::*;
+
+// Inputs that used to panic, overflow the stack or loop forever.
+const REGRESSIONS : &[&[u8]] = &[
+    b"",
+    b"\\",
+    b"=",
+    b"= a",
+    b":",
+    b":a",
+    b"(",
+    b"((x)",
+    b")",
+    b"| a b",
+    b"\\x |",
+    b"/\xff\xfe \x00",
+    b"= a b \\x x",
+];
+
+#[test]
+fn regressions_are_errors_or_terms() {
+    for code in REGRESSIONS {
+        let _ = parse_checked(code);
+    }
+    assert_eq!(to_string(&parse_checked(b"| a b").unwrap()), b"| a b".to_vec());
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let mut code = vec![b'/'; 100000];
+    code.extend_from_slice(b"x");
+    assert_eq!(parse_checked(&code).unwrap_err().message, "terms are nested too deeply");
+    let mut code = b"\\x ".repeat(100000);
+    code.extend_from_slice(b"x");
+    assert!(parse_checked(&code).is_err());
+}
+
+#[test]
+fn only_parse_checked_limits_nesting() {
+    let mut code : Vec<u8> = (0..600).flat_map(|idx| format!("\\x{} ", idx).into_bytes()).collect();
+    code.extend_from_slice(b"x599");
+    assert_eq!(parse_checked(&code).unwrap_err().message, "terms are nested too deeply");
+    assert_eq!(to_string(&from_string(&code).unwrap()), code);
+}
+
+#[test]
+fn long_blanks_dont_recurse() {
+    let mut code = b"(".to_vec();
+    code.extend_from_slice(&b" ".repeat(1000000));
+    code.extend_from_slice(b")");
+    code.extend_from_slice(&b"\n".repeat(1000000));
+    code.extend_from_slice(b"\\x x");
+    assert_eq!(to_string(&parse_checked(&code).unwrap()), b"\\x x".to_vec());
+}
+
+#[test]
+fn exponential_definitions_are_an_error() {
+    let mut code = b":a0 \\x x\n".to_vec();
+    for idx in 1..40 {
+        code.extend_from_slice(format!(":a{} /a{} a{}\n", idx, idx - 1, idx - 1).as_bytes());
+    }
+    code.extend_from_slice(b"a39");
+    let err = parse_checked(&code).unwrap_err();
+    assert!(err.message.contains("too large"));
+}
+
+#[test]
+fn random_bytes_never_panic() {
+    // A fixed xorshift stream, biased towards the bytes that mean something to the parser.
+    let alphabet = b"\\/|=:*() \nabx#-_";
+    let mut state : u64 = 0x2545F4914F6CDD1D;
+    for _ in 0..20000 {
+        let mut code = Vec::new();
+        let len = (state % 40) as usize;
+        for _ in 0..len {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            code.push(if state.is_multiple_of(8) { (state >> 8) as u8 } else { alphabet[(state >> 8) as usize % alphabet.len()] });
+        }
+        let _ = parse_checked(&code);
+        state = state.wrapping_add(1);
+    }
+}
@@ -0,0 +1,51 @@
+// Pins what the reducer does on the corpus of fixtures: how many rewrites each program takes, the
+// exact wiring of its normal form (as a hash of the net's words) and the term read back. Changes
+// to the encoding, the rules or read-back that aren't meant to change behavior must keep it.
+
+extern crate sic;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use sic::net;
+use sic::term::{from_net, from_string, to_net, to_string};
+
+const PINNED : &str = "tests/fixtures/pinned.txt";
+
+fn programs(dir : &Path, files : &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            programs(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "sic") {
+            files.push(path);
+        }
+    }
+}
+
+// FNV-1a over the words of a net, in the default layout.
+fn hash(words : &[u32]) -> u64 {
+    words.iter().fold(0xcbf29ce484222325, |hash, word| (hash ^ *word as u64).wrapping_mul(0x100000001b3))
+}
+
+fn describe(src : &[u8]) -> String {
+    let mut net = match from_string(src).map_err(|err| err.to_string()).and_then(|term| to_net(&term).map_err(|err| err.to_string())) {
+        Ok(net) => net,
+        Err(err) => return format!("error: {}", err)
+    };
+    let stats = net::reduce(&mut net);
+    let norm = from_net(&net).unwrap();
+    let text = String::from_utf8(to_string(&norm)).unwrap().replace('\n', "; ");
+    format!("rewrites: {}, net: {:016x}, normal form: {}", stats.rules, hash(&net::wide_words(&net)), text)
+}
+
+#[test]
+fn corpus_reduces_as_pinned() {
+    let mut files = Vec::new();
+    programs(Path::new("tests/fixtures"), &mut files);
+    files.sort();
+    let actual : String = files.iter()
+        .map(|path| format!("{}\n  {}\n", path.display(), describe(&fs::read(path).unwrap())))
+        .collect();
+    let pinned = fs::read_to_string(PINNED).unwrap();
+    assert!(actual == pinned, "the corpus now reduces to:\n{}", actual);
+}
@@ -0,0 +1,46 @@
+extern crate sic;
+
+use sic::net::*;
+use sic::term::{from_net, from_string, to_net, to_net_in};
+
+const PROGRAMS : [&str; 5] = [
+    "/\\x x \\y y",
+    "//\\f \\x = f0 f1 f /f0 /f1 x \\y y \\z z",
+    ":two \\s \\z = s0 s1 s /s0 /s1 z\n//two \\x x \\y y",
+    "= a b \\x x |a b",
+    "|#Foo /#Bar \\x x"
+];
+
+#[test]
+fn pooled_nets_reduce_like_fresh_ones() {
+    let mut pool = NetPool::new();
+    for round in 0..20 {
+        for code in PROGRAMS {
+            let term = from_string(code.as_bytes()).unwrap();
+            let mut fresh = to_net(&term).unwrap();
+            let mut pooled = pool.to_net(&term).unwrap();
+            assert_eq!(pooled, fresh, "net of {} in round {}", code, round);
+            assert_eq!(reduce(&mut pooled), reduce(&mut fresh));
+            assert_eq!(pool.from_net(pooled).unwrap(), from_net(&fresh).unwrap());
+        }
+        assert_eq!(pool.len(), 1);
+    }
+}
+
+#[test]
+fn nets_keep_their_room() {
+    let mut pool = NetPool::new();
+    let mut net = pool.to_net(&from_string(b":two \\s \\z = s0 s1 s /s0 /s1 z\n//two \\x x \\y y").unwrap()).unwrap();
+    reduce(&mut net);
+    let room = net.nodes.capacity();
+    pool.give(net);
+    let net = pool.take();
+    assert_eq!(net, root_net());
+    assert_eq!(net.nodes.capacity(), room);
+    // A term that can't be encoded leaves the net cleared.
+    let mut net = net;
+    assert!(to_net_in(&from_string(b"\\x |x x").unwrap(), &mut net).is_err());
+    assert_eq!(net, root_net());
+    assert!(pool.to_net(&from_string(b"\\x y").unwrap()).is_err());
+    assert_eq!(pool.len(), 1);
+}
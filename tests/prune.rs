@@ -0,0 +1,64 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::module::*;
+
+const PRUNED : &str = ":false \\t \\f f
+
+:not \\b \\t \\f //b f t
+
+(The answer)
+/not false
+";
+
+fn fixture() -> Vec<u8> {
+    std::fs::read("tests/fixtures/prune.sic").unwrap()
+}
+
+#[test]
+fn unreached_definitions_are_dropped() {
+    let (module, dropped) = parse_module_pruned(&fixture(), None, true).unwrap();
+    assert_eq!(String::from_utf8_lossy(&format_module(&module)), PRUNED);
+    let names : Vec<&[u8]> = dropped.iter().map(|nam| &nam[..]).collect();
+    assert_eq!(names, [&b"true"[..], b"broken", b"id", b"k"]);
+    // From a definition, without the main term.
+    let (module, dropped) = parse_module_pruned(&fixture(), Some(b"k"), true).unwrap();
+    assert_eq!(String::from_utf8_lossy(&format_module(&module)), "(Unused)\n:k \\a \\_ a\n");
+    assert_eq!(dropped.len(), 5);
+}
+
+#[test]
+fn broken_definitions_matter_when_reached() {
+    let err = parse_module_pruned(&fixture(), Some(b"broken"), true).unwrap_err();
+    assert_eq!(err.message, "parameter `x` is listed twice");
+    assert!(parse_module(&fixture()).is_err());
+    assert!(matches!(validate_program(&fixture())[..], [Diagnostic::Parse(_)]));
+}
+
+#[test]
+fn pruning_lets_evaluation_through() {
+    let src = String::from_utf8(fixture()).unwrap();
+    assert!(eval_str(&src, &EvalOptions::default()).is_err());
+    let opts = EvalOptions { prune: true, strict: true, ..EvalOptions::default() };
+    assert_eq!(eval_str(&src, &opts).unwrap().printed, "\\a \\- a");
+    let opts = EvalOptions { entry: Some("id".to_string()), inputs: vec!["\\y y".to_string()], ..opts };
+    assert_eq!(eval_str(&src, &opts).unwrap().printed, "\\a a");
+}
+
+#[test]
+fn command_line_prunes() {
+    let dir = std::env::temp_dir().join("sic-prune-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("pruned.sic");
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["prune", "tests/fixtures/prune.sic", "--entry", "main", "--verbose", "-o"]).arg(&file).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "pruned: true, broken, id, k\n");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), PRUNED);
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(["tests/fixtures/prune.sic", "--prune", "--verbose"]).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "\\a \\- a\n");
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "pruned: true, broken, id, k\n");
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg("tests/fixtures/prune.sic").output().unwrap();
+    assert!(!out.status.success());
+}
@@ -0,0 +1,53 @@
+extern crate sic;
+
+use std::fs;
+use std::process::Command;
+use sic::term::*;
+use sic::error::{LimitError, SicError};
+use sic::{eval_str, EvalOptions};
+
+fn normal(src : &str) -> Term {
+    reduce(&from_string(src.as_bytes()).unwrap()).unwrap()
+}
+
+#[test]
+fn recursive_length_of_a_bounded_list() {
+    let src = fs::read_to_string("tests/fixtures/rec.sic").unwrap();
+    let three = normal(":zero \\s \\z z :succ \\n \\s \\z = s0 s1 s /s0 //n s1 z /succ /succ /succ zero");
+    assert!(alpha_eq(&normal(&src), &three));
+}
+
+#[test]
+fn recursion_deeper_than_the_unfoldings_is_an_error() {
+    let src = fs::read_to_string("tests/fixtures/rec.sic").unwrap();
+    let defs = &src[..src.rfind("/len").unwrap()];
+    let list = |len : u32| (0..len).fold("#Nil".to_string(), |list, _| format!("//#Cons * {}", list));
+    let eval = |len : u32| eval_str(&format!("{}/len {}", defs, list(len)), &EvalOptions::default());
+    assert!(eval(REC_DEPTH - 1).is_ok());
+    match eval(REC_DEPTH) {
+        Err(SicError::Limit(LimitError::Recursion(nam))) => assert_eq!(nam, b"len"),
+        other => panic!("expected a recursion error, got {:?}", other.map(|outcome| outcome.printed))
+    }
+}
+
+#[test]
+fn recursive_name_used_once_at_most() {
+    let err = from_string(b"@rec f = \\x //f x f f").unwrap_err();
+    assert_eq!(err.message, "a recursive definition can use its name once at most");
+    assert_eq!(err.index, 9);
+}
+
+#[test]
+fn desugar_prints_the_unfolded_program() {
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["--desugar", "tests/fixtures/rec.sic"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(!stdout.contains("@rec") && !stdout.contains("match"));
+    // Each unfolding matches on its own copy of `l`.
+    assert_eq!(stdout.matches("#l //").count(), REC_DEPTH as usize);
+    assert!(stdout.contains("#TooDeep@len"));
+    assert!(from_string(stdout.as_bytes()).is_ok());
+}
@@ -0,0 +1,270 @@
+extern crate sic;
+
+use sic::net::*;
+use sic::data::par;
+use sic::error::NetError;
+use sic::term::{from_net, from_string, reduce_to_normal_form, to_net};
+
+const ADD : &[u8] = b":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+:three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z
+//add three three";
+
+#[test]
+fn progress_fires_every_stride_rewrites() {
+    let rules = reduce(&mut to_net(&from_string(ADD).unwrap()).unwrap()).rules;
+    for stride in &[1, 2, 5, rules + 1] {
+        let mut seen = Vec::new();
+        let stats = reduce_with_progress(&mut to_net(&from_string(ADD).unwrap()).unwrap(), *stride, &mut |stats : &Stats| seen.push(stats.rules));
+        assert_eq!(stats.rules, rules);
+        assert_eq!(seen.len() as u32, rules / stride);
+        assert!(seen.iter().enumerate().all(|(idx, rules)| *rules == (idx as u32 + 1) * stride));
+    }
+    let mut calls = 0;
+    reduce_with_progress(&mut to_net(&from_string(ADD).unwrap()).unwrap(), 0, &mut |_ : &Stats| calls += 1);
+    assert_eq!(calls, 0);
+}
+
+#[test]
+fn deltas_add_up_to_the_totals() {
+    let stats = reduce(&mut to_net(&from_string(ADD).unwrap()).unwrap());
+    for stride in &[1, 2, 5, stats.rules + 1] {
+        let mut deltas : Vec<Stats> = Vec::new();
+        let total = reduce_deltas(&mut to_net(&from_string(ADD).unwrap()).unwrap(), *stride, &mut |delta : &Stats| deltas.push(delta.clone()));
+        assert_eq!(total, stats);
+        assert!(deltas[..deltas.len() - 1].iter().all(|delta| delta.rules == *stride));
+        let mut sum = deltas[0].clone();
+        for delta in &deltas[1..] {
+            sum.merge(delta);
+        }
+        assert_eq!(sum, stats);
+    }
+}
+
+#[test]
+fn checkpoints_resume_where_they_stopped() {
+    let mut reduced = to_net(&from_string(ADD).unwrap()).unwrap();
+    let stats = reduce(&mut reduced);
+    let mut reducer = Reducer::new(to_net(&from_string(ADD).unwrap()).unwrap());
+    assert!(!reducer.run(stats.rules / 2));
+    let bytes = reducer.to_bytes();
+    drop(reducer);
+    let mut resumed = Reducer::from_bytes(&bytes).unwrap();
+    assert_eq!(resumed.stats().rules, stats.rules / 2);
+    assert!(resumed.run(u32::MAX));
+    assert_eq!(*resumed.stats(), stats);
+    assert!(nets_equal(resumed.net(), &reduced));
+    assert_eq!(from_net(&resumed.into_net()).unwrap(), from_net(&reduced).unwrap());
+    // Damaged checkpoints are refused.
+    let mut damaged = bytes.clone();
+    damaged[40] ^= 1;
+    assert!(matches!(Reducer::from_bytes(&damaged), Err(NetError::Checksum{..})));
+    assert!(Reducer::from_bytes(&bytes[..bytes.len() - 4]).is_err());
+}
+
+#[test]
+fn checkpoints_stopped_on_a_pair_without_a_way_back_are_refused() {
+    let net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let nodes = (net.nodes.len() / NODE_SIZE) as u32;
+    let lam = (1..nodes).find(|node| port(enter(&net, link(*node, PORT_MAIN))) == PORT_MAIN).unwrap();
+    // The walk is put on the active pair, with an empty `exit`, and the checksum made right.
+    let mut bytes = Reducer::new(net).to_bytes();
+    bytes[36..40].copy_from_slice(&link(lam, PORT_MAIN).to_le_bytes());
+    let end = bytes.len() - 4;
+    let crc = crc32(&bytes[..end]);
+    bytes[end..].copy_from_slice(&crc.to_le_bytes());
+    assert!(matches!(Reducer::from_bytes(&bytes), Err(NetError::BadEncoding(_))));
+}
+
+#[test]
+fn steps_end_where_reduce_does() {
+    let mut net = to_net(&from_string(ADD).unwrap()).unwrap();
+    let steps : Vec<Stats> = net.steps().collect();
+    let mut reduced = to_net(&from_string(ADD).unwrap()).unwrap();
+    let stats = reduce(&mut reduced);
+    assert!(steps.iter().enumerate().all(|(idx, step)| step.rules == idx as u32 + 1));
+    let last = steps.last().unwrap();
+    assert_eq!((last.rules, last.erasures, last.max_nodes), (stats.rules, stats.erasures, stats.max_nodes));
+    assert!(nets_equal(&net, &reduced));
+    assert_eq!(net.steps().count(), 0);
+}
+
+#[test]
+fn normal_form_comes_with_its_net() {
+    let (norm, net, stats) = reduce_to_normal_form(&from_string(ADD).unwrap()).unwrap();
+    assert_eq!(from_net(&net).unwrap(), norm);
+    assert_eq!(stats, reduce(&mut to_net(&from_string(ADD).unwrap()).unwrap()));
+    assert!(check_net(&net).is_ok());
+}
+
+#[test]
+fn binary_round_trip() {
+    let mut net = to_net(&from_string(ADD).unwrap()).unwrap();
+    assert_eq!(net_from_bytes(&net_to_bytes(&net)), Ok(net.clone()));
+    reduce(&mut net);
+    assert_eq!(net_from_bytes(&net_to_bytes(&net)), Ok(net.clone()));
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn corrupted_bytes_are_rejected() {
+    let bytes = net_to_bytes(&to_net(&from_string(ADD).unwrap()).unwrap());
+    for idx in 0..bytes.len() {
+        let mut flipped = bytes.clone();
+        flipped[idx] ^= 0x10;
+        match net_from_bytes(&flipped) {
+            Err(NetError::Checksum{..}) => {},
+            other => panic!("flipping byte {} gave {:?}", idx, other)
+        }
+    }
+    assert!(net_from_bytes(&bytes[..bytes.len() - 4]).is_err());
+    assert!(net_from_bytes(&bytes[..8]).is_err());
+}
+
+#[test]
+fn merge_sums_counts_and_keeps_the_peak() {
+    let mut stats = Stats { loops: 10, rules: 4, betas: 2, dupls: 1, annis: 1, erasures: 1, max_nodes: 30 };
+    stats.merge(&Stats { loops: 5, rules: 3, betas: 1, dupls: 2, annis: 0, erasures: 2, max_nodes: 12 });
+    assert_eq!(stats, Stats { loops: 15, rules: 7, betas: 3, dupls: 3, annis: 1, erasures: 3, max_nodes: 30 });
+    stats.merge(&Stats { max_nodes: 40, ..Stats::default() });
+    assert_eq!(stats.max_nodes, 40);
+}
+
+#[test]
+fn display_labels_each_count() {
+    let stats = Stats { loops: 10, rules: 4, betas: 2, dupls: 1, annis: 1, erasures: 1, max_nodes: 30 };
+    assert_eq!(stats.to_string(), "rules: 4, betas: 2, dupls: 1, annis: 1, erasures: 1, max_nodes: 30");
+    assert!(format!("{:?}", stats).starts_with("Stats { loops: 10, "));
+}
+
+#[test]
+fn discarding_counts_erasures() {
+    const DEFS : &str = ":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+:three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z";
+    let net = |main : &str| to_net(&from_string(format!("{}\n{}", DEFS, main).as_bytes()).unwrap()).unwrap();
+    assert_eq!(reduce(&mut net("//add three three")).erasures, 0);
+    // Applying a set erases the application; its argument is left as garbage.
+    assert_eq!(reduce(&mut net("/\\f /f //add three three *")).erasures, 1);
+    // `reduce_eager` collects the garbage too.
+    let stats = reduce_eager(&mut net("//\\x \\y y //add three three \\a a"));
+    assert!(stats.erasures > 10 && stats.erasures < stats.rules);
+}
+
+// Whether the part of a net behind a port of the root's pair still has active pairs.
+fn pending(net : &Net, side : u32) -> bool {
+    let pair = addr(enter(net, 0));
+    let mut seen = vec![0, pair];
+    let mut visit = vec![addr(enter(net, link(pair, side)))];
+    while let Some(node) = visit.pop() {
+        if seen.contains(&node) {
+            continue;
+        }
+        seen.push(node);
+        let other = enter(net, link(node, PORT_MAIN));
+        if port(other) == PORT_MAIN && addr(other) != pair && has_rule(kind(net, node), kind(net, addr(other))) {
+            return true;
+        }
+        visit.extend((0..3).map(|slot| addr(enter(net, link(node, slot)))));
+    }
+    false
+}
+
+#[test]
+fn fair_reduction_shares_rewrites() {
+    // The first element takes hundreds of rewrites, the second, apart from it, a few.
+    let long = b":c1 \\s \\z = a b s /a /b z
+:c2 \\s /c1 /c1 s
+:c3 \\s /c1 /c2 s
+:c4 \\s /c1 /c3 s
+:c5 \\s /c1 /c4 s
+:c6 \\s /c1 /c5 s
+//c6 \\p \\k /k p *";
+    let term = par(from_string(long).unwrap(), from_string(ADD).unwrap());
+    let mut lazy = to_net(&term).unwrap();
+    assert!(reduce_limited(&mut lazy, 100).is_err());
+    assert!(pending(&lazy, PORT_AUX1) && pending(&lazy, PORT_AUX2));
+    let mut fair = to_net(&term).unwrap();
+    assert!(reduce_fair(&mut fair, 100, 4).is_err());
+    assert!(pending(&fair, PORT_AUX1) && !pending(&fair, PORT_AUX2));
+    // Given enough rewrites, it ends where other reductions do.
+    let (norm, _, _) = reduce_to_normal_form(&from_string(ADD).unwrap()).unwrap();
+    let mut net = to_net(&from_string(ADD).unwrap()).unwrap();
+    reduce_fair(&mut net, u32::MAX, 4).unwrap();
+    assert_eq!(from_net(&net).unwrap(), norm);
+}
+
+#[test]
+#[should_panic]
+fn entering_past_the_end_panics() {
+    let net = to_net(&from_string(b"\\x x").unwrap()).unwrap();
+    enter(&net, link((net.nodes.len() / NODE_SIZE) as u32, PORT_MAIN));
+}
+
+#[test]
+fn interactions_count_active_pairs() {
+    for code in [&b"\\x x"[..], b"/\\x x \\y y", b"//\\x \\y y \\a a \\b b", b"| /\\x x * /\\y y *", ADD] {
+        let mut net = to_net(&from_string(code).unwrap()).unwrap();
+        assert_eq!(net.interactions(), active_pairs(&net).len());
+        // Partway through, annihilated nodes still face each other, but are freed.
+        while net.steps().next().is_some() {
+            assert_eq!(net.interactions(), active_pairs(&net).len());
+        }
+        reduce_eager(&mut net);
+        assert_eq!(net.interactions(), 0);
+    }
+}
+
+#[test]
+fn shared_snapshots_are_independent() {
+    let net = to_net(&from_string(ADD).unwrap()).unwrap();
+    let mut shared = SharedNet::new(net.clone());
+    let snapshot = shared.clone();
+    assert!(snapshot.shares(&shared));
+    assert_eq!(*snapshot, net);
+    // Reducing one copies it, leaving the snapshot as the net was.
+    reduce(shared.make_mut());
+    assert!(!snapshot.shares(&shared));
+    assert_eq!(*snapshot, net);
+    let mut norm = net.clone();
+    reduce(&mut norm);
+    assert_eq!(*shared, norm);
+    assert_eq!(snapshot.into_net(), net);
+    assert_eq!(shared.into_net(), norm);
+}
+
+#[test]
+fn bounded_reduction_stops_at_the_bound() {
+    // Each `dup` doubles the tree of identities the normal form is, to 4096 of them.
+    let code = format!(":dup \\t = t0 t1 t \\f //f t0 t1\n{}\\y y", "/dup ".repeat(12));
+    let net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    let mut partial = net.clone();
+    let stats = reduce_bounded(&mut partial, 1000).unwrap_err();
+    assert!(stats.max_nodes <= 1000 && node_count(&partial) > 990);
+    // The partial net is consistent, reads back, and reduces on to the normal form.
+    assert_eq!(check_net(&partial), Ok(()));
+    assert!(from_net(&partial).is_ok());
+    let mut norm = net.clone();
+    assert!(reduce(&mut norm).max_nodes > 8000);
+    reduce(&mut partial);
+    assert_eq!(from_net(&partial).unwrap(), from_net(&norm).unwrap());
+    // Within the bound, it's a full reduction.
+    let mut small = to_net(&from_string(ADD).unwrap()).unwrap();
+    let rules = reduce(&mut small.clone()).rules;
+    assert_eq!(reduce_bounded(&mut small, 1000).map(|stats| stats.rules), Ok(rules));
+}
+
+#[test]
+#[should_panic]
+fn reusing_a_node_past_the_end_panics() {
+    let mut net = root_net();
+    net.reuse.push(50_000_000);
+    new_node(&mut net, CON);
+}
+
+#[test]
+#[should_panic]
+fn reducing_a_link_past_the_end_panics() {
+    let mut net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let past = link((net.nodes.len() / NODE_SIZE) as u32 + 1000, PORT_AUX1);
+    net.nodes[slot(link(1, PORT_AUX2))] = past;
+    reduce(&mut net);
+}
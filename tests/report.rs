@@ -0,0 +1,114 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::net::*;
+use sic::report::*;
+use sic::term::{from_string, to_net};
+
+// Checks that the tags of an HTML fragment are balanced, returning how many elements it has.
+fn balanced(html : &str) -> usize {
+    let mut open : Vec<&str> = Vec::new();
+    let mut count = 0;
+    for tag in html.split('<').skip(1).map(|rest| &rest[..rest.find('>').unwrap()]) {
+        let name = tag.trim_start_matches('/').split([' ', '/']).next().unwrap();
+        if tag.starts_with('!') || tag.ends_with('/') || name == "meta" || name == "input" {
+            count += 1;
+        } else if tag.starts_with('/') {
+            assert_eq!(open.pop(), Some(name), "unbalanced </{}>", name);
+        } else {
+            open.push(name);
+            count += 1;
+        }
+    }
+    assert!(open.is_empty(), "unclosed {:?}", open);
+    count
+}
+
+#[test]
+fn text_is_escaped() {
+    assert_eq!(escape("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+}
+
+#[test]
+fn terms_are_highlighted() {
+    let html = term_html(&from_string(b"\\x = a b x |a b").unwrap());
+    balanced(&html);
+    assert!(html.starts_with("<pre class=\"term\"><span class=\"op\">\\</span><span class=\"name\">x</span>"));
+    assert_eq!(html.matches("class=\"op\"").count(), 3);
+    assert_eq!(html.matches("class=\"name\"").count(), 6);
+}
+
+#[test]
+fn tables_hold_the_counts() {
+    let net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let mut norm = net.clone();
+    let stats = reduce(&mut norm);
+    let html = stats_table(&stats);
+    assert_eq!(balanced(&html), 1 + 7 * 3);
+    assert!(html.contains("<tr><th>rewrites</th><td>1</td></tr>"));
+    assert!(html.contains("<tr><th>beta reductions</th><td>1</td></tr>"));
+    // Copying a lambda duplicates it, then annihilates the fans around its variable.
+    let stats = reduce(&mut to_net(&from_string(b"= a b \\x x \\f //f a b").unwrap()).unwrap());
+    let html = stats_table(&stats);
+    assert!(html.contains("<tr><th>duplications</th><td>1</td></tr>"));
+    assert!(html.contains("<tr><th>annihilations</th><td>1</td></tr>"));
+    let html = census_table(&net, &norm);
+    balanced(&html);
+    assert!(html.contains("<tr><th>CON</th><td>3</td><td>1</td></tr>"));
+    assert!(html.contains("<tr><th>ROOT</th><td>1</td><td>1</td></tr>"));
+}
+
+#[test]
+fn charts_and_drawings() {
+    let html = size_chart(&[4, 6, 8, 6, 2]);
+    balanced(&html);
+    assert!(html.contains("points=\"0.0,100.0 150.0,50.0 300.0,0.0 450.0,50.0 600.0,150.0\""));
+    assert!(html.contains(">4 rewrites<"));
+    // Long runs are sampled.
+    let sizes : Vec<u32> = (0..10000).collect();
+    assert!(size_chart(&sizes).matches(',').count() <= 520);
+    let net = to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap();
+    let html = net_svg(&net);
+    balanced(&html);
+    assert_eq!(html.matches("<circle").count(), 4);
+    assert_eq!(html.matches("<line").count(), 6);
+    let html = slider(&[net.clone(), net]);
+    balanced(&html);
+    assert_eq!(html.matches("class=\"frame\"").count(), 2);
+    assert!(html.contains("max=\"1\""));
+}
+
+#[test]
+fn small_runs_keep_snapshots() {
+    let run = record("/\\x x \\y y", to_net(&from_string(b"/\\x x \\y y").unwrap()).unwrap(), u32::MAX);
+    assert!(run.completed);
+    assert_eq!((run.sizes.clone(), run.snapshots.len()), (vec![4, 2], 2));
+    let code = format!(":dup \\t = t0 t1 t \\f //f t0 t1\n{}\\y y", "/dup ".repeat(6));
+    let run = record(&code, to_net(&from_string(code.as_bytes()).unwrap()).unwrap(), u32::MAX);
+    assert!(run.snapshots.is_empty());
+    assert_eq!(run.sizes.len() as u32, run.stats.rules + 1);
+    let run = record(&code, to_net(&from_string(code.as_bytes()).unwrap()).unwrap(), 10);
+    assert!(!run.completed);
+    assert!(to_html(&run).contains("<h2>Partial result</h2>"));
+}
+
+#[test]
+fn command_line_writes_a_page() {
+    let dir = std::env::temp_dir().join("sic-report-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let (file, page) = (dir.join("three.sic"), dir.join("three.html"));
+    std::fs::write(&file, "//\\x \\y /x y \\a a (<b>) \\b b").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).arg("--report").arg(&page).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "\\a a\n");
+    let html = std::fs::read_to_string(&page).unwrap();
+    balanced(&html);
+    assert!(html.starts_with("<!DOCTYPE html>\n<html>"));
+    assert!(html.contains("(&lt;b&gt;)"));
+    assert!(html.contains("<tr><th>rewrites</th><td>3</td></tr>"));
+    assert!(html.contains("<tr><th>beta reductions</th><td>3</td></tr>"));
+    assert!(html.contains("<tr><th>most nodes</th><td>8</td></tr>"));
+    assert!(html.contains(">3 rewrites<"));
+    assert_eq!(html.matches("class=\"frame\"").count(), 4);
+    assert!(!html.contains("src=") && !html.contains("href="));
+}
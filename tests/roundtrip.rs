@@ -0,0 +1,47 @@
+// Checks that the printer and the parser agree: for every program in the corpus, printing the
+// parsed term and parsing it again gives an alpha-equivalent term, with the same normal form.
+
+extern crate sic;
+
+use std::fs;
+use sic::term::*;
+use sic::testing::*;
+
+const CORPUS : &str = "tests/fixtures/roundtrip";
+
+// Returns a description of the first mismatch, if any.
+fn check(src : &[u8]) -> Result<(), String> {
+    let term = from_string(src).map_err(|err| format!("doesn't parse: {}", err))?;
+    check_round_trip_parse(&term)?;
+    let again = from_string(&to_string(&term)).unwrap();
+    let norm = reduce(&term).map_err(|err| format!("doesn't reduce: {}", err))?;
+    let again = reduce(&again).map_err(|err| format!("reparsed term doesn't reduce: {}", err))?;
+    if !alpha_eq(&norm, &again) {
+        return Err(format!("normal forms differ\n  parsed:   {}\n  reparsed: {}", norm, again));
+    }
+    check_round_trip_net(&norm)?;
+    check_reduction_matches_reference(&term, 10000)
+}
+
+#[test]
+fn corpus_round_trips() {
+    let mut files : Vec<_> = fs::read_dir(CORPUS).unwrap().map(|entry| entry.unwrap().path()).collect();
+    files.sort();
+    assert!(!files.is_empty());
+    let failures : Vec<String> = files.iter()
+        .filter_map(|path| check(&fs::read(path).unwrap()).err().map(|err| format!("{}: {}", path.display(), err)))
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+// Every prefix of a program in the corpus either parses or fails with an error, rather than making
+// the parser loop or overflow its stack.
+#[test]
+fn prefixes_parse_or_fail() {
+    for entry in fs::read_dir(CORPUS).unwrap() {
+        let src = fs::read(entry.unwrap().path()).unwrap();
+        for len in 0..src.len() + 1 {
+            let _ = from_string(&src[..len]);
+        }
+    }
+}
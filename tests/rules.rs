@@ -0,0 +1,52 @@
+extern crate sic;
+
+use std::fs;
+use std::process::Command;
+use sic::net::*;
+use sic::rules::*;
+
+const PAIRS : [(&str, &str); 8] = [("era", "era"), ("era", "con"), ("era", "fan"), ("era", "ctr"), ("con", "con"), ("con", "fan"), ("fan", "fan"), ("fan", "ctr")];
+
+fn sic(args : &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(args).output().unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+fn golden(a : &str, b : &str) -> String {
+    fs::read_to_string(format!("tests/fixtures/rules/{}_{}.txt", a, b)).unwrap()
+}
+
+#[test]
+fn diagrams_match_golden_files() {
+    for (a, b) in &PAIRS {
+        assert_eq!(sic(&["rules", "--pair", a, b]), golden(a, b), "{} >< {}", a, b);
+    }
+    let all : Vec<String> = PAIRS.iter().map(|(a, b)| golden(a, b)).collect();
+    assert_eq!(sic(&["rules"]), all.join("\n"));
+}
+
+#[test]
+fn rules_follow_rewrite() {
+    for (a, b) in pairs() {
+        let (mut net, x, y) = active_pair(a, b);
+        let before = node_count(&net);
+        rewrite(&mut net, x, y);
+        assert_eq!(node_count(&net) < before, a == b);
+    }
+    assert!(show_rule(FAN, CON).starts_with("FAN >< CON: commute\n"));
+}
+
+#[test]
+fn constructors_are_inert_against_lambdas_and_constructors() {
+    assert!(pairs().iter().all(|(a, b)| has_rule(*a, *b)));
+    assert_eq!(show_rule(CON, CTR), "CON >< CTR: no rule, the pair is inert\n");
+    assert_eq!(sic(&["rules", "--pair", "ctr", "ctr", "--example"]), "CTR >< CTR: no rule, the pair is inert\n");
+}
+
+#[test]
+fn example_prints_both_nets() {
+    let out = sic(&["rules", "--pair", "con", "con", "--example"]);
+    assert!(out.starts_with(&golden("con", "con")));
+    assert!(out.contains("  net before:\n") && out.contains("  net after:\n"));
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "serde")]
+
+extern crate bincode;
+extern crate serde_json;
+extern crate sic;
+
+use sic::net::*;
+use sic::term::{from_string, to_net, from_net, to_string};
+
+fn sample() -> Net {
+    to_net(&from_string(b":two \\s \\z = s0 s1 s /s0 /s1 z //two \\x x \\y y").unwrap()).unwrap()
+}
+
+#[test]
+fn json_round_trip() {
+    let net = sample();
+    let json = serde_json::to_string(&net).unwrap();
+    assert!(json.starts_with("{\"version\":2,"));
+    let back : Net = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, net);
+}
+
+#[test]
+fn bincode_round_trip() {
+    let mut net = sample();
+    reduce(&mut net);
+    let bytes = bincode::serialize(&net).unwrap();
+    let back : Net = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(back, net);
+    assert_eq!(to_string(&from_net(&back).unwrap()), b"\\a a".to_vec());
+}
+
+#[test]
+fn rejects_inconsistent_links() {
+    // Node 1's port 0 points to the root, but the root points to itself.
+    let json = "{\"version\":2,\"nodes\":[0,2,1,4,0,5,6,1],\"reuse\":[]}";
+    let err = serde_json::from_str::<Net>(json).unwrap_err();
+    assert!(err.to_string().contains("malformed net"));
+    let json = "{\"version\":3,\"nodes\":[0,2,1,4],\"reuse\":[]}";
+    let err = serde_json::from_str::<Net>(json).unwrap_err();
+    assert!(err.to_string().contains("unsupported net version 3"));
+}
+
+#[test]
+fn stats_field_names() {
+    let mut net = sample();
+    let stats = reduce(&mut net);
+    let json = serde_json::to_value(&stats).unwrap();
+    let mut fields : Vec<&str> = json.as_object().unwrap().keys().map(|key| key.as_str()).collect();
+    fields.sort();
+    assert_eq!(fields, vec!["annis", "betas", "dupls", "erasures", "loops", "max_nodes", "rules"]);
+    assert_eq!(json["rules"], stats.rules);
+    let back : Stats = serde_json::from_value(json).unwrap();
+    assert_eq!(back, stats);
+}
@@ -0,0 +1,58 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::term::{from_string, simplify, to_string, Term};
+
+fn simplified(src : &str) -> String {
+    String::from_utf8(to_string(&simplify(&from_string(src.as_bytes()).unwrap()))).unwrap()
+}
+
+fn var(nam : &str) -> Box<Term> {
+    Box::new(Term::Var{nam: nam.as_bytes().to_vec()})
+}
+
+#[test]
+fn identities_collapse() {
+    assert_eq!(simplified("/\\x x \\y y"), "\\y y");
+    assert_eq!(simplified("//\\x x \\y y \\z z"), "\\z z");
+    assert_eq!(simplified("/\\x \\y x \\z z"), "/\\x \\y x \\z z");
+}
+
+#[test]
+fn erased_copies_go_away() {
+    assert_eq!(simplified("= a b * //#Pair a b"), "//#Pair * *");
+    assert_eq!(simplified("/* \\x x"), "*");
+    assert_eq!(simplified("= a b * /a b"), "*");
+    // Erasing `x` would leave its lambda's variable dangling.
+    assert_eq!(simplified("\\x /* x"), "\\x /* x");
+}
+
+#[test]
+fn lets_of_pairs_paired_back_collapse() {
+    let dup = |val : Box<Term>, fst : &str, snd : &str| Term::Dup {
+        fst: b"a".to_vec(), snd: b"b".to_vec(), val, nxt: Box::new(Term::Par{fst: var(fst), snd: var(snd)})
+    };
+    let pair = || Box::new(Term::Par{fst: var("c"), snd: var("d")});
+    let under = |bod : Term| Term::Lam{nam: b"c".to_vec(), bod: Box::new(Term::Lam{nam: b"d".to_vec(), bod: Box::new(bod)})};
+    assert_eq!(to_string(&simplify(&under(dup(pair(), "a", "b")))), b"\\c \\d | c d".to_vec());
+    assert_eq!(simplify(&under(dup(pair(), "b", "a"))), under(dup(pair(), "b", "a")));
+    // A let copies a lambda rather than splitting it: `|\x x \x x`, not `\x x`.
+    let lam = Box::new(Term::Lam{nam: b"x".to_vec(), bod: var("x")});
+    assert_eq!(simplify(&dup(lam.clone(), "a", "b")), dup(lam, "a", "b"));
+}
+
+#[test]
+fn partial_results_are_simplified() {
+    let file = std::env::temp_dir().join(format!("sic_simplify_{}.sic", std::process::id()));
+    std::fs::write(&file, "/\\x x \\y y").unwrap();
+    let run = |simplify : bool| {
+        let mut args = vec![file.to_str().unwrap(), "--max-rewrites", "0"];
+        if simplify {
+            args.push("--simplify");
+        }
+        let out = Command::new(env!("CARGO_BIN_EXE_sic")).args(args).output().unwrap();
+        String::from_utf8(out.stdout).unwrap()
+    };
+    assert_eq!(run(false), "/\\a a \\b b\n");
+    assert_eq!(run(true), "\\b b\n");
+}
@@ -0,0 +1,80 @@
+extern crate sic;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use sic::{eval_str, EvalOptions};
+use sic::data::*;
+use sic::eval::Decode;
+use sic::net::reduce;
+use sic::term::{alpha_eq, from_net, from_string, to_net};
+
+// A few kilobytes of input. The bytes are small, so their numerals are too.
+fn text() -> Vec<u8> {
+    (0..2048u32).map(|idx| (idx * 7 % 13) as u8).collect()
+}
+
+// Runs a test on a thread with a stack large enough for the long lists it reads back.
+fn deep<F : FnOnce() + Send + 'static>(test : F) {
+    std::thread::Builder::new().stack_size(1 << 28).spawn(test).unwrap().join().unwrap();
+}
+
+// Reverses a list, as a list of constructors `#C`, ending in `#N`: Church lists read back from a
+// fold are left with their copies unresolved, but constructors are copied whole.
+const REVERSE : &str = ":rev l = ///l \\x \\k \\acc /k //#C x acc \\i i #N\nrev";
+
+#[test]
+fn bytes_are_built_in_the_net() {
+    for bytes in [&b""[..], b"a", b"hi\0\xff"] {
+        let mut built = to_net(&from_string(b"\\l l").unwrap()).unwrap();
+        apply_bytes(&mut built, bytes);
+        reduce(&mut built);
+        assert_eq!(bytes_of(&from_net(&built).unwrap()), Some(bytes.to_vec()));
+    }
+}
+
+#[test]
+fn identity_gives_the_input_back() {
+    deep(identity);
+}
+
+fn identity() {
+    let opts = EvalOptions { bytes_input: Some(text()), decode: Decode::String, ..EvalOptions::default() };
+    assert_eq!(eval_str(":id l = l\nid", &opts).unwrap().printed.as_bytes(), &text()[..]);
+    // After any inputs.
+    let opts = EvalOptions { inputs: vec!["\\a a".to_string()], ..opts };
+    assert_eq!(eval_str(":snd _ l = l\nsnd", &opts).unwrap().printed.as_bytes(), &text()[..]);
+}
+
+#[test]
+fn reversing_a_list() {
+    deep(reverse);
+}
+
+fn reverse() {
+    let opts = EvalOptions { bytes_input: Some(text()), ..EvalOptions::default() };
+    let reversed = eval_str(REVERSE, &opts).unwrap().term;
+    // The input reversed beforehand, turned into constructors in order.
+    let mut bytes = text();
+    bytes.reverse();
+    let opts = EvalOptions { bytes_input: Some(bytes), ..EvalOptions::default() };
+    let expected = eval_str(":ctrs l = //l \\x \\acc //#C x acc #N\nctrs", &opts).unwrap().term;
+    assert!(alpha_eq(&reversed, &expected));
+    // Small lists decode.
+    let opts = EvalOptions { bytes_input: Some(b"\x01\x02\x03".to_vec()), expand_dups: Some(u64::MAX), ..EvalOptions::default() };
+    let printed = eval_str(REVERSE, &opts).unwrap().printed;
+    assert_eq!(printed, "//#C \\a \\b /a /a /a b //#C \\f \\g /f /f g //#C \\j \\k /j k #N");
+}
+
+#[test]
+fn command_line_reads_stdin() {
+    let dir = std::env::temp_dir().join("sic-stdin-arg-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("id.sic");
+    std::fs::write(&file, ":id l = l\nid").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).args(["--stdin-arg", "--decode", "string"])
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(&text()).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(out.stdout, [&text()[..], b"\n"].concat());
+}
@@ -0,0 +1,49 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::{eval_str, EvalOptions};
+use sic::error::*;
+use sic::module::*;
+use sic::term::*;
+
+#[test]
+fn trailing_garbage_is_an_error() {
+    let err = parse_all(b"\\x x )").unwrap_err();
+    assert_eq!((err.index, err.message.as_str()), (5, "unexpected input after the term"));
+    let (rest, term) = parse_one(b"\\x x )").unwrap();
+    assert_eq!((rest, to_string(&term)), (&b" )"[..], b"\\x x".to_vec()));
+    assert_eq!(parse_module_all(b":id \\x x\n/id y\n)").unwrap_err().index, 15);
+}
+
+#[test]
+fn trailing_comments_are_fine() {
+    assert_eq!(to_string(&parse_all(b"\\x x (the identity)\n\n((nested) comment) ").unwrap()), b"\\x x".to_vec());
+    assert!(parse_module_all(b":id \\x x\n/id y (done)\n").is_ok());
+}
+
+#[test]
+fn a_second_term_is_an_error() {
+    let err = parse_all(b"/\\x x \\y y\n\\z z").unwrap_err();
+    assert_eq!(err.index, 11);
+    assert_eq!(err.to_string(), "parse error at byte 11: unexpected input after the term");
+    let strict = EvalOptions { strict: true, ..EvalOptions::default() };
+    match eval_str("/\\x x \\y y\n\\z z", &strict) {
+        Err(SicError::Parse(err)) => assert_eq!(err.index, 11),
+        other => panic!("expected a parse error, got {:?}", other)
+    }
+    assert_eq!(eval_str("/\\x x \\y y\n\\z z", &EvalOptions::default()).unwrap().printed, "\\a a");
+}
+
+#[test]
+fn cli_is_strict_unless_lenient() {
+    let dir = std::env::temp_dir().join("sic-strict-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("two.sic");
+    std::fs::write(&file, ":id \\x x\n/id id\n/id id\n").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).output().unwrap();
+    assert_eq!(out.status.code(), Some(65));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("byte 16: unexpected input after the main term"));
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg("--lenient").arg(&file).output().unwrap();
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "\\a a\n");
+}
@@ -0,0 +1,17 @@
+#![cfg(feature = "trace-log")]
+
+use std::process::Command;
+
+#[test]
+fn rewrites_are_logged() {
+    let out = Command::new(env!("CARGO_BIN_EXE_sic"))
+        .args(["-s", "tests/fixtures/roundtrip/application.sic"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let logged : Vec<&str> = stderr.lines().filter(|line| line.starts_with("rewrite: ")).collect();
+    assert_eq!(logged.len(), 3);
+    assert!(logged.iter().all(|line| line.contains(" ~ ")));
+    assert!(logged[0].starts_with("rewrite: Con "));
+}
@@ -0,0 +1,51 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::error::*;
+use sic::term::*;
+
+// Runs `sic --trace-terms` on a program, with more arguments.
+fn trace(name : &str, code : &str, args : &[&str]) -> std::process::Output {
+    let dir = std::env::temp_dir().join("sic-trace-terms-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join(name);
+    std::fs::write(&file, code).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_sic")).arg("--trace-terms").args(args).arg(&file).output().unwrap()
+}
+
+#[test]
+fn each_rewrite_is_read_back() {
+    let out = trace("three.sic", "//\\x \\y /x y \\a a \\b b", &[]);
+    assert!(out.status.success());
+    let expected = "(0)\n//\\a \\b /a b \\c c \\d d\n(1)\n/\\a /\\b b a \\c c\n(2)\n/\\a a \\b b\n(3)\n\\a a\n";
+    assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+    // Between steps, only every other term is read back, and the normal form always is.
+    let out = trace("three.sic", "//\\x \\y /x y \\a a \\b b", &["--trace-terms-every", "2"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "(0)\n//\\a \\b /a b \\c c \\d d\n(2)\n/\\a a \\b b\n(3)\n\\a a\n");
+}
+
+#[test]
+fn unchanged_terms_are_skipped() {
+    // Erasing the copies of an argument takes rewrites the term doesn't show.
+    let mut net = to_net(&from_string(b"/\\f \\x = f0 f1 f /f0 /f1 x /\\a \\_ a \\y y").unwrap()).unwrap();
+    let mut steps = Vec::new();
+    let stats = trace_terms(&mut net, 1, &mut |rules, term| steps.push((rules, to_string(&term.unwrap())))).unwrap();
+    assert!(steps.len() < stats.rules as usize + 1);
+    assert!(steps.windows(2).all(|pair| pair[0].1 != pair[1].1));
+    assert_eq!(steps.last().unwrap().1, b"\\- \\a a".to_vec());
+}
+
+#[test]
+fn large_nets_are_refused() {
+    // A balanced tree of pairs, shallow enough to parse, of over `MAX_TRACE_NODES` nodes.
+    fn pairs(depth : u32) -> String {
+        if depth == 0 { "*".to_string() } else { format!("| {} {}", pairs(depth - 1), pairs(depth - 1)) }
+    }
+    let code = pairs(12);
+    let mut net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    assert_eq!(trace_terms(&mut net, 1, &mut |_, _| panic!("traced a large net")), Err(LimitError::Traced(MAX_TRACE_NODES)));
+    let out = trace("large.sic", &code, &[]);
+    assert_eq!(out.status.code(), Some(75));
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("the net has more than 4096 nodes, too many to read back after each rewrite"));
+}
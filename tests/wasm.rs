@@ -0,0 +1,36 @@
+// Runs in a headless browser: `wasm-pack test --headless --firefox -- --features wasm`.
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+extern crate js_sys;
+extern crate sic;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const ADD : &str = ":add \\m \\n \\s \\z = s0 s1 s //m s0 //n s1 z
+:two \\s \\z = s0 s1 s /s0 /s1 z";
+
+fn field(obj : &JsValue, key : &str) -> JsValue {
+    Reflect::get(obj, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn evaluates_church_addition() {
+    let out = sic::wasm::evaluate(ADD, Some("//add two two".to_string()), 1000);
+    assert!(field(&out, "error").is_null());
+    let four = "= c d h\n= h g a\n= e f g\n\\a \\b /c /d /e /f b";
+    assert_eq!(field(&out, "result").as_string().unwrap(), four);
+    assert!(field(&field(&out, "stats"), "rules").as_f64().unwrap() > 0.0);
+}
+
+#[wasm_bindgen_test]
+fn reports_errors() {
+    let out = sic::wasm::evaluate("\\x", None, 1000);
+    assert!(field(&out, "result").is_null());
+    assert!(field(&out, "error").as_string().unwrap().starts_with("parse error"));
+}
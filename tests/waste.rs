@@ -0,0 +1,46 @@
+extern crate sic;
+
+use std::process::Command;
+use sic::net::reduce;
+use sic::term::{from_string, to_net};
+use sic::waste::*;
+
+const MUL : &str = ":three \\s \\z = s0 s1 s = s2 s3 s1 /s0 /s2 /s3 z\n:mul \\m \\n \\s /m /n s\n";
+
+fn waste(code : &str) -> Waste {
+    let mut net = to_net(&from_string(code.as_bytes()).unwrap()).unwrap();
+    let (stats, waste) = reduce_with_waste(&mut net);
+    assert_eq!(stats, reduce(&mut to_net(&from_string(code.as_bytes()).unwrap()).unwrap()));
+    assert_eq!(waste.rules, stats.rules);
+    waste
+}
+
+#[test]
+fn a_dropped_copy_is_wasted() {
+    // Nine is computed once and copied, and one of the copies is dropped.
+    let dropped = waste(&format!("{}= a b //mul three three\n//\\k \\_ k a b", MUL));
+    assert!(dropped.percent() > 30.0 && dropped.percent() < 70.0, "{}", dropped);
+    assert!(dropped.annihilated > 0);
+}
+
+#[test]
+fn what_is_used_is_not_wasted() {
+    let kept = waste(&format!("{}= a b //mul three three\n| a b", MUL));
+    assert_eq!(kept.wasted, 0);
+    assert_eq!(kept.percent(), 0.0);
+    // Arguments dropped before they're computed cost nothing.
+    assert_eq!(waste(&format!("{}//\\k \\_ k //mul three three //mul three three", MUL)).wasted, 0);
+    assert_eq!(waste("\\x x").percent(), 0.0);
+}
+
+#[test]
+fn command_line_prints_the_waste() {
+    let dir = std::env::temp_dir().join("sic-waste-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("drop.sic");
+    std::fs::write(&file, format!("{}= a b //mul three three\n//\\k \\_ k a b", MUL)).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_sic")).arg(&file).arg("--waste").output().unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(stdout.lines().last(), Some("wasted: 40.0% (12 of 30 rewrites), freed: 0 by erasure, 36 by annihilation"));
+}